@@ -0,0 +1,63 @@
+//! Throughput benchmarks for token counting.
+//!
+//! Run with `cargo bench --bench tokenization`.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokuin::models::ModelRegistry;
+use tokuin::parsers::Message;
+use tokuin::tokenizers::{chat, ChatOverhead};
+
+const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog. \
+Tokenizers split text into subword units, so prose, code and numbers \
+like 3.14159 or 2024-01-01 all exercise different merge paths.\n";
+
+/// Text of roughly `bytes` bytes built from a repeated paragraph.
+fn document(bytes: usize) -> String {
+    PARAGRAPH.repeat(bytes / PARAGRAPH.len() + 1)
+}
+
+/// Alternating user/assistant transcript with `count` messages.
+fn transcript(count: usize) -> Vec<Message> {
+    (0..count)
+        .map(|i| Message {
+            role: if i % 2 == 0 { "user" } else { "assistant" }.to_string(),
+            content: PARAGRAPH.to_string(),
+            name: None,
+            tool_call_id: None,
+        })
+        .collect()
+}
+
+fn count_tokens(c: &mut Criterion) {
+    let registry = ModelRegistry::new();
+    let tokenizer = registry.get_tokenizer("gpt-4").expect("gpt-4 tokenizer");
+
+    let mut group = c.benchmark_group("count_tokens/gpt-4");
+    for (name, input) in [
+        ("short_message", "Hello, how are you today?".to_string()),
+        ("document_10kb", document(10 * 1024)),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+            b.iter(|| tokenizer.count_tokens(black_box(input)).unwrap())
+        });
+    }
+
+    let messages = transcript(100);
+    let bytes: usize = messages.iter().map(|m| m.content.len()).sum();
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("transcript_100_messages", |b| {
+        b.iter(|| {
+            chat::count_chat_messages(
+                &*tokenizer,
+                black_box(&messages),
+                ChatOverhead::OPENAI,
+                None,
+            )
+            .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, count_tokens);
+criterion_main!(benches);
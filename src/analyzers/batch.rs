@@ -0,0 +1,333 @@
+/// Token and cost estimation for OpenAI Batch API input files.
+use crate::analyzers::types::{BatchItem, BatchSummary, SharedSystemPrompt};
+use crate::error::{AppError, ParseError};
+use crate::models::ModelRegistry;
+use crate::parsers::{BatchRequest, RoleMap};
+use crate::tokenizers::Tokenizer;
+use crate::utils::images::ImageTokens;
+use crate::utils::parallel::map_bounded;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+
+/// Fraction of the regular price charged for Batch API requests.
+pub const BATCH_DISCOUNT: f64 = 0.5;
+
+/// Estimate tokens and projected cost for each request in a batch.
+///
+/// The model named in each request body is used; `default_model` is the
+/// fallback for requests that omit it. When `price` is set, input tokens are
+/// priced (plus `expected_output` tokens per request, if given) and the batch
+/// discount is applied for models that support it.
+///
+/// Requests are counted on at most `jobs` threads. Items are returned in
+/// file order regardless of `jobs`.
+///
+/// # Errors
+///
+/// Returns an error if a request has no model and no default is given, or if
+/// a model has no tokenizer.
+pub fn estimate_batch(
+    registry: &ModelRegistry,
+    requests: &[BatchRequest],
+    default_model: Option<&str>,
+    price: bool,
+    expected_output: Option<usize>,
+    images: ImageTokens,
+    jobs: NonZeroUsize,
+) -> Result<BatchSummary, AppError> {
+    let models = request_models(requests, default_model)?;
+    let tokenizers = build_tokenizers(registry, &models)?;
+
+    let indices: Vec<usize> = (0..requests.len()).collect();
+    let counts = map_bounded(&indices, jobs, |&i| {
+        let tokenizer = &tokenizers[models[i]];
+        requests[i]
+            .messages
+            .iter()
+            .map(|message| images.count(tokenizer.as_ref(), &message.content))
+            .sum::<Result<usize, _>>()
+    });
+
+    let mut summary = BatchSummary::default();
+    let mut all_priced = price;
+    let mut total_cost = 0.0;
+
+    for ((request, &model), tokens) in requests.iter().zip(&models).zip(counts) {
+        let tokens = tokens?;
+        let tokenizer = &tokenizers[model];
+        let discounted = registry.supports_batch_discount(model);
+        let cost = if price {
+            // Embeddings models bill input only and have no output rate
+            let rates = registry.pricing_for(model).or_else(|| {
+                tokenizer
+                    .input_price_per_1k()
+                    .map(|input| (input, tokenizer.output_price_per_1k().unwrap_or(0.0)))
+            });
+            rates.map(|(input_rate, output_rate)| {
+                let output_tokens = expected_output.unwrap_or(0);
+                let cost = (tokens as f64 / 1000.0) * input_rate
+                    + (output_tokens as f64 / 1000.0) * output_rate;
+                if discounted {
+                    cost * BATCH_DISCOUNT
+                } else {
+                    cost
+                }
+            })
+        } else {
+            None
+        };
+
+        match cost {
+            Some(c) => total_cost += c,
+            None => all_priced = false,
+        }
+
+        summary.total_tokens += tokens;
+        summary.items.push(BatchItem {
+            custom_id: request.custom_id.clone(),
+            model: model.to_string(),
+            tokens,
+            cost,
+            discounted,
+        });
+    }
+
+    summary.total_cost = if all_priced { Some(total_cost) } else { None };
+    Ok(summary)
+}
+
+/// Find system prompts that several requests send verbatim.
+///
+/// A request's system prompt is the text of its system messages (roles are
+/// mapped through `roles`, so `developer` counts), joined by newlines.
+/// Requests without one are ignored. Every request after the first resends
+/// the prompt, and those tokens are what caching or hoisting it could save.
+/// Prompts are ordered by redundant tokens, largest first.
+///
+/// # Errors
+///
+/// Returns an error if a request has no model and no default is given, or if
+/// a model has no tokenizer.
+pub fn find_shared_system_prompts(
+    registry: &ModelRegistry,
+    requests: &[BatchRequest],
+    default_model: Option<&str>,
+    roles: &RoleMap,
+) -> Result<Vec<SharedSystemPrompt>, AppError> {
+    let models = request_models(requests, default_model)?;
+    let tokenizers = build_tokenizers(registry, &models)?;
+
+    // Requests grouped by system prompt, in order of first appearance
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut group_of: HashMap<String, usize> = HashMap::new();
+    for (i, request) in requests.iter().enumerate() {
+        let prompt = request
+            .messages
+            .iter()
+            .filter(|message| roles.canonical(&message.role) == "system")
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if prompt.is_empty() {
+            continue;
+        }
+        match group_of.get(&prompt) {
+            Some(&group) => groups[group].1.push(i),
+            None => {
+                group_of.insert(prompt.clone(), groups.len());
+                groups.push((prompt, vec![i]));
+            }
+        }
+    }
+
+    let mut shared = Vec::new();
+    for (prompt, indices) in groups.into_iter().filter(|(_, i)| i.len() > 1) {
+        let counts = indices
+            .iter()
+            .map(|&i| tokenizers[models[i]].count_tokens(&prompt))
+            .collect::<Result<Vec<usize>, _>>()?;
+        shared.push(SharedSystemPrompt {
+            custom_ids: indices
+                .iter()
+                .map(|&i| requests[i].custom_id.clone())
+                .collect(),
+            tokens: counts[0],
+            redundant_tokens: counts[1..].iter().sum(),
+        });
+    }
+    shared.sort_by_key(|prompt| std::cmp::Reverse(prompt.redundant_tokens));
+
+    Ok(shared)
+}
+
+/// The model for each request, falling back to `default_model`.
+fn request_models<'a>(
+    requests: &'a [BatchRequest],
+    default_model: Option<&'a str>,
+) -> Result<Vec<&'a str>, AppError> {
+    requests
+        .iter()
+        .map(|request| {
+            request.model.as_deref().or(default_model).ok_or_else(|| {
+                AppError::Parse(ParseError::MissingField {
+                    field: format!("body.model (custom_id '{}')", request.custom_id),
+                })
+            })
+        })
+        .collect()
+}
+
+/// One tokenizer per distinct model.
+fn build_tokenizers<'a>(
+    registry: &ModelRegistry,
+    models: &[&'a str],
+) -> Result<HashMap<&'a str, Box<dyn Tokenizer>>, AppError> {
+    // Tokenizers are expensive to build, so create one per distinct model
+    let mut tokenizers: HashMap<&str, Box<dyn Tokenizer>> = HashMap::new();
+    for &model in models {
+        if !tokenizers.contains_key(model) {
+            tokenizers.insert(model, registry.get_tokenizer(model)?);
+        }
+    }
+    Ok(tokenizers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_estimate_two_line_batch() {
+        use crate::parsers::BatchParser;
+
+        let input = r#"{"custom_id": "req-1", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gpt-4", "messages": [{"role": "user", "content": "Hello, world!"}]}}
+{"custom_id": "req-2", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gpt-4", "messages": [{"role": "system", "content": "Be brief."}, {"role": "user", "content": "Summarize the plot of Hamlet."}]}}"#;
+
+        let registry = ModelRegistry::new();
+        let requests = BatchParser::new().parse_batch(input).unwrap();
+        let summary = estimate_batch(
+            &registry,
+            &requests,
+            None,
+            true,
+            None,
+            ImageTokens::default(),
+            NonZeroUsize::MIN,
+        )
+        .unwrap();
+
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let count = |text: &str| tokenizer.count_tokens(text).unwrap();
+        let req1 = count("Hello, world!");
+        let req2 = count("Be brief.") + count("Summarize the plot of Hamlet.");
+
+        assert_eq!(summary.items.len(), 2);
+        assert_eq!(summary.items[0].custom_id, "req-1");
+        assert_eq!(summary.items[0].tokens, req1);
+        assert_eq!(summary.items[1].custom_id, "req-2");
+        assert_eq!(summary.items[1].tokens, req2);
+        assert_eq!(summary.total_tokens, req1 + req2);
+        assert!(summary.items.iter().all(|item| item.discounted));
+
+        // gpt-4 input is $0.03 per 1K tokens, halved for batch
+        let expected = ((req1 + req2) as f64 / 1000.0) * 0.03 * BATCH_DISCOUNT;
+        let total = summary.total_cost.expect("gpt-4 is priced");
+        assert!((total - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_estimate_batch_preserves_order_with_jobs() {
+        let requests: Vec<BatchRequest> = (0..50)
+            .map(|i| BatchRequest {
+                custom_id: format!("req-{}", i),
+                model: Some("chars".to_string()),
+                messages: vec![crate::parsers::Message {
+                    role: "user".to_string(),
+                    content: "x".repeat(i),
+                    name: None,
+                    tool_call_id: None,
+                }],
+            })
+            .collect();
+        let registry = ModelRegistry::new();
+        let jobs = NonZeroUsize::new(4).unwrap();
+        let summary = estimate_batch(
+            &registry,
+            &requests,
+            None,
+            false,
+            None,
+            ImageTokens::default(),
+            jobs,
+        )
+        .unwrap();
+
+        for (i, item) in summary.items.iter().enumerate() {
+            assert_eq!(item.custom_id, format!("req-{}", i));
+            assert_eq!(item.tokens, i);
+        }
+        assert_eq!(summary.total_tokens, (0..50).sum::<usize>());
+    }
+
+    #[test]
+    fn test_shared_system_prompt_across_three_requests() {
+        let message = |role: &str, content: &str| crate::parsers::Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            name: None,
+            tool_call_id: None,
+        };
+        let system = "You are a meticulous assistant. Answer in one short paragraph.";
+        let requests: Vec<BatchRequest> = ["a", "b", "c"]
+            .iter()
+            .map(|question| BatchRequest {
+                custom_id: format!("req-{}", question),
+                model: Some("words".to_string()),
+                messages: vec![message("system", system), message("user", question)],
+            })
+            .chain(std::iter::once(BatchRequest {
+                custom_id: "req-other".to_string(),
+                model: Some("words".to_string()),
+                messages: vec![message("developer", "Be brief."), message("user", "d")],
+            }))
+            .collect();
+
+        let registry = ModelRegistry::new();
+        let shared =
+            find_shared_system_prompts(&registry, &requests, None, &RoleMap::default()).unwrap();
+
+        assert_eq!(
+            shared,
+            vec![SharedSystemPrompt {
+                custom_ids: vec![
+                    "req-a".to_string(),
+                    "req-b".to_string(),
+                    "req-c".to_string()
+                ],
+                tokens: 10,
+                redundant_tokens: 20,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_estimate_batch_requires_model() {
+        let requests = vec![BatchRequest {
+            custom_id: "req-1".to_string(),
+            model: None,
+            messages: Vec::new(),
+        }];
+        let registry = ModelRegistry::new();
+        assert!(estimate_batch(
+            &registry,
+            &requests,
+            None,
+            false,
+            None,
+            ImageTokens::default(),
+            NonZeroUsize::MIN
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,193 @@
+/// Token budget allocation across prioritized prompt sections.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+
+/// How much of a section fits under the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionFit {
+    /// The whole section fits.
+    Fits,
+    /// Only the first part fits.
+    Truncated,
+    /// No room is left for the section.
+    Dropped,
+}
+
+impl SectionFit {
+    /// Lowercase name, e.g. `truncated`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SectionFit::Fits => "fits",
+            SectionFit::Truncated => "truncated",
+            SectionFit::Dropped => "dropped",
+        }
+    }
+}
+
+/// Tokens one section takes and how many of them it is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionAllocation {
+    /// Section label, e.g. `system`.
+    pub label: String,
+    /// Tokens in the whole section.
+    pub tokens: usize,
+    /// Tokens of the section kept under the budget.
+    pub allocated: usize,
+    /// Whether the section fits, is truncated or is dropped.
+    pub fit: SectionFit,
+}
+
+/// Allocation plan of one model's budget, in priority order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Total token budget.
+    pub budget: usize,
+    /// Sections, highest priority first.
+    pub sections: Vec<SectionAllocation>,
+}
+
+impl Allocation {
+    /// Tokens allocated across all sections.
+    pub fn used(&self) -> usize {
+        self.sections.iter().map(|s| s.allocated).sum()
+    }
+
+    /// Tokens of the budget left over.
+    pub fn remaining(&self) -> usize {
+        self.budget - self.used()
+    }
+
+    /// Tokens all sections would take in full.
+    pub fn requested(&self) -> usize {
+        self.sections.iter().map(|s| s.tokens).sum()
+    }
+}
+
+/// Allocate `budget` tokens to labeled sections, highest priority first.
+///
+/// Sections are given room in order. The first section that does not fit
+/// is truncated to what is left, and every section after it is dropped, so
+/// a lower-priority section never takes room from a higher one.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if a section cannot be tokenized.
+pub fn allocate(
+    tokenizer: &dyn Tokenizer,
+    sections: &[(String, String)],
+    budget: usize,
+) -> Result<Allocation, TokenizerError> {
+    let mut remaining = budget;
+    let sections = sections
+        .iter()
+        .map(|(label, text)| {
+            let tokens = tokenizer.count_tokens(text)?;
+            let (allocated, fit) = if tokens <= remaining {
+                (tokens, SectionFit::Fits)
+            } else if remaining > 0 {
+                (remaining, SectionFit::Truncated)
+            } else {
+                (0, SectionFit::Dropped)
+            };
+            // Nothing after a truncated section gets room
+            remaining = if fit == SectionFit::Fits {
+                remaining - tokens
+            } else {
+                0
+            };
+            Ok(SectionAllocation {
+                label: label.clone(),
+                tokens,
+                allocated,
+                fit,
+            })
+        })
+        .collect::<Result<Vec<_>, TokenizerError>>()?;
+
+    Ok(Allocation {
+        model: tokenizer.name().to_string(),
+        budget,
+        sections,
+    })
+}
+
+/// Parse a `LABEL=FILE` section.
+///
+/// # Errors
+///
+/// Returns a message if either side is missing.
+pub fn parse_section(spec: &str) -> Result<(String, String), String> {
+    match spec.split_once('=') {
+        Some((label, path)) if !label.trim().is_empty() && !path.trim().is_empty() => {
+            Ok((label.trim().to_string(), path.trim().to_string()))
+        }
+        _ => Err(format!("expected LABEL=FILE, got '{}'", spec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::WordTokenizer;
+
+    fn sections(specs: &[(&str, &str)]) -> Vec<(String, String)> {
+        specs
+            .iter()
+            .map(|(label, text)| (label.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_low_priority_section_is_dropped() {
+        let sections = sections(&[
+            ("system", "You answer questions about the docs."),
+            ("context", "one two three four five six seven eight"),
+            ("examples", "Q: hi A: hello"),
+        ]);
+        let plan = allocate(&WordTokenizer::new(), &sections, 14).unwrap();
+
+        let fits: Vec<(SectionFit, usize)> =
+            plan.sections.iter().map(|s| (s.fit, s.allocated)).collect();
+        assert_eq!(
+            fits,
+            [
+                (SectionFit::Fits, 6),
+                (SectionFit::Fits, 8),
+                (SectionFit::Dropped, 0)
+            ]
+        );
+        assert_eq!(plan.sections[2].tokens, 4);
+        assert_eq!(
+            (plan.used(), plan.remaining(), plan.requested()),
+            (14, 0, 18)
+        );
+    }
+
+    #[test]
+    fn test_first_overflowing_section_is_truncated() {
+        let sections = sections(&[
+            ("system", "Be brief."),
+            ("context", "one two three four five"),
+            ("examples", "hi"),
+        ]);
+        let plan = allocate(&WordTokenizer::new(), &sections, 5).unwrap();
+
+        assert_eq!(plan.sections[1].fit, SectionFit::Truncated);
+        assert_eq!(plan.sections[1].allocated, 3);
+        // Fits on its own, but only after the truncated section
+        assert_eq!(plan.sections[2].fit, SectionFit::Dropped);
+        assert_eq!(plan.remaining(), 0);
+    }
+
+    #[test]
+    fn test_parse_section() {
+        assert_eq!(
+            parse_section("context=docs.md"),
+            Ok(("context".to_string(), "docs.md".to_string()))
+        );
+        assert!(parse_section("docs.md").is_err());
+        assert!(parse_section("=docs.md").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+/// Corpus-wide token and cost statistics for a directory of transcripts.
+use crate::analyzers::roles::transcript_paths;
+use crate::error::AppError;
+use crate::output::aggregate::total_cost;
+use crate::output::{ResultAggregator, Stats, TokenBreakdown, TokenResult};
+use crate::utils::input::InputSource;
+use std::path::{Path, PathBuf};
+
+/// Number of heaviest files listed in corpus reports.
+pub const HEAVIEST_FILES: usize = 5;
+
+/// A transcript left out of a corpus summary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    /// Path of the transcript.
+    pub path: PathBuf,
+    /// Why it could not be counted.
+    pub reason: String,
+}
+
+/// Token statistics for every transcript in a directory.
+#[derive(Debug, Clone)]
+pub struct CorpusSummary {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Path of each transcript, in path order.
+    pub paths: Vec<PathBuf>,
+    /// Count of each transcript, matching `paths`.
+    pub results: Vec<TokenResult>,
+    /// Transcripts that could not be read or counted, in path order.
+    pub skipped: Vec<SkippedFile>,
+}
+
+impl CorpusSummary {
+    /// Number of transcripts.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether the directory holds no counted transcripts.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Tokens summed over every transcript.
+    pub fn total_tokens(&self) -> usize {
+        self.results.iter().map(|result| result.tokens).sum()
+    }
+
+    /// Statistics of the tokens per file, or `None` without files.
+    pub fn tokens_per_file(&self) -> Option<Stats> {
+        ResultAggregator::new(&self.results).tokens()
+    }
+
+    /// The `n` files with the most tokens, heaviest first; ties keep path order.
+    pub fn heaviest(&self, n: usize) -> Vec<(&Path, &TokenResult)> {
+        let mut files: Vec<(&Path, &TokenResult)> = self
+            .paths
+            .iter()
+            .map(PathBuf::as_path)
+            .zip(&self.results)
+            .collect();
+        files.sort_by_key(|(_, result)| std::cmp::Reverse(result.tokens));
+        files.truncate(n);
+        files
+    }
+
+    /// Tokens per role summed over every transcript.
+    pub fn breakdown(&self) -> TokenBreakdown {
+        let mut total = TokenBreakdown::new();
+        for breakdown in self.results.iter().filter_map(|r| r.breakdown.as_ref()) {
+            total += breakdown;
+        }
+        total
+    }
+
+    /// Total cost of sending every transcript once, or `None` if the model
+    /// has no pricing.
+    pub fn total_cost(&self) -> Option<f64> {
+        self.results
+            .iter()
+            .map(total_cost)
+            .sum::<Option<f64>>()
+            .filter(|_| !self.results.is_empty())
+    }
+}
+
+/// Count every transcript under `dir` with `count`.
+///
+/// Transcripts are found like `--group-by-role` does; `count` parses,
+/// tokenizes and prices each one. A file that can't be read or counted
+/// doesn't spoil the rest of the corpus: it is listed in `skipped` instead.
+///
+/// # Errors
+///
+/// Returns `AppError` if the directory can't be walked.
+pub fn summarize_corpus(
+    model: &str,
+    dir: &Path,
+    mut count: impl FnMut(&str) -> Result<TokenResult, AppError>,
+) -> Result<CorpusSummary, AppError> {
+    let mut summary = CorpusSummary {
+        model: model.to_string(),
+        paths: Vec::new(),
+        results: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for path in transcript_paths(dir)? {
+        let result = InputSource::file(path.display().to_string(), "transcript")
+            .read(None)
+            .and_then(|content| count(&content));
+        match result {
+            Ok(result) => {
+                summary.paths.push(path);
+                summary.results.push(result);
+            }
+            Err(error) => summary.skipped.push(SkippedFile {
+                path,
+                reason: error.to_string(),
+            }),
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(files: &[(&str, usize, Option<f64>)]) -> CorpusSummary {
+        CorpusSummary {
+            model: "gpt-4".to_string(),
+            paths: files
+                .iter()
+                .map(|(path, _, _)| PathBuf::from(path))
+                .collect(),
+            results: files
+                .iter()
+                .map(|&(_, tokens, cost)| TokenResult {
+                    tokens,
+                    input_cost: cost,
+                    ..Default::default()
+                })
+                .collect(),
+            skipped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_corpus_statistics() {
+        let summary = summary(&[
+            ("a.json", 100, Some(0.003)),
+            ("b.json", 400, Some(0.012)),
+            ("c.json", 100, Some(0.003)),
+            ("d.json", 200, Some(0.006)),
+        ]);
+        assert_eq!(summary.total_tokens(), 800);
+        let stats = summary.tokens_per_file().unwrap();
+        assert_eq!((stats.mean, stats.p50), (200.0, 100.0));
+        assert!((summary.total_cost().unwrap() - 0.024).abs() < 1e-12);
+
+        let heaviest: Vec<&Path> = summary.heaviest(3).into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            heaviest,
+            [
+                Path::new("b.json"),
+                Path::new("d.json"),
+                Path::new("a.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unpriced_and_empty_corpus() {
+        let unpriced = summary(&[("a.json", 10, Some(0.1)), ("b.json", 20, None)]);
+        assert!(unpriced.total_cost().is_none());
+
+        let empty = summary(&[]);
+        assert!(empty.is_empty());
+        assert!(empty.total_cost().is_none());
+        assert!(empty.tokens_per_file().is_none());
+    }
+
+    #[test]
+    fn test_unparseable_files_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.json"), "good").unwrap();
+        std::fs::write(dir.path().join("b.json"), "bad").unwrap();
+        std::fs::write(dir.path().join("c.json"), "good too").unwrap();
+
+        let summary = summarize_corpus("words", dir.path(), |content| {
+            if content == "bad" {
+                return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    "not a transcript".to_string(),
+                )));
+            }
+            Ok(TokenResult {
+                tokens: content.split_whitespace().count(),
+                ..Default::default()
+            })
+        })
+        .unwrap();
+
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary.total_tokens(), 3);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].path, dir.path().join("b.json"));
+        assert!(summary.skipped[0].reason.contains("not a transcript"));
+    }
+}
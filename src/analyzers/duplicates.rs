@@ -49,6 +49,8 @@ mod tests {
             messages: vec![Message {
                 role: "user".to_string(),
                 content: content.to_string(),
+                name: None,
+                tool_call_id: None,
             }],
             token_count: 10,
             input_cost: 0.0,
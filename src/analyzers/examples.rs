@@ -0,0 +1,83 @@
+/// Token counts for a prompt and a separate few-shot examples file.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+
+/// Separator placed between the prompt and the examples when combined.
+pub const EXAMPLES_SEPARATOR: &str = "\n\n";
+
+/// Token counts for a prompt, its examples and the two combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentCounts {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Tokens in the main prompt.
+    pub prompt: usize,
+    /// Tokens in the examples.
+    pub examples: usize,
+    /// Tokens in the prompt and examples joined by [`EXAMPLES_SEPARATOR`].
+    pub combined: usize,
+}
+
+impl SegmentCounts {
+    /// How far the combined count is from the sum of the segments.
+    ///
+    /// Merges across the join and the separator itself make the combined
+    /// text tokenize differently from the two parts on their own.
+    pub fn boundary_delta(&self) -> i64 {
+        self.combined as i64 - (self.prompt + self.examples) as i64
+    }
+}
+
+/// Join a prompt and its examples the way they are sent.
+pub fn combine(prompt: &str, examples: &str) -> String {
+    format!(
+        "{}{}{}",
+        prompt.trim_end(),
+        EXAMPLES_SEPARATOR,
+        examples.trim_start()
+    )
+}
+
+/// Count the prompt, the examples and their combination.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if any segment cannot be tokenized.
+pub fn count_segments(
+    tokenizer: &dyn Tokenizer,
+    prompt: &str,
+    examples: &str,
+) -> Result<SegmentCounts, TokenizerError> {
+    Ok(SegmentCounts {
+        model: tokenizer.name().to_string(),
+        prompt: tokenizer.count_tokens(prompt)?,
+        examples: tokenizer.count_tokens(examples)?,
+        combined: tokenizer.count_tokens(&combine(prompt, examples))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::WordTokenizer;
+
+    #[test]
+    fn test_count_segments() {
+        let tokenizer = WordTokenizer::new();
+        let counts = count_segments(
+            &tokenizer,
+            "Classify the sentiment.\n",
+            "\nInput: great\nOutput: positive",
+        )
+        .unwrap();
+
+        assert_eq!(counts.prompt, 3);
+        assert_eq!(counts.examples, 4);
+        assert_eq!(counts.combined, 7);
+        assert_eq!(counts.boundary_delta(), 0);
+        assert_eq!(
+            combine("Classify.\n", "\nInput: x"),
+            "Classify.\n\nInput: x"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+/// Statistics behind the `--explain-tokenization` walkthrough.
+use crate::error::TokenizerError;
+use crate::tokenizers::{decode_fragments, TokenFragment, Tokenizer};
+
+/// Longest input explained, in characters; the walkthrough is for short samples.
+pub const MAX_EXPLAIN_CHARS: usize = 2_000;
+
+/// Number of leading tokens shown with their decoded text.
+pub const EXPLAINED_TOKENS: usize = 8;
+
+/// How one model tokenizes a short input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Unicode characters in the input.
+    pub chars: usize,
+    /// UTF-8 bytes in the input.
+    pub bytes: usize,
+    /// Tokens in the input.
+    pub tokens: usize,
+    /// Tokens in the lowercased input.
+    pub lowercase_tokens: usize,
+    /// Tokens with every whitespace run collapsed to a single space.
+    pub collapsed_tokens: usize,
+    /// Decoded text of the first tokens, or `None` if the tokenizer only
+    /// approximates counts and has no real token boundaries.
+    pub fragments: Option<Vec<TokenFragment>>,
+}
+
+impl Explanation {
+    /// Average characters per token, or `None` for an empty input.
+    pub fn chars_per_token(&self) -> Option<f64> {
+        (self.tokens > 0).then(|| self.chars as f64 / self.tokens as f64)
+    }
+}
+
+/// Count `text` and its lowercase and whitespace-collapsed variants, and
+/// decode its first [`EXPLAINED_TOKENS`] tokens.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if the text cannot be tokenized.
+pub fn explain(tokenizer: &dyn Tokenizer, text: &str) -> Result<Explanation, TokenizerError> {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let fragments = if tokenizer.is_exact() {
+        let tokens = tokenizer.encode(text)?;
+        let shown = &tokens[..tokens.len().min(EXPLAINED_TOKENS)];
+        Some(decode_fragments(tokenizer, shown))
+    } else {
+        None
+    };
+
+    Ok(Explanation {
+        model: tokenizer.name().to_string(),
+        chars: text.chars().count(),
+        bytes: text.len(),
+        tokens: tokenizer.count_tokens(text)?,
+        lowercase_tokens: tokenizer.count_tokens(&text.to_lowercase())?,
+        collapsed_tokens: tokenizer.count_tokens(&collapsed)?,
+        fragments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::{CharTokenizer, WordTokenizer};
+
+    #[test]
+    fn test_explain_counts() {
+        let explanation = explain(&CharTokenizer::new(), "Héllo  World").unwrap();
+        assert_eq!(
+            (explanation.chars, explanation.bytes, explanation.tokens),
+            (12, 13, 12)
+        );
+        assert_eq!(explanation.collapsed_tokens, 11);
+        assert_eq!(explanation.chars_per_token(), Some(1.0));
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_explain_fragments() {
+        let tokenizer = crate::tokenizers::OpenAITokenizer::new("gpt-4").unwrap();
+        let explanation = explain(&tokenizer, &"Hello, world! ".repeat(4)).unwrap();
+
+        let fragments = explanation.fragments.unwrap();
+        assert_eq!(fragments.len(), EXPLAINED_TOKENS);
+        assert_eq!(fragments[1].to_string(), ",");
+    }
+
+    #[test]
+    fn test_approximate_tokenizer_has_no_fragments() {
+        let explanation = explain(&WordTokenizer::new(), "two words").unwrap();
+        assert_eq!(explanation.tokens, 2);
+        assert!(explanation.fragments.is_none());
+        assert_eq!(
+            explain(&WordTokenizer::new(), "")
+                .unwrap()
+                .chars_per_token(),
+            None
+        );
+    }
+}
@@ -0,0 +1,196 @@
+/// Distribution of per-message token counts.
+use crate::error::{AppError, ParseError};
+use crate::parsers::Message;
+use crate::tokenizers::Tokenizer;
+use crate::utils::images::ImageTokens;
+
+/// Default upper bounds of the histogram buckets: 0-10, 11-50, 51-200, 201+.
+pub const DEFAULT_BOUNDS: [usize; 3] = [10, 50, 200];
+
+/// Messages whose token counts fall in `min..=max`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket {
+    /// Smallest count in the bucket.
+    pub min: usize,
+    /// Largest count in the bucket, or `None` for the open-ended last bucket.
+    pub max: Option<usize>,
+    /// Number of messages in the bucket.
+    pub count: usize,
+}
+
+impl Bucket {
+    /// Label such as `11-50` or `201+`.
+    pub fn label(&self) -> String {
+        match self.max {
+            Some(max) => format!("{}-{}", self.min, max),
+            None => format!("{}+", self.min),
+        }
+    }
+}
+
+/// Per-message token counts bucketed by size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Buckets in ascending order; the last one is open-ended.
+    pub buckets: Vec<Bucket>,
+    /// Largest message, in tokens.
+    pub largest: usize,
+}
+
+impl Histogram {
+    /// Bucket `counts` by the ascending upper `bounds`.
+    ///
+    /// `[10, 50]` gives the buckets `0-10`, `11-50` and `51+`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseError::InvalidFormat` if `bounds` is not strictly
+    /// ascending.
+    pub fn from_counts(
+        model: &str,
+        counts: &[usize],
+        bounds: &[usize],
+    ) -> Result<Self, ParseError> {
+        if !bounds.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(ParseError::InvalidFormat(
+                "histogram bounds must be strictly ascending".to_string(),
+            ));
+        }
+
+        let mut buckets: Vec<Bucket> = bounds
+            .iter()
+            .scan(0, |min, &max| {
+                let bucket = Bucket {
+                    min: *min,
+                    max: Some(max),
+                    count: 0,
+                };
+                *min = max + 1;
+                Some(bucket)
+            })
+            .collect();
+        buckets.push(Bucket {
+            min: bounds.last().map_or(0, |max| max + 1),
+            max: None,
+            count: 0,
+        });
+
+        for &count in counts {
+            let index = bounds.partition_point(|&max| max < count);
+            buckets[index].count += 1;
+        }
+
+        Ok(Self {
+            model: model.to_string(),
+            buckets,
+            largest: counts.iter().copied().max().unwrap_or(0),
+        })
+    }
+
+    /// Total number of messages.
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+}
+
+/// Count each message with `tokenizer` and bucket the counts by `bounds`.
+///
+/// Inline base64 images are counted according to `images`.
+///
+/// # Errors
+///
+/// Returns `AppError` if a message cannot be tokenized or `bounds` is not
+/// strictly ascending.
+pub fn histogram(
+    tokenizer: &dyn Tokenizer,
+    messages: &[Message],
+    bounds: &[usize],
+    images: ImageTokens,
+) -> Result<Histogram, AppError> {
+    let counts = messages
+        .iter()
+        .map(|message| images.count(tokenizer, &message.content))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Histogram::from_counts(tokenizer.name(), &counts, bounds)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::CharTokenizer;
+    use crate::utils::images::IMAGE_TOKEN_ESTIMATE;
+
+    #[test]
+    fn test_default_buckets() {
+        let counts = [0, 3, 10, 11, 42, 50, 51, 200, 201, 5000];
+        let histogram = Histogram::from_counts("chars", &counts, &DEFAULT_BOUNDS).unwrap();
+
+        let labels: Vec<String> = histogram.buckets.iter().map(Bucket::label).collect();
+        assert_eq!(labels, ["0-10", "11-50", "51-200", "201+"]);
+        let sizes: Vec<usize> = histogram.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(sizes, [3, 3, 2, 2]);
+        assert_eq!(histogram.total(), 10);
+        assert_eq!(histogram.largest, 5000);
+    }
+
+    #[test]
+    fn test_custom_bounds_from_messages() {
+        let messages: Vec<Message> = ["a", "bbbbb", "cccccc", "dddddddddddd", "ee"]
+            .into_iter()
+            .map(|content| Message {
+                role: "user".to_string(),
+                content: content.to_string(),
+                name: None,
+                tool_call_id: None,
+            })
+            .collect();
+
+        let histogram = histogram(
+            &CharTokenizer::new(),
+            &messages,
+            &[2, 5],
+            ImageTokens::default(),
+        )
+        .unwrap();
+        let sizes: Vec<(String, usize)> = histogram
+            .buckets
+            .iter()
+            .map(|b| (b.label(), b.count))
+            .collect();
+        assert_eq!(
+            sizes,
+            [
+                ("0-2".to_string(), 2),
+                ("3-5".to_string(), 1),
+                ("6+".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_images_follow_the_image_mode() {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: "look data:image/png;base64,iVBORw0KGgoAAAANSUhEUg==".to_string(),
+            name: None,
+            tool_call_id: None,
+        }];
+
+        let largest = |images| {
+            histogram(&CharTokenizer::new(), &messages, &DEFAULT_BOUNDS, images)
+                .unwrap()
+                .largest
+        };
+        assert_eq!(largest(ImageTokens::Estimate), 5 + IMAGE_TOKEN_ESTIMATE);
+        assert_eq!(largest(ImageTokens::Strip), 5);
+        assert_eq!(largest(ImageTokens::Literal), messages[0].content.len());
+    }
+
+    #[test]
+    fn test_unordered_bounds_are_an_error() {
+        let error = Histogram::from_counts("chars", &[1, 2], &[50, 10]).unwrap_err();
+        assert!(error.to_string().contains("strictly ascending"));
+    }
+}
@@ -0,0 +1,146 @@
+/// Token savings from stripping markdown out of prompts.
+use crate::analyzers::roles::transcript_paths;
+use crate::error::AppError;
+use crate::tokenizers::Tokenizer;
+use crate::utils::markdown::{calculate_savings, strip_markdown};
+use std::path::{Path, PathBuf};
+
+/// Savings for one prompt, measured by re-tokenizing the stripped text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinifySavings {
+    /// Characters removed by stripping.
+    pub chars_saved: usize,
+    /// Tokens before stripping.
+    pub original_tokens: usize,
+    /// Tokens after stripping.
+    pub minified_tokens: usize,
+}
+
+impl MinifySavings {
+    /// Strip `original` and count both versions with `tokenizer`.
+    ///
+    /// Returns the savings together with the stripped text.
+    pub fn measure(tokenizer: &dyn Tokenizer, original: &str) -> Result<(Self, String), AppError> {
+        let stripped = strip_markdown(original);
+        let savings = Self {
+            chars_saved: calculate_savings(original, &stripped),
+            original_tokens: tokenizer.count_tokens(original)?,
+            minified_tokens: tokenizer.count_tokens(&stripped)?,
+        };
+        Ok((savings, stripped))
+    }
+
+    /// Tokens saved (zero if stripping made the prompt longer).
+    pub fn tokens_saved(&self) -> usize {
+        self.original_tokens.saturating_sub(self.minified_tokens)
+    }
+}
+
+/// Savings for one file in a directory summary.
+#[derive(Debug, Clone)]
+pub struct FileSavings {
+    /// Path of the prompt file.
+    pub path: PathBuf,
+    /// Measured savings.
+    pub savings: MinifySavings,
+}
+
+/// Aggregate savings across a directory of prompts.
+#[derive(Debug, Clone)]
+pub struct MinifySummary {
+    /// Tokenizer used for the counts.
+    pub model: String,
+    /// Per-file savings, largest token savings first.
+    pub files: Vec<FileSavings>,
+}
+
+impl MinifySummary {
+    /// Total characters saved.
+    pub fn chars_saved(&self) -> usize {
+        self.files.iter().map(|f| f.savings.chars_saved).sum()
+    }
+
+    /// Total tokens before stripping.
+    pub fn original_tokens(&self) -> usize {
+        self.files.iter().map(|f| f.savings.original_tokens).sum()
+    }
+
+    /// Total tokens after stripping.
+    pub fn minified_tokens(&self) -> usize {
+        self.files.iter().map(|f| f.savings.minified_tokens).sum()
+    }
+
+    /// Total tokens saved.
+    pub fn tokens_saved(&self) -> usize {
+        self.files.iter().map(|f| f.savings.tokens_saved()).sum()
+    }
+}
+
+/// Measure markdown-stripping savings for every prompt file under `dir`.
+///
+/// Only text-like files (`md`, `markdown`, `txt`, `prompt`) are stripped;
+/// JSON and YAML prompts are left out since they carry no markdown syntax
+/// of their own.
+///
+/// # Errors
+///
+/// Returns `AppError` if the directory can't be walked, or a file cannot be
+/// read or tokenized.
+pub fn summarize_directory(
+    tokenizer: &dyn Tokenizer,
+    dir: &Path,
+) -> Result<MinifySummary, AppError> {
+    let paths = transcript_paths(dir)?.into_iter().filter(|path| {
+        !path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    });
+
+    let mut files = Vec::new();
+    for path in paths {
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            AppError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to read {}: {}", path.display(), e),
+            ))
+        })?;
+        let (savings, _) = MinifySavings::measure(tokenizer, &content)?;
+        files.push(FileSavings { path, savings });
+    }
+    // Stable sort keeps path order among equal savings
+    files.sort_by_key(|f| std::cmp::Reverse(f.savings.tokens_saved()));
+
+    Ok(MinifySummary {
+        model: tokenizer.name().to_string(),
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::CharTokenizer;
+
+    #[test]
+    fn test_summarize_directory_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.md"), "# Title\n\n**bold** text").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "plain").unwrap();
+        std::fs::write(dir.path().join("c.json"), "[]").unwrap();
+
+        let tokenizer = CharTokenizer::new();
+        let summary = summarize_directory(&tokenizer, dir.path()).unwrap();
+
+        assert_eq!(summary.files.len(), 2);
+        let (a, _) = MinifySavings::measure(&tokenizer, "# Title\n\n**bold** text").unwrap();
+        let (b, _) = MinifySavings::measure(&tokenizer, "plain").unwrap();
+        assert!(a.tokens_saved() > 0);
+        assert_eq!(summary.tokens_saved(), a.tokens_saved() + b.tokens_saved());
+        assert_eq!(
+            summary.original_tokens(),
+            a.original_tokens + b.original_tokens
+        );
+        assert_eq!(summary.chars_saved(), a.chars_saved + b.chars_saved);
+        assert!(summary.files[0].path.ends_with("a.md"));
+    }
+}
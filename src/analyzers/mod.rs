@@ -1,6 +1,20 @@
+pub mod batch;
+pub mod budget;
+pub mod corpus;
 /// Prompt library analysis module.
 pub mod duplicates;
+pub mod examples;
+pub mod explain;
+pub mod histogram;
+#[cfg(feature = "markdown")]
+pub mod minify;
+pub mod replay;
+pub mod roles;
+#[cfg(feature = "sampling")]
+pub mod sampling;
 pub mod scanner;
+pub mod transcript_diff;
 pub mod types;
+pub mod whitespace;
 
 pub use scanner::PromptScanner;
@@ -0,0 +1,162 @@
+/// Cost of a recorded conversation, attributed to each message.
+use crate::error::TokenizerError;
+use crate::parsers::{Message, RoleMap};
+use crate::tokenizers::Tokenizer;
+use crate::utils::images::ImageTokens;
+
+/// Which side of the bill a message was charged to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Billed {
+    /// Sent to the model: system, user and tool messages.
+    Input,
+    /// Generated by the model: assistant messages.
+    Output,
+}
+
+/// Tokens and cost of one recorded message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageCost {
+    /// The message role as recorded.
+    pub role: String,
+    /// Tokens in the message content.
+    pub tokens: usize,
+    /// Whether the message was billed as input or output.
+    pub billed: Billed,
+    /// Cost of the message (in USD).
+    pub cost: f64,
+}
+
+/// Per-message costs of a recorded conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayCost {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Input price per 1K tokens (USD).
+    pub input_rate: f64,
+    /// Output price per 1K tokens (USD).
+    pub output_rate: f64,
+    /// Cost of each message, in transcript order.
+    pub messages: Vec<MessageCost>,
+}
+
+impl ReplayCost {
+    /// Tokens billed on `side`.
+    pub fn tokens(&self, side: Billed) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| m.billed == side)
+            .map(|m| m.tokens)
+            .sum()
+    }
+
+    /// Cost billed on `side` (in USD).
+    pub fn cost(&self, side: Billed) -> f64 {
+        self.messages
+            .iter()
+            .filter(|m| m.billed == side)
+            .map(|m| m.cost)
+            .sum()
+    }
+
+    /// Total cost of the conversation (in USD).
+    pub fn total(&self) -> f64 {
+        self.messages.iter().map(|m| m.cost).sum()
+    }
+}
+
+/// Price each message of a recorded conversation.
+///
+/// Assistant messages are what the model generated and are billed at the
+/// output rate; every other role, including `tool` results, was sent to the
+/// model and is billed at the input rate. Roles are matched after `roles`
+/// aliases are applied, and inline base64 images are counted per `images`.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if a message cannot be tokenized.
+pub fn replay_cost(
+    tokenizer: &dyn Tokenizer,
+    messages: &[Message],
+    (input_rate, output_rate): (f64, f64),
+    roles: &RoleMap,
+    images: ImageTokens,
+) -> Result<ReplayCost, TokenizerError> {
+    let messages = messages
+        .iter()
+        .map(|message| {
+            let tokens = images.count(tokenizer, &message.content)?;
+            let (billed, rate) = match roles.canonical(&message.role) {
+                "assistant" => (Billed::Output, output_rate),
+                _ => (Billed::Input, input_rate),
+            };
+            Ok(MessageCost {
+                role: message.role.clone(),
+                tokens,
+                billed,
+                cost: tokens as f64 / 1000.0 * rate,
+            })
+        })
+        .collect::<Result<_, TokenizerError>>()?;
+
+    Ok(ReplayCost {
+        model: tokenizer.name().to_string(),
+        input_rate,
+        output_rate,
+        messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::WordTokenizer;
+
+    #[test]
+    fn test_message_costs_sum_to_total() {
+        let messages: Vec<Message> = [
+            ("system", "You are a weather bot."),
+            ("user", "Weather in Paris?"),
+            ("assistant", "Calling the weather tool now."),
+            ("tool", "{\"temp\": 18}"),
+            ("assistant", "It is 18 degrees in Paris today."),
+        ]
+        .into_iter()
+        .map(|(role, content)| Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            name: None,
+            tool_call_id: None,
+        })
+        .collect();
+
+        let replay = replay_cost(
+            &WordTokenizer::new(),
+            &messages,
+            (1.0, 3.0),
+            &RoleMap::default(),
+            ImageTokens::default(),
+        )
+        .unwrap();
+
+        let billed: Vec<Billed> = replay.messages.iter().map(|m| m.billed).collect();
+        assert_eq!(
+            billed,
+            [
+                Billed::Input,
+                Billed::Input,
+                Billed::Output,
+                Billed::Input,
+                Billed::Output
+            ]
+        );
+        // 5 + 3 + 2 input words and 5 + 7 output words
+        assert_eq!(replay.tokens(Billed::Input), 10);
+        assert_eq!(replay.tokens(Billed::Output), 12);
+        assert!((replay.messages[3].cost - 0.002).abs() < 1e-12);
+        assert!((replay.total() - (0.010 + 0.036)).abs() < 1e-12);
+
+        let sum: f64 = replay.messages.iter().map(|m| m.cost).sum();
+        assert!((sum - replay.total()).abs() < 1e-12);
+        assert!((replay.cost(Billed::Input) + replay.cost(Billed::Output) - sum).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,90 @@
+/// Role breakdowns summed across a directory of transcripts.
+use crate::error::AppError;
+use crate::output::TokenBreakdown;
+use crate::utils::input::InputSource;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Role breakdown for one transcript file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileBreakdown {
+    /// Path of the transcript.
+    pub path: PathBuf,
+    /// Tokens per role in the file.
+    pub breakdown: TokenBreakdown,
+}
+
+/// Per-file role breakdowns and their sum.
+#[derive(Debug, Clone)]
+pub struct RoleSummary {
+    /// Tokenizer used for the counts.
+    pub model: String,
+    /// Breakdown for each file, in path order.
+    pub files: Vec<FileBreakdown>,
+}
+
+impl RoleSummary {
+    /// Tokens per role summed over every file.
+    pub fn total(&self) -> TokenBreakdown {
+        let mut total = TokenBreakdown::new();
+        for file in &self.files {
+            total += &file.breakdown;
+        }
+        total
+    }
+}
+
+/// Transcript files under `dir`, in path order.
+///
+/// Files with a `json`, `txt`, `md`, `markdown` or `prompt` extension count
+/// as transcripts.
+///
+/// # Errors
+///
+/// Returns `AppError::Io` if part of the directory can't be walked, such as
+/// a subdirectory that can't be read.
+pub fn transcript_paths(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut paths = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(|e| AppError::Io(e.into()))?;
+        let is_transcript = entry.path().extension().is_some_and(|ext| {
+            matches!(
+                ext.to_string_lossy().to_lowercase().as_str(),
+                "json" | "txt" | "md" | "markdown" | "prompt"
+            )
+        });
+        if entry.file_type().is_file() && is_transcript {
+            paths.push(entry.into_path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Count the role breakdown of every transcript under `dir` with `count`.
+///
+/// Transcripts are found by [`transcript_paths`]; `count` parses and
+/// tokenizes each one.
+///
+/// # Errors
+///
+/// Returns `AppError` if the directory can't be walked, a file cannot be
+/// read or `count` fails.
+pub fn summarize_roles(
+    model: &str,
+    dir: &Path,
+    mut count: impl FnMut(&str) -> Result<TokenBreakdown, AppError>,
+) -> Result<RoleSummary, AppError> {
+    let paths = transcript_paths(dir)?;
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let content = InputSource::file(path.display().to_string(), "transcript").read(None)?;
+        let breakdown = count(&content)?;
+        files.push(FileBreakdown { path, breakdown });
+    }
+
+    Ok(RoleSummary {
+        model: model.to_string(),
+        files,
+    })
+}
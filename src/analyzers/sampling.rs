@@ -0,0 +1,225 @@
+/// Token estimates for large inputs from a random sample of chunks.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+use std::io::{self, Read};
+
+/// Target size of a sampled chunk in bytes; chunks end on a character boundary.
+pub const SAMPLE_CHUNK_BYTES: usize = 8 * 1024;
+
+/// Token estimate extrapolated from sampled chunks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledEstimate {
+    /// Model (tokenizer) used for the counts.
+    pub model: String,
+    /// Estimated tokens in the whole input.
+    pub tokens: usize,
+    /// Tokens counted in the sampled chunks.
+    pub sampled_tokens: usize,
+    /// Bytes in the sampled chunks.
+    pub sampled_bytes: usize,
+    /// Bytes in the whole input.
+    pub total_bytes: usize,
+    /// Number of chunks counted.
+    pub sampled_chunks: usize,
+    /// Number of chunks the input splits into.
+    pub total_chunks: usize,
+}
+
+impl SampledEstimate {
+    /// Whether every chunk was counted, making the estimate exact.
+    pub fn is_exact(&self) -> bool {
+        self.sampled_chunks == self.total_chunks
+    }
+}
+
+/// Chunks drawn at random from an input, with the size of the whole input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSample {
+    /// The sampled chunks, in input order.
+    pub chunks: Vec<String>,
+    /// Bytes in the whole input.
+    pub total_bytes: usize,
+    /// Number of chunks the input splits into.
+    pub total_chunks: usize,
+}
+
+impl ChunkSample {
+    /// Read `reader` in chunks of about [`SAMPLE_CHUNK_BYTES`] and keep
+    /// `sample` of them, chosen at random.
+    ///
+    /// Only the kept chunks are held, so memory use doesn't grow with the
+    /// input. Chunks are chosen with `rng`, so a generator with a fixed seed
+    /// keeps the same chunks on every run. An input with no more than
+    /// `sample` chunks is kept whole.
+    ///
+    /// # Errors
+    ///
+    /// Returns `io::ErrorKind::InvalidData` if the input isn't UTF-8, or the
+    /// reader's error if reading fails.
+    pub fn read<R: Read>(reader: R, sample: usize, rng: &mut fastrand::Rng) -> io::Result<Self> {
+        let mut reader = io::BufReader::new(reader);
+        // Reservoir sampling: each chunk replaces a kept one with falling odds
+        let mut kept: Vec<(usize, String)> = Vec::new();
+        let mut total_bytes = 0;
+        let mut total_chunks = 0;
+        while let Some(chunk) = next_chunk(&mut reader)? {
+            total_bytes += chunk.len();
+            if kept.len() < sample {
+                kept.push((total_chunks, chunk));
+            } else {
+                let slot = rng.usize(..=total_chunks);
+                if slot < sample {
+                    kept[slot] = (total_chunks, chunk);
+                }
+            }
+            total_chunks += 1;
+        }
+        kept.sort_unstable_by_key(|(index, _)| *index);
+
+        Ok(Self {
+            chunks: kept.into_iter().map(|(_, chunk)| chunk).collect(),
+            total_bytes,
+            total_chunks,
+        })
+    }
+}
+
+/// Read the next chunk of about [`SAMPLE_CHUNK_BYTES`], extended to the end
+/// of its last character; `None` at the end of the input.
+fn next_chunk(reader: &mut impl Read) -> io::Result<Option<String>> {
+    let mut bytes = Vec::with_capacity(SAMPLE_CHUNK_BYTES + 3);
+    reader
+        .by_ref()
+        .take(SAMPLE_CHUNK_BYTES as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+
+    // Finish a character cut off at the end of the chunk
+    let lead = bytes
+        .iter()
+        .rposition(|&byte| byte & 0xC0 != 0x80)
+        .unwrap_or(0);
+    let width = match bytes[lead] {
+        byte if byte >= 0xF0 => 4,
+        byte if byte >= 0xE0 => 3,
+        byte if byte >= 0xC0 => 2,
+        _ => 1,
+    };
+    let missing = (lead + width).saturating_sub(bytes.len());
+    reader.take(missing as u64).read_to_end(&mut bytes)?;
+
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Estimate the tokens in the sampled input with `tokenizer`.
+///
+/// The sampled chunks' tokens per byte are scaled up to the whole input,
+/// so every model counting the same sample is compared on the same text.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if a chunk cannot be tokenized.
+pub fn estimate_sampled(
+    tokenizer: &dyn Tokenizer,
+    sample: &ChunkSample,
+) -> Result<SampledEstimate, TokenizerError> {
+    let mut sampled_tokens = 0;
+    let mut sampled_bytes = 0;
+    for chunk in &sample.chunks {
+        sampled_tokens += tokenizer.count_tokens(chunk)?;
+        sampled_bytes += chunk.len();
+    }
+    let tokens = if sampled_bytes == 0 {
+        0
+    } else {
+        (sampled_tokens as f64 * sample.total_bytes as f64 / sampled_bytes as f64).round() as usize
+    };
+
+    Ok(SampledEstimate {
+        model: tokenizer.name().to_string(),
+        tokens,
+        sampled_tokens,
+        sampled_bytes,
+        total_bytes: sample.total_bytes,
+        sampled_chunks: sample.chunks.len(),
+        total_chunks: sample.total_chunks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::WordTokenizer;
+
+    fn corpus() -> String {
+        (0..5_000)
+            .map(|i| format!("line {} {}\n", i, "word ".repeat(i % 13)))
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_samples_repeat() {
+        let text = corpus();
+        let tokenizer = WordTokenizer::new();
+        let sample =
+            || ChunkSample::read(text.as_bytes(), 4, &mut fastrand::Rng::with_seed(42)).unwrap();
+        let first = estimate_sampled(&tokenizer, &sample()).unwrap();
+        let second = estimate_sampled(&tokenizer, &sample()).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.sampled_chunks, 4);
+        assert!(first.total_chunks > 4);
+        assert!(!first.is_exact());
+
+        let exact = tokenizer.count_tokens(&text).unwrap() as f64;
+        assert!((first.tokens as f64 - exact).abs() / exact < 0.2);
+    }
+
+    #[test]
+    fn test_small_input_is_counted_in_full() {
+        let sample =
+            ChunkSample::read("just a few words".as_bytes(), 8, &mut fastrand::Rng::new()).unwrap();
+        let estimate = estimate_sampled(&WordTokenizer::new(), &sample).unwrap();
+        assert!(estimate.is_exact());
+        assert_eq!(estimate.tokens, 4);
+    }
+
+    #[test]
+    fn test_chunks_end_on_char_boundaries() {
+        let text = format!("a{}", "é🦀".repeat(SAMPLE_CHUNK_BYTES / 2));
+        let sample =
+            ChunkSample::read(text.as_bytes(), usize::MAX, &mut fastrand::Rng::new()).unwrap();
+        assert_eq!(sample.chunks.concat(), text);
+        assert_eq!(sample.total_bytes, text.len());
+        assert!(sample.total_chunks > 1);
+        assert!(sample
+            .chunks
+            .iter()
+            .all(|chunk| chunk.len() <= SAMPLE_CHUNK_BYTES + 3));
+    }
+
+    #[test]
+    fn test_sample_keeps_chunks_in_input_order() {
+        let text: String = (0..2_000).map(|i| format!("{:08}\n", i)).collect();
+        let sample =
+            ChunkSample::read(text.as_bytes(), 3, &mut fastrand::Rng::with_seed(7)).unwrap();
+        assert_eq!(sample.chunks.len(), 3);
+        assert_eq!(sample.total_bytes, text.len());
+        let starts: Vec<usize> = sample
+            .chunks
+            .iter()
+            .map(|chunk| text.find(chunk.as_str()).unwrap())
+            .collect();
+        assert!(starts.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_an_error() {
+        let error = ChunkSample::read(&[b'a', 0xFF][..], 4, &mut fastrand::Rng::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,10 +1,12 @@
 /// Directory scanner for prompt files.
 use crate::analyzers::duplicates::normalize_content;
-use crate::analyzers::types::{LibraryInsights, PromptAnalysis};
+use crate::analyzers::types::{LibraryInsights, PromptAnalysis, ScanProgress};
 use crate::error::AppError;
+use crate::models::cost::compute_cost;
+use crate::models::pricing::ModelPricing;
 use crate::models::ModelRegistry;
 use crate::parsers::{JsonParser, Parser, TextParser};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 /// File format detection.
@@ -37,44 +39,74 @@ impl PromptScanner {
 
     /// Scan a directory and analyze all prompt files.
     pub fn scan_directory(&self, dir: &Path) -> Result<Vec<PromptAnalysis>, AppError> {
+        self.scan_directory_with_progress(dir, |_| {})
+    }
+
+    /// Scan a directory, invoking `on_progress` after each candidate file.
+    ///
+    /// Candidate files are collected up front so the callback always knows
+    /// the total, which lets callers render a determinate progress bar.
+    pub fn scan_directory_with_progress<F>(
+        &self,
+        dir: &Path,
+        mut on_progress: F,
+    ) -> Result<Vec<PromptAnalysis>, AppError>
+    where
+        F: FnMut(&ScanProgress),
+    {
+        let files = Self::collect_prompt_files(dir);
         let mut analyses = Vec::new();
+        let mut progress = ScanProgress {
+            processed: 0,
+            total: files.len(),
+            tokens: 0,
+        };
 
-        for entry in WalkDir::new(dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                if matches!(
-                    ext_lower.as_str(),
-                    "txt" | "md" | "json" | "yaml" | "yml" | "prompt"
-                ) {
-                    match self.analyze_file(path) {
-                        Ok(Some(analysis)) => analyses.push(analysis),
-                        Ok(None) => {
-                            // File format not supported, skip silently
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Failed to analyze {}: {}", path.display(), e);
-                        }
-                    }
+        for path in &files {
+            match self.analyze_file(path) {
+                Ok(Some(analysis)) => {
+                    progress.tokens += analysis.token_count;
+                    analyses.push(analysis);
+                }
+                Ok(None) => {
+                    // File format not supported, skip silently
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to analyze {}: {}", path.display(), e);
                 }
             }
+            progress.processed += 1;
+            on_progress(&progress);
         }
 
         Ok(analyses)
     }
 
+    /// Collect all files under `dir` with a recognised prompt extension.
+    fn collect_prompt_files(dir: &Path) -> Vec<PathBuf> {
+        WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path().extension().is_some_and(|ext| {
+                    matches!(
+                        ext.to_string_lossy().to_lowercase().as_str(),
+                        "txt" | "md" | "json" | "yaml" | "yml" | "prompt"
+                    )
+                })
+            })
+            .map(|e| e.into_path())
+            .collect()
+    }
+
     /// Analyze a single file and return its analysis.
     fn analyze_file(&self, path: &Path) -> Result<Option<PromptAnalysis>, AppError> {
         let content = std::fs::read_to_string(path).map_err(|e| {
-            AppError::Io(std::io::Error::other(format!(
-                "Failed to read {}: {}",
-                path.display(),
-                e
-            )))
+            AppError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to read {}: {}", path.display(), e),
+            ))
         })?;
 
         let format = Self::detect_format(path, &content);
@@ -94,16 +126,18 @@ impl PromptScanner {
 
         // Calculate costs
         let pricing = self.registry.pricing_for(&self.model);
-        let (input_cost, output_cost) = if let Some((input_rate, output_rate)) = pricing {
-            (
-                (total_tokens as f64 / 1000.0) * input_rate,
+        let cost = pricing
+            .map(|(input, output)| {
+                let pricing = ModelPricing {
+                    input,
+                    output,
+                    typical_output_ratio: None,
+                };
                 // Estimate 100 output tokens
-                (100.0 / 1000.0) * output_rate,
-            )
-        } else {
-            (0.0, 0.0)
-        };
-        let total_cost = input_cost + output_cost;
+                compute_cost(&pricing, total_tokens, 100, 0)
+            })
+            .unwrap_or_default();
+        let (input_cost, output_cost, total_cost) = (cost.input, cost.output, cost.total);
 
         // Check context limits
         let exceeds_limit = if let Some(limit) = self.context_limit {
@@ -140,12 +174,11 @@ impl PromptScanner {
         if let Some(ext) = path.extension() {
             let ext_lower = ext.to_string_lossy().to_lowercase();
             match ext_lower.as_str() {
-                "json" => {
+                "json"
                     if content.trim_start().starts_with('{')
-                        || content.trim_start().starts_with('[')
-                    {
-                        return FileFormat::Json;
-                    }
+                        || content.trim_start().starts_with('[') =>
+                {
+                    return FileFormat::Json;
                 }
                 "md" | "markdown" => return FileFormat::Markdown,
                 "yaml" | "yml" => return FileFormat::Yaml,
@@ -277,7 +310,6 @@ mod tests {
     use super::*;
     use crate::analyzers::types::PromptAnalysis;
     use crate::parsers::Message;
-    use std::path::PathBuf;
 
     #[test]
     fn test_calculate_distribution() {
@@ -315,6 +347,8 @@ mod tests {
             messages: vec![Message {
                 role: "user".to_string(),
                 content: "test".to_string(),
+                name: None,
+                tool_call_id: None,
             }],
             token_count: tokens,
             input_cost: 0.0,
@@ -0,0 +1,359 @@
+/// Per-message diff of two transcripts, aligned by message key.
+use crate::error::TokenizerError;
+use crate::parsers::Message;
+use crate::tokenizers::Tokenizer;
+use crate::utils::images::ImageTokens;
+
+/// Largest `before × after` table aligned message by message; longer
+/// transcripts fall back to aligning by index.
+const MAX_ALIGNMENT_CELLS: usize = 4_000_000;
+
+/// How a message changed between two transcripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Same key and content on both sides.
+    Unchanged,
+    /// Same key, different content.
+    Changed,
+    /// Only in the modified transcript.
+    Added,
+    /// Only in the original transcript.
+    Removed,
+}
+
+impl ChangeKind {
+    /// Lowercase name, e.g. `added`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Unchanged => "unchanged",
+            ChangeKind::Changed => "changed",
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+        }
+    }
+}
+
+/// A message of the original transcript paired with its counterpart in the
+/// modified one, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    /// How the message changed.
+    pub kind: ChangeKind,
+    /// Index in the original transcript, unless the message was added.
+    pub before: Option<usize>,
+    /// Index in the modified transcript, unless the message was removed.
+    pub after: Option<usize>,
+}
+
+/// Token change of one aligned message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageDelta {
+    /// How the message changed.
+    pub kind: ChangeKind,
+    /// The message's participant name, or its role if it has none.
+    pub key: String,
+    /// Content tokens in the original, unless the message was added.
+    pub before: Option<usize>,
+    /// Content tokens in the modified transcript, unless it was removed.
+    pub after: Option<usize>,
+}
+
+impl MessageDelta {
+    /// Signed token change of the message.
+    pub fn difference(&self) -> i64 {
+        self.after.unwrap_or(0) as i64 - self.before.unwrap_or(0) as i64
+    }
+}
+
+/// Key messages are matched on: the participant name, or the role.
+pub fn message_key(message: &Message) -> &str {
+    message.name.as_deref().unwrap_or(&message.role)
+}
+
+/// Align two transcripts message by message.
+///
+/// Messages with the same key and content are matched first, keeping their
+/// order, so a message inserted or removed anywhere shows up as just that
+/// change. Between two matches, the remaining messages are paired in order
+/// by key as `Changed`; what is left over is `Added` or `Removed`.
+///
+/// Transcripts are aligned by index instead when the keys are ambiguous,
+/// i.e. a key repeats between two matches on one side while the other side
+/// has it too, so it isn't clear which message was edited. Very long
+/// transcripts are aligned by index as well.
+pub fn align(before: &[Message], after: &[Message]) -> Vec<Alignment> {
+    if before.len().saturating_mul(after.len()) > MAX_ALIGNMENT_CELLS {
+        return align_by_index(before, after);
+    }
+
+    let same =
+        |b: &Message, a: &Message| message_key(b) == message_key(a) && b.content == a.content;
+
+    // Longest common subsequence of identical messages, from the back
+    let width = after.len() + 1;
+    let mut lengths = vec![0usize; (before.len() + 1) * width];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i * width + j] = if same(&before[i], &after[j]) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut matches = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() && j < after.len() {
+        if same(&before[i], &after[j]) {
+            matches.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    // The unmatched runs between matches, ending with the one after the last
+    let mut gaps = Vec::with_capacity(matches.len() + 1);
+    let (mut gap_before, mut gap_after) = (0, 0);
+    for &(i, j) in matches.iter().chain([&(before.len(), after.len())]) {
+        gaps.push((gap_before..i, gap_after..j));
+        (gap_before, gap_after) = (i + 1, j + 1);
+    }
+    if gaps
+        .iter()
+        .any(|(b, a)| is_ambiguous(&before[b.clone()], &after[a.clone()]))
+    {
+        return align_by_index(before, after);
+    }
+
+    let mut alignment = Vec::new();
+    for (index, (gap_before, gap_after)) in gaps.into_iter().enumerate() {
+        pair_gap(before, after, gap_before, gap_after, &mut alignment);
+        if let Some(&(i, j)) = matches.get(index) {
+            alignment.push(Alignment {
+                kind: ChangeKind::Unchanged,
+                before: Some(i),
+                after: Some(j),
+            });
+        }
+    }
+    alignment
+}
+
+/// Whether pairing two unmatched runs by key has more than one answer: a
+/// key found on both sides repeats on at least one of them.
+fn is_ambiguous(before: &[Message], after: &[Message]) -> bool {
+    let count =
+        |messages: &[Message], key: &str| messages.iter().filter(|m| message_key(m) == key).count();
+    before.iter().map(message_key).any(|key| {
+        let (b, a) = (count(before, key), count(after, key));
+        a > 0 && (a > 1 || b > 1)
+    })
+}
+
+/// Pair the unmatched messages between two matches in order by key.
+fn pair_gap(
+    before: &[Message],
+    after: &[Message],
+    before_range: std::ops::Range<usize>,
+    after_range: std::ops::Range<usize>,
+    alignment: &mut Vec<Alignment>,
+) {
+    let removed = |index| Alignment {
+        kind: ChangeKind::Removed,
+        before: Some(index),
+        after: None,
+    };
+
+    let mut next = before_range.start;
+    for j in after_range {
+        let key = message_key(&after[j]);
+        match (next..before_range.end).find(|&i| message_key(&before[i]) == key) {
+            Some(i) => {
+                alignment.extend((next..i).map(removed));
+                alignment.push(Alignment {
+                    kind: ChangeKind::Changed,
+                    before: Some(i),
+                    after: Some(j),
+                });
+                next = i + 1;
+            }
+            None => alignment.push(Alignment {
+                kind: ChangeKind::Added,
+                before: None,
+                after: Some(j),
+            }),
+        }
+    }
+    alignment.extend((next..before_range.end).map(removed));
+}
+
+/// Pair messages at the same index.
+fn align_by_index(before: &[Message], after: &[Message]) -> Vec<Alignment> {
+    (0..before.len().max(after.len()))
+        .map(|index| {
+            let (b, a) = (before.get(index), after.get(index));
+            let kind = match (b, a) {
+                (Some(b), Some(a)) if b.content == a.content && b.role == a.role => {
+                    ChangeKind::Unchanged
+                }
+                (Some(_), Some(_)) => ChangeKind::Changed,
+                (None, _) => ChangeKind::Added,
+                (_, None) => ChangeKind::Removed,
+            };
+            Alignment {
+                kind,
+                before: b.map(|_| index),
+                after: a.map(|_| index),
+            }
+        })
+        .collect()
+}
+
+/// Align two transcripts and count each message's content tokens.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if a message cannot be tokenized.
+pub fn diff_messages(
+    tokenizer: &dyn Tokenizer,
+    before: &[Message],
+    after: &[Message],
+    images: ImageTokens,
+) -> Result<Vec<MessageDelta>, TokenizerError> {
+    let count = |messages: &[Message], index: Option<usize>| {
+        index
+            .map(|index| images.count(tokenizer, &messages[index].content))
+            .transpose()
+    };
+
+    align(before, after)
+        .into_iter()
+        .map(|alignment| {
+            let message = match (alignment.after, alignment.before) {
+                (Some(index), _) => &after[index],
+                (None, Some(index)) => &before[index],
+                (None, None) => unreachable!("an alignment has at least one side"),
+            };
+            Ok(MessageDelta {
+                kind: alignment.kind,
+                key: message_key(message).to_string(),
+                before: count(before, alignment.before)?,
+                after: count(after, alignment.after)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::CharTokenizer;
+
+    fn message(role: &str, name: Option<&str>, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            name: name.map(str::to_string),
+            tool_call_id: None,
+        }
+    }
+
+    fn kinds(alignment: &[Alignment]) -> Vec<ChangeKind> {
+        alignment.iter().map(|a| a.kind).collect()
+    }
+
+    #[test]
+    fn test_inserted_message_is_the_only_change() {
+        let before = vec![
+            message("system", None, "Be brief."),
+            message("user", None, "Hi"),
+            message("assistant", None, "Hello!"),
+            message("user", None, "Bye"),
+        ];
+        let mut after = before.clone();
+        after.insert(2, message("user", None, "Also, what time is it?"));
+
+        let deltas = diff_messages(
+            &CharTokenizer::new(),
+            &before,
+            &after,
+            ImageTokens::default(),
+        )
+        .unwrap();
+        let added: Vec<&MessageDelta> = deltas
+            .iter()
+            .filter(|d| d.kind != ChangeKind::Unchanged)
+            .collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].kind, ChangeKind::Added);
+        assert_eq!(added[0].key, "user");
+        assert_eq!((added[0].before, added[0].after), (None, Some(22)));
+        assert_eq!(deltas.len(), 5);
+    }
+
+    #[test]
+    fn test_edits_pair_by_name() {
+        let before = vec![
+            message("user", Some("alice"), "Cats"),
+            message("user", Some("bob"), "Dogs"),
+            message("assistant", None, "Both"),
+        ];
+        let after = vec![
+            message("user", Some("bob"), "Dogs, obviously"),
+            message("assistant", None, "Both"),
+        ];
+
+        let alignment = align(&before, &after);
+        assert_eq!(
+            kinds(&alignment),
+            [
+                ChangeKind::Removed,
+                ChangeKind::Changed,
+                ChangeKind::Unchanged
+            ]
+        );
+        assert_eq!(
+            (alignment[1].before, alignment[1].after),
+            (Some(1), Some(0))
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_keys_align_by_index() {
+        // Was "Hi" edited into "Hello" or "Hey", and which one is new?
+        let before = vec![
+            message("system", None, "Be brief."),
+            message("user", None, "Hi"),
+        ];
+        let after = vec![
+            message("system", None, "Be brief."),
+            message("user", None, "Hello"),
+            message("user", None, "Hey"),
+        ];
+        let alignment = align(&before, &after);
+        assert_eq!(alignment, align_by_index(&before, &after));
+        assert_eq!(
+            kinds(&alignment),
+            [
+                ChangeKind::Unchanged,
+                ChangeKind::Changed,
+                ChangeKind::Added
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_alignment_fallback() {
+        let before = vec![message("user", None, "a"), message("user", None, "b")];
+        let after = vec![message("user", None, "x")];
+        assert_eq!(
+            kinds(&align_by_index(&before, &after)),
+            [ChangeKind::Changed, ChangeKind::Removed]
+        );
+    }
+}
@@ -70,3 +70,54 @@ impl Default for LibraryInsights {
         Self::new()
     }
 }
+
+/// Progress snapshot reported while scanning a directory.
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(dead_code)] // Fields are read by the progress bar when the `progress` feature is enabled
+pub struct ScanProgress {
+    /// Number of candidate files processed so far
+    pub processed: usize,
+    /// Total number of candidate files found
+    pub total: usize,
+    /// Running token sum across successfully analyzed files
+    pub tokens: usize,
+}
+
+/// Token count and cost for one request in a Batch API file.
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    /// The request's `custom_id`
+    pub custom_id: String,
+    /// Model used for tokenization and pricing
+    pub model: String,
+    /// Input tokens for the request
+    pub tokens: usize,
+    /// Projected cost after any batch discount (if pricing is known)
+    pub cost: Option<f64>,
+    /// Whether the batch discount was applied
+    pub discounted: bool,
+}
+
+/// Aggregated estimate for a Batch API file.
+#[derive(Debug, Clone, Default)]
+pub struct BatchSummary {
+    /// Per-request results, in file order
+    pub items: Vec<BatchItem>,
+    /// Total input tokens across the batch
+    pub total_tokens: usize,
+    /// Total projected cost (only if every request could be priced)
+    pub total_cost: Option<f64>,
+    /// System prompts repeated across requests (only when requested)
+    pub shared_system: Option<Vec<SharedSystemPrompt>>,
+}
+
+/// A system prompt sent verbatim by several requests in a Batch API file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedSystemPrompt {
+    /// `custom_id`s of the requests sharing the prompt, in file order
+    pub custom_ids: Vec<String>,
+    /// Tokens of the prompt, as counted for the first request using it
+    pub tokens: usize,
+    /// Tokens sent again by every request after the first
+    pub redundant_tokens: usize,
+}
@@ -0,0 +1,89 @@
+/// Advisory checks for trimmable whitespace in message content.
+use crate::error::TokenizerError;
+use crate::parsers::Message;
+use crate::tokenizers::Tokenizer;
+
+/// Whitespace found in a message that could likely be trimmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhitespaceIssue {
+    /// Zero-based index of the message.
+    pub index: usize,
+    /// Role of the message.
+    pub role: String,
+    /// Some line ends in spaces or tabs.
+    pub trailing_whitespace: bool,
+    /// The content starts or ends with a newline.
+    pub edge_newlines: bool,
+    /// Some line is indented with tabs.
+    pub tab_indentation: bool,
+    /// Tokens saved by trimming the whitespace.
+    pub savings: usize,
+}
+
+impl WhitespaceIssue {
+    /// Short description of the issues found, e.g. "trailing whitespace".
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.trailing_whitespace {
+            parts.push("trailing whitespace");
+        }
+        if self.edge_newlines {
+            parts.push("leading/trailing newlines");
+        }
+        if self.tab_indentation {
+            parts.push("tab indentation");
+        }
+        parts.join(", ")
+    }
+}
+
+/// Content with trailing whitespace, edge newlines and tab indentation removed.
+pub fn trim_whitespace(content: &str) -> String {
+    content
+        .trim_matches(|c| c == '\n' || c == '\r')
+        .lines()
+        .map(|line| line.trim_start_matches('\t').trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find messages whose whitespace could be trimmed.
+///
+/// This is advisory only: content is never modified. Savings are measured with
+/// `tokenizer` by counting the content before and after [`trim_whitespace`].
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if a message cannot be tokenized.
+pub fn find_whitespace_issues(
+    tokenizer: &dyn Tokenizer,
+    messages: &[Message],
+) -> Result<Vec<WhitespaceIssue>, TokenizerError> {
+    let mut issues = Vec::new();
+
+    for (index, message) in messages.iter().enumerate() {
+        let content = &message.content;
+        let trailing_whitespace = content
+            .lines()
+            .any(|line| line.ends_with(' ') || line.ends_with('\t'));
+        let edge_newlines = content.starts_with(['\n', '\r']) || content.ends_with('\n');
+        let tab_indentation = content.lines().any(|line| line.starts_with('\t'));
+
+        if !(trailing_whitespace || edge_newlines || tab_indentation) {
+            continue;
+        }
+
+        let before = tokenizer.count_tokens(content)?;
+        let after = tokenizer.count_tokens(&trim_whitespace(content))?;
+        issues.push(WhitespaceIssue {
+            index,
+            role: message.role.clone(),
+            trailing_whitespace,
+            edge_newlines,
+            tab_indentation,
+            savings: before.saturating_sub(after),
+        });
+    }
+
+    Ok(issues)
+}
@@ -1,15 +1,49 @@
-use crate::error::AppError;
+use crate::analyzers::budget::parse_section;
+use crate::analyzers::transcript_diff::{diff_messages, ChangeKind, MessageDelta};
+use crate::error::{AppError, ModelError};
+#[cfg(feature = "load-test")]
+use crate::http::client::ApiKeys;
+use crate::models::break_even::break_even_points;
+use crate::models::cost::compute_cost;
+use crate::models::pricing::ModelPricing;
+use crate::models::projection::{parse_throughput, ResponseProjection};
+use crate::models::registry::parse_deployment;
 use crate::models::ModelRegistry;
 #[cfg(feature = "markdown")]
 use crate::output::MarkdownFormatter;
-use crate::output::{Formatter, JsonFormatter, TextFormatter, TokenBreakdown, TokenResult};
-use crate::parsers::{JsonParser, Parser as InputParser, TextParser};
+use crate::output::{
+    Formatter, JsonFormatter, ModelFailure, ReportMetadata, ResultAggregator, TextFormatter,
+    TokenBreakdown, TokenResult, WarningKind, Warnings,
+};
+use crate::parsers::completion::completion_texts;
+use crate::parsers::roles::{self, parse_role_alias, StrictRolesParser};
+use crate::parsers::{
+    BatchParser, InputFormat, JsonParser, JsonPaths, LimitAction, MessageLimit, RoleMap,
+};
+use crate::tokenizers::divergence::{find_divergence, Divergence};
 use crate::tokenizers::Tokenizer;
-#[cfg(feature = "markdown")]
-use crate::utils::markdown;
+use crate::tokenizers::{
+    chat, verify_roundtrip, ChatCounter, ChatOverhead, ChatTemplate, RoundTrip,
+};
+use crate::utils::color::{self, ColorChoice};
+use crate::utils::comments::{self, CommentStyle};
+use crate::utils::currency::{parse_currency_code, parse_fx_rate, parse_markup, Currency};
+use crate::utils::frontmatter;
+use crate::utils::images::{self, ImageTokens, IMAGE_TOKEN_ESTIMATE};
+use crate::utils::input::InputSource;
+use crate::utils::line_endings::{self, LineEndings};
+use crate::utils::numbers::NumberLocale;
+use crate::utils::parallel;
+use crate::utils::preview::{preview, DEFAULT_PREVIEW_CHARS};
+use crate::utils::unicode::UnicodeNormalization;
 /// CLI argument parsing and command execution.
-use clap::{Parser, Subcommand, ValueEnum};
-use std::io::{self, Read};
+use clap::builder::ArgPredicate;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
 #[cfg(feature = "watch")]
 use std::path::PathBuf;
 #[cfg(feature = "watch")]
@@ -25,195 +59,89 @@ pub struct Cli {
     pub command: Option<Command>,
 
     // Legacy flat arguments for backward compatibility
-    /// Input file path (use '-' for stdin or omit for direct text input)
-    #[arg(value_name = "FILE|TEXT")]
-    pub input: Option<String>,
-
-    /// Model to use for tokenization (e.g., gpt-4, gpt-3.5-turbo)
-    #[arg(short, long)]
-    pub model: Option<String>,
-
-    /// Compare multiple models
-    #[arg(short, long, num_args = 1..)]
-    pub compare: Vec<String>,
-
-    /// Show token breakdown by role (system/user/assistant)
-    #[arg(short, long)]
-    pub breakdown: bool,
-
-    /// Output format
-    #[arg(short, long, value_enum, default_value = "text")]
-    pub format: OutputFormat,
-
-    /// Show pricing information
-    #[arg(short, long)]
-    pub price: bool,
-
-    /// Strip markdown formatting to show token savings
-    #[arg(long)]
-    #[cfg(feature = "markdown")]
-    pub minify: bool,
-
-    /// Compare two prompts and show token differences
-    #[arg(long)]
-    pub diff: Option<String>,
+    #[command(flatten)]
+    pub estimate: EstimateArgs,
 
-    /// Watch file for changes and re-run automatically
-    #[arg(short, long)]
-    #[cfg(feature = "watch")]
-    pub watch: bool,
-
-    /// Path to a custom pricing configuration (TOML).
+    /// Path or http(s) URL of a custom pricing configuration (TOML).
     #[arg(long, value_name = "FILE", global = true)]
     pub pricing_file: Option<String>,
+
+    /// When to use ANSI colors in human-readable output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        global = true
+    )]
+    pub color: ColorChoice,
+
+    /// Disable ANSI colors (same as setting NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
 }
 
 /// Available commands.
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Estimate tokens and costs (default behavior)
-    Estimate {
-        /// Input file path (use '-' for stdin or omit for direct text input)
-        #[arg(value_name = "FILE|TEXT")]
-        input: Option<String>,
-
-        /// Model to use for tokenization (e.g., gpt-4, gpt-3.5-turbo)
-        #[arg(short, long)]
-        model: Option<String>,
-
-        /// Compare multiple models
-        #[arg(short, long, num_args = 1..)]
-        compare: Vec<String>,
-
-        /// Show token breakdown by role (system/user/assistant)
-        #[arg(short, long)]
-        breakdown: bool,
-
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "text")]
-        format: OutputFormat,
-
-        /// Show pricing information
-        #[arg(short, long)]
-        price: bool,
-
-        /// Strip markdown formatting to show token savings
-        #[arg(long)]
-        #[cfg(feature = "markdown")]
-        minify: bool,
-
-        /// Compare two prompts and show token differences
-        #[arg(long)]
-        diff: Option<String>,
-
-        /// Watch file for changes and re-run automatically
-        #[arg(short, long)]
-        #[cfg(feature = "watch")]
-        watch: bool,
-    },
+    Estimate(Box<EstimateArgs>),
 
     /// Run load tests against LLM APIs
     #[cfg(feature = "load-test")]
     #[command(name = "load-test")]
-    LoadTest {
-        /// Model to use (e.g., gpt-4, claude-2)
-        #[arg(short, long)]
-        model: String,
-
-        /// API endpoint URL (optional, uses provider default if not specified)
-        #[arg(long)]
-        endpoint: Option<String>,
-
-        /// API key (or use environment variable)
-        #[arg(long)]
-        api_key: Option<String>,
-
-        /// OpenAI API key (alternative to --api-key)
-        #[arg(long)]
-        openai_api_key: Option<String>,
-
-        /// Anthropic API key
-        #[arg(long)]
-        anthropic_api_key: Option<String>,
-
-        /// OpenRouter API key
-        #[arg(long)]
-        openrouter_api_key: Option<String>,
-
-        /// Target provider (defaults to automatic detection)
-        #[arg(long, value_enum)]
-        provider: Option<Provider>,
-
-        /// Number of concurrent requests
-        #[arg(short, long, default_value = "10")]
-        concurrency: usize,
-
-        /// Total number of requests to make
-        #[arg(short, long)]
-        runs: usize,
+    LoadTest(Box<LoadTestArgs>),
 
-        /// Prompt file (JSONL, YAML, or text) or use stdin
-        #[arg(short, long)]
-        prompt_file: Option<String>,
-
-        /// Think time between requests (e.g., "250-750ms" or "500ms")
-        #[arg(long)]
-        think_time: Option<String>,
-
-        /// Retry count on failure
-        #[arg(long, default_value = "3")]
-        retry: u32,
-
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "text")]
-        output_format: LoadTestOutputFormat,
+    /// Analyze a prompt library directory
+    #[command(name = "analyze-prompts")]
+    Analyze(AnalyzeArgs),
+}
 
-        /// Estimate costs (dry run without making API calls)
-        #[arg(long)]
-        dry_run: bool,
+/// Analyze-prompts command arguments.
+#[derive(clap::Args, Debug, Clone)]
+pub struct AnalyzeArgs {
+    /// Directory to analyze
+    #[arg(value_name = "DIR")]
+    folder: String,
 
-        /// Maximum cost threshold (stop if exceeded)
-        #[arg(long)]
-        max_cost: Option<f64>,
+    /// Model to use for tokenization (default: gpt-4)
+    #[arg(short, long, default_value = "gpt-4")]
+    model: String,
 
-        /// Show cost estimation
-        #[arg(short, long)]
-        estimate_cost: bool,
-    },
+    /// Top N most expensive prompts to show
+    #[arg(long, default_value = "10")]
+    top_n: usize,
 
-    /// Analyze a prompt library directory
-    #[command(name = "analyze-prompts")]
-    Analyze {
-        /// Directory to analyze
-        #[arg(value_name = "DIR")]
-        folder: String,
+    /// Monthly invocation count for cost projection
+    #[arg(long, default_value = "1000")]
+    monthly_invocations: u64,
 
-        /// Model to use for tokenization (default: gpt-4)
-        #[arg(short, long, default_value = "gpt-4")]
-        model: String,
+    /// Model context window limit to check against
+    #[arg(long)]
+    context_limit: Option<usize>,
 
-        /// Top N most expensive prompts to show
-        #[arg(long, default_value = "10")]
-        top_n: usize,
+    /// Output format (or set TOKUIN_FORMAT)
+    #[arg(short, long, value_parser = OutputFormatParser, default_value = "text", env = "TOKUIN_FORMAT")]
+    format: OutputFormat,
 
-        /// Monthly invocation count for cost projection
-        #[arg(long, default_value = "1000")]
-        monthly_invocations: u64,
+    /// Suppress progress and status messages on stderr
+    #[arg(short, long)]
+    quiet: bool,
 
-        /// Model context window limit to check against
-        #[arg(long)]
-        context_limit: Option<usize>,
+    /// Show costs in this currency (ISO code such as EUR; requires --fx-rate unless USD)
+    #[arg(long, value_name = "CODE", value_parser = parse_currency_code)]
+    currency: Option<String>,
 
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "text")]
-        format: OutputFormat,
-    },
+    /// Exchange rate from USD to --currency, in units per dollar
+    #[arg(long, value_name = "RATE", value_parser = parse_fx_rate, requires = "currency")]
+    fx_rate: Option<f64>,
 }
 
 /// Output format options.
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, Default, ValueEnum)]
 pub enum OutputFormat {
     /// Human-readable text output
+    #[default]
     Text,
     /// JSON output for scripting
     Json,
@@ -222,6 +150,84 @@ pub enum OutputFormat {
     Markdown,
 }
 
+/// Parser for `--format` that names the cargo feature a format needs when
+/// it isn't compiled in, instead of clap's generic invalid value error.
+#[derive(Debug, Clone, Copy)]
+struct OutputFormatParser;
+
+impl clap::builder::TypedValueParser for OutputFormatParser {
+    type Value = OutputFormat;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<OutputFormat, clap::Error> {
+        clap::builder::EnumValueParser::<OutputFormat>::new()
+            .parse_ref(cmd, arg, value)
+            .map_err(|e| match value.to_str().and_then(format_feature) {
+                Some(feature) => clap::Error::raw(
+                    clap::error::ErrorKind::InvalidValue,
+                    format!(
+                        "{} output requires building with the `{}` feature\n",
+                        value.to_string_lossy(),
+                        feature
+                    ),
+                )
+                .with_cmd(cmd),
+                None => e,
+            })
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        Some(Box::new(
+            OutputFormat::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value),
+        ))
+    }
+}
+
+/// The cargo feature `format` needs, if it names a format compiled out of
+/// this build.
+fn format_feature(format: &str) -> Option<&'static str> {
+    match format {
+        "markdown" if !cfg!(feature = "markdown") => Some("markdown"),
+        _ => None,
+    }
+}
+
+/// What the `--breakdown` groups tokens by.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum BreakdownBy {
+    /// System, user and assistant totals
+    #[default]
+    Role,
+    /// One total per participant `name`; unnamed messages count under their role
+    Name,
+}
+
+/// Provider role framing counted as chat overhead.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum FramingStyle {
+    /// Fixed per-message and reply-priming tokens (OpenAI chat)
+    Openai,
+    /// `Human:`/`Assistant:` turn markers (Claude)
+    Anthropic,
+}
+
+impl FramingStyle {
+    fn overhead(self) -> ChatOverhead {
+        match self {
+            FramingStyle::Openai => ChatOverhead::OPENAI,
+            FramingStyle::Anthropic => ChatOverhead::ANTHROPIC,
+        }
+    }
+}
+
 /// Load test output format options.
 #[cfg(feature = "load-test")]
 #[derive(Debug, Clone, ValueEnum)]
@@ -261,121 +267,88 @@ impl Provider {
 }
 
 impl Cli {
+    /// Whether errors should go unreported on stderr (`--quiet`).
+    pub fn quiet(&self) -> bool {
+        match &self.command {
+            Some(Command::Estimate(args)) => args.quiet || self.estimate.quiet,
+            Some(_) => false,
+            None => self.estimate.quiet,
+        }
+    }
+
+    /// Check flag combinations clap can't express on its own.
+    ///
+    /// `--canonical` makes clap default `--format` to JSON, so any other
+    /// format here was given explicitly, on the command line or in
+    /// `TOKUIN_FORMAT`.
+    pub fn check_conflicts(&self) -> Result<(), clap::Error> {
+        let subcommand = match &self.command {
+            Some(Command::Estimate(args)) => Some(&**args),
+            _ => None,
+        };
+        for args in std::iter::once(&self.estimate).chain(subcommand) {
+            if args.canonical && !matches!(args.format, OutputFormat::Json) {
+                let format = args
+                    .format
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_string());
+                return Err(Self::command().error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    format!(
+                        "the argument '--canonical' cannot be used with '--format {}'",
+                        format.unwrap_or_default()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Execute the CLI command.
     pub fn run(self) -> Result<(), AppError> {
+        let color = color::stdout_color_enabled(self.color, self.no_color);
+
         match self.command {
-            Some(Command::Estimate {
-                input,
-                model,
-                compare,
-                breakdown,
-                format,
-                price,
-                #[cfg(feature = "markdown")]
-                minify,
-                diff,
-                #[cfg(feature = "watch")]
-                watch,
-            }) => {
-                // Use subcommand args, but fall back to top-level args if not provided
-                let estimate_args = EstimateArgs {
-                    input: input.or(self.input),
-                    model: model.or(self.model),
-                    compare: if !compare.is_empty() {
-                        compare
-                    } else {
-                        self.compare
-                    },
-                    breakdown: breakdown || self.breakdown,
-                    format,
-                    price: price || self.price,
-                    #[cfg(feature = "markdown")]
-                    minify: minify || self.minify,
-                    diff: diff.or(self.diff),
-                    #[cfg(feature = "watch")]
-                    watch: watch || self.watch,
-                    pricing_file: self.pricing_file.clone(),
-                };
-                Self::run_estimate(estimate_args)
-            }
+            Some(Command::Estimate(args)) => Self::run_estimate(EstimateArgs {
+                color,
+                pricing_file: self.pricing_file,
+                // Flags given after `estimate` win over those given before it
+                ..args.or(self.estimate)
+            }),
             #[cfg(feature = "load-test")]
-            Some(Command::LoadTest {
-                model,
-                endpoint,
-                api_key,
-                openai_api_key,
-                anthropic_api_key,
-                openrouter_api_key,
-                provider,
-                concurrency,
-                runs,
-                prompt_file,
-                think_time,
-                retry,
-                output_format,
-                dry_run,
-                max_cost,
-                estimate_cost,
-            }) => {
-                let load_args = LoadTestArgs {
-                    model,
-                    endpoint,
-                    api_key,
-                    openai_api_key,
-                    anthropic_api_key,
-                    openrouter_api_key,
-                    provider,
-                    concurrency,
-                    runs,
-                    prompt_file,
-                    think_time,
-                    retry,
-                    output_format,
-                    dry_run,
-                    max_cost,
-                    estimate_cost,
-                    pricing_file: self.pricing_file.clone(),
-                };
-                Self::run_load_test(load_args)
-            }
-            Some(Command::Analyze {
-                folder,
-                model,
-                top_n,
-                monthly_invocations,
-                context_limit,
-                format,
-            }) => Self::run_analyze(
-                folder,
-                model,
-                top_n,
-                monthly_invocations,
-                context_limit,
-                format,
-            ),
-            None => {
-                // Backward compatibility: use flat structure
-                let estimate_args = EstimateArgs {
-                    input: self.input,
-                    model: self.model,
-                    compare: self.compare,
-                    breakdown: self.breakdown,
-                    format: self.format,
-                    price: self.price,
-                    #[cfg(feature = "markdown")]
-                    minify: self.minify,
-                    diff: self.diff,
-                    #[cfg(feature = "watch")]
-                    watch: self.watch,
-                    pricing_file: self.pricing_file,
-                };
-                Self::run_estimate(estimate_args)
-            }
+            Some(Command::LoadTest(args)) => Self::run_load_test(LoadTestArgs {
+                pricing_file: self.pricing_file,
+                ..*args
+            }),
+            Some(Command::Analyze(args)) => Self::run_analyze(args),
+            None => Self::run_estimate(EstimateArgs {
+                color,
+                pricing_file: self.pricing_file,
+                ..self.estimate
+            }),
         }
     }
 
     /// Run estimate command (existing functionality).
     fn run_estimate(args: EstimateArgs) -> Result<(), AppError> {
+        let quiet = args.quiet;
+        let mut warnings = Warnings::new();
+        let result = Self::estimate(args, &mut warnings);
+        warnings.flush(quiet);
+        result
+    }
+
+    /// Run the estimate command, collecting its warnings in `warnings`.
+    ///
+    /// Warnings not already embedded in JSON output are printed to stderr
+    /// before the results, or by [`run_estimate`](Self::run_estimate) once
+    /// the run ends.
+    fn estimate(args: EstimateArgs, warnings: &mut Warnings) -> Result<(), AppError> {
+        // Describes the model only, so no input is read
+        if args.model_info {
+            return Self::run_model_info(&args);
+        }
+
         #[cfg(feature = "watch")]
         if args.watch {
             return Self::run_watch(&args);
@@ -386,23 +359,114 @@ impl Cli {
             return Self::run_diff(&args, diff_file);
         }
 
-        let registry = ModelRegistry::new_with_pricing(args.pricing_file.as_deref())
-            .map_err(AppError::Model)?;
+        if let Some(ref since_file) = args.since_file {
+            return Self::run_since_file(&args, since_file);
+        }
+
+        let mut registry = Self::registry(&args)?;
+        if args.warmup {
+            Self::warmup(&args, &mut registry, warnings)?;
+        }
+        let registry = registry;
+        if args.compare_providers {
+            Self::describe_provider_flagships(&registry, warnings);
+        }
+
+        if let Some(budget) = args.allocate {
+            return Self::run_allocate(&args, &registry, budget, warnings);
+        }
+
+        if !args.inputs.is_empty() {
+            return Self::run_grid(&args, &registry, warnings);
+        }
+
+        if args.compare_inputs {
+            return Self::run_compare_inputs(&args, &registry, warnings);
+        }
+
+        // A directory of prompts gets an aggregate savings table
+        #[cfg(feature = "markdown")]
+        if args.minify {
+            if let Some(dir) = args
+                .input
+                .as_deref()
+                .map(std::path::Path::new)
+                .filter(|path| path.is_dir())
+            {
+                return Self::run_minify_summary(&args, &registry, dir, warnings);
+            }
+        }
+
+        if args.group_by_role {
+            return Self::run_group_by_role(&args, &registry, warnings);
+        }
+        if let Some(dir) = &args.compare_prompts_from_dir {
+            return Self::run_corpus(&args, &registry, std::path::Path::new(dir), warnings);
+        }
+
+        // Sampled chunks are picked as the input is read
+        #[cfg(feature = "sampling")]
+        if let Some(sample) = args.sample {
+            return Self::run_sample(&args, &registry, sample.get(), warnings);
+        }
+
+        // Large JSON message arrays are counted as they are read
+        if let Some(reader) = Self::stream_source(&args)? {
+            return Self::run_stream(&args, &registry, reader, warnings);
+        }
 
         // Determine input
-        let input = Self::get_input(&args.input)?;
+        let input = InputSource::from_arg(args.input.as_deref()).read(args.max_bytes)?;
+
+        if let Some(examples) = &args.examples {
+            return Self::run_examples(&args, &registry, &input, examples, warnings);
+        }
+
+        // OpenAI Batch API files name their own model per request
+        let input_format = args.input_format.unwrap_or_default();
+        if input_format == InputFormat::Auto
+            && Self::json_paths(&args).is_none()
+            && BatchParser::is_batch_input(&input)
+        {
+            return Self::run_batch(&args, &registry, &input);
+        }
+
+        let options = Self::count_options(&args)?;
+        let models = Self::resolve_models(&args, &registry, warnings)?;
+        if args.fail_if_unpriced && options.price {
+            Self::check_priced(&registry, &models)?;
+        }
+        let baseline = Self::resolve_baseline(&args, &registry, &models)?;
+
+        let input = Self::frontmatter_input(&args, &registry, &models[0], input, warnings)?;
+        let input = if args.trim_trailing_newline {
+            Self::trim_trailing_newline_input(&args, &registry, &models[0], input, warnings)?
+        } else {
+            input
+        };
+        let input = match args.strip_comments {
+            Some(style) => {
+                Self::strip_comments_input(&args, &registry, &models[0], &input, style, warnings)?
+            }
+            None => input,
+        };
+        let input = match args.line_endings {
+            Some(endings) if endings != LineEndings::Preserve => {
+                Self::line_endings_input(&args, &registry, &models[0], input, endings, warnings)?
+            }
+            _ => input,
+        };
+        let input = match args.unicode_normalize {
+            Some(form) if form != UnicodeNormalization::None => {
+                Self::unicode_normalize_input(&args, &registry, &models[0], input, form, warnings)?
+            }
+            _ => input,
+        };
 
         // Apply minify if requested
         #[cfg(feature = "markdown")]
         let original_input = if args.minify {
-            let stripped = markdown::strip_markdown(&input);
-            let savings = markdown::calculate_savings(&input, &stripped);
-            eprintln!(
-                "Markdown stripped: {} characters saved (~{} tokens)",
-                savings,
-                savings / 4
-            );
-            stripped
+            Self::minify_input(&args, &registry, &models[0], &input, warnings)?
         } else {
             input.clone()
         };
@@ -410,875 +474,3970 @@ impl Cli {
         #[cfg(not(feature = "markdown"))]
         let original_input = input.clone();
 
-        let breakdown = args.breakdown;
-        let price = args.price;
+        if args.verify_roundtrip {
+            return Self::run_verify_roundtrip(&args, &registry, &models, &original_input);
+        }
 
-        // Determine models to use
-        let models = if !args.compare.is_empty() {
-            args.compare.clone()
-        } else if let Some(model) = &args.model {
-            vec![model.clone()]
-        } else {
-            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
-                "No model specified. Use --model or --compare".to_string(),
-            )));
-        };
+        if args.compare_encodings_detail {
+            return Self::run_encoding_divergence(&registry, &models, &original_input);
+        }
+
+        if args.explain_tokenization {
+            return Self::run_explain(&args, &registry, &models, &original_input);
+        }
 
         // Parse input
-        let parser: Box<dyn InputParser> = if original_input.trim_start().starts_with('{')
-            || original_input.trim_start().starts_with('[')
-        {
-            Box::new(JsonParser::new())
-        } else {
-            Box::new(TextParser::new())
+        let parser = Self::message_parser(&args, &original_input);
+
+        let mut messages = parser.parse(&original_input)?;
+        if let Some(limit) = Self::message_limit(&args) {
+            let dropped = limit.apply(&mut messages)?;
+            Self::warn_truncated(limit, dropped, warnings);
+        }
+        let messages = match &args.chat_template {
+            Some(path) => Self::apply_chat_template(path, &messages, warnings)?,
+            None => messages,
         };
 
-        let messages = parser.parse(&original_input)?;
+        let empty = chat::count_empty(&messages);
+        if messages.len() > 1 && empty > 0 {
+            warnings.push(
+                WarningKind::EmptyContent,
+                format!("{} message(s) have empty content", empty),
+            );
+        }
+        Self::report_images(&args, &messages, warnings);
+
+        if args.warn_whitespace {
+            Self::report_whitespace(&args, &registry, &models[0], &messages, warnings)?;
+        }
+
+        if args.replay_cost {
+            return Self::run_replay_cost(&args, &registry, &models[0], &messages);
+        }
+
+        if args.histogram {
+            return Self::run_histogram(&args, &registry, &models[0], &messages);
+        }
 
         // Process each model
-        let mut results = Vec::new();
-        for model_name in &models {
-            let tokenizer = registry.get_tokenizer(model_name)?;
-            let tokenizer_name = tokenizer.name().to_string();
-            let pricing_override = if price {
-                registry
-                    .pricing_for(model_name)
-                    .or_else(|| registry.pricing_for(&tokenizer_name))
-            } else {
-                None
-            };
-            let result = Self::count_tokens(
-                &*tokenizer,
-                &messages,
-                &tokenizer_name,
-                breakdown,
-                price,
-                pricing_override,
-            )?;
-            results.push(result);
+        let (results, failures) = if let Some(path) = &args.tokenizer_file {
+            let tokenizer = registry.tokenizer_from_file(&models[0], path)?;
+            let result = Self::count_tokens(&*tokenizer, &messages, &models[0], options, None)?;
+            (vec![result], Vec::new())
+        } else {
+            Self::collect_results(&registry, &models, &messages, options, args.strict)?
+        };
+
+        Self::print_results(&args, models.len(), &results, &failures, baseline, warnings)
+    }
+
+    /// Note how inline base64 images are being counted.
+    fn report_images(
+        args: &EstimateArgs,
+        messages: &[crate::parsers::Message],
+        warnings: &mut Warnings,
+    ) {
+        let mode = args.image_tokens.unwrap_or_default();
+        if mode == ImageTokens::Literal {
+            return;
+        }
+        let count: usize = messages
+            .iter()
+            .map(|message| images::strip_data_uris(&message.content).1)
+            .sum();
+        if count == 0 {
+            return;
+        }
+        match mode {
+            ImageTokens::Estimate => warnings.push(WarningKind::Images, format!(
+                "{} inline base64 image(s) counted as {} tokens each (--image-tokens literal counts the base64 text)",
+                count, IMAGE_TOKEN_ESTIMATE
+            )),
+            _ => warnings.push(WarningKind::Images, format!(
+                "stripped {} inline base64 image(s); they are not counted",
+                count
+            )),
         }
+    }
 
-        // Format and print output
-        let formatter: Box<dyn Formatter> = match args.format {
-            OutputFormat::Text => Box::new(TextFormatter::new(args.breakdown)),
-            OutputFormat::Json => Box::new(JsonFormatter::new()),
-            #[cfg(feature = "markdown")]
-            OutputFormat::Markdown => Box::new(MarkdownFormatter::new(args.breakdown)),
+    /// The `--json-path-*` pointers, if any were given.
+    fn json_paths(args: &EstimateArgs) -> Option<JsonPaths> {
+        let paths = JsonPaths {
+            messages: args.json_path_messages.clone(),
+            role: args.json_path_role.clone(),
+            content: args.json_path_content.clone(),
         };
+        (paths != JsonPaths::default()).then_some(paths)
+    }
 
-        if results.len() == 1 {
-            println!("{}", formatter.format_result(&results[0]));
+    /// Parser for `input`: JSON at the `--json-path-*` pointers when given,
+    /// otherwise the `--input-format` parser.
+    fn message_parser(args: &EstimateArgs, input: &str) -> Box<dyn crate::parsers::Parser> {
+        let parser: Box<dyn crate::parsers::Parser> = match Self::json_paths(args) {
+            Some(paths) => Box::new(paths),
+            None => args.input_format.unwrap_or_default().parser_for(input),
+        };
+        if args.strict_json {
+            Box::new(StrictRolesParser::new(parser, Self::role_map(args)))
         } else {
-            println!("{}", formatter.format_comparison(&results));
+            parser
         }
-
-        Ok(())
     }
 
-    /// Run load test command.
-    #[cfg(feature = "load-test")]
-    fn run_load_test(args: LoadTestArgs) -> Result<(), AppError> {
-        use crate::http::client::ClientConfig;
-        use crate::http::providers::anthropic::AnthropicClient;
-        use crate::http::providers::generic::GenericClient;
-        use crate::http::providers::openai::OpenAIClient;
-        use crate::http::providers::openrouter::OpenRouterClient;
-        use crate::simulator::config::SimulatorConfig;
-        use crate::simulator::simulator::Simulator;
-        use std::sync::Arc;
-        use std::time::Duration;
+    /// The `--max-messages` limit, if one was given.
+    fn message_limit(args: &EstimateArgs) -> Option<MessageLimit> {
+        args.max_messages.map(|max| MessageLimit {
+            max,
+            action: args.on_max_messages.unwrap_or_default(),
+        })
+    }
 
-        let provider = args.resolve_provider();
+    /// Warn that messages past `--max-messages` were not counted.
+    fn warn_truncated(limit: MessageLimit, dropped: usize, warnings: &mut Warnings) {
+        if dropped > 0 {
+            warnings.push(
+                WarningKind::MessagesTruncated,
+                format!(
+                    "counted the first {} of {} messages (--max-messages {})",
+                    limit.max,
+                    limit.max + dropped,
+                    limit.max
+                ),
+            );
+        }
+    }
 
-        let api_key = Cli::resolve_api_key(&args, &provider)?;
+    /// Render messages with `--chat-template` into the single prompt a model sees.
+    fn apply_chat_template(
+        path: &str,
+        messages: &[crate::parsers::Message],
+        warnings: &mut Warnings,
+    ) -> Result<Vec<crate::parsers::Message>, AppError> {
+        let template = ChatTemplate::from_file(path)?;
+        let rendered = template.render(messages)?;
+        warnings.note(format!(
+            "Chat template applied: {} messages rendered to {} characters",
+            messages.len(),
+            rendered.chars().count()
+        ));
+        Ok(vec![crate::parsers::Message {
+            role: "user".to_string(),
+            content: rendered,
+            name: None,
+            tool_call_id: None,
+        }])
+    }
 
-        // Get prompt
-        let prompt = if let Some(ref prompt_file) = args.prompt_file {
-            std::fs::read_to_string(prompt_file).map_err(|e| {
-                AppError::Io(std::io::Error::other(format!(
-                    "Failed to read prompt file: {}",
-                    e
-                )))
-            })?
-        } else {
-            // Read from stdin
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            buffer
-        };
+    /// Load the selected models' tokenizers up front, so counting excludes
+    /// their construction, and note how long each took.
+    fn warmup(
+        args: &EstimateArgs,
+        registry: &mut ModelRegistry,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        for model in Self::resolve_models(args, registry, warnings)? {
+            let elapsed = match registry.warmup(&model) {
+                Ok(elapsed) => elapsed,
+                // Unknown models are reported with the results unless --strict
+                Err(e) if args.strict => return Err(e.into()),
+                Err(_) => continue,
+            };
+            warnings.note(format!(
+                "Warmed up {} in {:.1} ms",
+                model,
+                elapsed.as_secs_f64() * 1000.0
+            ));
+        }
+        Ok(())
+    }
 
-        if prompt.trim().is_empty() {
-            return Err(AppError::Config("Prompt cannot be empty".to_string()));
+    /// Model registry with pricing overrides and Azure deployment mappings.
+    fn registry(args: &EstimateArgs) -> Result<ModelRegistry, AppError> {
+        let mut registry = ModelRegistry::new_with_pricing(args.pricing_file.as_deref())
+            .map_err(AppError::Model)?;
+        for (deployment, model) in &args.azure_deployment {
+            registry.add_deployment(deployment.as_str(), model.as_str());
         }
+        Ok(registry)
+    }
 
-        // Build simulator config
-        let mut sim_config = SimulatorConfig::new(args.concurrency, args.runs);
-        sim_config.retry = args.retry;
-        sim_config.dry_run = args.dry_run;
-        sim_config.max_cost = args.max_cost;
-        sim_config.timeout = Duration::from_secs(60);
+    /// Display currency for costs from `--currency` and `--fx-rate`.
+    fn currency(args: &EstimateArgs) -> Result<Currency, AppError> {
+        Self::resolve_currency(args.currency.as_deref(), args.fx_rate)
+    }
 
-        if let Some(ref think_time_str) = args.think_time {
-            sim_config.think_time =
-                Some(SimulatorConfig::parse_think_time(think_time_str).map_err(AppError::Config)?);
+    /// Display currency for a `--currency` code and `--fx-rate`.
+    fn resolve_currency(code: Option<&str>, fx_rate: Option<f64>) -> Result<Currency, AppError> {
+        let invalid =
+            |message: String| AppError::Parse(crate::error::ParseError::InvalidFormat(message));
+        match (code, fx_rate) {
+            (None, _) | (Some("USD"), None) => Ok(Currency::usd()),
+            (Some(code), Some(rate)) => Currency::new(code, rate).map_err(invalid),
+            (Some(code), None) => Err(invalid(format!(
+                "--currency {} requires --fx-rate (units of {} per USD)",
+                code, code
+            ))),
         }
+    }
 
-        // Create HTTP client config
-        let endpoint = args.endpoint.clone().unwrap_or_default();
-        let model = args.model.clone();
-        let estimate_cost = args.estimate_cost;
-        let pricing_registry = if estimate_cost {
-            Some(
-                ModelRegistry::new_with_pricing(args.pricing_file.as_deref())
-                    .map_err(AppError::Model)?,
-            )
+    /// Counting options for the estimate command.
+    fn count_options(args: &EstimateArgs) -> Result<CountOptions, AppError> {
+        let expected_outputs = if args.compare.is_empty() {
+            HashMap::new()
         } else {
-            None
+            compare_expected_outputs(&expand_compare_models(&args.compare)?)?.1
         };
+        Ok(CountOptions {
+            breakdown: args.breakdown,
+            // A cost ceiling or cheapest pick can only be checked with pricing
+            price: args.price || args.max_cost.is_some() || args.cheapest,
+            expected_output: args.expected_output,
+            expected_outputs,
+            chat_overhead: args.chat_overhead,
+            framing: args.framing,
+            emit_tokens: args.emit_tokens,
+            roles: Self::role_map(args),
+            throughput: args.throughput,
+            content_preview: args.content_preview.unwrap_or(DEFAULT_PREVIEW_CHARS),
+            role_markers: args.include_role_markers,
+            // Set per model by `CountOptions::for_model`
+            output_ratio: None,
+            images: args.image_tokens.unwrap_or_default(),
+            by_name: args.breakdown_by == Some(BreakdownBy::Name),
+            response: Self::response_texts(args)?,
+        })
+    }
 
-        if provider == Provider::Generic && endpoint.is_empty() && !args.dry_run {
-            return Err(AppError::Config(
-                "Generic provider requires --endpoint to be specified".to_string(),
-            ));
-        }
+    /// Completion texts of the `--response` file, if one is given.
+    fn response_texts(args: &EstimateArgs) -> Result<Option<Vec<String>>, AppError> {
+        args.response
+            .as_deref()
+            .map(|path| {
+                InputSource::file(path, "response file")
+                    .read(args.max_bytes)
+                    .map(|response| completion_texts(&response))
+            })
+            .transpose()
+    }
 
-        let client_config = ClientConfig {
-            endpoint,
-            api_key: api_key.clone(),
-            timeout: Duration::from_secs(60),
-            headers: Vec::new(),
-        };
+    /// Role aliases for the breakdown: the defaults plus `--role-alias`.
+    fn role_map(args: &EstimateArgs) -> RoleMap {
+        args.role_alias
+            .iter()
+            .fold(RoleMap::default(), |roles, (alias, role)| {
+                roles.with_alias(alias.as_str(), role.as_str())
+            })
+    }
 
-        // Create client based on detected provider
-        use crate::http::client::LlmClientEnum;
-        let client = match provider {
-            Provider::Openrouter => Arc::new(LlmClientEnum::OpenRouter(OpenRouterClient::new(
-                client_config,
-            )?)),
-            Provider::Openai => Arc::new(LlmClientEnum::OpenAI(OpenAIClient::new(client_config)?)),
-            Provider::Anthropic => Arc::new(LlmClientEnum::Anthropic(AnthropicClient::new(
-                client_config,
-            )?)),
-            Provider::Generic => {
-                Arc::new(LlmClientEnum::Generic(GenericClient::new(client_config)?))
+    /// Reader for a streamed estimate, if the input should be streamed.
+    ///
+    /// `--stream` streams the input file or stdin. Without it, only files of at
+    /// least [`STREAM_THRESHOLD_BYTES`] holding a JSON array are streamed.
+    fn stream_source(args: &EstimateArgs) -> Result<Option<Box<dyn Read>>, AppError> {
+        // Minified input has to be rewritten as a whole first
+        #[cfg(feature = "markdown")]
+        if args.minify {
+            return Ok(None);
+        }
+        if args.strip_comments.is_some()
+            || args.strip_frontmatter
+            || args.trim_trailing_newline
+            || args
+                .line_endings
+                .is_some_and(|endings| endings != LineEndings::Preserve)
+            || args
+                .unicode_normalize
+                .is_some_and(|form| form != UnicodeNormalization::None)
+            || Self::json_paths(args).is_some()
+        {
+            return Ok(None);
+        }
+
+        let path = args.input.as_deref().filter(|path| *path != "-");
+        let source = InputSource::from_arg(path);
+        let input_format = args.input_format.unwrap_or_default();
+        if args.stream {
+            if input_format == InputFormat::Text {
+                return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    "--stream requires JSON input".to_string(),
+                )));
             }
-        };
+            return Ok(Some(source.open(args.max_bytes)?));
+        }
 
-        if args.dry_run {
-            eprintln!(
-                "Dry run mode: No API calls will be made (provider: {})",
-                provider.as_str()
-            );
-        } else {
-            eprintln!(
-                "Starting load test using provider '{}' with {} requests at concurrency {}",
-                provider.as_str(),
-                args.runs,
-                args.concurrency
-            );
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let whole_input = input_format == InputFormat::Text
+            || args.warn_whitespace
+            || args.verify_roundtrip
+            || args.compare_encodings_detail
+            || args.emit_tokens
+            || args.tokenizer_file.is_some()
+            || args.chat_template.is_some();
+        let large = std::fs::metadata(path).is_ok_and(|m| m.len() >= STREAM_THRESHOLD_BYTES);
+        if whole_input || !large {
+            return Ok(None);
         }
 
-        let simulator = Simulator::new(sim_config);
+        // Text and JSONL batch files are still read whole
+        let mut file = io::BufReader::new(source.open(args.max_bytes)?);
+        let is_array = file
+            .fill_buf()?
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            == Some(&b'[');
+        Ok(is_array.then(|| Box::new(file) as Box<dyn Read>))
+    }
 
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| AppError::Config(format!("Failed to create async runtime: {}", e)))?;
+    /// Count a streamed JSON message array with every model in a single pass.
+    ///
+    /// Only running totals and the role breakdown are kept, not a count per
+    /// message, so memory use doesn't grow with the transcript.
+    fn run_stream(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        reader: Box<dyn Read>,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let baseline = Self::resolve_baseline(args, registry, &models)?;
+        let options = Self::count_options(args)?;
 
-        // Create progress bar
-        let progress_bar = if !args.dry_run {
-            let pb = indicatif::ProgressBar::new(args.runs as u64);
-            pb.set_style(
-                indicatif::ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} {msg}")
-                    .unwrap()
-                    .progress_chars("#>-"),
-            );
-            pb.set_message("Starting load test...");
-            Some(Arc::new(pb))
-        } else {
-            None
-        };
+        let mut resolved = Vec::new();
+        let mut failures = Vec::new();
+        for model_name in &models {
+            match Self::resolve_model(registry, model_name, options.price) {
+                Ok(model) => resolved.push((model, options.for_model(registry, model_name))),
+                Err(e) if args.strict => return Err(e),
+                Err(e) => failures.push(ModelFailure {
+                    model: model_name.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
 
-        let results = if let Some(ref pb) = progress_bar {
-            rt.block_on(simulator.run_with_progress(client, &prompt, &model, Some(pb.clone())))?
-        } else {
-            rt.block_on(simulator.run(client, &prompt, &model))?
-        };
+        let mut tallies: Vec<MessageTally> = resolved
+            .iter()
+            .map(|(model, options)| {
+                MessageTally::new(&*model.tokenizer, &model.name, options.clone())
+                    .without_per_message()
+            })
+            .collect();
+        let limit = Self::message_limit(args);
+        let max = limit.map_or(usize::MAX, |limit| limit.max);
+        let roles = args.strict_json.then(|| Self::role_map(args));
+        let mut error = None;
+        let mut empty = 0;
+        let mut seen = 0;
+        let count = JsonParser::new()
+            .stream(reader, |message| {
+                seen += 1;
+                if let Some(roles) = &roles {
+                    if !roles::is_known(&message.role, roles) {
+                        error = Some(AppError::Parse(crate::error::ParseError::UnknownRole {
+                            role: message.role.clone(),
+                            index: seen,
+                        }));
+                        return ControlFlow::Break(());
+                    }
+                }
+                // Messages past the limit are only counted towards the total
+                if seen > max {
+                    return match limit {
+                        // No need to read on just to report the total
+                        Some(limit) if limit.action == LimitAction::Error => {
+                            error = Some(AppError::Parse(limit.exceeded()));
+                            ControlFlow::Break(())
+                        }
+                        _ => ControlFlow::Continue(()),
+                    };
+                }
+                if message.content.trim().is_empty() {
+                    empty += 1;
+                }
+                for tally in &mut tallies {
+                    if let Err(e) = tally.add(&message) {
+                        error = Some(e);
+                        return ControlFlow::Break(());
+                    }
+                }
+                ControlFlow::Continue(())
+            })
+            .map_err(|e| match e {
+                // --max-bytes passed while reading
+                crate::error::ParseError::Io(e) => crate::utils::input::too_large(&e)
+                    .unwrap_or(AppError::Parse(crate::error::ParseError::Io(e))),
+                e => AppError::Parse(e),
+            })?;
+        if let Some(e) = error {
+            return Err(e);
+        }
+        if let Some(limit) = limit {
+            Self::warn_truncated(limit, limit.check(count)?, warnings);
+        }
 
-        // Calculate and display metrics
-        Self::display_load_test_results(
-            &results,
-            &model,
-            &args.output_format,
-            estimate_cost,
-            pricing_registry.as_ref(),
-        )?;
+        if count > 1 && empty > 0 {
+            warnings.push(
+                WarningKind::EmptyContent,
+                format!("{} message(s) have empty content", empty),
+            );
+        }
 
-        Ok(())
-    }
+        let results = tallies
+            .into_iter()
+            .zip(&resolved)
+            .map(|(tally, (model, _))| tally.finish(model.pricing_override))
+            .collect::<Result<Vec<_>, _>>()?;
 
-    #[cfg(feature = "load-test")]
-    fn resolve_api_key(args: &LoadTestArgs, provider: &Provider) -> Result<String, AppError> {
-        let key = match provider {
-            Provider::Openai => args
-                .openai_api_key
-                .clone()
-                .or_else(|| std::env::var("OPENAI_API_KEY").ok())
-                .or_else(|| args.api_key.clone())
-                .or_else(|| std::env::var("API_KEY").ok()),
-            Provider::Openrouter => args
-                .openrouter_api_key
-                .clone()
-                .or_else(|| std::env::var("OPENROUTER_API_KEY").ok())
-                .or_else(|| args.api_key.clone())
-                .or_else(|| std::env::var("API_KEY").ok()),
-            Provider::Anthropic => args
-                .anthropic_api_key
-                .clone()
-                .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-                .or_else(|| args.api_key.clone())
-                .or_else(|| std::env::var("API_KEY").ok()),
-            Provider::Generic => args
-                .api_key
-                .clone()
-                .or_else(|| std::env::var("API_KEY").ok()),
-        };
+        Self::print_results(args, models.len(), &results, &failures, baseline, warnings)
+    }
 
-        match (provider, key) {
-            (Provider::Generic, Some(key)) => Ok(key),
-            (Provider::Generic, None) => Ok(String::new()),
-            (_, Some(key)) => Ok(key),
-            (_, None) if args.dry_run => Ok("dummy-key-for-dry-run".to_string()),
-            (Provider::Openai, None) => Err(AppError::Config(
-                "API key required for OpenAI. Use --openai-api-key, --api-key, or set OPENAI_API_KEY/API_KEY."
-                    .to_string(),
-            )),
-            (Provider::Openrouter, None) => Err(AppError::Config(
-                "API key required for OpenRouter. Use --openrouter-api-key, --api-key, or set OPENROUTER_API_KEY/API_KEY."
-                    .to_string(),
-            )),
-            (Provider::Anthropic, None) => Err(AppError::Config(
-                "API key required for Anthropic. Use --anthropic-api-key, --api-key, or set ANTHROPIC_API_KEY/API_KEY."
-                    .to_string(),
-            )),
+    /// Determine the models to count with from `--tokenizer-file`, `--compare` or `--model`.
+    fn resolve_models(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        warnings: &mut Warnings,
+    ) -> Result<Vec<String>, AppError> {
+        if let Some(path) = &args.tokenizer_file {
+            // Name the result after --model, or the tokenizer file itself
+            Ok(vec![args.model.clone().unwrap_or_else(|| path.clone())])
+        } else if args.compare_providers {
+            Ok(registry
+                .provider_flagships()
+                .into_iter()
+                .map(|info| info.model.clone())
+                .collect())
+        } else if !args.compare.is_empty() {
+            let (models, _) = compare_expected_outputs(&expand_compare_models(&args.compare)?)?;
+            if args.allow_duplicates {
+                return Ok(models);
+            }
+            let (models, duplicates) = dedupe_models(registry, models);
+            for (duplicate, kept) in duplicates {
+                warnings.push(WarningKind::DuplicateModel, format!(
+                    "skipping duplicate model '{}' (same as '{}'); pass --allow-duplicates to keep it",
+                    duplicate, kept
+                ));
+            }
+            Ok(models)
+        } else if let Some(model) = &args.model {
+            Ok(vec![model.clone()])
+        } else {
+            Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "No model specified. Use --model, --compare or --compare-providers".to_string(),
+            )))
         }
     }
 
-    /// Display load test results.
-    #[cfg(feature = "load-test")]
-    fn display_load_test_results(
-        results: &[crate::simulator::simulator::RequestResult],
-        model: &str,
-        output_format: &LoadTestOutputFormat,
-        estimate_cost: bool,
-        pricing_registry: Option<&ModelRegistry>,
-    ) -> Result<(), AppError> {
-        let total_requests = results.len();
-        let successful = results.iter().filter(|r| r.success).count();
-        let failed = total_requests.saturating_sub(successful);
-        let success_rate = if total_requests > 0 {
-            (successful as f64 / total_requests as f64) * 100.0
-        } else {
-            0.0
-        };
-        let failure_rate = if total_requests > 0 {
-            100.0 - success_rate
-        } else {
-            0.0
-        };
+    /// Note on stderr which model stands in for each provider with
+    /// `--compare-providers`, and which counts are approximate.
+    fn describe_provider_flagships(registry: &ModelRegistry, warnings: &mut Warnings) {
+        use crate::models::registry::PROVIDER_FLAGSHIPS;
 
-        let latencies: Vec<u64> = results
+        let flagships = registry.provider_flagships();
+        let chosen: Vec<String> = flagships
             .iter()
-            .filter_map(|r| if r.success { Some(r.latency_ms) } else { None })
+            .map(|info| {
+                if !registry.is_exact(&info.model) {
+                    format!("{} ({}, approximate)", info.model, info.provider)
+                } else {
+                    format!("{} ({})", info.model, info.provider)
+                }
+            })
             .collect();
+        warnings.note(format!("Comparing providers: {}", chosen.join(", ")));
 
-        let total_tokens_reported: usize = results.iter().filter_map(|r| r.total_tokens).sum();
-        let input_tokens: usize = results.iter().filter_map(|r| r.input_tokens).sum();
-        let output_tokens: usize = results.iter().filter_map(|r| r.output_tokens).sum();
+        let missing: Vec<&str> = PROVIDER_FLAGSHIPS
+            .iter()
+            .filter(|(provider, _)| !flagships.iter().any(|info| info.provider == *provider))
+            .map(|(provider, _)| *provider)
+            .collect();
+        if !missing.is_empty() {
+            warnings.note(format!("Not built in: {}", missing.join(", ")));
+        }
+    }
 
-        let avg_latency = if !latencies.is_empty() {
-            latencies.iter().sum::<u64>() as f64 / latencies.len() as f64
-        } else {
-            0.0
+    /// Result name of `--baseline`, which must be one of the compared models.
+    fn resolve_baseline(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        models: &[String],
+    ) -> Result<Option<String>, AppError> {
+        let Some(baseline) = &args.baseline else {
+            return Ok(None);
         };
+        if !models.contains(baseline) {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!(
+                    "Baseline model '{}' is not among the compared models",
+                    baseline
+                ),
+            )));
+        }
+        // Results are named after the tokenizer, which may differ from an alias
+        Ok(Some(
+            registry
+                .get_tokenizer(baseline)
+                .map(|tokenizer| tokenizer.name().to_string())
+                .unwrap_or_else(|_| baseline.clone()),
+        ))
+    }
 
-        let p50 = if !latencies.is_empty() {
-            let mut sorted = latencies.clone();
-            sorted.sort_unstable();
-            sorted[sorted.len() / 2]
-        } else {
-            0
-        };
+    /// Print estimate results and failures, then apply `--max-cost`.
+    fn print_results(
+        args: &EstimateArgs,
+        model_count: usize,
+        results: &[TokenResult],
+        failures: &[ModelFailure],
+        baseline: Option<String>,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        if args.cheapest {
+            for failure in failures {
+                warnings.push(
+                    WarningKind::SkippedModel,
+                    format!("skipping {}: {}", failure.model, failure.error),
+                );
+            }
+            let cheapest = Self::cheapest(results, args.context_limit, args.expected_output)?;
+            println!("{}", cheapest.model);
+            return Ok(());
+        }
 
-        let p95 = if !latencies.is_empty() {
-            let mut sorted = latencies.clone();
-            sorted.sort_unstable();
-            let index = ((sorted.len() as f64) * 0.95).ceil() as usize;
-            let index = index.clamp(0, sorted.len().saturating_sub(1));
-            sorted[index]
-        } else {
-            0
-        };
+        let metadata = args.with_metadata.then(|| {
+            let input = match args.input.as_deref() {
+                None | Some("-") => "stdin",
+                Some(path) => path,
+            };
+            let pricing = ModelRegistry::pricing_file(args.pricing_file.as_deref());
+            ReportMetadata::new(input, pricing.as_deref())
+        });
 
-        let (input_cost, output_cost, total_cost) = if estimate_cost {
-            if let Some(registry) = pricing_registry {
-                if let Some((input_rate, output_rate)) = registry
-                    .pricing_for(model)
-                    .or_else(|| registry.pricing_for(model.rsplit('/').next().unwrap_or(model)))
-                {
-                    let input_cost = (input_tokens as f64 / 1000.0) * input_rate;
-                    let output_cost = (output_tokens as f64 / 1000.0) * output_rate;
-                    (
-                        Some(input_cost),
-                        Some(output_cost),
-                        Some(input_cost + output_cost),
-                    )
+        // Format and print output
+        let currency = Self::currency(args)?;
+        let single = results.len() == 1 && failures.is_empty();
+        let formatter: Box<dyn Formatter> = match args.format {
+            OutputFormat::Text => {
+                let separator =
+                    (!args.no_separators).then(|| args.locale.unwrap_or_default().separator());
+                Box::new(
+                    TextFormatter::new(args.breakdown)
+                        .with_color(args.color)
+                        .with_separator(separator)
+                        .with_baseline(baseline)
+                        .with_currency(currency)
+                        .with_markup(args.markup)
+                        .with_winners(args.winners)
+                        .with_headroom(args.headroom),
+                )
+            }
+            OutputFormat::Json => {
+                let formatter = JsonFormatter::new()
+                    .with_baseline(baseline)
+                    .with_metadata(metadata.clone())
+                    .with_currency(currency)
+                    .with_markup(args.markup)
+                    .with_cost_micros(args.cost_micros)
+                    .with_canonical(args.canonical)
+                    .with_winners(args.winners)
+                    .with_headroom(args.headroom);
+                // JSON carries the warnings when the report has room for them
+                let json_warnings = if !results.is_empty() && formatter.embeds_warnings(single) {
+                    warnings.take()
                 } else {
-                    (None, None, None)
-                }
-            } else {
-                (None, None, None)
+                    Vec::new()
+                };
+                Box::new(formatter.with_warnings(json_warnings))
             }
-        } else {
-            (None, None, None)
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => Box::new(
+                MarkdownFormatter::new(args.breakdown)
+                    .with_baseline(baseline)
+                    .with_currency(currency)
+                    .with_markup(args.markup)
+                    .with_winners(args.winners)
+                    .with_headroom(args.headroom),
+            ),
         };
 
-        match output_format {
-            LoadTestOutputFormat::Text => {
-                println!("\n=== Load Test Results ===");
-                println!("Total Requests: {}", total_requests);
-                println!("Successful: {} ({:.1}%)", successful, success_rate);
-                println!("Failed: {} ({:.1}%)", failed, failure_rate);
-                println!("\nLatency (ms):");
-                println!("  Average: {:.2}", avg_latency);
-                println!("  p50: {}", p50);
-                println!("  p95: {}", p95);
-                if total_tokens_reported > 0 {
-                    println!("  Total tokens reported: {}", total_tokens_reported);
-                }
+        if let Some(metadata) = &metadata {
+            let header = formatter.format_metadata(metadata);
+            if !header.is_empty() {
+                println!("{}", header);
+                println!();
+            }
+        }
 
-                if let Some(sample) = results
-                    .iter()
-                    .find(|r| r.success)
-                    .and_then(|r| r.content.as_ref())
-                {
-                    let preview = sample.chars().take(80).collect::<String>();
-                    println!("  Example response: {}", preview);
-                }
+        warnings.flush(args.quiet);
 
-                if let Some(err) = results
-                    .iter()
-                    .find(|r| !r.success)
-                    .and_then(|r| r.error.as_ref())
-                {
-                    println!("  Last error: {}", err);
+        if single {
+            println!("{}", formatter.format_result(&results[0]));
+        } else if !results.is_empty() {
+            println!("{}", formatter.format_comparison(results));
+
+            if let Some(expected_output) = args.expected_output {
+                let points = break_even_points(results);
+                if !points.is_empty() {
+                    let section = formatter.format_break_even(expected_output, &points);
+                    if !section.is_empty() {
+                        println!();
+                        println!("{}", section);
+                    }
                 }
+            }
+        }
 
-                if estimate_cost {
-                    println!("\nToken Usage:");
-                    println!("  Input tokens: {}", input_tokens);
-                    println!("  Output tokens: {}", output_tokens);
-
-                    if let (Some(in_cost), Some(out_cost), Some(total)) =
-                        (input_cost, output_cost, total_cost)
-                    {
-                        println!("\nCost Estimation:");
-                        println!("  Input cost: ${:.6}", in_cost);
-                        println!("  Output cost: ${:.6}", out_cost);
-                        println!("  Total cost: ${:.6}", total);
-                    }
+        if !failures.is_empty() {
+            let section = formatter.format_failures(failures);
+            // Keep stdout a single parseable document for JSON output
+            if matches!(args.format, OutputFormat::Json) {
+                warnings.note(section);
+                warnings.flush(args.quiet);
+            } else {
+                if !results.is_empty() {
+                    println!();
                 }
+                println!("{}", section);
             }
-            LoadTestOutputFormat::Json => {
-                let json = serde_json::json!({
-                    "total_requests": total_requests,
-                    "successful": successful,
-                    "failed": failed,
-                    "success_rate": success_rate,
-                    "latency_ms": {
-                        "average": avg_latency,
-                        "p50": p50,
-                        "p95": p95,
-                    },
-                    "tokens": {
-                        "reported": total_tokens_reported,
-                        "input": input_tokens,
-                        "output": output_tokens,
-                    },
-                    "cost_usd": {
-                        "input": input_cost,
-                        "output": output_cost,
-                        "total": total_cost,
-                    },
-                });
+            return Err(AppError::ComparisonFailed {
+                failed: failures.len(),
+                total: model_count,
+            });
+        }
+
+        if let Some(limit) = args.max_cost {
+            Self::check_max_cost(results, limit, args.expected_output.is_some(), warnings)?;
+        }
+        Self::check_token_band(results, args.min_tokens, args.max_tokens)?;
+        if args.check_context {
+            Self::check_context(results)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fail on the first model whose context window the prompt overflows.
+    fn check_context(results: &[TokenResult]) -> Result<(), AppError> {
+        match results.iter().find(|result| {
+            result
+                .prompt_headroom()
+                .is_some_and(|headroom| headroom < 0)
+        }) {
+            Some(result) => Err(AppError::ContextWindowExceeded {
+                model: result.model.clone(),
+                tokens: result.tokens,
+                window: result.context_window.unwrap_or_default(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Warn about trimmable whitespace, measured with `model`.
+    fn report_whitespace(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model: &str,
+        messages: &[crate::parsers::Message],
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::whitespace::find_whitespace_issues;
+
+        let tokenizer = Self::tokenizer_for(args, registry, model)?;
+
+        for issue in find_whitespace_issues(&*tokenizer, messages)? {
+            warnings.push(
+                WarningKind::Whitespace,
+                format!(
+                    "message {} ({}) has {}; trimming saves ~{} tokens",
+                    issue.index + 1,
+                    issue.role,
+                    issue.describe(),
+                    issue.savings
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that each model's tokenizer round-trips the input unchanged.
+    fn run_verify_roundtrip(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        models: &[String],
+        input: &str,
+    ) -> Result<(), AppError> {
+        let mut failed = 0;
+
+        for model in models {
+            let tokenizer = Self::tokenizer_for(args, registry, model)?;
+
+            match verify_roundtrip(&*tokenizer, input)? {
+                RoundTrip::Passed { tokens } => {
+                    println!("{}: round-trip OK ({} tokens)", model, tokens);
+                }
+                RoundTrip::Mismatch {
+                    position,
+                    expected,
+                    actual,
+                } => {
+                    failed += 1;
+                    println!(
+                        "{}: round-trip mismatch at character {}: expected {:?}, got {:?}",
+                        model, position, expected, actual
+                    );
+                }
+                RoundTrip::Unsupported => {
+                    println!(
+                        "{}: round-trip not supported (approximate tokenizer)",
+                        model
+                    );
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(AppError::RoundTripFailed {
+                failed,
+                total: models.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Show where two models' tokenizations of the input first diverge.
+    fn run_encoding_divergence(
+        registry: &ModelRegistry,
+        models: &[String],
+        input: &str,
+    ) -> Result<(), AppError> {
+        let [left_model, right_model] = models else {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!(
+                    "--compare-encodings-detail needs exactly two models, got {}",
+                    models.len()
+                ),
+            )));
+        };
+        let left = registry.get_tokenizer(left_model)?;
+        let right = registry.get_tokenizer(right_model)?;
+
+        let width = left_model.len().max(right_model.len()) + 1;
+        let label = |model: &str| format!("{:<width$}", format!("{}:", model), width = width);
+        println!("{} {} tokens", label(left_model), left.count_tokens(input)?);
+        println!(
+            "{} {} tokens",
+            label(right_model),
+            right.count_tokens(input)?
+        );
+        println!();
+
+        match find_divergence(&*left, &*right, input)? {
+            Divergence::None { tokens } => {
                 println!(
-                    "{}",
-                    serde_json::to_string_pretty(&json).map_err(AppError::Json)?
+                    "No divergence: both split the text into the same {} tokens",
+                    tokens
                 );
             }
-            LoadTestOutputFormat::Csv => {
-                let format_opt = |value: Option<f64>| -> String {
-                    value.map(|v| format!("{:.6}", v)).unwrap_or_default()
+            Divergence::At {
+                position,
+                token,
+                left: left_context,
+                right: right_context,
+            } => {
+                let show = |tokens: &[String]| {
+                    tokens
+                        .iter()
+                        .map(|t| format!("{:?}", t))
+                        .collect::<Vec<_>>()
+                        .join(" ")
                 };
-
-                println!("total_requests,successful,failed,success_rate,avg_latency_ms,p50_latency_ms,p95_latency_ms,total_tokens,input_tokens,output_tokens,input_cost_usd,output_cost_usd,total_cost_usd");
                 println!(
-                    "{},{},{},{:.4},{:.2},{},{},{},{},{},{},{},{}",
-                    total_requests,
-                    successful,
-                    failed,
-                    success_rate,
-                    avg_latency,
-                    p50,
-                    p95,
-                    total_tokens_reported,
-                    input_tokens,
-                    output_tokens,
-                    format_opt(input_cost),
-                    format_opt(output_cost),
-                    format_opt(total_cost)
+                    "Token boundaries diverge at character {} (token {}):",
+                    position,
+                    token + 1
                 );
+                println!("  {} {}", label(left_model), show(&left_context));
+                println!("  {} {}", label(right_model), show(&right_context));
             }
-            _ => {
-                println!("Format {:?} not yet implemented", output_format);
+            Divergence::Unsupported => {
+                println!(
+                    "Divergence not supported: an approximate tokenizer has no token boundaries"
+                );
             }
         }
 
         Ok(())
     }
 
-    /// Run analyze-prompts command.
-    fn run_analyze(
-        folder: String,
-        model: String,
-        top_n: usize,
-        monthly_invocations: u64,
-        context_limit: Option<usize>,
-        format: OutputFormat,
+    /// Walk through how each model tokenizes a short input.
+    fn run_explain(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        models: &[String],
+        input: &str,
     ) -> Result<(), AppError> {
-        use crate::analyzers::PromptScanner;
-        use crate::output::InsightsFormatter;
-        use std::path::Path;
+        use crate::analyzers::explain::{explain, MAX_EXPLAIN_CHARS};
+        use crate::output::ExplainFormatter;
 
-        let folder_path = Path::new(&folder);
-        if !folder_path.exists() {
+        if !matches!(args.format, OutputFormat::Text) {
             return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
-                format!("Directory does not exist: {}", folder),
+                "--explain-tokenization only supports text output".to_string(),
             )));
         }
-        if !folder_path.is_dir() {
+        let chars = input.chars().count();
+        if chars > MAX_EXPLAIN_CHARS {
             return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
-                format!("Path is not a directory: {}", folder),
+                format!(
+                    "--explain-tokenization is for short inputs: got {} characters, at most {}",
+                    chars, MAX_EXPLAIN_CHARS
+                ),
             )));
         }
 
-        // Create model registry
-        let registry = ModelRegistry::new_with_pricing(None).map_err(AppError::Model)?;
+        let mut explanations = Vec::with_capacity(models.len());
+        for model in models {
+            let tokenizer = Self::tokenizer_for(args, registry, model)?;
+            explanations.push(explain(&*tokenizer, input)?);
+        }
+        println!("{}", ExplainFormatter::format_text(&explanations));
 
-        // Create scanner
-        let scanner = PromptScanner::new(registry, model.clone(), context_limit);
+        Ok(())
+    }
 
-        eprintln!("Scanning directory: {}", folder);
-        let analyses = scanner.scan_directory(folder_path)?;
+    /// Estimate each model's tokens from a random sample of the input's chunks.
+    ///
+    /// The chunks are picked while the input is read, and every model counts
+    /// the same ones.
+    #[cfg(feature = "sampling")]
+    fn run_sample(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        sample: usize,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::sampling::{estimate_sampled, ChunkSample};
+        use crate::output::SampleFormatter;
+
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let reader = InputSource::from_arg(args.input.as_deref()).open(args.max_bytes)?;
+        let mut rng = match args.seed {
+            Some(seed) => fastrand::Rng::with_seed(seed),
+            None => fastrand::Rng::new(),
+        };
+        let sample = ChunkSample::read(reader, sample, &mut rng).map_err(|e| {
+            // --max-bytes passed while reading
+            crate::utils::input::too_large(&e).unwrap_or(AppError::Io(e))
+        })?;
 
-        if analyses.is_empty() {
-            eprintln!("No prompt files found in directory.");
+        let mut estimates = Vec::with_capacity(models.len());
+        for model in &models {
+            let tokenizer = Self::tokenizer_for(args, registry, model)?;
+            estimates.push(estimate_sampled(&*tokenizer, &sample)?);
+        }
+
+        match args.format {
+            OutputFormat::Text => {
+                println!("{}", SampleFormatter::format_text(&estimates, args.seed))
+            }
+            OutputFormat::Json => {
+                let output = SampleFormatter::format_json(&estimates, args.seed)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!(
+                    "{}",
+                    SampleFormatter::format_markdown(&estimates, args.seed)
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The priced result with the lowest total cost.
+    ///
+    /// With a context limit, results whose prompt plus expected output (the
+    /// model's own, else `expected_output`) would not fit are skipped. Ties
+    /// keep the earlier model.
+    fn cheapest(
+        results: &[TokenResult],
+        context_limit: Option<usize>,
+        expected_output: Option<usize>,
+    ) -> Result<&TokenResult, AppError> {
+        let aggregate = ResultAggregator::new(results);
+        if aggregate.cost().is_none() {
+            return Err(AppError::NoEligibleModel(
+                "none of the compared models has known pricing".to_string(),
+            ));
+        }
+
+        aggregate
+            .cheapest(|result| {
+                context_limit.map_or(true, |limit| {
+                    let output = result.expected_output.or(expected_output);
+                    result.tokens + output.unwrap_or(0) <= limit
+                })
+            })
+            .ok_or_else(|| {
+                AppError::NoEligibleModel(format!(
+                    "no priced model fits the context limit of {} tokens",
+                    context_limit.unwrap_or_default()
+                ))
+            })
+    }
+
+    /// Fail if any result's estimated cost exceeds `limit` (in USD).
+    ///
+    /// The cost is the input cost, plus the output cost when an expected
+    /// output length was given. Results without pricing cannot be verified and
+    /// only produce a warning.
+    fn check_max_cost(
+        results: &[TokenResult],
+        limit: f64,
+        include_output: bool,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        let mut worst: Option<(&TokenResult, f64)> = None;
+
+        for result in results {
+            let Some(input_cost) = result.input_cost else {
+                warnings.push(
+                    WarningKind::Unpriced,
+                    format!("no pricing for {}; cannot verify --max-cost", result.model),
+                );
+                continue;
+            };
+            let output_cost = if include_output || result.expected_output.is_some() {
+                result.output_cost.unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            let cost = input_cost + output_cost;
+            if cost > limit && worst.map_or(true, |(_, c)| cost > c) {
+                worst = Some((result, cost));
+            }
+        }
+
+        match worst {
+            Some((result, cost)) => Err(AppError::CostLimitExceeded {
+                model: result.model.clone(),
+                cost,
+                limit,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail if the largest token count falls outside `--min-tokens` and
+    /// `--max-tokens`.
+    fn check_token_band(
+        results: &[TokenResult],
+        min: Option<usize>,
+        max: Option<usize>,
+    ) -> Result<(), AppError> {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    format!("--min-tokens {} is above --max-tokens {}", min, max),
+                )));
+            }
+        }
+        let Some(largest) = ResultAggregator::new(results).most_tokens() else {
             return Ok(());
+        };
+
+        match (min, max) {
+            (Some(min), _) if largest.tokens < min => Err(AppError::BelowMinTokens {
+                model: largest.model.clone(),
+                tokens: largest.tokens,
+                min,
+            }),
+            (_, Some(max)) if largest.tokens > max => Err(AppError::AboveMaxTokens {
+                model: largest.model.clone(),
+                tokens: largest.tokens,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if any of `models` has no pricing data.
+    ///
+    /// Models that can't be resolved are left for counting to report.
+    fn check_priced(registry: &ModelRegistry, models: &[String]) -> Result<(), AppError> {
+        let unpriced: Vec<String> = models
+            .iter()
+            .filter(|name| {
+                Self::resolve_model(registry, name, true).is_ok_and(|model| {
+                    model.pricing_override.is_none()
+                        && model.tokenizer.input_price_per_1k().is_none()
+                })
+            })
+            .cloned()
+            .collect();
+
+        if unpriced.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Unpriced(unpriced))
+        }
+    }
+
+    /// Tokenizer for `model_name`: the `--tokenizer-file` one if given,
+    /// else the registry's.
+    fn tokenizer_for(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+    ) -> Result<Box<dyn Tokenizer>, ModelError> {
+        match &args.tokenizer_file {
+            Some(path) => registry.tokenizer_from_file(model_name, path),
+            None => registry.get_tokenizer(model_name),
+        }
+    }
+
+    /// Strip leading frontmatter from the input if `--strip-frontmatter` is
+    /// set and report the savings; otherwise only note that the input has
+    /// frontmatter.
+    ///
+    /// Savings are counted like `--strip-comments`.
+    fn frontmatter_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: String,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        let Some(found) = frontmatter::split_frontmatter(&input) else {
+            return Ok(input);
+        };
+        if !args.strip_frontmatter {
+            warnings.note(format!(
+                "Input starts with {} frontmatter; pass --strip-frontmatter to exclude it",
+                found.format
+            ));
+            return Ok(input);
+        }
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let original = tokenizer.count_tokens(&input)?;
+        let remaining = tokenizer.count_tokens(found.body)?;
+        warnings.note(format!(
+            "{} frontmatter stripped: {} characters, {} tokens saved",
+            found.format,
+            found.block.chars().count(),
+            original.saturating_sub(remaining)
+        ));
+        Ok(found.body.to_string())
+    }
+
+    /// `content` without its leading frontmatter if `--strip-frontmatter` is
+    /// set.
+    ///
+    /// For paths that count many files at once, where a savings note per file
+    /// would be noise.
+    fn without_frontmatter<'a>(args: &EstimateArgs, content: &'a str) -> &'a str {
+        match frontmatter::split_frontmatter(content) {
+            Some(found) if args.strip_frontmatter => found.body,
+            _ => content,
+        }
+    }
+
+    /// Strip code comments from the input and report the savings.
+    ///
+    /// Savings are re-tokenized with the first model's tokenizer like
+    /// `--minify`.
+    fn strip_comments_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: &str,
+        style: CommentStyle,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        let stripped = comments::strip_comments(input, style);
+        let chars_saved = input.chars().count() - stripped.chars().count();
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let original = tokenizer.count_tokens(input)?;
+        let remaining = tokenizer.count_tokens(&stripped)?;
+        warnings.note(format!(
+            "Comments stripped: {} characters, {} tokens saved",
+            chars_saved,
+            original.saturating_sub(remaining)
+        ));
+        Ok(stripped)
+    }
+
+    /// Convert the input's line endings and report how the count changes.
+    ///
+    /// The difference is counted with the first model's tokenizer.
+    fn line_endings_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: String,
+        endings: LineEndings,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        let (converted, target) = match endings {
+            LineEndings::Lf => (line_endings::count_crlf(&input), "LF"),
+            LineEndings::Crlf => (line_endings::count_bare_lf(&input), "CRLF"),
+            LineEndings::Preserve => return Ok(input),
+        };
+        if converted == 0 {
+            return Ok(input);
+        }
+        let normalized = endings.normalize(&input).into_owned();
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let before = tokenizer.count_tokens(&input)?;
+        let after = tokenizer.count_tokens(&normalized)?;
+        warnings.note(format!(
+            "Line endings converted to {}: {} line breaks, {} -> {} tokens ({:+})",
+            target,
+            converted,
+            before,
+            after,
+            after as i64 - before as i64
+        ));
+        Ok(normalized)
+    }
+
+    /// Strip one trailing newline from the input and report the token
+    /// difference it makes.
+    fn trim_trailing_newline_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: String,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        let Some(trimmed) = line_endings::trim_trailing_newline(&input) else {
+            return Ok(input);
+        };
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let before = tokenizer.count_tokens(&input)?;
+        let after = tokenizer.count_tokens(trimmed)?;
+        if before == after {
+            warnings.note(format!(
+                "Trailing newline trimmed: {} tokens (count unchanged)",
+                after
+            ));
+        } else {
+            warnings.note(format!(
+                "Trailing newline trimmed: {} -> {} tokens ({:+})",
+                before,
+                after,
+                after as i64 - before as i64
+            ));
+        }
+        Ok(trimmed.to_string())
+    }
+
+    /// Apply a Unicode normalization form to the input and report the token
+    /// difference it makes.
+    #[cfg(feature = "unicode")]
+    fn unicode_normalize_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: String,
+        form: UnicodeNormalization,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        let normalized = form.normalize(&input).into_owned();
+        let name = match form {
+            UnicodeNormalization::Nfc => "NFC",
+            UnicodeNormalization::Nfkc => "NFKC",
+            UnicodeNormalization::None => return Ok(input),
+        };
+        if normalized == input {
+            warnings.note(format!(
+                "Unicode normalization ({}): input already normalized",
+                name
+            ));
+            return Ok(input);
+        }
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let before = tokenizer.count_tokens(&input)?;
+        let after = tokenizer.count_tokens(&normalized)?;
+        warnings.note(format!(
+            "Unicode normalization ({}): {} -> {} tokens ({:+})",
+            name,
+            before,
+            after,
+            after as i64 - before as i64
+        ));
+        Ok(normalized)
+    }
+
+    /// `--unicode-normalize` needs the `unicode` feature.
+    #[cfg(not(feature = "unicode"))]
+    fn unicode_normalize_input(
+        _args: &EstimateArgs,
+        _registry: &ModelRegistry,
+        _model_name: &str,
+        _input: String,
+        _form: UnicodeNormalization,
+        _warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+            "--unicode-normalize requires tokuin to be built with the `unicode` feature"
+                .to_string(),
+        )))
+    }
+
+    /// Strip markdown from the input and report the savings.
+    ///
+    /// Savings are re-tokenized with the first model's tokenizer, or the
+    /// `--tokenizer-file` one if given.
+    #[cfg(feature = "markdown")]
+    fn minify_input(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        input: &str,
+        warnings: &mut Warnings,
+    ) -> Result<String, AppError> {
+        use crate::analyzers::minify::MinifySavings;
+
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let (savings, stripped) = MinifySavings::measure(&*tokenizer, input)?;
+        warnings.push(
+            WarningKind::MinifySavings,
+            format!(
+                "Markdown stripped: {} characters, {} tokens saved",
+                savings.chars_saved,
+                savings.tokens_saved()
+            ),
+        );
+        Ok(stripped)
+    }
+
+    /// Report markdown-stripping savings across a directory of prompts.
+    #[cfg(feature = "markdown")]
+    fn run_minify_summary(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        dir: &std::path::Path,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::minify::summarize_directory;
+        use crate::output::MinifyFormatter;
+
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let tokenizer = Self::tokenizer_for(args, registry, &models[0])?;
+        let summary = summarize_directory(&*tokenizer, dir)?;
+
+        match args.format {
+            OutputFormat::Text => println!("{}", MinifyFormatter::format_text(&summary)),
+            OutputFormat::Json => {
+                let output = MinifyFormatter::format_json(&summary)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            OutputFormat::Markdown => println!("{}", MinifyFormatter::format_markdown(&summary)),
+        }
+
+        Ok(())
+    }
+
+    /// Report corpus-wide statistics for every transcript in `dir`, once per
+    /// model.
+    fn run_corpus(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        dir: &std::path::Path,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::corpus::summarize_corpus;
+        use crate::output::CorpusFormatter;
+
+        if !dir.is_dir() {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!(
+                    "--compare-prompts-from-dir needs a directory, got '{}'",
+                    dir.display()
+                ),
+            )));
+        }
+
+        // The projected cost is the point of the report, so always price
+        let options = CountOptions {
+            breakdown: true,
+            price: true,
+            ..Self::count_options(args)?
+        };
+
+        let mut summaries = Vec::new();
+        let mut skipped = Vec::new();
+        for model in Self::resolve_models(args, registry, warnings)? {
+            let tokenizer = Self::tokenizer_for(args, registry, &model)?;
+            let pricing = registry
+                .pricing_for(&model)
+                .or_else(|| registry.pricing_for(tokenizer.name()));
+
+            let summary = summarize_corpus(&model, dir, |content| {
+                let content = Self::without_frontmatter(args, content);
+                let messages = Self::message_parser(args, content).parse(content)?;
+                Self::count_tokens(&*tokenizer, &messages, &model, options.clone(), pricing)
+            })?;
+            // A file that doesn't parse fails the same way for every model
+            for file in &summary.skipped {
+                let message = format!("skipped {}: {}", file.path.display(), file.reason);
+                if !skipped.contains(&message) {
+                    warnings.push(WarningKind::SkippedFile, message.clone());
+                    skipped.push(message);
+                }
+            }
+            summaries.push(summary);
+        }
+        if summaries.iter().all(|summary| summary.is_empty()) {
+            warnings.push(
+                WarningKind::EmptyContent,
+                format!("no transcripts counted in '{}'", dir.display()),
+            );
+        }
+
+        let formatter = CorpusFormatter::new()
+            .with_currency(Self::currency(args)?)
+            .with_markup(args.markup)
+            .with_cost_micros(args.cost_micros);
+        match args.format {
+            OutputFormat::Text => println!("{}", formatter.format_text(&summaries)),
+            OutputFormat::Json => {
+                let output = formatter
+                    .format_json(&summaries)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => println!("{}", formatter.format_markdown(&summaries)),
+        }
+
+        Ok(())
+    }
+
+    /// Sum the role breakdown of every transcript in the input directory.
+    fn run_group_by_role(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::roles::summarize_roles;
+        use crate::output::RolesFormatter;
+
+        let dir = args
+            .input
+            .as_deref()
+            .map(std::path::Path::new)
+            .filter(|path| path.is_dir())
+            .ok_or_else(|| {
+                AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    "--group-by-role requires a directory of transcripts as input".to_string(),
+                ))
+            })?;
+
+        let options = CountOptions {
+            breakdown: true,
+            price: false,
+            ..Self::count_options(args)?
+        };
+
+        let mut summaries = Vec::new();
+        for model in Self::resolve_models(args, registry, warnings)? {
+            let tokenizer = Self::tokenizer_for(args, registry, &model)?;
+            summaries.push(summarize_roles(&model, dir, |content| {
+                let content = Self::without_frontmatter(args, content);
+                let messages = Self::message_parser(args, content).parse(content)?;
+                let result =
+                    Self::count_tokens(&*tokenizer, &messages, &model, options.clone(), None)?;
+                Ok(result.breakdown.unwrap_or_default())
+            })?);
         }
 
-        eprintln!("Analyzed {} prompt files", analyses.len());
+        match args.format {
+            OutputFormat::Text => {
+                println!(
+                    "{}",
+                    RolesFormatter::format_text(&summaries, args.breakdown)
+                )
+            }
+            OutputFormat::Json => {
+                let output = RolesFormatter::format_json(&summaries)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!(
+                    "{}",
+                    RolesFormatter::format_markdown(&summaries, args.breakdown)
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count a prompt and a few-shot examples file separately and combined.
+    fn run_examples(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        prompt: &str,
+        examples_path: &str,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::examples::count_segments;
+        use crate::output::ExamplesFormatter;
+
+        let examples = InputSource::file(examples_path, "examples file").read(args.max_bytes)?;
+
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let mut counts = Vec::with_capacity(models.len());
+        for model in &models {
+            let tokenizer = Self::tokenizer_for(args, registry, model)?;
+            counts.push(count_segments(&*tokenizer, prompt, &examples)?);
+        }
+
+        match args.format {
+            OutputFormat::Text => println!("{}", ExamplesFormatter::format_text(&counts)),
+            OutputFormat::Json => {
+                let output = ExamplesFormatter::format_json(&counts)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!("{}", ExamplesFormatter::format_markdown(&counts))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plan how the `--section` files share a token budget for each model.
+    fn run_allocate(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        budget: usize,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::budget::allocate;
+        use crate::output::AllocationFormatter;
+
+        let sections = args
+            .section
+            .iter()
+            .map(|(label, path)| {
+                Ok((
+                    label.clone(),
+                    InputSource::file(path, "section file").read(args.max_bytes)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let mut plans = Vec::with_capacity(models.len());
+        for model in &models {
+            let tokenizer = Self::tokenizer_for(args, registry, model)?;
+            plans.push(allocate(&*tokenizer, &sections, budget)?);
+        }
+
+        match args.format {
+            OutputFormat::Text => println!("{}", AllocationFormatter::format_text(&plans)),
+            OutputFormat::Json => {
+                let output = AllocationFormatter::format_json(&plans)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!("{}", AllocationFormatter::format_markdown(&plans))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Price each message of a recorded conversation for one model.
+    fn run_replay_cost(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        messages: &[crate::parsers::Message],
+    ) -> Result<(), AppError> {
+        use crate::analyzers::replay::replay_cost;
+        use crate::output::ReplayFormatter;
+
+        let model = Self::resolve_model(registry, model_name, true)?;
+        let tokenizer = match &args.tokenizer_file {
+            Some(path) => registry.tokenizer_from_file(model_name, path)?,
+            None => model.tokenizer,
+        };
+        // Input-only models such as embeddings have no output rate
+        let rates = match model.pricing_override {
+            Some(rates) => rates,
+            None => match tokenizer.input_price_per_1k() {
+                Some(input) => (input, tokenizer.output_price_per_1k().unwrap_or(0.0)),
+                None => return Err(AppError::Unpriced(vec![model.name])),
+            },
+        };
+
+        let replay = replay_cost(
+            &*tokenizer,
+            messages,
+            rates,
+            &Self::role_map(args),
+            args.image_tokens.unwrap_or_default(),
+        )?;
+
+        let currency = Self::currency(args)?;
+        match args.format {
+            OutputFormat::Text => println!("{}", ReplayFormatter::format_text(&replay, &currency)),
+            OutputFormat::Json => {
+                let output = ReplayFormatter::format_json(&replay, &currency)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!("{}", ReplayFormatter::format_markdown(&replay, &currency))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count every `--inputs` file with every model and print the grid.
+    fn run_grid(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        Self::count_grid(
+            args,
+            registry,
+            ("--inputs", "files"),
+            &args.inputs,
+            |path| {
+                let input = InputSource::from_arg(Some(path)).read(args.max_bytes)?;
+                Ok((path.to_string(), input))
+            },
+            warnings,
+        )
+    }
+
+    /// Count each positional argument as an inline prompt against every
+    /// model and print a grid, as `--inputs` does for files.
+    fn run_compare_inputs(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        let prompts: Vec<String> = args
+            .input
+            .iter()
+            .chain(&args.extra_inputs)
+            .cloned()
+            .collect();
+        if prompts.is_empty() {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "--compare-inputs needs at least one prompt argument".to_string(),
+            )));
+        }
+        Self::count_grid(
+            args,
+            registry,
+            ("--compare-inputs", "prompts"),
+            &prompts,
+            |prompt| {
+                let input = InputSource::Text(prompt.to_string()).read(args.max_bytes)?;
+                Ok((preview(prompt, DEFAULT_PREVIEW_CHARS), input))
+            },
+            warnings,
+        )
+    }
+
+    /// Count every input against every model and print the grid.
+    ///
+    /// `read` turns each of `inputs` into its row label and text; `source`
+    /// names the flag and what the inputs are for the grid size error.
+    fn count_grid(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        source: (&str, &str),
+        inputs: &[String],
+        read: impl Fn(&str) -> Result<(String, String), AppError>,
+        warnings: &mut Warnings,
+    ) -> Result<(), AppError> {
+        use crate::output::grid::{GridFormatter, GridRow, TokenGrid, MAX_GRID_CELLS};
+
+        let models = Self::resolve_models(args, registry, warnings)?;
+        let cells = inputs.len() * models.len();
+        if cells > MAX_GRID_CELLS {
+            let (flag, noun) = source;
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!(
+                    "{} grid of {} {} x {} models has {} cells; the limit is {}",
+                    flag,
+                    inputs.len(),
+                    noun,
+                    models.len(),
+                    cells,
+                    MAX_GRID_CELLS
+                ),
+            )));
+        }
+
+        // Unknown models become an n/a column unless --strict
+        let mut tokenizers = Vec::new();
+        for model in &models {
+            let tokenizer = Self::tokenizer_for(args, registry, model);
+            match tokenizer {
+                Ok(tokenizer) => tokenizers.push(Some(tokenizer)),
+                Err(e) if args.strict => return Err(e.into()),
+                Err(e) => {
+                    warnings.push(
+                        WarningKind::SkippedModel,
+                        format!("skipping {}: {}", model, e),
+                    );
+                    tokenizers.push(None);
+                }
+            }
+        }
+
+        let options = Self::count_options(args)?;
+        let mut rows = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let (label, input) = read(input)?;
+            let input = Self::without_frontmatter(args, &input);
+            let messages = Self::message_parser(args, input).parse(input)?;
+            let tokens = models
+                .iter()
+                .zip(&tokenizers)
+                .map(|(model, tokenizer)| match tokenizer {
+                    Some(tokenizer) => {
+                        let options = options.for_model(registry, model);
+                        Self::count_tokens(&**tokenizer, &messages, model, options, None)
+                            .map(|result| Some(result.tokens))
+                    }
+                    None => Ok(None),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(GridRow {
+                input: label,
+                tokens,
+            });
+        }
+
+        let grid = TokenGrid { models, rows };
+        if args.csv {
+            println!("{}", GridFormatter::format_csv(&grid));
+            return Ok(());
+        }
+        match args.format {
+            OutputFormat::Text => {
+                let separator =
+                    (!args.no_separators).then(|| args.locale.unwrap_or_default().separator());
+                println!("{}", GridFormatter::format_text(&grid, separator));
+            }
+            OutputFormat::Json => {
+                let output = GridFormatter::format_json(&grid)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => println!("{}", GridFormatter::format_markdown(&grid)),
+        }
+
+        Ok(())
+    }
+
+    /// Print what tokuin knows about `--model`.
+    fn run_model_info(args: &EstimateArgs) -> Result<(), AppError> {
+        use crate::output::{ModelDetails, ModelInfoFormatter};
+
+        let registry = Self::registry(args)?;
+        let model_name = args.model.as_deref().unwrap_or_default();
+        let tokenizer = Self::tokenizer_for(args, &registry, model_name)?;
+        let info = registry.get_model_info(model_name);
+        let (input_price, output_price) = match registry.pricing_for(model_name) {
+            Some((input, output)) => (Some(input), Some(output)),
+            None => (
+                tokenizer.input_price_per_1k(),
+                tokenizer.output_price_per_1k(),
+            ),
+        };
+        let pricing_source = match info.and_then(|info| info.pricing_file.clone()) {
+            Some(path) => Some(path),
+            None if input_price.is_some() => Some("defaults".to_string()),
+            None => None,
+        };
+
+        let details = ModelDetails {
+            model: tokenizer.name().to_string(),
+            provider: info.map(|info| info.provider.clone()),
+            encoding: tokenizer.encoding().map(str::to_string),
+            exact: tokenizer.is_exact(),
+            context_window: tokenizer.context_window(),
+            input_price,
+            output_price,
+            pricing_source,
+        };
+
+        let currency = Self::currency(args)?;
+        match args.format {
+            OutputFormat::Text => {
+                println!("{}", ModelInfoFormatter::format_text(&details, &currency))
+            }
+            OutputFormat::Json => {
+                let output = ModelInfoFormatter::format_json(&details, &currency)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!(
+                    "{}",
+                    ModelInfoFormatter::format_markdown(&details, &currency)
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print a histogram of per-message token counts for one model.
+    fn run_histogram(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        model_name: &str,
+        messages: &[crate::parsers::Message],
+    ) -> Result<(), AppError> {
+        use crate::analyzers::histogram::{histogram, DEFAULT_BOUNDS};
+        use crate::output::HistogramFormatter;
+
+        let bounds = args.histogram_buckets.as_deref().unwrap_or(&DEFAULT_BOUNDS);
+        if !bounds.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "--histogram-buckets must be strictly ascending, e.g. 10,50,200".to_string(),
+            )));
+        }
+        let tokenizer = Self::tokenizer_for(args, registry, model_name)?;
+        let histogram = histogram(
+            &*tokenizer,
+            messages,
+            bounds,
+            args.image_tokens.unwrap_or_default(),
+        )?;
+
+        match args.format {
+            OutputFormat::Text => println!("{}", HistogramFormatter::format_text(&histogram)),
+            OutputFormat::Json => {
+                let output = HistogramFormatter::format_json(&histogram)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                println!("{}", HistogramFormatter::format_markdown(&histogram))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Estimate an OpenAI Batch API input file.
+    fn run_batch(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        input: &str,
+    ) -> Result<(), AppError> {
+        use crate::analyzers::batch::{estimate_batch, find_shared_system_prompts};
+        use crate::output::BatchFormatter;
+
+        let requests = BatchParser::new().parse_batch(input)?;
+        let mut summary = estimate_batch(
+            registry,
+            &requests,
+            args.model.as_deref(),
+            args.price,
+            args.expected_output,
+            args.image_tokens.unwrap_or_default(),
+            args.jobs.unwrap_or_else(parallel::default_jobs),
+        )?;
+        if args.dedupe_system {
+            summary.shared_system = Some(find_shared_system_prompts(
+                registry,
+                &requests,
+                args.model.as_deref(),
+                &Self::role_map(args),
+            )?);
+        }
+
+        let currency = Self::currency(args)?;
+        match args.format {
+            OutputFormat::Json => {
+                let output = BatchFormatter::format_json(&summary, &currency)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            // Markdown falls back to the text table for now
+            _ => println!("{}", BatchFormatter::format_text(&summary, &currency)),
+        }
+
+        Ok(())
+    }
+
+    /// Count tokens for every requested model.
+    ///
+    /// In strict mode the first failing model aborts the run. Otherwise
+    /// failures are collected so that the successful results can still be shown.
+    fn collect_results(
+        registry: &ModelRegistry,
+        models: &[String],
+        messages: &[crate::parsers::Message],
+        options: CountOptions,
+        strict: bool,
+    ) -> Result<(Vec<TokenResult>, Vec<ModelFailure>), AppError> {
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
+
+        for model_name in models {
+            match Self::count_for_model(
+                registry,
+                model_name,
+                messages,
+                options.for_model(registry, model_name),
+            ) {
+                Ok(result) => results.push(result),
+                Err(e) if strict => return Err(e),
+                Err(e) => failures.push(ModelFailure {
+                    model: model_name.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        Ok((results, failures))
+    }
+
+    /// Look up a model's tokenizer, canonical name and pricing override.
+    fn resolve_model(
+        registry: &ModelRegistry,
+        model_name: &str,
+        price: bool,
+    ) -> Result<ResolvedModel, AppError> {
+        let tokenizer = registry.get_tokenizer(model_name)?;
+        let pricing_override = if price {
+            registry
+                .pricing_for(model_name)
+                .or_else(|| registry.pricing_for(tokenizer.name()))
+        } else {
+            None
+        };
+        Ok(ResolvedModel {
+            name: tokenizer.name().to_string(),
+            tokenizer,
+            pricing_override,
+        })
+    }
+
+    /// Resolve the tokenizer and pricing for a model and count its tokens.
+    fn count_for_model(
+        registry: &ModelRegistry,
+        model_name: &str,
+        messages: &[crate::parsers::Message],
+        options: CountOptions,
+    ) -> Result<TokenResult, AppError> {
+        let model = Self::resolve_model(registry, model_name, options.price)?;
+        Self::count_tokens(
+            &*model.tokenizer,
+            messages,
+            &model.name,
+            options,
+            model.pricing_override,
+        )
+    }
+
+    /// Run load test command.
+    #[cfg(feature = "load-test")]
+    fn run_load_test(args: LoadTestArgs) -> Result<(), AppError> {
+        use crate::http::client::ClientConfig;
+        use crate::http::providers::anthropic::AnthropicClient;
+        use crate::http::providers::generic::GenericClient;
+        use crate::http::providers::openai::OpenAIClient;
+        use crate::http::providers::openrouter::OpenRouterClient;
+        use crate::simulator::simulator::Simulator;
+        use std::sync::Arc;
+
+        let provider = args.resolve_provider();
+
+        let api_keys = Cli::resolve_api_keys(&args, &provider)?;
+
+        // Get prompt
+        let prompt = match args.prompt_file.as_deref() {
+            Some(path) => InputSource::file(path, "prompt file"),
+            None => InputSource::Stdin,
+        }
+        .read(None)?;
+
+        if prompt.trim().is_empty() {
+            return Err(AppError::Config("Prompt cannot be empty".to_string()));
+        }
+
+        // Build simulator config
+        let mut sim_config = args.settings()?;
+        sim_config.dry_run = args.dry_run;
+
+        // Create HTTP client config
+        let endpoint = args.endpoint.clone().unwrap_or_default();
+        let model = args.model.clone();
+        let estimate_cost = args.estimate_cost;
+        let pricing_registry = if estimate_cost || sim_config.max_cost.is_some() {
+            Some(
+                ModelRegistry::new_with_pricing(args.pricing_file.as_deref())
+                    .map_err(AppError::Model)?,
+            )
+        } else {
+            None
+        };
+
+        if let (Some(limit), Some(registry)) = (sim_config.max_cost, pricing_registry.as_ref()) {
+            sim_config.pricing = Self::attempt_pricing(registry, &model, &prompt);
+            if sim_config.pricing.is_none() && !args.dry_run {
+                eprintln!(
+                    "Warning: no pricing for '{}'; --max-cost ${:.4} cannot be enforced",
+                    model, limit
+                );
+            }
+        }
+
+        if provider == Provider::Generic && endpoint.is_empty() && !args.dry_run {
+            return Err(AppError::Config(
+                "Generic provider requires --endpoint to be specified".to_string(),
+            ));
+        }
+
+        if api_keys.len() > 1 {
+            eprintln!(
+                "Rotating across {} API keys: {}",
+                api_keys.len(),
+                api_keys.redacted().join(", ")
+            );
+        }
+
+        let client_config = ClientConfig {
+            endpoint,
+            api_keys,
+            timeout: sim_config.timeout,
+            headers: Vec::new(),
+            ..Default::default()
+        };
+
+        // Create client based on detected provider
+        use crate::http::client::LlmClientEnum;
+        let client = match provider {
+            Provider::Openrouter => Arc::new(LlmClientEnum::OpenRouter(OpenRouterClient::new(
+                client_config,
+            )?)),
+            Provider::Openai => Arc::new(LlmClientEnum::OpenAI(OpenAIClient::new(client_config)?)),
+            Provider::Anthropic => Arc::new(LlmClientEnum::Anthropic(AnthropicClient::new(
+                client_config,
+            )?)),
+            Provider::Generic => {
+                Arc::new(LlmClientEnum::Generic(GenericClient::new(client_config)?))
+            }
+        };
+
+        if args.dry_run {
+            eprintln!(
+                "Dry run mode: No API calls will be made (provider: {})",
+                provider.as_str()
+            );
+        } else {
+            eprintln!(
+                "Starting load test using provider '{}' with {} requests at concurrency {}",
+                provider.as_str(),
+                sim_config.runs,
+                sim_config.concurrency
+            );
+        }
+
+        let runs = sim_config.runs;
+        let max_cost = sim_config.max_cost;
+        let simulator = Simulator::new(sim_config);
+
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| AppError::Config(format!("Failed to create async runtime: {}", e)))?;
+
+        // Create progress bar
+        let progress_bar = if !args.dry_run {
+            let pb = indicatif::ProgressBar::new(runs as u64);
+            pb.set_style(
+                indicatif::ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} {msg}")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            );
+            pb.set_message("Starting load test...");
+            Some(Arc::new(pb))
+        } else {
+            None
+        };
+
+        let results = if let Some(ref pb) = progress_bar {
+            rt.block_on(simulator.run_with_progress(client, &prompt, &model, Some(pb.clone())))?
+        } else {
+            rt.block_on(simulator.run(client, &prompt, &model))?
+        };
+
+        if results.len() < runs {
+            eprintln!(
+                "Stopped after {} of {} requests: --max-cost ${:.4} reached (${:.4} spent, retries included)",
+                results.len(),
+                runs,
+                max_cost.unwrap_or_default(),
+                simulator.spent().unwrap_or_default()
+            );
+        }
+
+        // Calculate and display metrics
+        Self::display_load_test_results(
+            &results,
+            &model,
+            &args.output_format,
+            estimate_cost,
+            pricing_registry.as_ref(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Rates for `model` in the load test, trying the name without its
+    /// provider prefix (`openai/gpt-4`) as well.
+    #[cfg(feature = "load-test")]
+    fn load_test_rates(registry: &ModelRegistry, model: &str) -> Option<(f64, f64)> {
+        registry
+            .pricing_for(model)
+            .or_else(|| registry.pricing_for(model.rsplit('/').next().unwrap_or(model)))
+    }
+
+    /// Per-attempt pricing for `--max-cost`, with the prompt counted by the
+    /// model's tokenizer or else estimated at four characters per token.
+    #[cfg(feature = "load-test")]
+    fn attempt_pricing(
+        registry: &ModelRegistry,
+        model: &str,
+        prompt: &str,
+    ) -> Option<crate::simulator::budget::AttemptPricing> {
+        let (input_per_1k, output_per_1k) = Self::load_test_rates(registry, model)?;
+        let bare = model.rsplit('/').next().unwrap_or(model);
+        let prompt_tokens = registry
+            .get_tokenizer(model)
+            .or_else(|_| registry.get_tokenizer(bare))
+            .ok()
+            .and_then(|tokenizer| tokenizer.count_tokens(prompt).ok())
+            .unwrap_or_else(|| (prompt.chars().count() + 3) / 4);
+        // Like the estimate, output is projected on the prompt size, scaled
+        // by the model's typical output ratio if it has one
+        let expected_output_tokens = registry
+            .typical_output_ratio(model)
+            .map_or(prompt_tokens, |ratio| {
+                (prompt_tokens as f64 * ratio).round() as usize
+            });
+        Some(crate::simulator::budget::AttemptPricing {
+            input_per_1k,
+            output_per_1k,
+            prompt_tokens,
+            expected_output_tokens,
+        })
+    }
+
+    /// API keys for the load test: one per `--api-key-env` variable, in
+    /// order, or else the single key for the provider.
+    #[cfg(feature = "load-test")]
+    fn resolve_api_keys(args: &LoadTestArgs, provider: &Provider) -> Result<ApiKeys, AppError> {
+        if args.api_key_env.is_empty() {
+            return Ok(Self::resolve_api_key(args, provider)?.into());
+        }
+
+        let keys = args
+            .api_key_env
+            .iter()
+            .map(|var| match std::env::var(var) {
+                Ok(key) if !key.trim().is_empty() => Ok(key),
+                _ if args.dry_run => Ok("dummy-key-for-dry-run".to_string()),
+                _ => Err(AppError::Config(format!(
+                    "Environment variable {} from --api-key-env is not set",
+                    var
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ApiKeys::new(keys))
+    }
+
+    #[cfg(feature = "load-test")]
+    fn resolve_api_key(args: &LoadTestArgs, provider: &Provider) -> Result<String, AppError> {
+        let key = match provider {
+            Provider::Openai => args
+                .openai_api_key
+                .clone()
+                .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+                .or_else(|| args.api_key.clone())
+                .or_else(|| std::env::var("API_KEY").ok()),
+            Provider::Openrouter => args
+                .openrouter_api_key
+                .clone()
+                .or_else(|| std::env::var("OPENROUTER_API_KEY").ok())
+                .or_else(|| args.api_key.clone())
+                .or_else(|| std::env::var("API_KEY").ok()),
+            Provider::Anthropic => args
+                .anthropic_api_key
+                .clone()
+                .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
+                .or_else(|| args.api_key.clone())
+                .or_else(|| std::env::var("API_KEY").ok()),
+            Provider::Generic => args
+                .api_key
+                .clone()
+                .or_else(|| std::env::var("API_KEY").ok()),
+        };
+
+        match (provider, key) {
+            (Provider::Generic, Some(key)) => Ok(key),
+            (Provider::Generic, None) => Ok(String::new()),
+            (_, Some(key)) => Ok(key),
+            (_, None) if args.dry_run => Ok("dummy-key-for-dry-run".to_string()),
+            (Provider::Openai, None) => Err(AppError::Config(
+                "API key required for OpenAI. Use --openai-api-key, --api-key, or set OPENAI_API_KEY/API_KEY."
+                    .to_string(),
+            )),
+            (Provider::Openrouter, None) => Err(AppError::Config(
+                "API key required for OpenRouter. Use --openrouter-api-key, --api-key, or set OPENROUTER_API_KEY/API_KEY."
+                    .to_string(),
+            )),
+            (Provider::Anthropic, None) => Err(AppError::Config(
+                "API key required for Anthropic. Use --anthropic-api-key, --api-key, or set ANTHROPIC_API_KEY/API_KEY."
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Display load test results.
+    #[cfg(feature = "load-test")]
+    fn display_load_test_results(
+        results: &[crate::simulator::simulator::RequestResult],
+        model: &str,
+        output_format: &LoadTestOutputFormat,
+        estimate_cost: bool,
+        pricing_registry: Option<&ModelRegistry>,
+    ) -> Result<(), AppError> {
+        let total_requests = results.len();
+        let successful = results.iter().filter(|r| r.success).count();
+        let failed = total_requests.saturating_sub(successful);
+        let success_rate = if total_requests > 0 {
+            (successful as f64 / total_requests as f64) * 100.0
+        } else {
+            0.0
+        };
+        let failure_rate = if total_requests > 0 {
+            100.0 - success_rate
+        } else {
+            0.0
+        };
+
+        let latencies: Vec<u64> = results
+            .iter()
+            .filter_map(|r| if r.success { Some(r.latency_ms) } else { None })
+            .collect();
+
+        let total_tokens_reported: usize = results.iter().filter_map(|r| r.total_tokens).sum();
+        let input_tokens: usize = results.iter().filter_map(|r| r.input_tokens).sum();
+        let output_tokens: usize = results.iter().filter_map(|r| r.output_tokens).sum();
+
+        let avg_latency = if !latencies.is_empty() {
+            latencies.iter().sum::<u64>() as f64 / latencies.len() as f64
+        } else {
+            0.0
+        };
+
+        let p50 = if !latencies.is_empty() {
+            let mut sorted = latencies.clone();
+            sorted.sort_unstable();
+            sorted[sorted.len() / 2]
+        } else {
+            0
+        };
+
+        let p95 = if !latencies.is_empty() {
+            let mut sorted = latencies.clone();
+            sorted.sort_unstable();
+            let index = ((sorted.len() as f64) * 0.95).ceil() as usize;
+            let index = index.clamp(0, sorted.len().saturating_sub(1));
+            sorted[index]
+        } else {
+            0
+        };
+
+        let (input_cost, output_cost, total_cost) = if estimate_cost {
+            if let Some(registry) = pricing_registry {
+                if let Some((input_rate, output_rate)) = Self::load_test_rates(registry, model) {
+                    let pricing = ModelPricing {
+                        input: input_rate,
+                        output: output_rate,
+                        typical_output_ratio: None,
+                    };
+                    let cost = compute_cost(&pricing, input_tokens, output_tokens, 0);
+                    (Some(cost.input), Some(cost.output), Some(cost.total))
+                } else {
+                    (None, None, None)
+                }
+            } else {
+                (None, None, None)
+            }
+        } else {
+            (None, None, None)
+        };
+
+        match output_format {
+            LoadTestOutputFormat::Text => {
+                println!("\n=== Load Test Results ===");
+                println!("Total Requests: {}", total_requests);
+                println!("Successful: {} ({:.1}%)", successful, success_rate);
+                println!("Failed: {} ({:.1}%)", failed, failure_rate);
+                println!("\nLatency (ms):");
+                println!("  Average: {:.2}", avg_latency);
+                println!("  p50: {}", p50);
+                println!("  p95: {}", p95);
+                if total_tokens_reported > 0 {
+                    println!("  Total tokens reported: {}", total_tokens_reported);
+                }
+
+                if let Some(sample) = results
+                    .iter()
+                    .find(|r| r.success)
+                    .and_then(|r| r.content.as_ref())
+                {
+                    println!("  Example response: {}", preview(sample, 80));
+                }
+
+                if let Some(err) = results
+                    .iter()
+                    .find(|r| !r.success)
+                    .and_then(|r| r.error.as_ref())
+                {
+                    println!("  Last error: {}", err);
+                }
+
+                if estimate_cost {
+                    println!("\nToken Usage:");
+                    println!("  Input tokens: {}", input_tokens);
+                    println!("  Output tokens: {}", output_tokens);
+
+                    if let (Some(in_cost), Some(out_cost), Some(total)) =
+                        (input_cost, output_cost, total_cost)
+                    {
+                        println!("\nCost Estimation:");
+                        println!("  Input cost: ${:.6}", in_cost);
+                        println!("  Output cost: ${:.6}", out_cost);
+                        println!("  Total cost: ${:.6}", total);
+                    }
+                }
+            }
+            LoadTestOutputFormat::Json => {
+                let json = serde_json::json!({
+                    "total_requests": total_requests,
+                    "successful": successful,
+                    "failed": failed,
+                    "success_rate": success_rate,
+                    "latency_ms": {
+                        "average": avg_latency,
+                        "p50": p50,
+                        "p95": p95,
+                    },
+                    "tokens": {
+                        "reported": total_tokens_reported,
+                        "input": input_tokens,
+                        "output": output_tokens,
+                    },
+                    "cost_usd": {
+                        "input": input_cost,
+                        "output": output_cost,
+                        "total": total_cost,
+                    },
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&json).map_err(AppError::Json)?
+                );
+            }
+            LoadTestOutputFormat::Csv => {
+                let format_opt = |value: Option<f64>| -> String {
+                    value.map(|v| format!("{:.6}", v)).unwrap_or_default()
+                };
+
+                println!("total_requests,successful,failed,success_rate,avg_latency_ms,p50_latency_ms,p95_latency_ms,total_tokens,input_tokens,output_tokens,input_cost_usd,output_cost_usd,total_cost_usd");
+                println!(
+                    "{},{},{},{:.4},{:.2},{},{},{},{},{},{},{},{}",
+                    total_requests,
+                    successful,
+                    failed,
+                    success_rate,
+                    avg_latency,
+                    p50,
+                    p95,
+                    total_tokens_reported,
+                    input_tokens,
+                    output_tokens,
+                    format_opt(input_cost),
+                    format_opt(output_cost),
+                    format_opt(total_cost)
+                );
+            }
+            _ => {
+                println!("Format {:?} not yet implemented", output_format);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run analyze-prompts command.
+    fn run_analyze(args: AnalyzeArgs) -> Result<(), AppError> {
+        use crate::analyzers::PromptScanner;
+        use crate::output::InsightsFormatter;
+        use std::path::Path;
+
+        let currency = Self::resolve_currency(args.currency.as_deref(), args.fx_rate)?;
+        let AnalyzeArgs {
+            folder,
+            model,
+            top_n,
+            monthly_invocations,
+            context_limit,
+            format,
+            quiet,
+            ..
+        } = args;
+
+        let folder_path = Path::new(&folder);
+        if !folder_path.exists() {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!("Directory does not exist: {}", folder),
+            )));
+        }
+        if !folder_path.is_dir() {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                format!("Path is not a directory: {}", folder),
+            )));
+        }
+
+        // Create model registry
+        let registry = ModelRegistry::new_with_pricing(None).map_err(AppError::Model)?;
+
+        // Create scanner
+        let scanner = PromptScanner::new(registry, model.clone(), context_limit);
+
+        if !quiet {
+            eprintln!("Scanning directory: {}", folder);
+        }
+
+        #[cfg(feature = "progress")]
+        let analyses = {
+            use std::io::IsTerminal;
+
+            if quiet || !io::stderr().is_terminal() {
+                scanner.scan_directory(folder_path)?
+            } else {
+                let pb = indicatif::ProgressBar::new(0);
+                pb.set_style(
+                    indicatif::ProgressStyle::default_bar()
+                        .template(
+                            "{spinner:.green} [{wide_bar:.cyan/blue}] {pos}/{len} files {msg}",
+                        )
+                        .expect("the progress template is a valid constant")
+                        .progress_chars("#>-"),
+                );
+
+                let analyses = scanner.scan_directory_with_progress(folder_path, |progress| {
+                    pb.set_length(progress.total as u64);
+                    pb.set_position(progress.processed as u64);
+                    pb.set_message(format!("({} tokens)", progress.tokens));
+                });
+                pb.finish_and_clear();
+
+                analyses?
+            }
+        };
+
+        #[cfg(not(feature = "progress"))]
+        let analyses = scanner.scan_directory(folder_path)?;
+
+        if analyses.is_empty() {
+            if !quiet {
+                eprintln!("No prompt files found in directory.");
+            }
+            return Ok(());
+        }
+
+        if !quiet {
+            eprintln!("Analyzed {} prompt files", analyses.len());
+        }
+
+        // Generate insights
+        let insights = PromptScanner::generate_insights(&analyses, top_n, monthly_invocations);
+
+        // Format output
+        match format {
+            OutputFormat::Text => {
+                let output =
+                    InsightsFormatter::format_text(&insights, &model, context_limit, &currency);
+                println!("{}", output);
+            }
+            OutputFormat::Json => {
+                let output = InsightsFormatter::format_json(&insights, &currency)
+                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+                println!("{}", output);
+            }
+            #[cfg(feature = "markdown")]
+            OutputFormat::Markdown => {
+                // For now, use text format for markdown
+                let output =
+                    InsightsFormatter::format_text(&insights, &model, context_limit, &currency);
+                println!("{}", output);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count tokens for messages using the specified tokenizer.
+    fn count_tokens(
+        tokenizer: &dyn Tokenizer,
+        messages: &[crate::parsers::Message],
+        model_name: &str,
+        options: CountOptions,
+        pricing_override: Option<(f64, f64)>,
+    ) -> Result<TokenResult, AppError> {
+        let mut tally = MessageTally::new(tokenizer, model_name, options);
+        for message in messages {
+            tally.add(message)?;
+        }
+        tally.finish(pricing_override)
+    }
+
+    /// Run in diff mode, comparing two prompts.
+    fn run_diff(args: &EstimateArgs, diff_file: &str) -> Result<(), AppError> {
+        let registry = Self::registry(args)?;
+        let currency = Self::currency(args)?;
+
+        // Get both inputs
+        let (input1, input2) = if diff_file == "-" {
+            let input = InputSource::from_arg(args.input.as_deref()).read(args.max_bytes)?;
+            let separator = args
+                .diff_separator
+                .as_deref()
+                .unwrap_or(DEFAULT_DIFF_SEPARATOR);
+            split_diff_input(&input, separator)?
+        } else {
+            let input1 = InputSource::from_arg(args.input.as_deref()).read(args.max_bytes)?;
+            let input2 = InputSource::file(diff_file, "diff file").read(args.max_bytes)?;
+            (input1, input2)
+        };
+
+        // Determine model
+        let model = args.model.as_ref().ok_or_else(|| {
+            AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "Model required for diff mode. Use --model".to_string(),
+            ))
+        })?;
+
+        let tokenizer = Self::tokenizer_for(args, &registry, model)?;
+        let (result1, result2) =
+            Self::count_pair(args, &registry, &*tokenizer, model, &input1, &input2)?;
+        let messages = if args.diff_messages {
+            Some(Self::diff_transcripts(args, &*tokenizer, &input1, &input2)?)
+        } else {
+            None
+        };
+
+        // Show diff
+        let diff = result2.tokens as i64 - result1.tokens as i64;
+        let percent = percent_change(result1.tokens, result2.tokens);
+        // With an expected output or response, compare what the whole request costs
+        let with_output = args.expected_output.is_some() || args.response.is_some();
+        let costs = if args.price {
+            DiffCost::of(&result1, with_output).zip(DiffCost::of(&result2, with_output))
+        } else {
+            None
+        };
+        let cost_diff = costs.map(|(cost1, cost2)| cost2.total - cost1.total);
+        let costs = costs.filter(|_| with_output);
+
+        if matches!(args.format, OutputFormat::Json) {
+            #[derive(serde::Serialize)]
+            struct DiffJson<'a> {
+                model: &'a str,
+                original: usize,
+                modified: usize,
+                difference: i64,
+                /// `null` when the original is empty and there is no relative change
+                #[serde(skip_serializing_if = "Option::is_none")]
+                percent_change: Option<Option<f64>>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                cost_difference: Option<f64>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                currency: Option<&'a str>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                original_cost: Option<DiffCost>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                modified_cost: Option<DiffCost>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                messages: Option<Vec<MessageDiffJson<'a>>>,
+            }
+
+            #[derive(serde::Serialize)]
+            struct MessageDiffJson<'a> {
+                change: &'static str,
+                key: &'a str,
+                original: Option<usize>,
+                modified: Option<usize>,
+                difference: i64,
+            }
+
+            let json = DiffJson {
+                model,
+                original: result1.tokens,
+                modified: result2.tokens,
+                difference: diff,
+                percent_change: args.diff_percent.then_some(percent),
+                cost_difference: cost_diff.map(|diff| currency.convert(diff)),
+                currency: cost_diff.map(|_| currency.code.as_str()),
+                original_cost: costs.map(|(cost1, _)| cost1.converted(&currency)),
+                modified_cost: costs.map(|(_, cost2)| cost2.converted(&currency)),
+                messages: messages.as_ref().map(|deltas| {
+                    deltas
+                        .iter()
+                        .map(|delta| MessageDiffJson {
+                            change: delta.kind.as_str(),
+                            key: &delta.key,
+                            original: delta.before,
+                            modified: delta.after,
+                            difference: delta.difference(),
+                        })
+                        .collect()
+                }),
+            };
+            let output = serde_json::to_string_pretty(&json)
+                .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
+            println!("{}", output);
+            return Ok(());
+        }
+
+        println!("Model: {}", model);
+        println!("Original: {} tokens", result1.tokens);
+        println!("Modified: {} tokens", result2.tokens);
+        if args.diff_percent {
+            println!(
+                "Difference: {}{} tokens ({})",
+                if diff >= 0 { "+" } else { "" },
+                diff,
+                format_percent_change(result1.tokens, result2.tokens)
+            );
+        } else {
+            println!(
+                "Difference: {}{} tokens",
+                if diff >= 0 { "+" } else { "" },
+                diff
+            );
+        }
+
+        if let Some((cost1, cost2)) = costs {
+            println!("Original cost: {}", cost1.format(&currency));
+            println!("Modified cost: {}", cost2.format(&currency));
+        }
+        if let Some(cost_diff) = cost_diff {
+            println!("Cost difference: {}", currency.format(cost_diff.abs(), 4));
+        }
+
+        if let Some(deltas) = messages {
+            println!();
+            println!("Messages:");
+            for line in format_message_diff(&deltas) {
+                println!("  {}", line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Align two transcripts message by message for `--diff-messages`.
+    fn diff_transcripts(
+        args: &EstimateArgs,
+        tokenizer: &dyn Tokenizer,
+        input1: &str,
+        input2: &str,
+    ) -> Result<Vec<MessageDelta>, AppError> {
+        let messages1 = Self::message_parser(args, input1).parse(input1)?;
+        let messages2 = Self::message_parser(args, input2).parse(input2)?;
+        Ok(diff_messages(
+            tokenizer,
+            &messages1,
+            &messages2,
+            args.image_tokens.unwrap_or_default(),
+        )?)
+    }
+
+    /// Compare the input against a stored baseline, printing only the delta.
+    ///
+    /// The delta is current minus baseline tokens. Growth beyond
+    /// `--max-growth` (by default, any growth) is an error.
+    fn run_since_file(args: &EstimateArgs, since_file: &str) -> Result<(), AppError> {
+        let registry = Self::registry(args)?;
+
+        let input = InputSource::from_arg(args.input.as_deref()).read(args.max_bytes)?;
+        let baseline = InputSource::file(since_file, "baseline file").read(args.max_bytes)?;
+
+        let model = args.model.as_ref().ok_or_else(|| {
+            AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "Model required for --since-file. Use --model".to_string(),
+            ))
+        })?;
+
+        let tokenizer = Self::tokenizer_for(args, &registry, model)?;
+        let (before, after) =
+            Self::count_pair(args, &registry, &*tokenizer, model, &baseline, &input)?;
+        let delta = after.tokens as i64 - before.tokens as i64;
+        if delta > 0 {
+            println!("+{}", delta);
+        } else {
+            println!("{}", delta);
+        }
+
+        let limit = args.max_growth.unwrap_or(0);
+        if delta > limit as i64 {
+            return Err(AppError::TokenGrowthExceeded { delta, limit });
+        }
+
+        Ok(())
+    }
+
+    /// Count two prompts with the same model for a before/after comparison.
+    fn count_pair(
+        args: &EstimateArgs,
+        registry: &ModelRegistry,
+        tokenizer: &dyn Tokenizer,
+        model: &str,
+        input1: &str,
+        input2: &str,
+    ) -> Result<(TokenResult, TokenResult), AppError> {
+        if args.fail_if_unpriced && args.price {
+            Self::check_priced(registry, &[model.to_string()])?;
+        }
+
+        // Parse both inputs
+        let messages1 = Self::message_parser(args, input1).parse(input1)?;
+        let messages2 = Self::message_parser(args, input2).parse(input2)?;
+
+        // Count tokens for both
+        let pricing_override = if args.price {
+            registry.pricing_for(model)
+        } else {
+            None
+        };
+        let options = CountOptions {
+            breakdown: false,
+            price: args.price,
+            expected_output: args.expected_output,
+            chat_overhead: args.chat_overhead,
+            framing: args.framing,
+            emit_tokens: args.emit_tokens,
+            roles: Self::role_map(args),
+            throughput: args.throughput,
+            content_preview: args.content_preview.unwrap_or(DEFAULT_PREVIEW_CHARS),
+            role_markers: args.include_role_markers,
+            expected_outputs: HashMap::new(),
+            output_ratio: registry.typical_output_ratio(model),
+            images: args.image_tokens.unwrap_or_default(),
+            by_name: args.breakdown_by == Some(BreakdownBy::Name),
+            response: Self::response_texts(args)?,
+        };
+
+        let result1 = Self::count_tokens(
+            tokenizer,
+            &messages1,
+            model,
+            options.clone(),
+            pricing_override,
+        )?;
+        let result2 = Self::count_tokens(tokenizer, &messages2, model, options, pricing_override)?;
+
+        Ok((result1, result2))
+    }
+
+    /// Run in watch mode, monitoring file for changes.
+    ///
+    /// Paths given with `--watch-dir` are watched as well, so prompts
+    /// assembled from partials re-run when any fragment changes.
+    #[cfg(feature = "watch")]
+    fn run_watch(args: &EstimateArgs) -> Result<(), AppError> {
+        let input_file = args.input.as_ref().ok_or_else(|| {
+            AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "File path required for watch mode".to_string(),
+            ))
+        })?;
+
+        if input_file == "-" {
+            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                "Cannot watch stdin. Provide a file path.".to_string(),
+            )));
+        }
+
+        let mut paths = vec![PathBuf::from(input_file)];
+        paths.extend(args.watch_dir.iter().map(PathBuf::from));
+        for path in &paths {
+            if !path.exists() {
+                return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    format!("File not found: {}", path.display()),
+                )));
+            }
+        }
+
+        if args.watch_dir.is_empty() {
+            println!(
+                "Watching '{}' for changes. Press Ctrl+C to stop.",
+                input_file
+            );
+        } else {
+            println!(
+                "Watching '{}' and {} more path(s) for changes. Press Ctrl+C to stop.",
+                input_file,
+                args.watch_dir.len()
+            );
+        }
+
+        // Run initial analysis
+        Self::run_estimate_once(args)?;
+
+        watch_paths(&paths, WATCH_DEBOUNCE, args.quiet, || {
+            println!("\n--- File changed, re-analyzing ---\n");
+            if let Err(e) = Self::run_estimate_once(args) {
+                if !args.quiet {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Run a single analysis (used by watch mode).
+    #[cfg(feature = "watch")]
+    fn run_estimate_once(args: &EstimateArgs) -> Result<(), AppError> {
+        let mut new_args = args.clone();
+        new_args.watch = false;
+        Self::run_estimate(new_args)
+    }
+}
+
+/// Quiet period after a change before re-running, so that a burst of
+/// events (an editor saving several files, or writing one file in steps)
+/// triggers a single run.
+#[cfg(feature = "watch")]
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watch `paths` and call `on_change` after each debounced burst of changes.
+///
+/// Directories are watched recursively. Watcher errors go to stderr unless
+/// `quiet` is set. Returns when `on_change` breaks or the watcher shuts down.
+#[cfg(feature = "watch")]
+fn watch_paths(
+    paths: &[PathBuf],
+    debounce: Duration,
+    quiet: bool,
+    mut on_change: impl FnMut() -> ControlFlow<()>,
+) -> Result<(), AppError> {
+    use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+    use std::sync::mpsc;
+
+    // Create channel for file events
+    let (tx, rx) = mpsc::channel();
+
+    // Create watcher with config
+    let config = Config::default().with_poll_interval(Duration::from_secs(1));
+    let mut watcher = RecommendedWatcher::new(tx, config).map_err(|e| {
+        AppError::Io(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to create file watcher: {}", e),
+        ))
+    })?;
+
+    for path in paths {
+        let mode = if path.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode).map_err(|e| {
+            AppError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to watch '{}': {}", path.display(), e),
+            ))
+        })?;
+    }
+
+    // Watch for changes
+    loop {
+        match rx.recv() {
+            Ok(Ok(event)) => {
+                let changed = matches!(
+                    event.kind,
+                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+                );
+                if !changed {
+                    continue;
+                }
+                // Swallow the rest of the burst
+                while rx.recv_timeout(debounce).is_ok() {}
+                if on_change().is_break() {
+                    return Ok(());
+                }
+            }
+            Ok(Err(e)) => {
+                if !quiet {
+                    eprintln!("Watch error: {}", e);
+                }
+            }
+            Err(e) => {
+                if !quiet {
+                    eprintln!("Channel error: {}", e);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Separator line used by `--diff -` when `--diff-separator` is not given.
+const DEFAULT_DIFF_SEPARATOR: &str = "---DIFF---";
+
+/// Split a single input into the two sides of a diff.
+///
+/// The separator must appear on a line of its own; the text before it is the
+/// original prompt and the text after it the modified one.
+fn split_diff_input(input: &str, separator: &str) -> Result<(String, String), AppError> {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == separator {
+            let original = &input[..offset];
+            let modified = &input[offset + line.len()..];
+            return Ok((original.to_string(), modified.to_string()));
+        }
+        offset += line.len();
+    }
+
+    Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+        format!("Diff separator '{}' not found in input", separator),
+    )))
+}
+
+/// Request cost of one side of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+struct DiffCost {
+    input: f64,
+    /// Cost of `--expected-output` tokens, when given and the model has an
+    /// output rate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<f64>,
+    total: f64,
+}
+
+impl DiffCost {
+    /// Cost of a priced result, with its output cost if `with_output`.
+    fn of(result: &TokenResult, with_output: bool) -> Option<Self> {
+        let input = result.input_cost?;
+        let output = result.output_cost.filter(|_| with_output);
+        Some(Self {
+            input,
+            output,
+            total: input + output.unwrap_or(0.0),
+        })
+    }
+
+    /// The same cost converted from USD into `currency`.
+    fn converted(self, currency: &Currency) -> Self {
+        Self {
+            input: currency.convert(self.input),
+            output: self.output.map(|output| currency.convert(output)),
+            total: currency.convert(self.total),
+        }
+    }
+
+    /// Total with its parts in `currency`, e.g.
+    /// `$0.0120 (input $0.0060 + output $0.0060)`.
+    fn format(&self, currency: &Currency) -> String {
+        match self.output {
+            Some(output) => format!(
+                "{} (input {} + output {})",
+                currency.format(self.total, 4),
+                currency.format(self.input, 4),
+                currency.format(output, 4)
+            ),
+            None => format!("{} (input only)", currency.format(self.total, 4)),
+        }
+    }
+}
+
+/// Change from `before` to `after` tokens as a percentage of `before`.
+///
+/// Returns `None` when `before` is zero, where any growth is new content.
+fn percent_change(before: usize, after: usize) -> Option<f64> {
+    (before > 0).then(|| (after as f64 - before as f64) / before as f64 * 100.0)
+}
+
+/// The change from `before` to `after` as a signed percentage such as `+12.5%`.
+///
+/// Growth from an empty original is reported as `new`, and no change from
+/// one as `n/a`.
+fn format_percent_change(before: usize, after: usize) -> String {
+    match percent_change(before, after) {
+        Some(p) if p >= 0.0 => format!("+{:.1}%", p),
+        Some(p) => format!("{:.1}%", p),
+        None if after > 0 => "new".to_string(),
+        None => "n/a".to_string(),
+    }
+}
+
+/// One line per aligned message for `--diff-messages`, keys padded to align.
+///
+/// Lines start with `=` (unchanged), `~` (changed), `+` (added) or `-` (removed).
+fn format_message_diff(deltas: &[MessageDelta]) -> Vec<String> {
+    let width = deltas
+        .iter()
+        .map(|delta| delta.key.chars().count())
+        .max()
+        .unwrap_or(0);
+    deltas
+        .iter()
+        .map(|delta| {
+            let (before, after) = (delta.before.unwrap_or(0), delta.after.unwrap_or(0));
+            let (marker, tokens) = match delta.kind {
+                ChangeKind::Unchanged => ('=', format!("{} tokens", after)),
+                ChangeKind::Changed => (
+                    '~',
+                    format!(
+                        "{} -> {} tokens ({}{})",
+                        before,
+                        after,
+                        if delta.difference() >= 0 { "+" } else { "" },
+                        delta.difference()
+                    ),
+                ),
+                ChangeKind::Added => ('+', format!("{} tokens (added)", after)),
+                ChangeKind::Removed => ('-', format!("{} tokens (removed)", before)),
+            };
+            format!(
+                "{} {:<width$}  {}",
+                marker,
+                delta.key,
+                tokens,
+                width = width
+            )
+        })
+        .collect()
+}
+
+/// Split `MODEL:TOKENS` compare entries into model names and expected outputs.
+///
+/// Only an all-digit suffix is read as an expected output, so model names
+/// that contain a colon (e.g. `:free` variants) are kept intact.
+///
+/// # Errors
+///
+/// Returns an error if one model is given two different expected outputs.
+fn compare_expected_outputs(
+    entries: &[String],
+) -> Result<(Vec<String>, HashMap<String, usize>), AppError> {
+    let mut models = Vec::with_capacity(entries.len());
+    let mut expected_outputs = HashMap::new();
+    for entry in entries {
+        let split = entry.rsplit_once(':').filter(|(model, tokens)| {
+            !model.is_empty() && !tokens.is_empty() && tokens.bytes().all(|b| b.is_ascii_digit())
+        });
+        let Some((model, tokens)) = split else {
+            models.push(entry.clone());
+            continue;
+        };
+        let tokens: usize = tokens.parse().map_err(|_| {
+            AppError::Parse(crate::error::ParseError::InvalidFormat(format!(
+                "Expected output in --compare entry '{}' is too large",
+                entry
+            )))
+        })?;
+        if let Some(previous) = expected_outputs.insert(model.to_string(), tokens) {
+            if previous != tokens {
+                return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+                    format!(
+                        "Conflicting expected outputs for '{}' in --compare: {} and {}",
+                        model, previous, tokens
+                    ),
+                )));
+            }
+        }
+        models.push(model.to_string());
+    }
+    Ok((models, expected_outputs))
+}
+
+/// Expand `--compare` entries, reading `@path` entries as model list files.
+///
+/// A list file names one model per line; blank lines and `#` comments are
+/// skipped. Inline names and files can be mixed, and order is preserved.
+fn expand_compare_models(entries: &[String]) -> Result<Vec<String>, AppError> {
+    let mut models = Vec::new();
+    for entry in entries {
+        let Some(path) = entry.strip_prefix('@') else {
+            models.push(entry.trim().to_string());
+            continue;
+        };
+        let content = InputSource::file(path, "model list").read(None)?;
+        models.extend(
+            content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or("").trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+    }
+    models.retain(|model| !model.is_empty());
+
+    if models.is_empty() {
+        return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
+            "No models to compare: --compare list is empty".to_string(),
+        )));
+    }
+    Ok(models)
+}
+
+/// Drop models that resolve to the same canonical name as an earlier one.
+///
+/// Returns the models to keep, in order, and each dropped model paired
+/// with the one it duplicates.
+fn dedupe_models(
+    registry: &ModelRegistry,
+    models: Vec<String>,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let mut kept: Vec<(String, String)> = Vec::with_capacity(models.len());
+    let mut duplicates = Vec::new();
+    for model in models {
+        let canonical = registry.resolve_alias(&model);
+        match kept.iter().find(|(_, seen)| *seen == canonical) {
+            Some((first, _)) => duplicates.push((model, first.clone())),
+            None => kept.push((model, canonical)),
+        }
+    }
+    (
+        kept.into_iter().map(|(model, _)| model).collect(),
+        duplicates,
+    )
+}
+
+/// JSON array inputs at least this large are streamed without `--stream`.
+const STREAM_THRESHOLD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Maximum number of token ids included in output by `--emit-tokens`.
+const MAX_EMITTED_TOKEN_IDS: usize = 100_000;
+
+/// A model's tokenizer with its canonical name and pricing override.
+struct ResolvedModel {
+    tokenizer: Box<dyn Tokenizer>,
+    name: String,
+    pricing_override: Option<(f64, f64)>,
+}
+
+/// Running token counts for one model, fed one message at a time.
+struct MessageTally<'a> {
+    tokenizer: &'a dyn Tokenizer,
+    model_name: &'a str,
+    options: CountOptions,
+    counter: ChatCounter<'a>,
+    /// Per-message counts; `None` when streaming, so memory stays flat.
+    per_message: Option<Vec<(String, usize)>>,
+    /// Content previews matching `per_message` (with `--content-preview`).
+    previews: Option<Vec<String>>,
+    breakdown: Option<TokenBreakdown>,
+    token_ids: Option<Vec<usize>>,
+    token_ids_truncated: bool,
+    /// Tokens estimated for inline images removed from the content.
+    image_tokens: usize,
+    /// Tokens per participant name (with `--breakdown-by name`).
+    by_name: Option<HashMap<String, usize>>,
+}
+
+impl<'a> MessageTally<'a> {
+    fn new(tokenizer: &'a dyn Tokenizer, model_name: &'a str, options: CountOptions) -> Self {
+        let overhead = match options.framing {
+            Some(style) => style.overhead(),
+            None if options.chat_overhead => ChatOverhead::for_model(model_name),
+            None => ChatOverhead::default(),
+        };
+        let emit_ids = options.emit_tokens && tokenizer.is_exact();
+        let mut counter = ChatCounter::new(tokenizer, overhead);
+        if options.role_markers {
+            counter = counter.with_role_markers();
+        }
+        Self {
+            tokenizer,
+            model_name,
+            counter,
+            per_message: Some(Vec::new()),
+            previews: (options.content_preview > 0).then(Vec::new),
+            breakdown: options.breakdown.then(TokenBreakdown::new),
+            by_name: (options.breakdown && options.by_name).then(HashMap::new),
+            options,
+            token_ids: emit_ids.then(Vec::new),
+            token_ids_truncated: false,
+            image_tokens: 0,
+        }
+    }
+
+    /// Keep only the running totals and role breakdown, not a count per message.
+    fn without_per_message(mut self) -> Self {
+        self.per_message = None;
+        self.previews = None;
+        self
+    }
+
+    fn add(&mut self, message: &crate::parsers::Message) -> Result<(), AppError> {
+        let (content, image_tokens) = self.options.images.apply(&message.content);
+        let stripped;
+        let message = match content {
+            Cow::Borrowed(_) => message,
+            Cow::Owned(content) => {
+                stripped = crate::parsers::Message {
+                    role: message.role.clone(),
+                    content,
+                    name: message.name.clone(),
+                    tool_call_id: message.tool_call_id.clone(),
+                };
+                &stripped
+            }
+        };
+        let count = match self.token_ids {
+            Some(ref mut token_ids) => {
+                let (count, ids) = self.counter.add_encoded(message)?;
+                let room = MAX_EMITTED_TOKEN_IDS - token_ids.len();
+                self.token_ids_truncated |= ids.len() > room;
+                token_ids.extend(ids.into_iter().take(room));
+                count
+            }
+            None => self.counter.add(message)?,
+        } + image_tokens;
+        self.image_tokens += image_tokens;
+        if let Some(ref mut per_message) = self.per_message {
+            per_message.push((message.role.clone(), count));
+        }
+        if let Some(ref mut previews) = self.previews {
+            previews.push(preview(&message.content, self.options.content_preview));
+        }
+        if let Some(ref mut by_name) = self.by_name {
+            let name = match &message.name {
+                Some(name) => name.clone(),
+                None => self.options.roles.canonical(&message.role).to_string(),
+            };
+            *by_name.entry(name).or_default() += count;
+        }
+        if let Some(ref mut bd) = self.breakdown {
+            match self.options.roles.canonical(&message.role) {
+                "system" => bd.system += count,
+                "user" => bd.user += count,
+                "assistant" => bd.assistant += count,
+                "tool" => bd.tool += count,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, pricing_override: Option<(f64, f64)>) -> Result<TokenResult, AppError> {
+        let mut count = self.counter.finish()?;
+        count.content += self.image_tokens;
+        let total = count.total();
+        let mut token_breakdown = self.breakdown;
+        if let Some(ref mut bd) = token_breakdown {
+            bd.total = total;
+        }
+
+        // Calculate costs
+        let (input_rate, output_rate) = if self.options.price {
+            (
+                pricing_override
+                    .map(|p| p.0)
+                    .or_else(|| self.tokenizer.input_price_per_1k()),
+                pricing_override
+                    .map(|p| p.1)
+                    .or_else(|| self.tokenizer.output_price_per_1k()),
+            )
+        } else {
+            (None, None)
+        };
+
+        // A recorded response is priced on what it actually contains
+        let response_tokens = match &self.options.response {
+            Some(texts) => Some(
+                texts
+                    .iter()
+                    .map(|text| self.tokenizer.count_tokens(text))
+                    .sum::<Result<usize, _>>()?,
+            ),
+            None => None,
+        };
+        let expected_output = response_tokens.or(self.options.expected_output);
+
+        // Without an expected output length, output cost is projected on the input
+        // size, scaled by the model's typical output ratio if it has one
+        let output_tokens = expected_output.unwrap_or_else(|| {
+            self.options
+                .output_ratio
+                .map_or(total, |ratio| (total as f64 * ratio).round() as usize)
+        });
+        // Each side is priced on its own rate; input-only models (embeddings)
+        // have no output rate and no output cost
+        let pricing = ModelPricing {
+            input: input_rate.unwrap_or(0.0),
+            output: output_rate.unwrap_or(0.0),
+            typical_output_ratio: None,
+        };
+        let cost = compute_cost(&pricing, total, output_tokens, 0);
+
+        Ok(TokenResult {
+            model: self.model_name.to_string(),
+            tokens: total,
+            content_tokens: count.content,
+            overhead_tokens: count.overhead,
+            input_cost: input_rate.map(|_| cost.input),
+            output_cost: output_rate.map(|_| cost.output),
+            breakdown: token_breakdown,
+            by_name: self.by_name,
+            input_rate,
+            output_rate,
+            expected_output,
+            response_tokens,
+            context_window: self.tokenizer.context_window(),
+            // A single message adds nothing over the total
+            messages: self.per_message.filter(|m| m.len() > 1),
+            message_previews: self.previews.filter(|p| p.len() > 1),
+            token_ids: self.token_ids,
+            token_ids_truncated: self.token_ids_truncated,
+            approximate: self.options.emit_tokens && !self.tokenizer.is_exact(),
+            encoding: self.tokenizer.encoding().map(str::to_string),
+            projection: self
+                .options
+                .throughput
+                .zip(expected_output)
+                .map(|(rate, output)| {
+                    ResponseProjection::new(output, rate, input_rate.map(|_| cost.total))
+                }),
+        })
+    }
+}
+
+/// Options controlling how tokens and costs are counted.
+#[derive(Debug, Clone, Default)]
+struct CountOptions {
+    breakdown: bool,
+    price: bool,
+    expected_output: Option<usize>,
+    chat_overhead: bool,
+    framing: Option<FramingStyle>,
+    emit_tokens: bool,
+    roles: RoleMap,
+    throughput: Option<f64>,
+    content_preview: usize,
+    role_markers: bool,
+    /// Per-model expected output from `--compare MODEL:TOKENS` entries
+    expected_outputs: HashMap<String, usize>,
+    /// Output length as a multiple of the input, when no expected output is given
+    output_ratio: Option<f64>,
+    /// How inline base64 images are counted
+    images: ImageTokens,
+    /// Break the total down by participant name instead of role
+    by_name: bool,
+    /// Completion texts from `--response`, counted as the output
+    response: Option<Vec<String>>,
+}
+
+impl CountOptions {
+    /// Options for counting `model`, with its own expected output if it has
+    /// one and its typical output ratio from the registry.
+    fn for_model(&self, registry: &ModelRegistry, model: &str) -> CountOptions {
+        let mut options = self.clone();
+        if let Some(&expected) = self.expected_outputs.get(model) {
+            options.expected_output = Some(expected);
+        }
+        options.output_ratio = registry.typical_output_ratio(model);
+        options
+    }
+}
+
+/// Estimate command arguments, given before or after `estimate`.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct EstimateArgs {
+    /// Input file path (use '-' for stdin or omit for direct text input)
+    #[arg(value_name = "FILE|TEXT")]
+    input: Option<String>,
+
+    /// Model to use for tokenization (e.g., gpt-4, gpt-3.5-turbo)
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Compare multiple models (`@FILE` reads one model per line; `MODEL:TOKENS` sets its expected output)
+    #[arg(short, long, num_args = 1..)]
+    compare: Vec<String>,
+
+    /// Show token breakdown by role (system/user/assistant)
+    #[arg(short, long)]
+    breakdown: bool,
+
+    /// Output format (or set TOKUIN_FORMAT)
+    #[arg(
+        short,
+        long,
+        value_parser = OutputFormatParser,
+        default_value = "text",
+        default_value_if("canonical", ArgPredicate::IsPresent, Some("json")),
+        env = "TOKUIN_FORMAT"
+    )]
+    format: OutputFormat,
+
+    /// Show pricing information
+    #[arg(short, long)]
+    price: bool,
+
+    /// Strip markdown formatting to show token savings
+    #[arg(long)]
+    #[cfg_attr(feature = "sampling", arg(conflicts_with = "sample"))]
+    #[cfg(feature = "markdown")]
+    minify: bool,
+
+    /// Compare two prompts and show token differences ('-' splits the input on a separator)
+    #[arg(long)]
+    diff: Option<String>,
+
+    /// Watch file for changes and re-run automatically
+    #[arg(short, long)]
+    #[cfg(feature = "watch")]
+    watch: bool,
+
+    /// Also watch these files or directories (recursively) and re-run when they change
+    #[arg(long, value_name = "PATH", requires = "watch")]
+    #[cfg(feature = "watch")]
+    watch_dir: Vec<String>,
+
+    /// Abort on the first model that fails instead of reporting partial results
+    #[arg(long)]
+    strict: bool,
+
+    /// Expected number of output tokens, used for output cost and break-even analysis
+    #[arg(long, value_name = "TOKENS")]
+    expected_output: Option<usize>,
+
+    /// Maximum parallel jobs for batch counting (default: number of CPUs)
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Count the fixed per-message and reply-priming tokens chat APIs add
+    #[arg(long)]
+    chat_overhead: bool,
+
+    /// Separator line splitting the input into two prompts for `--diff -` (default: ---DIFF---)
+    #[arg(long, value_name = "LINE")]
+    diff_separator: Option<String>,
+
+    /// Include token ids in JSON output (exact tokenizers only)
+    #[arg(long)]
+    emit_tokens: bool,
+
+    /// Count with a local tokenizer file (tokenizer.json or SentencePiece .model) instead of a built-in model
+    #[arg(long, value_name = "PATH", conflicts_with = "compare")]
+    tokenizer_file: Option<String>,
+
+    /// Fail when the estimated cost (input plus expected output) exceeds this many USD
+    #[arg(long, value_name = "USD")]
+    max_cost: Option<f64>,
+
+    /// Check that encoding then decoding the input reproduces it
+    #[arg(long)]
+    verify_roundtrip: bool,
+
+    /// Report trimmable whitespace in message content and the tokens it costs
+    #[arg(long)]
+    warn_whitespace: bool,
+
+    /// Role framing to count as chat overhead (default: detected from the model; implies --chat-overhead)
+    #[arg(long, value_enum, value_name = "STYLE")]
+    framing: Option<FramingStyle>,
+
+    /// Count a JSON message array as it is read instead of loading it whole (automatic for large files)
+    #[arg(long, conflicts_with_all = ["diff", "warn_whitespace", "verify_roundtrip", "tokenizer_file", "emit_tokens"])]
+    stream: bool,
+
+    /// Thousands separator style for token counts in text output (en, de, fr)
+    #[arg(long, value_enum, value_name = "LOCALE")]
+    locale: Option<NumberLocale>,
+
+    /// Print token counts in text output without thousands separators
+    #[arg(long, conflicts_with = "locale")]
+    no_separators: bool,
 
-        // Generate insights
-        let insights = PromptScanner::generate_insights(&analyses, top_n, monthly_invocations);
+    /// Input format, overriding detection from the content (auto, text, json)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    input_format: Option<InputFormat>,
 
-        // Format output
-        match format {
-            OutputFormat::Text => {
-                let output = InsightsFormatter::format_text(&insights, &model, context_limit);
-                println!("{}", output);
-            }
-            OutputFormat::Json => {
-                let output = InsightsFormatter::format_json(&insights)
-                    .map_err(|e| AppError::Parse(crate::error::ParseError::InvalidJson(e)))?;
-                println!("{}", output);
-            }
-            #[cfg(feature = "markdown")]
-            OutputFormat::Markdown => {
-                // For now, use text format for markdown
-                let output = InsightsFormatter::format_text(&insights, &model, context_limit);
-                println!("{}", output);
-            }
-        }
+    /// Show each compared model's token delta against this model
+    #[arg(long, value_name = "MODEL", requires = "compare")]
+    baseline: Option<String>,
 
-        Ok(())
-    }
+    /// Count role ALIAS as ROLE in the breakdown (repeatable; developer=system is built in)
+    #[arg(long, value_name = "ALIAS=ROLE", value_parser = parse_role_alias)]
+    role_alias: Vec<(String, String)>,
 
-    /// Get input from file, stdin, or argument.
-    fn get_input(input: &Option<String>) -> Result<String, AppError> {
-        if let Some(input) = input {
-            if input == "-" {
-                // Read from stdin
-                let mut buffer = String::new();
-                io::stdin().read_to_string(&mut buffer)?;
-                Ok(buffer)
-            } else {
-                // Read from file
-                std::fs::read_to_string(input).map_err(|e| {
-                    AppError::Io(std::io::Error::other(format!(
-                        "Failed to read file '{}': {}",
-                        input, e
-                    )))
-                })
-            }
-        } else {
-            // Read from stdin (if no argument provided)
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
-            Ok(buffer)
-        }
-    }
+    /// Few-shot examples file, counted as its own segment and combined with the prompt
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["diff", "stream"])]
+    examples: Option<String>,
 
-    /// Count tokens for messages using the specified tokenizer.
-    fn count_tokens(
-        tokenizer: &dyn Tokenizer,
-        messages: &[crate::parsers::Message],
-        model_name: &str,
-        breakdown: bool,
-        price: bool,
-        pricing_override: Option<(f64, f64)>,
-    ) -> Result<TokenResult, AppError> {
-        let mut total = 0;
-        let mut token_breakdown = if breakdown {
-            Some(TokenBreakdown::new())
-        } else {
-            None
-        };
+    /// Keep repeated models in --compare instead of dropping duplicates
+    #[arg(long, requires = "compare")]
+    allow_duplicates: bool,
 
-        for message in messages {
-            let count = tokenizer.count_tokens(&message.content)?;
-            total += count;
-
-            if let Some(ref mut bd) = token_breakdown {
-                match message.role.as_str() {
-                    "system" => bd.system += count,
-                    "user" => bd.user += count,
-                    "assistant" => bd.assistant += count,
-                    _ => {}
-                }
-            }
-        }
+    /// Print only the name of the cheapest priced model in --compare
+    #[arg(long, requires = "compare")]
+    cheapest: bool,
 
-        if let Some(ref mut bd) = token_breakdown {
-            bd.total = total;
-        }
+    /// With --cheapest, skip models whose prompt plus expected output exceeds this many tokens
+    #[arg(long, value_name = "TOKENS", requires = "cheapest")]
+    context_limit: Option<usize>,
 
-        // Calculate costs
-        let override_input = pricing_override.map(|p| p.0);
-        let override_output = pricing_override.map(|p| p.1);
+    /// Strip code comments in the given language (c, hash, html, or a language name) to show token savings
+    #[arg(long, value_enum, value_name = "LANG", conflicts_with_all = ["diff", "stream"])]
+    strip_comments: Option<CommentStyle>,
 
-        let input_cost = if price {
-            override_input
-                .or_else(|| tokenizer.input_price_per_1k())
-                .map(|rate| (total as f64 / 1000.0) * rate)
-        } else {
-            None
-        };
+    /// Compare against a stored baseline prompt and print only the signed token delta
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["diff", "compare", "examples"])]
+    since_file: Option<String>,
 
-        let output_cost = if price {
-            override_output
-                .or_else(|| tokenizer.output_price_per_1k())
-                .map(|rate| (total as f64 / 1000.0) * rate)
-        } else {
-            None
-        };
+    /// Fail when --since-file growth exceeds this many tokens (default: any growth)
+    #[arg(long, value_name = "TOKENS", requires = "since_file")]
+    max_growth: Option<usize>,
 
-        Ok(TokenResult {
-            model: model_name.to_string(),
-            tokens: total,
-            input_cost,
-            output_cost,
-            breakdown: token_breakdown,
-        })
-    }
+    /// Print nothing on stderr: no warnings, notes or errors; the exit status reports failure
+    #[arg(short, long)]
+    quiet: bool,
 
-    /// Run in diff mode, comparing two prompts.
-    fn run_diff(args: &EstimateArgs, diff_file: &str) -> Result<(), AppError> {
-        let registry = ModelRegistry::new_with_pricing(args.pricing_file.as_deref())
-            .map_err(AppError::Model)?;
+    /// Map an Azure OpenAI deployment name to the model it serves (repeatable, NAME=MODEL)
+    #[arg(long, value_name = "NAME=MODEL", value_parser = parse_deployment)]
+    azure_deployment: Vec<(String, String)>,
 
-        // Get both inputs
-        let input1 = Self::get_input(&args.input)?;
-        let input2 = std::fs::read_to_string(diff_file).map_err(|e| {
-            AppError::Io(std::io::Error::other(format!(
-                "Failed to read diff file '{}': {}",
-                diff_file, e
-            )))
-        })?;
+    /// Typical output throughput in tokens/sec, to project response latency with --expected-output
+    #[arg(long, value_name = "TOKENS_PER_SEC", value_parser = parse_throughput, requires = "expected_output")]
+    throughput: Option<f64>,
 
-        // Determine model
-        let model = args.model.as_ref().ok_or_else(|| {
-            AppError::Parse(crate::error::ParseError::InvalidFormat(
-                "Model required for diff mode. Use --model".to_string(),
-            ))
-        })?;
+    /// Include a metadata header (version, timestamp, input and pricing source) in the report
+    #[arg(long)]
+    with_metadata: bool,
 
-        let tokenizer = registry.get_tokenizer(model)?;
+    /// Report system prompts repeated across Batch API requests and the tokens they resend
+    #[arg(long)]
+    dedupe_system: bool,
 
-        // Parse both inputs
-        let parser1: Box<dyn InputParser> =
-            if input1.trim_start().starts_with('{') || input1.trim_start().starts_with('[') {
-                Box::new(JsonParser::new())
-            } else {
-                Box::new(TextParser::new())
-            };
+    /// Characters of each message's content to show in per-message output (0 hides content; default 60)
+    #[arg(long, value_name = "N")]
+    content_preview: Option<usize>,
 
-        let parser2: Box<dyn InputParser> =
-            if input2.trim_start().starts_with('{') || input2.trim_start().starts_with('[') {
-                Box::new(JsonParser::new())
-            } else {
-                Box::new(TextParser::new())
-            };
+    /// Exit non-zero if pricing is requested for a model without pricing data
+    #[arg(long)]
+    fail_if_unpriced: bool,
 
-        let messages1 = parser1.parse(&input1)?;
-        let messages2 = parser2.parse(&input2)?;
+    /// Show costs in this currency (ISO code such as EUR; requires --fx-rate unless USD)
+    #[arg(long, value_name = "CODE", value_parser = parse_currency_code)]
+    currency: Option<String>,
 
-        // Count tokens for both
-        let pricing_override = if args.price {
-            registry.pricing_for(model)
-        } else {
-            None
-        };
+    /// Exchange rate from USD to --currency, in units per dollar
+    #[arg(long, value_name = "RATE", value_parser = parse_fx_rate, requires = "currency")]
+    fx_rate: Option<f64>,
 
-        let result1 = Self::count_tokens(
-            &*tokenizer,
-            &messages1,
-            model,
-            false,
-            args.price,
-            pricing_override,
-        )?;
-        let result2 = Self::count_tokens(
-            &*tokenizer,
-            &messages2,
-            model,
-            false,
-            args.price,
-            pricing_override,
-        )?;
+    /// Show where the two --compare models first split the input into different tokens
+    #[arg(long, requires = "compare", conflicts_with_all = ["diff", "stream", "verify_roundtrip", "tokenizer_file"])]
+    compare_encodings_detail: bool,
 
-        // Show diff
-        let diff = result2.tokens as i64 - result1.tokens as i64;
-        println!("Model: {}", model);
-        println!("Original: {} tokens", result1.tokens);
-        println!("Modified: {} tokens", result2.tokens);
-        println!(
-            "Difference: {}{} tokens",
-            if diff >= 0 { "+" } else { "" },
-            diff
-        );
+    /// Sum the role breakdown of every transcript in a directory (per file with --breakdown)
+    #[arg(long, conflicts_with_all = ["diff", "stream", "examples"])]
+    group_by_role: bool,
 
-        if args.price {
-            if let (Some(cost1), Some(cost2)) = (result1.input_cost, result2.input_cost) {
-                let cost_diff = cost2 - cost1;
-                println!("Cost difference: ${:.4}", cost_diff.abs());
-            }
-        }
+    /// Normalize line endings before counting and report the token difference [default: preserve]
+    #[arg(long, value_name = "STYLE", value_enum)]
+    line_endings: Option<LineEndings>,
 
-        Ok(())
-    }
+    /// Count each message's role string (system/user/assistant) with the message, as OpenAI does
+    #[arg(long)]
+    include_role_markers: bool,
+
+    /// Render messages with a Jinja chat template (template file or tokenizer_config.json) and count the full prompt
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["chat_overhead", "framing", "include_role_markers", "breakdown", "stream", "group_by_role"])]
+    chat_template: Option<String>,
+
+    /// Refuse inputs with more than N messages (see --on-max-messages)
+    #[arg(long, value_name = "N")]
+    max_messages: Option<usize>,
+
+    /// What to do above --max-messages: error or truncate [default: error]
+    #[arg(long, value_enum, value_name = "ACTION", requires = "max_messages")]
+    on_max_messages: Option<LimitAction>,
+
+    /// With --diff, also show the change as a percentage of the original count
+    #[arg(long, requires = "diff")]
+    diff_percent: bool,
+
+    /// Construct and prime the selected tokenizers before counting, reporting load time on stderr
+    #[arg(long, conflicts_with = "tokenizer_file")]
+    warmup: bool,
+
+    /// Price each message of a recorded conversation: assistant turns at the output rate, all others at the input rate
+    #[arg(long, conflicts_with_all = ["compare", "diff", "stream", "examples", "group_by_role", "chat_template"])]
+    replay_cost: bool,
+
+    /// Apply Unicode normalization before counting and report the token difference [default: none]
+    #[arg(long, value_name = "FORM", value_enum)]
+    unicode_normalize: Option<UnicodeNormalization>,
+
+    /// JSON pointer to the messages array in a custom payload, e.g. /request/body/messages
+    #[arg(long, value_name = "POINTER")]
+    json_path_messages: Option<String>,
+
+    /// JSON pointer to the role within each message [default: /role]
+    #[arg(long, value_name = "POINTER")]
+    json_path_role: Option<String>,
+
+    /// JSON pointer to the content within each message [default: /content]
+    #[arg(long, value_name = "POINTER")]
+    json_path_content: Option<String>,
+
+    /// Bucket messages by token count and print a histogram of message sizes
+    #[arg(long, conflicts_with_all = ["compare", "diff", "stream", "examples", "group_by_role", "replay_cost"])]
+    histogram: bool,
+
+    /// Upper bounds of the histogram buckets, ascending [default: 10,50,200]
+    #[arg(
+        long,
+        value_name = "N,N,...",
+        value_delimiter = ',',
+        requires = "histogram"
+    )]
+    histogram_buckets: Option<Vec<usize>>,
+
+    /// Compare one flagship model per built-in provider (e.g. gpt-4 vs gemini-2.5-pro)
+    #[arg(long, conflicts_with_all = ["compare", "model", "tokenizer_file"])]
+    compare_providers: bool,
+
+    /// Show details for --model (provider, encoding, context window, prices) without reading any input
+    #[arg(long, requires = "model", conflicts_with_all = ["compare", "diff", "since_file", "compare_providers"])]
+    model_info: bool,
+
+    /// How to count inline base64 data:image URIs: a fixed estimate per image, stripped, or as literal text [default: estimate]
+    #[arg(long, value_name = "MODE", value_enum)]
+    image_tokens: Option<ImageTokens>,
+
+    /// Count each prompt file against every model and print a grid (rows = files, columns = models)
+    #[arg(long, value_name = "FILE", num_args = 1.., conflicts_with_all = ["input", "diff", "since_file", "stream", "examples", "replay_cost", "histogram", "cheapest"])]
+    inputs: Vec<String>,
+
+    /// Print the --inputs grid as CSV
+    #[arg(long, requires = "inputs")]
+    csv: bool,
+
+    /// Group the --breakdown by role, or by participant name (unnamed messages fall back to their role) [default: role]
+    #[arg(long, value_name = "KEY", value_enum, requires = "breakdown")]
+    breakdown_by: Option<BreakdownBy>,
+
+    /// Fail (exit code 3) when the token count is below N; with --compare, the largest count is checked
+    #[arg(long, value_name = "N")]
+    min_tokens: Option<usize>,
+
+    /// Fail (exit code 4) when the token count is above N; with --compare, the largest count is checked
+    #[arg(long, value_name = "N")]
+    max_tokens: Option<usize>,
+
+    /// With --diff, also align the transcripts message by message and show each change
+    #[arg(long, requires = "diff")]
+    diff_messages: bool,
+
+    /// Print stable JSON for snapshot tests: results sorted by model, floats rounded, no metadata (implies --format json)
+    #[arg(long)]
+    canonical: bool,
 
-    /// Run in watch mode, monitoring file for changes.
-    #[cfg(feature = "watch")]
-    fn run_watch(args: &EstimateArgs) -> Result<(), AppError> {
-        use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-        use std::sync::mpsc;
+    /// Reject inputs larger than N bytes instead of reading them
+    #[arg(long, value_name = "N")]
+    max_bytes: Option<usize>,
 
-        let input_file = args.input.as_ref().ok_or_else(|| {
-            AppError::Parse(crate::error::ParseError::InvalidFormat(
-                "File path required for watch mode".to_string(),
-            ))
-        })?;
+    /// With --compare, name the winning models by fewest tokens, lowest input and total cost, and most context headroom
+    #[arg(long)]
+    winners: bool,
 
-        if input_file == "-" {
-            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
-                "Cannot watch stdin. Provide a file path.".to_string(),
-            )));
-        }
+    /// Reject messages whose role is not system, developer, user, assistant, tool or function (or a --role-alias)
+    #[arg(long)]
+    strict_json: bool,
 
-        let path = PathBuf::from(input_file);
-        if !path.exists() {
-            return Err(AppError::Parse(crate::error::ParseError::InvalidFormat(
-                format!("File not found: {}", input_file),
-            )));
-        }
+    /// Show the tokens left in the model's context window after the prompt
+    #[arg(long)]
+    headroom: bool,
 
-        println!(
-            "Watching '{}' for changes. Press Ctrl+C to stop.",
-            input_file
-        );
+    /// Fail when the prompt is larger than the model's context window
+    #[arg(long)]
+    check_context: bool,
 
-        // Create channel for file events
-        let (tx, rx) = mpsc::channel();
+    /// Plan how --section files share a token budget, in priority order
+    #[arg(long, value_name = "TOKENS", requires = "section")]
+    allocate: Option<usize>,
 
-        // Create watcher with config
-        let config = Config::default().with_poll_interval(Duration::from_secs(1));
-        let mut watcher = RecommendedWatcher::new(tx, config).map_err(|e| {
-            AppError::Io(std::io::Error::other(format!(
-                "Failed to create file watcher: {}",
-                e
-            )))
-        })?;
+    /// Labeled prompt section for --allocate, highest priority first (repeatable)
+    #[arg(long, value_name = "LABEL=FILE", value_parser = parse_section, requires = "allocate")]
+    section: Vec<(String, String)>,
 
-        watcher
-            .watch(&path, RecursiveMode::NonRecursive)
-            .map_err(|e| {
-                AppError::Io(std::io::Error::other(format!(
-                    "Failed to watch file: {}",
-                    e
-                )))
-            })?;
+    /// Explain step by step how a short input is tokenized (text only)
+    #[arg(long, conflicts_with_all = ["compare_encodings_detail", "verify_roundtrip"])]
+    explain_tokenization: bool,
 
-        // Run initial analysis
-        Self::run_estimate_once(args)?;
+    /// Strip one trailing newline before counting and report the token difference
+    #[arg(long)]
+    trim_trailing_newline: bool,
+
+    /// Estimate large plain-text inputs from this many randomly sampled chunks, read as is
+    #[arg(
+        long,
+        value_name = "CHUNKS",
+        conflicts_with_all = [
+            "strip_comments",
+            "strip_frontmatter",
+            "trim_trailing_newline",
+            "line_endings",
+            "unicode_normalize",
+            "compare_inputs",
+        ]
+    )]
+    #[cfg(feature = "sampling")]
+    sample: Option<NonZeroUsize>,
+
+    /// Seed for --sample, so repeated runs pick the same chunks
+    #[arg(long, requires = "sample")]
+    #[cfg(feature = "sampling")]
+    seed: Option<u64>,
+
+    /// Recorded completion (text or an OpenAI chat-completion JSON) counted and priced as the output
+    #[arg(long, value_name = "FILE", conflicts_with = "expected_output")]
+    response: Option<String>,
+
+    /// Also show costs with this percentage markup over the provider's price
+    #[arg(long, value_name = "PERCENT", value_parser = parse_markup)]
+    markup: Option<f64>,
+
+    /// Treat each positional argument as an inline prompt and print one row per prompt
+    #[arg(long, conflicts_with_all = ["inputs", "diff", "since_file", "stream", "examples", "replay_cost", "histogram", "cheapest", "allocate"])]
+    compare_inputs: bool,
+
+    /// Further inline prompts counted with --compare-inputs
+    #[arg(value_name = "TEXT", requires = "compare_inputs")]
+    extra_inputs: Vec<String>,
+
+    /// Strip leading YAML (---) or TOML (+++) frontmatter before counting and report the savings
+    #[arg(long, conflicts_with_all = ["diff", "stream"])]
+    strip_frontmatter: bool,
+
+    /// With --format json, report costs as integer micro-units (millionths of the currency) instead of floats
+    #[arg(long)]
+    cost_micros: bool,
 
-        // Watch for changes
-        loop {
-            match rx.recv() {
-                Ok(Ok(event)) => {
-                    if event.kind.is_modify() {
-                        println!("\n--- File changed, re-analyzing ---\n");
-                        if let Err(e) = Self::run_estimate_once(args) {
-                            eprintln!("Error: {}", e);
-                        }
-                    }
-                }
-                Ok(Err(e)) => {
-                    eprintln!("Watch error: {}", e);
-                }
-                Err(e) => {
-                    eprintln!("Channel error: {}", e);
-                    break;
-                }
-            }
-        }
+    /// Report corpus-wide tokens, heaviest files, role breakdown and projected cost for a directory of transcripts, per --compare model
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["diff", "stream", "examples", "group_by_role"])]
+    compare_prompts_from_dir: Option<String>,
 
-        Ok(())
+    // Global options, set from `Cli`
+    #[arg(skip)]
+    color: bool,
+    #[arg(skip)]
+    pricing_file: Option<String>,
+}
+
+impl EstimateArgs {
+    /// Fill in flags not given here from `fallback`.
+    ///
+    /// The output format is always taken from `self`, as clap gives it a
+    /// default value either way.
+    fn or(self, fallback: EstimateArgs) -> Self {
+        Self {
+            input: self.input.or_flag(fallback.input),
+            model: self.model.or_flag(fallback.model),
+            compare: self.compare.or_flag(fallback.compare),
+            breakdown: self.breakdown.or_flag(fallback.breakdown),
+            // A --canonical before the subcommand picked JSON there
+            format: if fallback.canonical && !self.canonical {
+                fallback.format
+            } else {
+                self.format
+            },
+            price: self.price.or_flag(fallback.price),
+            #[cfg(feature = "markdown")]
+            minify: self.minify.or_flag(fallback.minify),
+            diff: self.diff.or_flag(fallback.diff),
+            #[cfg(feature = "watch")]
+            watch: self.watch.or_flag(fallback.watch),
+            #[cfg(feature = "watch")]
+            watch_dir: self.watch_dir.or_flag(fallback.watch_dir),
+            strict: self.strict.or_flag(fallback.strict),
+            expected_output: self.expected_output.or_flag(fallback.expected_output),
+            jobs: self.jobs.or_flag(fallback.jobs),
+            chat_overhead: self.chat_overhead.or_flag(fallback.chat_overhead),
+            diff_separator: self.diff_separator.or_flag(fallback.diff_separator),
+            emit_tokens: self.emit_tokens.or_flag(fallback.emit_tokens),
+            tokenizer_file: self.tokenizer_file.or_flag(fallback.tokenizer_file),
+            max_cost: self.max_cost.or_flag(fallback.max_cost),
+            verify_roundtrip: self.verify_roundtrip.or_flag(fallback.verify_roundtrip),
+            warn_whitespace: self.warn_whitespace.or_flag(fallback.warn_whitespace),
+            framing: self.framing.or_flag(fallback.framing),
+            stream: self.stream.or_flag(fallback.stream),
+            locale: self.locale.or_flag(fallback.locale),
+            no_separators: self.no_separators.or_flag(fallback.no_separators),
+            input_format: self.input_format.or_flag(fallback.input_format),
+            baseline: self.baseline.or_flag(fallback.baseline),
+            role_alias: self.role_alias.or_flag(fallback.role_alias),
+            examples: self.examples.or_flag(fallback.examples),
+            allow_duplicates: self.allow_duplicates.or_flag(fallback.allow_duplicates),
+            cheapest: self.cheapest.or_flag(fallback.cheapest),
+            context_limit: self.context_limit.or_flag(fallback.context_limit),
+            strip_comments: self.strip_comments.or_flag(fallback.strip_comments),
+            since_file: self.since_file.or_flag(fallback.since_file),
+            max_growth: self.max_growth.or_flag(fallback.max_growth),
+            quiet: self.quiet.or_flag(fallback.quiet),
+            azure_deployment: self.azure_deployment.or_flag(fallback.azure_deployment),
+            throughput: self.throughput.or_flag(fallback.throughput),
+            with_metadata: self.with_metadata.or_flag(fallback.with_metadata),
+            dedupe_system: self.dedupe_system.or_flag(fallback.dedupe_system),
+            content_preview: self.content_preview.or_flag(fallback.content_preview),
+            fail_if_unpriced: self.fail_if_unpriced.or_flag(fallback.fail_if_unpriced),
+            currency: self.currency.or_flag(fallback.currency),
+            fx_rate: self.fx_rate.or_flag(fallback.fx_rate),
+            compare_encodings_detail: self
+                .compare_encodings_detail
+                .or_flag(fallback.compare_encodings_detail),
+            group_by_role: self.group_by_role.or_flag(fallback.group_by_role),
+            line_endings: self.line_endings.or_flag(fallback.line_endings),
+            include_role_markers: self
+                .include_role_markers
+                .or_flag(fallback.include_role_markers),
+            chat_template: self.chat_template.or_flag(fallback.chat_template),
+            max_messages: self.max_messages.or_flag(fallback.max_messages),
+            on_max_messages: self.on_max_messages.or_flag(fallback.on_max_messages),
+            diff_percent: self.diff_percent.or_flag(fallback.diff_percent),
+            warmup: self.warmup.or_flag(fallback.warmup),
+            replay_cost: self.replay_cost.or_flag(fallback.replay_cost),
+            unicode_normalize: self.unicode_normalize.or_flag(fallback.unicode_normalize),
+            json_path_messages: self.json_path_messages.or_flag(fallback.json_path_messages),
+            json_path_role: self.json_path_role.or_flag(fallback.json_path_role),
+            json_path_content: self.json_path_content.or_flag(fallback.json_path_content),
+            histogram: self.histogram.or_flag(fallback.histogram),
+            histogram_buckets: self.histogram_buckets.or_flag(fallback.histogram_buckets),
+            compare_providers: self.compare_providers.or_flag(fallback.compare_providers),
+            model_info: self.model_info.or_flag(fallback.model_info),
+            image_tokens: self.image_tokens.or_flag(fallback.image_tokens),
+            inputs: self.inputs.or_flag(fallback.inputs),
+            csv: self.csv.or_flag(fallback.csv),
+            breakdown_by: self.breakdown_by.or_flag(fallback.breakdown_by),
+            min_tokens: self.min_tokens.or_flag(fallback.min_tokens),
+            max_tokens: self.max_tokens.or_flag(fallback.max_tokens),
+            diff_messages: self.diff_messages.or_flag(fallback.diff_messages),
+            canonical: self.canonical.or_flag(fallback.canonical),
+            max_bytes: self.max_bytes.or_flag(fallback.max_bytes),
+            winners: self.winners.or_flag(fallback.winners),
+            strict_json: self.strict_json.or_flag(fallback.strict_json),
+            headroom: self.headroom.or_flag(fallback.headroom),
+            check_context: self.check_context.or_flag(fallback.check_context),
+            allocate: self.allocate.or_flag(fallback.allocate),
+            section: self.section.or_flag(fallback.section),
+            explain_tokenization: self
+                .explain_tokenization
+                .or_flag(fallback.explain_tokenization),
+            trim_trailing_newline: self
+                .trim_trailing_newline
+                .or_flag(fallback.trim_trailing_newline),
+            #[cfg(feature = "sampling")]
+            sample: self.sample.or_flag(fallback.sample),
+            #[cfg(feature = "sampling")]
+            seed: self.seed.or_flag(fallback.seed),
+            response: self.response.or_flag(fallback.response),
+            markup: self.markup.or_flag(fallback.markup),
+            compare_inputs: self.compare_inputs.or_flag(fallback.compare_inputs),
+            extra_inputs: self.extra_inputs.or_flag(fallback.extra_inputs),
+            strip_frontmatter: self.strip_frontmatter.or_flag(fallback.strip_frontmatter),
+            cost_micros: self.cost_micros.or_flag(fallback.cost_micros),
+            compare_prompts_from_dir: self
+                .compare_prompts_from_dir
+                .or_flag(fallback.compare_prompts_from_dir),
+            color: self.color,
+            pricing_file: self.pricing_file,
+        }
     }
+}
 
-    /// Run a single analysis (used by watch mode).
-    #[cfg(feature = "watch")]
-    fn run_estimate_once(args: &EstimateArgs) -> Result<(), AppError> {
-        let mut new_args = args.clone();
-        new_args.watch = false;
-        Self::run_estimate(new_args)
+/// A flag value that falls back to another when not given.
+trait OrFlag {
+    fn or_flag(self, fallback: Self) -> Self;
+}
+
+impl OrFlag for bool {
+    fn or_flag(self, fallback: Self) -> Self {
+        self || fallback
     }
 }
 
-/// Estimate command arguments (for internal use).
-#[derive(Debug, Clone)]
-pub struct EstimateArgs {
-    input: Option<String>,
-    model: Option<String>,
-    compare: Vec<String>,
-    breakdown: bool,
-    format: OutputFormat,
-    price: bool,
-    #[cfg(feature = "markdown")]
-    minify: bool,
-    diff: Option<String>,
-    #[cfg(feature = "watch")]
-    watch: bool,
-    pricing_file: Option<String>,
+impl<T> OrFlag for Option<T> {
+    fn or_flag(self, fallback: Self) -> Self {
+        self.or(fallback)
+    }
 }
 
-#[cfg(feature = "watch")]
-impl Default for EstimateArgs {
-    fn default() -> Self {
-        Self {
-            input: None,
-            model: None,
-            compare: Vec::new(),
-            breakdown: false,
-            format: OutputFormat::Text,
-            price: false,
-            #[cfg(feature = "markdown")]
-            minify: false,
-            diff: None,
-            watch: false,
-            pricing_file: None,
+impl<T> OrFlag for Vec<T> {
+    fn or_flag(self, fallback: Self) -> Self {
+        if self.is_empty() {
+            fallback
+        } else {
+            self
         }
     }
 }
 
-/// Load test command arguments (for internal use).
+/// Load test command arguments.
 #[cfg(feature = "load-test")]
-#[derive(Debug, Clone)]
-struct LoadTestArgs {
+#[derive(clap::Args, Debug, Clone)]
+pub struct LoadTestArgs {
+    /// Model to use (e.g., gpt-4, claude-2)
+    #[arg(short, long)]
     model: String,
+
+    /// API endpoint URL (optional, uses provider default if not specified)
+    #[arg(long)]
     endpoint: Option<String>,
+
+    /// API key (or use environment variable)
+    #[arg(long)]
     api_key: Option<String>,
+
+    /// OpenAI API key (alternative to --api-key)
+    #[arg(long)]
     openai_api_key: Option<String>,
+
+    /// Anthropic API key
+    #[arg(long)]
     anthropic_api_key: Option<String>,
+
+    /// OpenRouter API key
+    #[arg(long)]
     openrouter_api_key: Option<String>,
+
+    /// Environment variables holding API keys to rotate across requests (comma-separated or repeated)
+    #[arg(long, value_name = "VARS", value_delimiter = ',')]
+    api_key_env: Vec<String>,
+
+    /// Target provider (defaults to automatic detection)
+    #[arg(long, value_enum)]
     provider: Option<Provider>,
-    concurrency: usize,
-    runs: usize,
+
+    /// Load-test config file with a `[load_test]` section (flags override its values)
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
+    /// Number of concurrent requests [default: 10]
+    #[arg(short, long)]
+    concurrency: Option<usize>,
+
+    /// Total number of requests to make (required unless set in --config)
+    #[arg(short, long)]
+    runs: Option<usize>,
+
+    /// Prompt file (JSONL, YAML, or text) or use stdin
+    #[arg(short, long)]
     prompt_file: Option<String>,
+
+    /// Think time between requests (e.g., "250-750ms", "500ms" or "1-2s")
+    #[arg(long)]
     think_time: Option<String>,
-    retry: u32,
+
+    /// Retry count on failure [default: 3]
+    #[arg(long)]
+    retry: Option<u32>,
+
+    /// Request timeout in seconds [default: 60]
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Seed for think-time randomness, for reproducible runs
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
     output_format: LoadTestOutputFormat,
+
+    /// Estimate costs (dry run without making API calls)
+    #[arg(long)]
     dry_run: bool,
+
+    /// Maximum cost threshold (stop if exceeded)
+    #[arg(long)]
     max_cost: Option<f64>,
+
+    /// Show cost estimation
+    #[arg(short, long)]
     estimate_cost: bool,
+
+    // Global option, set from `Cli`
+    #[arg(skip)]
     pricing_file: Option<String>,
 }
 
 #[cfg(feature = "load-test")]
-impl From<Command> for LoadTestArgs {
-    fn from(cmd: Command) -> Self {
-        match cmd {
-            Command::LoadTest {
-                model,
-                endpoint,
-                api_key,
-                openai_api_key,
-                anthropic_api_key,
-                openrouter_api_key,
-                provider,
-                concurrency,
-                runs,
-                prompt_file,
-                think_time,
-                retry,
-                output_format,
-                dry_run,
-                max_cost,
-                estimate_cost,
-            } => Self {
-                model,
-                endpoint,
-                api_key,
-                openai_api_key,
-                anthropic_api_key,
-                openrouter_api_key,
-                provider,
-                concurrency,
-                runs,
-                prompt_file,
-                think_time,
-                retry,
-                output_format,
-                dry_run,
-                max_cost,
-                estimate_cost,
-                pricing_file: None,
-            },
-            _ => panic!("Not a LoadTest command"),
-        }
+impl LoadTestArgs {
+    /// Resolve the simulator configuration, layering flags over `--config`.
+    fn settings(&self) -> Result<crate::simulator::config::SimulatorConfig, AppError> {
+        use crate::simulator::config::LoadTestSettings;
+
+        let from_file = match &self.config {
+            Some(path) => LoadTestSettings::from_file(path).map_err(AppError::Config)?,
+            None => LoadTestSettings::default(),
+        };
+        let from_flags = LoadTestSettings {
+            concurrency: self.concurrency,
+            runs: self.runs,
+            retry: self.retry,
+            think_time: self.think_time.clone(),
+            timeout: self.timeout,
+            max_cost: self.max_cost,
+            seed: self.seed,
+        };
+        from_file
+            .overridden_by(from_flags)
+            .to_simulator_config()
+            .map_err(AppError::Config)
     }
-}
 
-#[cfg(feature = "load-test")]
-impl LoadTestArgs {
     fn resolve_provider(&self) -> Provider {
         if let Some(provider) = &self.provider {
             return provider.clone();
@@ -1305,56 +4464,22 @@ impl LoadTestArgs {
                 return Provider::Openrouter;
             }
         }
-
-        if self.openrouter_api_key.is_some() {
-            return Provider::Openrouter;
-        }
-        if self.anthropic_api_key.is_some() {
-            return Provider::Anthropic;
-        }
-
-        Provider::Openai
-    }
-}
-
-#[cfg(feature = "load-test")]
-impl From<Command> for EstimateArgs {
-    fn from(cmd: Command) -> Self {
-        match cmd {
-            Command::Estimate {
-                input,
-                model,
-                compare,
-                breakdown,
-                format,
-                price,
-                #[cfg(feature = "markdown")]
-                minify,
-                diff,
-                #[cfg(feature = "watch")]
-                watch,
-            } => Self {
-                input,
-                model,
-                compare,
-                breakdown,
-                format,
-                price,
-                #[cfg(feature = "markdown")]
-                minify,
-                diff,
-                #[cfg(feature = "watch")]
-                watch,
-                pricing_file: None,
-            },
-            _ => panic!("Not an Estimate command"),
-        }
+
+        if self.openrouter_api_key.is_some() {
+            return Provider::Openrouter;
+        }
+        if self.anthropic_api_key.is_some() {
+            return Provider::Anthropic;
+        }
+
+        Provider::Openai
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parsers::{Parser as InputParser, TextParser};
     use clap::Parser;
 
     #[test]
@@ -1371,11 +4496,11 @@ mod tests {
         .expect("CLI args should parse");
 
         assert!(cli.command.is_none(), "legacy usage keeps flat args");
-        assert_eq!(cli.model.as_deref(), Some("gpt-4"));
-        assert_eq!(cli.input.as_deref(), Some("hello world"));
-        assert!(cli.price);
+        assert_eq!(cli.estimate.model.as_deref(), Some("gpt-4"));
+        assert_eq!(cli.estimate.input.as_deref(), Some("hello world"));
+        assert!(cli.estimate.price);
 
-        match cli.format {
+        match cli.estimate.format {
             OutputFormat::Json => {}
             other => panic!("expected JSON format, got {:?}", other),
         }
@@ -1397,37 +4522,959 @@ mod tests {
         ])
         .expect("subcommand args should parse");
 
-        let command = cli.command.expect("estimate subcommand expected");
-        match command {
-            Command::Estimate {
-                input,
-                model,
-                compare,
-                breakdown,
-                format,
-                price,
-                #[cfg(feature = "markdown")]
-                    minify: _,
-                diff,
-                #[cfg(feature = "watch")]
-                    watch: _,
-            } => {
-                assert_eq!(input.as_deref(), Some("prompt.txt"));
-                assert_eq!(model.as_deref(), Some("gpt-4o-mini"));
-                assert_eq!(compare, vec!["gpt-4-turbo", "gpt-3.5-turbo"]);
-                assert!(breakdown);
-                assert!(!price);
-                assert!(diff.is_none());
-                match format {
-                    OutputFormat::Text => {}
-                    other => panic!("expected text format default, got {:?}", other),
-                }
+        let Some(Command::Estimate(args)) = cli.command else {
+            panic!("estimate subcommand expected");
+        };
+        let expected = EstimateArgs {
+            input: Some("prompt.txt".to_string()),
+            model: Some("gpt-4o-mini".to_string()),
+            compare: vec!["gpt-4-turbo".to_string(), "gpt-3.5-turbo".to_string()],
+            breakdown: true,
+            ..Default::default()
+        };
+        // Every other flag keeps its default
+        assert_eq!(format!("{:?}", args), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn estimate_subcommand_falls_back_to_flags_before_it() {
+        let cli = Cli::try_parse_from([
+            "tokuin",
+            "--model",
+            "gpt-4",
+            "--price",
+            "--expected-output",
+            "100",
+            "estimate",
+            "--expected-output",
+            "200",
+            "prompt.txt",
+        ])
+        .expect("mixed args should parse");
+
+        let Some(Command::Estimate(args)) = cli.command else {
+            panic!("estimate subcommand expected");
+        };
+        let args = args.or(cli.estimate);
+        assert_eq!(args.model.as_deref(), Some("gpt-4"));
+        assert!(args.price);
+        assert_eq!(args.expected_output, Some(200));
+        assert_eq!(args.input.as_deref(), Some("prompt.txt"));
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn collect_results_reports_partial_failures() {
+        let registry = ModelRegistry::new();
+        let messages = TextParser::new().parse("Hello, world!").unwrap();
+        let models = vec!["gpt-4".to_string(), "not-a-model".to_string()];
+
+        let (results, failures) = Cli::collect_results(
+            &registry,
+            &models,
+            &messages,
+            CountOptions::default(),
+            false,
+        )
+        .expect("non-strict mode collects failures");
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].model, "gpt-4");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].model, "not-a-model");
+        assert!(failures[0].error.contains("not-a-model"));
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn collect_results_strict_fails_fast() {
+        let registry = ModelRegistry::new();
+        let messages = TextParser::new().parse("Hello, world!").unwrap();
+        let models = vec!["gpt-4".to_string(), "not-a-model".to_string()];
+
+        let result =
+            Cli::collect_results(&registry, &models, &messages, CountOptions::default(), true);
+        assert!(result.is_err(), "strict mode should abort on failure");
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn check_priced_names_unpriced_models() {
+        let registry = ModelRegistry::new();
+
+        assert!(Cli::check_priced(&registry, &["gpt-4".to_string()]).is_ok());
+
+        let models = vec![
+            "gpt-4".to_string(),
+            "words".to_string(),
+            "not-a-model".to_string(),
+        ];
+        match Cli::check_priced(&registry, &models) {
+            Err(AppError::Unpriced(models)) => assert_eq!(models, vec!["words"]),
+            other => panic!("expected Unpriced, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn check_max_cost_under_and_over_threshold() {
+        let results = vec![TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1000,
+            input_cost: Some(0.03),
+            output_cost: Some(0.06),
+            ..Default::default()
+        }];
+
+        assert!(Cli::check_max_cost(&results, 0.05, false, &mut Warnings::new()).is_ok());
+        assert!(Cli::check_max_cost(&results, 0.10, true, &mut Warnings::new()).is_ok());
+
+        match Cli::check_max_cost(&results, 0.05, true, &mut Warnings::new()) {
+            Err(AppError::CostLimitExceeded { model, cost, limit }) => {
+                assert_eq!(model, "gpt-4");
+                assert!((cost - 0.09).abs() < 1e-9);
+                assert_eq!(limit, 0.05);
+            }
+            other => panic!("expected CostLimitExceeded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn check_context_fails_over_window() {
+        let result = |model: &str, tokens, window| TokenResult {
+            model: model.to_string(),
+            tokens,
+            context_window: window,
+            ..Default::default()
+        };
+        let under = vec![
+            result("gpt-4", 8000, Some(8192)),
+            result("chars", 9000, None),
+        ];
+        assert!(Cli::check_context(&under).is_ok());
+
+        let over = vec![
+            result("gpt-4o", 9000, Some(128_000)),
+            result("gpt-4", 9000, Some(8192)),
+        ];
+        let error = Cli::check_context(&over).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "9000 tokens for gpt-4 exceed its 8192-token context window"
+        );
+    }
+
+    #[test]
+    fn check_token_band_uses_largest_count() {
+        let result = |model: &str, tokens| TokenResult {
+            model: model.to_string(),
+            tokens,
+            ..Default::default()
+        };
+        let results = vec![result("gpt-4", 90), result("chars", 400)];
+
+        // In band
+        assert!(Cli::check_token_band(&results, Some(100), Some(500)).is_ok());
+        assert!(Cli::check_token_band(&results, None, None).is_ok());
+        // Below the floor
+        match Cli::check_token_band(&[result("gpt-4", 90)], Some(100), Some(500)) {
+            Err(e @ AppError::BelowMinTokens { .. }) => {
+                assert_eq!(e.exit_code(), 3);
+                assert_eq!(
+                    e.to_string(),
+                    "90 tokens for gpt-4 is below --min-tokens 100"
+                );
+            }
+            other => panic!("expected BelowMinTokens, got {:?}", other),
+        }
+        // Above the ceiling
+        match Cli::check_token_band(&results, None, Some(300)) {
+            Err(AppError::AboveMaxTokens { model, tokens, .. }) => {
+                assert_eq!((model.as_str(), tokens), ("chars", 400));
             }
-            #[allow(unreachable_patterns)]
-            _ => panic!("unexpected command variant"),
+            other => panic!("expected AboveMaxTokens, got {:?}", other),
+        }
+        assert!(matches!(
+            Cli::check_token_band(&results, Some(10), Some(5)),
+            Err(AppError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn check_max_cost_without_pricing_only_warns() {
+        let results = vec![TokenResult {
+            model: "chars".to_string(),
+            tokens: 10,
+            ..Default::default()
+        }];
+        let mut warnings = Warnings::new();
+        assert!(Cli::check_max_cost(&results, 0.0, true, &mut warnings).is_ok());
+        assert_eq!(warnings.take()[0].kind, WarningKind::Unpriced);
+    }
+
+    #[test]
+    #[cfg(all(feature = "markdown", feature = "openai"))]
+    fn minify_savings_are_a_json_warning() {
+        let registry = ModelRegistry::new();
+        let mut warnings = Warnings::new();
+        let input = "# Title\n\nSome **bold** and *italic* text.";
+        let minified = Cli::minify_input(
+            &EstimateArgs::default(),
+            &registry,
+            "gpt-4",
+            input,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(minified.len() < input.len());
+
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 8,
+            ..Default::default()
+        };
+        let output = JsonFormatter::new()
+            .with_warnings(warnings.take())
+            .format_result(&result);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["tokens"], 8);
+        assert_eq!(value["warnings"][0]["kind"], "minify_savings");
+        assert!(value["warnings"][0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Markdown stripped:"));
+        assert!(warnings.take().is_empty());
+    }
+
+    #[test]
+    fn cheapest_picks_lowest_total_cost() {
+        let result = |model: &str, tokens, cost: Option<f64>| TokenResult {
+            model: model.to_string(),
+            tokens,
+            input_cost: cost,
+            output_cost: cost,
+            ..Default::default()
+        };
+        let results = vec![
+            result("chars", 10, None),
+            result("gpt-4", 100, Some(0.03)),
+            result("gpt-3.5-turbo", 120, Some(0.001)),
+            result("gpt-4o-mini", 120, Some(0.001)),
+        ];
+
+        // Ties keep the earlier model
+        let cheapest = Cli::cheapest(&results, None, None).unwrap();
+        assert_eq!(cheapest.model, "gpt-3.5-turbo");
+
+        let cheapest = Cli::cheapest(&results, Some(110), None).unwrap();
+        assert_eq!(cheapest.model, "gpt-4");
+        assert!(matches!(
+            Cli::cheapest(&results, Some(110), Some(20)),
+            Err(AppError::NoEligibleModel(_))
+        ));
+    }
+
+    #[test]
+    fn compare_entries_split_expected_output() {
+        let entries: Vec<String> = ["gpt-4:500", "gpt-4o", "org/model:free", "gpt-4:500"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let (models, outputs) = compare_expected_outputs(&entries).unwrap();
+        assert_eq!(models, ["gpt-4", "gpt-4o", "org/model:free", "gpt-4"]);
+        assert_eq!(outputs, HashMap::from([("gpt-4".to_string(), 500)]));
+
+        let conflicting = ["gpt-4:500".to_string(), "gpt-4:800".to_string()];
+        assert!(compare_expected_outputs(&conflicting).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn compare_entries_price_their_own_expected_output() {
+        let registry = ModelRegistry::new();
+        let messages = TextParser::new().parse("Hello, world!").unwrap();
+        let entries = ["gpt-4:500".to_string(), "gpt-3.5-turbo:800".to_string()];
+        let (models, expected_outputs) = compare_expected_outputs(&entries).unwrap();
+        let options = CountOptions {
+            price: true,
+            expected_output: Some(100),
+            expected_outputs,
+            ..CountOptions::default()
+        };
+
+        let (results, _) =
+            Cli::collect_results(&registry, &models, &messages, options, true).unwrap();
+        assert_eq!(results[0].expected_output, Some(500));
+        assert!((results[0].output_cost.unwrap() - 0.5 * 0.06).abs() < 1e-12);
+        assert_eq!(results[1].expected_output, Some(800));
+        assert!((results[1].output_cost.unwrap() - 0.8 * 0.002).abs() < 1e-12);
+    }
+
+    #[test]
+    fn typical_output_ratio_scales_projected_output() {
+        let mut registry = ModelRegistry::new();
+        registry.set_typical_output_ratio("gpt-4", 2.0);
+        // 1000 input tokens
+        let messages = TextParser::new().parse(&["hello"; 1000].join(" ")).unwrap();
+        let models = ["gpt-4".to_string(), "gpt-3.5-turbo".to_string()];
+        let options = CountOptions {
+            price: true,
+            ..CountOptions::default()
+        };
+
+        let (results, _) =
+            Cli::collect_results(&registry, &models, &messages, options.clone(), true).unwrap();
+        assert_eq!(results[0].tokens, 1000);
+        // Output cost on 2x the input tokens
+        assert!((results[0].output_cost.unwrap() - 2.0 * 0.06).abs() < 1e-12);
+        // No ratio: output as long as the input
+        assert!((results[1].output_cost.unwrap() - 0.002).abs() < 1e-12);
+
+        // An explicit expected output wins over the ratio
+        let options = CountOptions {
+            expected_output: Some(500),
+            ..options
+        };
+        let (results, _) =
+            Cli::collect_results(&registry, &models, &messages, options, true).unwrap();
+        assert!((results[0].output_cost.unwrap() - 0.5 * 0.06).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cheapest_without_pricing_is_an_error() {
+        let results = vec![TokenResult {
+            model: "chars".to_string(),
+            tokens: 10,
+            ..Default::default()
+        }];
+        match Cli::cheapest(&results, None, None) {
+            Err(AppError::NoEligibleModel(reason)) => assert!(reason.contains("pricing")),
+            other => panic!("expected NoEligibleModel, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn diff_percent_change() {
+        assert_eq!(percent_change(80, 90), Some(12.5));
+        assert_eq!(format_percent_change(80, 90), "+12.5%");
+        assert_eq!(format_percent_change(40, 30), "-25.0%");
+        assert_eq!(format_percent_change(7, 7), "+0.0%");
+
+        assert_eq!(percent_change(0, 5), None);
+        assert_eq!(format_percent_change(0, 5), "new");
+        assert_eq!(format_percent_change(0, 0), "n/a");
+    }
+
+    #[test]
+    fn diff_cost_in_currency() {
+        let cost = DiffCost {
+            input: 0.01,
+            output: Some(0.02),
+            total: 0.03,
+        };
+        let eur = Currency::new("EUR", 0.5).unwrap();
+        assert_eq!(
+            cost.format(&eur),
+            "€0.0150 (input €0.0050 + output €0.0100)"
+        );
+        assert_eq!(cost.converted(&eur).output, Some(0.01));
+
+        let input_only = DiffCost {
+            output: None,
+            ..cost
+        };
+        assert_eq!(input_only.format(&Currency::usd()), "$0.0300 (input only)");
+    }
+
+    #[test]
+    fn split_diff_input_on_separator() {
+        let input = "first prompt\n---DIFF---\nsecond prompt\n";
+        let (original, modified) = split_diff_input(input, DEFAULT_DIFF_SEPARATOR).unwrap();
+        assert_eq!(original, "first prompt\n");
+        assert_eq!(modified, "second prompt\n");
+
+        let (original, modified) = split_diff_input("a\r\n===\r\nb", "===").unwrap();
+        assert_eq!(original, "a\r\n");
+        assert_eq!(modified, "b");
+
+        assert!(split_diff_input("no separator here", DEFAULT_DIFF_SEPARATOR).is_err());
+    }
+
+    #[test]
+    fn format_message_diff_marks_changes() {
+        let delta = |kind, key: &str, before, after| MessageDelta {
+            kind,
+            key: key.to_string(),
+            before,
+            after,
+        };
+        let lines = format_message_diff(&[
+            delta(ChangeKind::Unchanged, "system", Some(5), Some(5)),
+            delta(ChangeKind::Changed, "user", Some(10), Some(7)),
+            delta(ChangeKind::Added, "alice", None, Some(3)),
+            delta(ChangeKind::Removed, "assistant", Some(4), None),
+        ]);
+        assert_eq!(
+            lines,
+            [
+                "= system     5 tokens",
+                "~ user       10 -> 7 tokens (-3)",
+                "+ alice      3 tokens (added)",
+                "- assistant  4 tokens (removed)",
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_compare_models_reads_list_files() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# curated set\ngpt-4\n\n  gpt-3.5-turbo  # cheap\n").unwrap();
+        let list = format!("@{}", file.path().display());
+
+        let models = expand_compare_models(&[list.clone(), "claude-3-haiku".to_string()]).unwrap();
+        assert_eq!(models, vec!["gpt-4", "gpt-3.5-turbo", "claude-3-haiku"]);
+
+        let mut empty = tempfile::NamedTempFile::new().unwrap();
+        writeln!(empty, "# nothing here\n\n").unwrap();
+        let empty_list = format!("@{}", empty.path().display());
+        assert!(expand_compare_models(&[empty_list]).is_err());
+        assert!(expand_compare_models(&["@/nonexistent/models.txt".to_string()]).is_err());
+    }
+
+    #[test]
+    fn dedupe_models_keeps_first_of_alias_equivalents() {
+        let registry = ModelRegistry::new();
+        let models = ["gpt-4", "openai/gpt-4", "gpt-3.5-turbo", "gpt-4"]
+            .map(String::from)
+            .to_vec();
+
+        let (kept, duplicates) = dedupe_models(&registry, models);
+        assert_eq!(kept, vec!["gpt-4", "gpt-3.5-turbo"]);
+        assert_eq!(
+            duplicates,
+            vec![
+                ("openai/gpt-4".to_string(), "gpt-4".to_string()),
+                ("gpt-4".to_string(), "gpt-4".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn emitted_token_ids_round_trip() {
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let text = "Hello, world! Token ids should decode back to this text.";
+        let messages = TextParser::new().parse(text).unwrap();
+        let options = CountOptions {
+            emit_tokens: true,
+            ..CountOptions::default()
+        };
+
+        let result = Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options, None).unwrap();
+        let ids = result.token_ids.expect("exact tokenizer emits ids");
+        assert_eq!(ids.len(), result.tokens);
+        assert!(!result.token_ids_truncated);
+        assert!(!result.approximate);
+        assert_eq!(tokenizer.decode(&ids).unwrap(), messages[0].content);
+    }
+
+    #[test]
+    fn emitted_token_ids_skipped_for_approximate_tokenizers() {
+        let tokenizer = crate::tokenizers::WordTokenizer::new();
+        let messages = TextParser::new().parse("three little words").unwrap();
+        let options = CountOptions {
+            emit_tokens: true,
+            ..CountOptions::default()
+        };
+
+        let result = Cli::count_tokens(&tokenizer, &messages, "words", options, None).unwrap();
+        assert!(result.token_ids.is_none());
+        assert!(result.approximate);
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn streamed_breakdown_matches_buffered() {
+        let roles = ["system", "user", "assistant", "developer"];
+        let transcript = serde_json::to_string(
+            &(0..20_000)
+                .map(|i| {
+                    serde_json::json!({
+                        "role": roles[i % roles.len()],
+                        "content": format!("Message {} says héllo 語 {}", i, "word ".repeat(i % 7)),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let options = CountOptions {
+            breakdown: true,
+            chat_overhead: true,
+            ..CountOptions::default()
+        };
+
+        let messages = JsonParser::new().parse(&transcript).unwrap();
+        let buffered =
+            Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options.clone(), None).unwrap();
+
+        let mut tally = MessageTally::new(&*tokenizer, "gpt-4", options).without_per_message();
+        let count = JsonParser::new()
+            .stream(transcript.as_bytes(), |message| {
+                tally.add(&message).unwrap();
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        let streamed = tally.finish(None).unwrap();
+
+        assert_eq!(count, 20_000);
+        assert_eq!(streamed.tokens, buffered.tokens);
+        assert_eq!(streamed.content_tokens, buffered.content_tokens);
+        assert_eq!(streamed.overhead_tokens, buffered.overhead_tokens);
+        assert_eq!(streamed.breakdown, buffered.breakdown);
+        assert_eq!(buffered.messages.map(|m| m.len()), Some(20_000));
+        assert!(streamed.messages.is_none());
+    }
+
+    #[test]
+    fn role_markers_added_to_each_message_and_role() {
+        let tokenizer = crate::tokenizers::CharTokenizer::new();
+        let messages: Vec<_> = [("system", "Be brief."), ("assistant", "Ok")]
+            .into_iter()
+            .map(|(role, content)| crate::parsers::Message {
+                role: role.to_string(),
+                content: content.to_string(),
+                name: None,
+                tool_call_id: None,
+            })
+            .collect();
+        let count = |role_markers| {
+            let options = CountOptions {
+                breakdown: true,
+                chat_overhead: true,
+                role_markers,
+                ..CountOptions::default()
+            };
+            Cli::count_tokens(&tokenizer, &messages, "gpt-4", options, None).unwrap()
+        };
+
+        let plain = count(false);
+        let marked = count(true);
+        let tokens = |result: &TokenResult| -> Vec<usize> {
+            result
+                .messages
+                .iter()
+                .flatten()
+                .map(|(_, tokens)| *tokens)
+                .collect()
+        };
+        // "system" and "assistant" are 6 and 9 characters
+        let expected: Vec<usize> = tokens(&plain)
+            .iter()
+            .zip([6, 9])
+            .map(|(t, r)| t + r)
+            .collect();
+        assert_eq!(tokens(&marked), expected);
+        let (plain_bd, marked_bd) = (plain.breakdown.unwrap(), marked.breakdown.unwrap());
+        assert_eq!(marked_bd.system, plain_bd.system + 6);
+        assert_eq!(marked_bd.assistant, plain_bd.assistant + 9);
+        assert_eq!(marked.tokens, plain.tokens + 15);
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn embeddings_priced_for_input_only() {
+        let registry = ModelRegistry::new();
+        let model = Cli::resolve_model(&registry, "text-embedding-3-small", true).unwrap();
+        let messages = vec![crate::parsers::Message {
+            role: "user".to_string(),
+            content: "Embed this document".to_string(),
+            name: None,
+            tool_call_id: None,
+        }];
+        let options = CountOptions {
+            price: true,
+            expected_output: Some(500),
+            ..CountOptions::default()
+        };
+        let result = Cli::count_tokens(
+            &*model.tokenizer,
+            &messages,
+            &model.name,
+            options,
+            model.pricing_override,
+        )
+        .unwrap();
+
+        assert_eq!(result.tokens, 3);
+        assert!((result.input_cost.unwrap() - 3.0 / 1000.0 * 0.00002).abs() < 1e-15);
+        assert_eq!(result.output_cost, None);
+        assert_eq!(result.output_rate, None);
+        assert_eq!(result.total_cost(), result.input_cost);
+    }
+
+    #[test]
+    fn content_preview_truncates_message_content() {
+        let tokenizer = crate::tokenizers::WordTokenizer::new();
+        let messages = vec![
+            crate::parsers::Message {
+                role: "system".to_string(),
+                content: "Answer every question\nin one sentence.".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            crate::parsers::Message {
+                role: "user".to_string(),
+                content: "Hi".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        let options = CountOptions {
+            content_preview: 12,
+            ..CountOptions::default()
+        };
+
+        let result = Cli::count_tokens(&tokenizer, &messages, "words", options, None).unwrap();
+        assert_eq!(
+            result.message_previews,
+            Some(vec!["Answer every…".to_string(), "Hi".to_string()])
+        );
+
+        let result = Cli::count_tokens(
+            &tokenizer,
+            &messages,
+            "words",
+            CountOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert!(result.messages.is_some());
+        assert_eq!(result.message_previews, None);
+    }
+
+    #[test]
+    fn breakdown_by_name_totals_per_participant() {
+        let messages = JsonParser::new()
+            .parse(
+                r#"[
+                    {"role": "system", "content": "Debate."},
+                    {"role": "user", "name": "alice", "content": "Cats"},
+                    {"role": "user", "name": "bob", "content": "Dogs, obviously"},
+                    {"role": "user", "name": "alice", "content": "Cats again"},
+                    {"role": "assistant", "content": "Both"}
+                ]"#,
+            )
+            .unwrap();
+        let options = CountOptions {
+            breakdown: true,
+            by_name: true,
+            ..CountOptions::default()
+        };
+
+        let result = Cli::count_tokens(
+            &crate::tokenizers::CharTokenizer::new(),
+            &messages,
+            "chars",
+            options,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            result.participants(),
+            [("bob", 15), ("alice", 14), ("system", 7), ("assistant", 4)]
+        );
+        // The role breakdown is still kept
+        assert_eq!(result.breakdown.unwrap().user, 29);
+
+        let result = Cli::count_tokens(
+            &crate::tokenizers::CharTokenizer::new(),
+            &messages,
+            "chars",
+            CountOptions {
+                breakdown: true,
+                ..CountOptions::default()
+            },
+            None,
+        )
+        .unwrap();
+        assert!(result.by_name.is_none());
+    }
+
+    #[test]
+    fn breakdown_by_name_keeps_the_name_of_messages_with_images() {
+        let messages = JsonParser::new()
+            .parse(
+                r#"[{"role": "user", "name": "alice", "content": "Look data:image/png;base64,iVBORw0KGgo="}]"#,
+            )
+            .unwrap();
+        let options = CountOptions {
+            breakdown: true,
+            by_name: true,
+            images: ImageTokens::Strip,
+            ..CountOptions::default()
+        };
+
+        let result = Cli::count_tokens(
+            &crate::tokenizers::CharTokenizer::new(),
+            &messages,
+            "chars",
+            options,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.participants(), [("alice", 5)]);
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn chat_overhead_counts_empty_messages() {
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let messages = vec![
+            crate::parsers::Message {
+                role: "system".to_string(),
+                content: "Be brief.".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            crate::parsers::Message {
+                role: "user".to_string(),
+                content: String::new(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        let options = CountOptions {
+            breakdown: true,
+            chat_overhead: true,
+            ..CountOptions::default()
+        };
+
+        let plain = Cli::count_tokens(
+            &*tokenizer,
+            &messages,
+            "gpt-4",
+            CountOptions::default(),
+            None,
+        )
+        .unwrap();
+        let result = Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options, None).unwrap();
+
+        let overhead = ChatOverhead::OPENAI;
+        assert_eq!(
+            result.tokens,
+            plain.tokens + 2 * overhead.per_message + overhead.reply_priming
+        );
+        assert_eq!(result.breakdown.unwrap().user, overhead.per_message);
+        assert_eq!(result.content_tokens, plain.tokens);
+        assert_eq!(
+            result.overhead_tokens,
+            2 * overhead.per_message + overhead.reply_priming
+        );
+        assert_eq!(
+            result.content_tokens + result.overhead_tokens,
+            result.tokens
+        );
+        assert_eq!(plain.overhead_tokens, 0);
+        assert_eq!(plain.content_tokens, plain.tokens);
+    }
+
+    #[test]
+    fn tool_results_count_in_the_tool_bucket() {
+        use crate::parsers::Parser;
+
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let messages = JsonParser::new()
+            .parse(
+                r#"[
+                    {"role": "user", "content": "Weather in Paris?"},
+                    {"role": "tool", "tool_call_id": "call_abc123", "content": "{\"temp\": 18, \"sky\": \"clear\"}"}
+                ]"#,
+            )
+            .unwrap();
+        let options = CountOptions {
+            breakdown: true,
+            ..CountOptions::default()
+        };
+        let result = Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options, None).unwrap();
+        let breakdown = result.breakdown.unwrap();
+
+        let content = tokenizer
+            .count_tokens(r#"{"temp": 18, "sky": "clear"}"#)
+            .unwrap();
+        let id = tokenizer.count_tokens("call_abc123").unwrap();
+        assert_eq!(breakdown.tool, content + id);
+        assert_eq!(
+            breakdown.user + breakdown.tool,
+            breakdown.total,
+            "The id counts towards the total"
+        );
+        assert_eq!(result.content_tokens, breakdown.total);
+    }
+
+    #[test]
+    fn developer_role_counts_under_system() {
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let messages = vec![
+            crate::parsers::Message {
+                role: "developer".to_string(),
+                content: "Answer in French.".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            crate::parsers::Message {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        let count = |roles| {
+            let options = CountOptions {
+                breakdown: true,
+                roles,
+                ..CountOptions::default()
+            };
+            let result = Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options, None).unwrap();
+            result.breakdown.unwrap()
+        };
+
+        let developer = tokenizer.count_tokens("Answer in French.").unwrap();
+        let breakdown = count(RoleMap::default());
+        assert_eq!(breakdown.system, developer);
+        assert_eq!(breakdown.system + breakdown.user, breakdown.total);
+
+        // Mapping the alias to itself keeps it out of the system bucket
+        let breakdown = count(RoleMap::default().with_alias("developer", "developer"));
+        assert_eq!(breakdown.system, 0);
+
+        let cli = Cli::try_parse_from(["tokuin", "--role-alias", "human=user", "hi"]).unwrap();
+        assert_eq!(
+            cli.estimate.role_alias,
+            vec![("human".to_string(), "user".to_string())]
+        );
+        assert!(Cli::try_parse_from(["tokuin", "--role-alias", "human", "hi"]).is_err());
+    }
+
+    #[test]
+    fn format_names_the_feature_it_needs() {
+        let cli = Cli::try_parse_from(["tokuin", "--format", "json", "hi"]).unwrap();
+        assert!(matches!(cli.estimate.format, OutputFormat::Json));
+        assert_eq!(format_feature("json"), None);
+
+        let parsed = Cli::try_parse_from(["tokuin", "--format", "markdown", "hi"]);
+        if cfg!(feature = "markdown") {
+            assert!(parsed.is_ok());
+            assert_eq!(format_feature("markdown"), None);
+        } else {
+            let message = parsed.err().unwrap().to_string();
+            assert!(
+                message.contains("markdown output requires building with the `markdown` feature")
+            );
         }
     }
 
+    #[test]
+    fn framing_attributes_role_markers_to_breakdown() {
+        let registry = ModelRegistry::new();
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        let messages = vec![
+            crate::parsers::Message {
+                role: "user".to_string(),
+                content: "Hello there".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            crate::parsers::Message {
+                role: "assistant".to_string(),
+                content: "Hi".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        let count = |framing| {
+            let options = CountOptions {
+                breakdown: true,
+                framing: Some(framing),
+                ..CountOptions::default()
+            };
+            Cli::count_tokens(&*tokenizer, &messages, "gpt-4", options, None).unwrap()
+        };
+
+        let openai = count(FramingStyle::Openai);
+        let anthropic = count(FramingStyle::Anthropic);
+        assert_eq!(openai.content_tokens, anthropic.content_tokens);
+        assert_ne!(openai.overhead_tokens, anthropic.overhead_tokens);
+
+        let human = tokenizer.count_tokens("\n\nHuman: ").unwrap();
+        let content = tokenizer.count_tokens("Hello there").unwrap();
+        let breakdown = anthropic.breakdown.unwrap();
+        assert_eq!(breakdown.user, content + human);
+        assert_eq!(openai.breakdown.unwrap().user, content + 3);
+        assert_eq!(breakdown.total, anthropic.tokens);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn watch_dir_change_to_second_file_triggers_rerun() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main.txt");
+        let partials = dir.path().join("partials");
+        std::fs::write(&main, "main").unwrap();
+        std::fs::create_dir(&partials).unwrap();
+        let fragment = partials.join("fragment.txt");
+        std::fs::write(&fragment, "one").unwrap();
+
+        let cli = Cli::try_parse_from([
+            "tokuin",
+            "--watch",
+            "--watch-dir",
+            partials.to_str().unwrap(),
+            main.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.estimate.watch_dir,
+            vec![partials.to_str().unwrap().to_string()]
+        );
+
+        // Keep touching the fragment until the watcher reports a change
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                for i in 0..100 {
+                    if done.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                    std::fs::write(&fragment, format!("edit {}", i)).unwrap();
+                }
+            })
+        };
+
+        let mut runs = 0;
+        watch_paths(&[main, partials], Duration::from_millis(10), false, || {
+            runs += 1;
+            ControlFlow::Break(())
+        })
+        .unwrap();
+        done.store(true, Ordering::SeqCst);
+        writer.join().unwrap();
+
+        assert_eq!(runs, 1);
+    }
+
     #[cfg(feature = "load-test")]
     mod load_test_cli {
         use super::*;
@@ -1451,36 +5498,46 @@ mod tests {
 
             let command = cli.command.expect("load-test subcommand expected");
             match command {
-                Command::LoadTest {
-                    model,
-                    endpoint,
-                    api_key,
-                    openai_api_key,
-                    anthropic_api_key,
-                    openrouter_api_key,
-                    provider,
-                    concurrency,
-                    runs,
-                    prompt_file,
-                    think_time,
-                    retry,
-                    output_format,
-                    dry_run,
-                    max_cost,
-                    estimate_cost,
-                } => {
+                Command::LoadTest(args) => {
+                    let LoadTestArgs {
+                        model,
+                        endpoint,
+                        api_key,
+                        openai_api_key,
+                        anthropic_api_key,
+                        openrouter_api_key,
+                        api_key_env,
+                        provider,
+                        config,
+                        concurrency,
+                        runs,
+                        prompt_file,
+                        think_time,
+                        retry,
+                        timeout,
+                        seed,
+                        output_format,
+                        dry_run,
+                        max_cost,
+                        estimate_cost,
+                        pricing_file: _,
+                    } = *args;
                     assert_eq!(model, "openai/gpt-4");
                     assert!(endpoint.is_none());
                     assert_eq!(api_key.as_deref(), Some("sk-test"));
                     assert!(openai_api_key.is_none());
                     assert!(anthropic_api_key.is_none());
                     assert!(openrouter_api_key.is_none());
+                    assert!(api_key_env.is_empty());
                     assert!(provider.is_none());
-                    assert_eq!(concurrency, 2);
-                    assert_eq!(runs, 5);
+                    assert!(config.is_none());
+                    assert_eq!(concurrency, Some(2));
+                    assert_eq!(runs, Some(5));
                     assert!(prompt_file.is_none());
                     assert!(think_time.is_none());
-                    assert_eq!(retry, 3, "default retry should be 3");
+                    assert!(retry.is_none(), "unset retry falls back to the default");
+                    assert!(timeout.is_none());
+                    assert!(seed.is_none());
                     assert!(dry_run, "flag should enable dry-run mode");
                     assert!(max_cost.is_none());
                     assert!(!estimate_cost);
@@ -1520,36 +5577,46 @@ mod tests {
 
             let command = cli.command.expect("load-test subcommand expected");
             match command {
-                Command::LoadTest {
-                    model,
-                    endpoint,
-                    api_key,
-                    openai_api_key,
-                    anthropic_api_key,
-                    openrouter_api_key,
-                    provider,
-                    concurrency,
-                    runs,
-                    prompt_file,
-                    think_time,
-                    retry,
-                    output_format,
-                    dry_run,
-                    max_cost,
-                    estimate_cost,
-                } => {
+                Command::LoadTest(args) => {
+                    let LoadTestArgs {
+                        model,
+                        endpoint,
+                        api_key,
+                        openai_api_key,
+                        anthropic_api_key,
+                        openrouter_api_key,
+                        api_key_env,
+                        provider,
+                        config,
+                        concurrency,
+                        runs,
+                        prompt_file,
+                        think_time,
+                        retry,
+                        timeout,
+                        seed,
+                        output_format,
+                        dry_run,
+                        max_cost,
+                        estimate_cost,
+                        pricing_file: _,
+                    } = *args;
                     assert_eq!(model, "openrouter/anthropic-sonnet");
                     assert!(endpoint.is_none());
                     assert!(api_key.is_none());
                     assert!(openai_api_key.is_none());
                     assert!(anthropic_api_key.is_none());
                     assert_eq!(openrouter_api_key.as_deref(), Some("sk-or"));
+                    assert!(api_key_env.is_empty());
                     assert!(provider.is_none());
-                    assert_eq!(concurrency, 4);
-                    assert_eq!(runs, 10);
+                    assert!(config.is_none());
+                    assert_eq!(concurrency, Some(4));
+                    assert_eq!(runs, Some(10));
                     assert!(prompt_file.is_none());
                     assert_eq!(think_time.as_deref(), Some("250-500ms"));
-                    assert_eq!(retry, 5);
+                    assert_eq!(retry, Some(5));
+                    assert!(timeout.is_none());
+                    assert!(seed.is_none());
                     assert!(!dry_run);
                     assert!(max_cost.is_none());
                     assert!(estimate_cost);
@@ -1582,14 +5649,10 @@ mod tests {
             ])
             .expect("load-test args should parse with provider flag");
 
-            let command = cli.command.expect("load-test subcommand expected");
-            if let Command::LoadTest { provider, .. } = &command {
-                assert_eq!(provider.as_ref(), Some(&Provider::Anthropic));
-            } else {
-                panic!("unexpected command variant");
-            }
-
-            let args = LoadTestArgs::from(command);
+            let Some(Command::LoadTest(args)) = cli.command else {
+                panic!("load-test subcommand expected");
+            };
+            assert_eq!(args.provider.as_ref(), Some(&Provider::Anthropic));
             assert_eq!(args.resolve_provider(), Provider::Anthropic);
         }
 
@@ -1609,9 +5672,46 @@ mod tests {
             ])
             .expect("load-test args should parse");
 
-            let command = cli.command.expect("load-test subcommand expected");
-            let args = LoadTestArgs::from(command);
+            let Some(Command::LoadTest(args)) = cli.command else {
+                panic!("load-test subcommand expected");
+            };
             assert_eq!(args.resolve_provider(), Provider::Anthropic);
         }
+
+        #[test]
+        fn load_test_config_file_with_flag_override() {
+            use std::io::Write;
+
+            let mut config = tempfile::NamedTempFile::new().unwrap();
+            writeln!(
+                config,
+                "[load_test]\nruns = 40\nconcurrency = 4\nthink_time = \"100-200ms\"\nseed = 7"
+            )
+            .unwrap();
+            let path = config.path().to_str().unwrap();
+
+            let cli = Cli::try_parse_from([
+                "tokuin",
+                "load-test",
+                "--model",
+                "gpt-4",
+                "--config",
+                path,
+                "--concurrency",
+                "2",
+            ])
+            .expect("load-test args should parse without --runs when a config is given");
+
+            let Some(Command::LoadTest(args)) = cli.command else {
+                panic!("load-test subcommand expected");
+            };
+            let sim_config = args.settings().unwrap();
+            assert_eq!(sim_config.runs, 40);
+            assert_eq!(sim_config.concurrency, 2);
+            assert_eq!(sim_config.retry, 3);
+            assert_eq!(sim_config.seed, Some(7));
+            let think_time = sim_config.think_time.unwrap();
+            assert_eq!((think_time.min_ms, think_time.max_ms), (100, 200));
+        }
     }
 }
@@ -55,6 +55,12 @@ pub enum ParseError {
     #[error("Missing required field: {field}")]
     MissingField { field: String },
 
+    #[error(
+        "Unknown role '{role}' in message {index} (expected one of {})",
+        crate::parsers::roles::KNOWN_ROLES.join(", ")
+    )]
+    UnknownRole { role: String, index: usize },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -74,6 +80,55 @@ pub enum AppError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("{failed} of {total} models failed")]
+    ComparisonFailed { failed: usize, total: usize },
+
+    #[error("Estimated cost ${cost:.6} for {model} exceeds --max-cost ${limit:.6}")]
+    CostLimitExceeded {
+        model: String,
+        cost: f64,
+        limit: f64,
+    },
+
+    #[error("{tokens} tokens for {model} is below --min-tokens {min}")]
+    BelowMinTokens {
+        model: String,
+        tokens: usize,
+        min: usize,
+    },
+
+    #[error("{tokens} tokens for {model} exceeds --max-tokens {max}")]
+    AboveMaxTokens {
+        model: String,
+        tokens: usize,
+        max: usize,
+    },
+
+    #[error("{tokens} tokens for {model} exceed its {window}-token context window")]
+    ContextWindowExceeded {
+        model: String,
+        tokens: usize,
+        window: usize,
+    },
+
+    #[error("{input} is larger than --max-bytes {limit}")]
+    InputTooLarge { input: String, limit: usize },
+
+    #[error("Token count grew by {delta}, more than --max-growth {limit}")]
+    TokenGrowthExceeded { delta: i64, limit: usize },
+
+    #[error("Round-trip verification failed for {failed} of {total} models")]
+    RoundTripFailed { failed: usize, total: usize },
+
+    #[error("No eligible model: {0}")]
+    NoEligibleModel(String),
+
+    #[error(
+        "No pricing data for {}; add it to a pricing file and pass --pricing-file (see docs/PRICING_TEMPLATE.toml)",
+        .0.join(", ")
+    )]
+    Unpriced(Vec<String>),
+
     #[cfg(feature = "load-test")]
     #[error("HTTP error: {0}")]
     Http(String),
@@ -91,6 +146,21 @@ pub enum AppError {
     Json(#[from] serde_json::Error),
 }
 
+impl AppError {
+    /// Process exit code for this error.
+    ///
+    /// Token band checks get their own codes so scripts can tell a prompt
+    /// that is too short (3) from one that is too long (4); every other
+    /// error exits with 1.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::BelowMinTokens { .. } => 3,
+            AppError::AboveMaxTokens { .. } => 4,
+            _ => 1,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +190,27 @@ mod tests {
         let _ = ModelError::InvalidPricing("pricing issue".into());
     }
 
+    #[test]
+    fn token_band_errors_have_distinct_exit_codes() {
+        let below = AppError::BelowMinTokens {
+            model: "gpt-4".into(),
+            tokens: 5,
+            min: 10,
+        };
+        let above = AppError::AboveMaxTokens {
+            model: "gpt-4".into(),
+            tokens: 50,
+            max: 10,
+        };
+        assert_eq!(below.exit_code(), 3);
+        assert_eq!(above.exit_code(), 4);
+        assert_eq!(AppError::NoEligibleModel("none".into()).exit_code(), 1);
+        assert_eq!(
+            below.to_string(),
+            "5 tokens for gpt-4 is below --min-tokens 10"
+        );
+    }
+
     #[test]
     fn parse_error_variants_construct() {
         let json_err = serde_json::from_str::<Value>("invalid").unwrap_err();
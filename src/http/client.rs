@@ -4,6 +4,10 @@ use crate::error::AppError;
 #[cfg(feature = "load-test")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "load-test")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "load-test")]
+use std::sync::Arc;
+#[cfg(feature = "load-test")]
 use std::time::Duration;
 
 /// Response from an LLM API call.
@@ -84,12 +88,14 @@ impl LlmClient for LlmClientEnum {
 pub struct ClientConfig {
     /// API endpoint URL
     pub endpoint: String,
-    /// API key
-    pub api_key: String,
+    /// API keys, used in turn by successive requests
+    pub api_keys: ApiKeys,
     /// Request timeout
     pub timeout: Duration,
     /// Additional headers
     pub headers: Vec<(String, String)>,
+    /// Retry behavior for transient failures
+    pub retry: RetryPolicy,
 }
 
 #[cfg(feature = "load-test")]
@@ -97,9 +103,338 @@ impl Default for ClientConfig {
     fn default() -> Self {
         Self {
             endpoint: String::new(),
-            api_key: String::new(),
+            api_keys: ApiKeys::default(),
             timeout: Duration::from_secs(60),
             headers: Vec::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// API keys that requests take in round-robin order.
+///
+/// Spreading requests over several keys keeps each one under its provider
+/// rate limit. Clones share the rotation, so every client built from one
+/// config cycles through the same sequence. `Debug` output is redacted.
+#[cfg(feature = "load-test")]
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    keys: Arc<[String]>,
+    next: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "load-test")]
+impl ApiKeys {
+    /// Rotate through `keys` in order.
+    pub fn new(keys: Vec<String>) -> Self {
+        Self {
+            keys: keys.into(),
+            next: Arc::new(AtomicUsize::new(0)),
         }
     }
+
+    /// The key for the next request, or `""` if there are no keys.
+    pub fn next_key(&self) -> &str {
+        if self.keys.is_empty() {
+            return "";
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.keys.len();
+        &self.keys[index]
+    }
+
+    /// Number of keys in the rotation.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether there are no keys.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The keys with all but their last four characters hidden, for logs.
+    pub fn redacted(&self) -> Vec<String> {
+        self.keys.iter().map(|key| redact_key(key)).collect()
+    }
+}
+
+#[cfg(feature = "load-test")]
+impl std::fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.redacted()).finish()
+    }
+}
+
+#[cfg(feature = "load-test")]
+impl From<String> for ApiKeys {
+    fn from(key: String) -> Self {
+        Self::new(vec![key])
+    }
+}
+
+#[cfg(feature = "load-test")]
+impl From<&str> for ApiKeys {
+    fn from(key: &str) -> Self {
+        Self::new(vec![key.to_string()])
+    }
+}
+
+/// Hide an API key except for its last four characters.
+///
+/// Keys too short to keep anything hidden are masked entirely.
+#[cfg(feature = "load-test")]
+fn redact_key(key: &str) -> String {
+    let chars = key.chars().count();
+    if chars < 12 {
+        return "****".to_string();
+    }
+    let tail: String = key.chars().skip(chars - 4).collect();
+    format!("****{}", tail)
+}
+
+/// Retry policy for transient HTTP failures.
+///
+/// Rate limits (429), server errors (5xx), timeouts and connection failures
+/// are retried. A `Retry-After` header (in seconds) takes precedence over the
+/// exponential backoff.
+#[cfg(feature = "load-test")]
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound for any single delay
+    pub max_delay: Duration,
+}
+
+#[cfg(feature = "load-test")]
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        // The load-test simulator retries whole requests, so don't multiply attempts
+        Self {
+            max_retries: 0,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+#[cfg(feature = "load-test")]
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (zero-based), honoring `Retry-After`.
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt));
+        retry_after.unwrap_or(backoff).min(self.max_delay)
+    }
+}
+
+/// Send a request with the shared timeout, header, retry and error handling.
+///
+/// `build` is called for every attempt and should return the request with the
+/// provider's payload and auth headers; the custom headers from `config` are
+/// added afterwards. Non-success responses become `AppError::Api` prefixed
+/// with `error_label` (e.g. "Anthropic API error").
+///
+/// # Errors
+///
+/// Returns `AppError::Http` if the request cannot be sent and `AppError::Api`
+/// for a non-success status once retries are exhausted.
+#[cfg(feature = "load-test")]
+pub async fn send_with_retry<F>(
+    config: &ClientConfig,
+    error_label: &str,
+    build: F,
+) -> Result<reqwest::Response, AppError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let policy = &config.retry;
+    let mut attempt = 0;
+
+    loop {
+        let mut req = build().timeout(config.timeout);
+        for (key, value) in &config.headers {
+            req = req.header(key, value);
+        }
+
+        let (error, retry_after) = match req.send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let retryable =
+                    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                let error = AppError::Api(format!("{} ({}): {}", error_label, status, error_text));
+                if !retryable {
+                    return Err(error);
+                }
+                (error, retry_after)
+            }
+            Err(e) => {
+                let retryable = e.is_timeout() || e.is_connect();
+                let error = AppError::Http(format!("Request failed: {}", e));
+                if !retryable {
+                    return Err(error);
+                }
+                (error, None)
+            }
+        };
+
+        if attempt >= policy.max_retries {
+            return Err(error);
+        }
+        tokio::time::sleep(policy.delay(attempt, retry_after)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "load-test")]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn config_with_retries(max_retries: u32) -> ClientConfig {
+        ClientConfig {
+            retry: RetryPolicy {
+                max_retries,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(10),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_api_keys_rotate_in_order() {
+        let keys = ApiKeys::new(vec!["a".into(), "b".into(), "c".into()]);
+        let shared = keys.clone();
+        let used: Vec<&str> = (0..7)
+            .map(|i| {
+                if i % 2 == 0 {
+                    keys.next_key()
+                } else {
+                    shared.next_key()
+                }
+            })
+            .collect();
+        assert_eq!(used, ["a", "b", "c", "a", "b", "c", "a"]);
+
+        assert_eq!(ApiKeys::default().next_key(), "");
+    }
+
+    #[test]
+    fn test_api_keys_are_redacted() {
+        let keys = ApiKeys::new(vec!["sk-proj-1234567890abcd".into(), "short".into()]);
+        assert_eq!(keys.redacted(), ["****abcd", "****"]);
+        let debug = format!(
+            "{:?}",
+            ClientConfig {
+                api_keys: keys,
+                ..Default::default()
+            }
+        );
+        assert!(!debug.contains("sk-proj"));
+    }
+
+    #[test]
+    fn test_delay_backoff_and_retry_after() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        assert_eq!(policy.delay(0, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(400));
+        assert_eq!(policy.delay(5, None), Duration::from_secs(1));
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_millis(250))),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_until_exhausted() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/fail");
+                then.status(503).body("unavailable");
+            })
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = server.url("/fail");
+        let result = send_with_retry(&config_with_retries(2), "Test API error", || {
+            client.post(&url)
+        })
+        .await;
+
+        match result {
+            Err(AppError::Api(message)) => {
+                assert!(message.starts_with("Test API error (503"));
+                assert!(message.contains("unavailable"));
+            }
+            other => panic!("expected API error, got {:?}", other.map(|r| r.status())),
+        }
+        mock.assert_hits_async(3).await;
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/bad");
+                then.status(400).body("bad request");
+            })
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = server.url("/bad");
+        let result = send_with_retry(&config_with_retries(3), "Test API error", || {
+            client.post(&url)
+        })
+        .await;
+
+        assert!(matches!(result, Err(AppError::Api(_))));
+        mock.assert_hits_async(1).await;
+    }
+
+    #[tokio::test]
+    async fn applies_custom_headers_and_returns_success() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/ok")
+                    .header("X-Custom-Header", "custom-value");
+                then.status(200).body("ok");
+            })
+            .await;
+
+        let mut config = config_with_retries(0);
+        config.headers = vec![("X-Custom-Header".into(), "custom-value".into())];
+        let client = reqwest::Client::new();
+        let url = server.url("/ok");
+        let response = send_with_retry(&config, "Test API error", || client.post(&url))
+            .await
+            .expect("request should succeed");
+
+        assert_eq!(response.text().await.unwrap(), "ok");
+        mock.assert_async().await;
+    }
 }
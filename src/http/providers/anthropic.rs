@@ -2,7 +2,7 @@
 #[cfg(feature = "load-test")]
 use crate::error::AppError;
 #[cfg(feature = "load-test")]
-use crate::http::client::{ClientConfig, LlmClient, LlmResponse};
+use crate::http::client::{send_with_retry, ClientConfig, LlmClient, LlmResponse};
 #[cfg(feature = "load-test")]
 use reqwest::Client;
 #[cfg(feature = "load-test")]
@@ -52,34 +52,16 @@ impl LlmClient for AnthropicClient {
             temperature: Some(0.7),
         };
 
-        let mut req = self
-            .client
-            .post(&endpoint)
-            .header("x-api-key", &self.config.api_key)
-            .header("content-type", "application/json")
-            .header("anthropic-version", "2023-06-01");
-
-        for (key, value) in &self.config.headers {
-            req = req.header(key, value);
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| AppError::Http(format!("Request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Api(format!(
-                "Anthropic API error ({}): {}",
-                status, error_text
-            )));
-        }
+        let api_key = self.config.api_keys.next_key();
+        let response = send_with_retry(&self.config, "Anthropic API error", || {
+            self.client
+                .post(&endpoint)
+                .header("x-api-key", api_key)
+                .header("content-type", "application/json")
+                .header("anthropic-version", "2023-06-01")
+                .json(&request)
+        })
+        .await?;
 
         let api_response: AnthropicResponse = response
             .json()
@@ -198,9 +180,10 @@ mod tests {
 
         let config = ClientConfig {
             endpoint: format!("{}{}", server.base_url(), path),
-            api_key: "sk-test".into(),
+            api_keys: "sk-test".into(),
             timeout: Duration::from_secs(30),
             headers: Vec::new(),
+            ..Default::default()
         };
 
         let client = AnthropicClient::new(config).expect("client initialization");
@@ -2,7 +2,7 @@
 #[cfg(feature = "load-test")]
 use crate::error::AppError;
 #[cfg(feature = "load-test")]
-use crate::http::client::{ClientConfig, LlmClient, LlmResponse};
+use crate::http::client::{send_with_retry, ClientConfig, LlmClient, LlmResponse};
 #[cfg(feature = "load-test")]
 use reqwest::Client;
 #[cfg(feature = "load-test")]
@@ -43,33 +43,15 @@ impl LlmClient for GenericClient {
             prompt: prompt.to_string(),
         };
 
-        let mut req = self.client.post(&self.config.endpoint);
-
-        if !self.config.api_key.is_empty() {
-            req = req.header("Authorization", format!("Bearer {}", self.config.api_key));
-        }
-
-        for (key, value) in &self.config.headers {
-            req = req.header(key, value);
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| AppError::Http(format!("Request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Api(format!(
-                "Generic API error ({}): {}",
-                status, error_text
-            )));
-        }
+        let api_key = self.config.api_keys.next_key();
+        let response = send_with_retry(&self.config, "Generic API error", || {
+            let mut req = self.client.post(&self.config.endpoint);
+            if !api_key.is_empty() {
+                req = req.header("Authorization", format!("Bearer {}", api_key));
+            }
+            req.json(&request)
+        })
+        .await?;
 
         let api_response: GenericResponse = response
             .json()
@@ -174,6 +156,7 @@ struct GenericUsage {
 #[cfg(all(test, feature = "load-test"))]
 mod tests {
     use super::*;
+    use crate::http::client::ApiKeys;
     use httpmock::prelude::*;
     use serde_json::json;
     use std::time::Duration;
@@ -208,9 +191,10 @@ mod tests {
 
         let config = ClientConfig {
             endpoint: format!("{}{}", server.base_url(), path),
-            api_key: "generic-key".into(),
+            api_keys: "generic-key".into(),
             timeout: Duration::from_secs(15),
             headers: vec![("X-Custom".into(), "Value".into())],
+            ..Default::default()
         };
 
         let client = GenericClient::new(config).expect("generic client init");
@@ -231,9 +215,10 @@ mod tests {
     async fn generic_client_requires_endpoint() {
         let config = ClientConfig {
             endpoint: String::new(),
-            api_key: String::new(),
+            api_keys: ApiKeys::default(),
             timeout: Duration::from_secs(15),
             headers: Vec::new(),
+            ..Default::default()
         };
 
         let client = GenericClient::new(config).expect("generic client init");
@@ -2,7 +2,7 @@
 #[cfg(feature = "load-test")]
 use crate::error::AppError;
 #[cfg(feature = "load-test")]
-use crate::http::client::{ClientConfig, LlmClient, LlmResponse};
+use crate::http::client::{send_with_retry, ClientConfig, LlmClient, LlmResponse};
 #[cfg(feature = "load-test")]
 use reqwest::Client;
 #[cfg(feature = "load-test")]
@@ -103,34 +103,15 @@ impl LlmClient for OpenAIClient {
             temperature: Some(0.7),
         };
 
-        let mut req = self
-            .client
-            .post(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json");
-
-        // Add custom headers
-        for (key, value) in &self.config.headers {
-            req = req.header(key, value);
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| AppError::Http(format!("Request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Api(format!(
-                "API error ({}): {}",
-                status, error_text
-            )));
-        }
+        let api_key = self.config.api_keys.next_key();
+        let response = send_with_retry(&self.config, "API error", || {
+            self.client
+                .post(&endpoint)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .header("Content-Type", "application/json")
+                .json(&request)
+        })
+        .await?;
 
         let api_response: OpenAIResponse = response
             .json()
@@ -164,6 +145,7 @@ impl LlmClient for OpenAIClient {
 #[cfg(feature = "load-test")]
 mod tests {
     use super::*;
+    use crate::http::client::ApiKeys;
     use httpmock::prelude::*;
     use serde_json::json;
     use std::time::Duration;
@@ -172,9 +154,10 @@ mod tests {
     fn test_openai_client_creation() {
         let config = ClientConfig {
             endpoint: String::new(),
-            api_key: "test-key".to_string(),
+            api_keys: "test-key".into(),
             timeout: Duration::from_secs(60),
             headers: Vec::new(),
+            ..Default::default()
         };
         let client = OpenAIClient::new(config);
         assert!(client.is_ok());
@@ -190,9 +173,10 @@ mod tests {
     fn test_provider_name() {
         let config = ClientConfig {
             endpoint: String::new(),
-            api_key: "test-key".to_string(),
+            api_keys: "test-key".into(),
             timeout: Duration::from_secs(60),
             headers: Vec::new(),
+            ..Default::default()
         };
         let client = OpenAIClient::new(config).unwrap();
         assert_eq!(client.provider_name(), "openai");
@@ -234,9 +218,10 @@ mod tests {
 
         let config = ClientConfig {
             endpoint: format!("{}{}", server.base_url(), path),
-            api_key: "test-key".to_string(),
+            api_keys: "test-key".into(),
             timeout: Duration::from_secs(30),
             headers: vec![("X-Custom-Header".into(), "custom-value".into())],
+            ..Default::default()
         };
 
         let client = OpenAIClient::new(config).expect("client initialization");
@@ -253,4 +238,40 @@ mod tests {
 
         mock.assert_async().await;
     }
+
+    #[tokio::test]
+    async fn send_request_rotates_api_keys() {
+        let server = MockServer::start_async().await;
+        let mut mocks = Vec::new();
+        for key in ["key-a", "key-b", "key-c"] {
+            let mock = server
+                .mock_async(|when, then| {
+                    when.method(POST)
+                        .path("/v1/chat/completions")
+                        .header("Authorization", format!("Bearer {}", key));
+                    then.status(200).json_body(json!({
+                        "choices": [{ "message": { "content": "pong" } }]
+                    }));
+                })
+                .await;
+            mocks.push(mock);
+        }
+
+        let config = ClientConfig {
+            endpoint: server.url("/v1/chat/completions"),
+            api_keys: ApiKeys::new(vec!["key-a".into(), "key-b".into(), "key-c".into()]),
+            ..Default::default()
+        };
+        let client = OpenAIClient::new(config).expect("client initialization");
+        for _ in 0..6 {
+            client
+                .send_request("ping", "gpt-4o-mini")
+                .await
+                .expect("request should succeed");
+        }
+
+        for mock in mocks {
+            mock.assert_hits_async(2).await;
+        }
+    }
 }
@@ -2,7 +2,7 @@
 #[cfg(feature = "load-test")]
 use crate::error::AppError;
 #[cfg(feature = "load-test")]
-use crate::http::client::{ClientConfig, LlmClient, LlmResponse};
+use crate::http::client::{send_with_retry, ClientConfig, LlmClient, LlmResponse};
 #[cfg(feature = "load-test")]
 use reqwest::Client;
 #[cfg(feature = "load-test")]
@@ -106,38 +106,20 @@ impl LlmClient for OpenRouterClient {
             temperature: Some(0.7),
         };
 
-        let mut req = self
-            .client
-            .post(&endpoint)
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            // OpenRouter requires HTTP-Referer header (can be any URL)
-            .header("HTTP-Referer", "https://github.com/nooscraft/tokuin")
-            // Optional: X-Title header for identifying the application
-            .header("X-Title", "Tokuin");
-
-        // Add custom headers (these will override defaults if same key)
-        for (key, value) in &self.config.headers {
-            req = req.header(key, value);
-        }
-
-        let response = req
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| AppError::Http(format!("Request failed: {}", e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(AppError::Api(format!(
-                "OpenRouter API error ({}): {}",
-                status, error_text
-            )));
-        }
+        // Custom headers from the config are added after these defaults
+        let api_key = self.config.api_keys.next_key();
+        let response = send_with_retry(&self.config, "OpenRouter API error", || {
+            self.client
+                .post(&endpoint)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .header("Content-Type", "application/json")
+                // OpenRouter requires HTTP-Referer header (can be any URL)
+                .header("HTTP-Referer", "https://github.com/nooscraft/tokuin")
+                // Optional: X-Title header for identifying the application
+                .header("X-Title", "Tokuin")
+                .json(&request)
+        })
+        .await?;
 
         let api_response: OpenRouterResponse = response
             .json()
@@ -179,9 +161,10 @@ mod tests {
     fn test_openrouter_client_creation() {
         let config = ClientConfig {
             endpoint: String::new(),
-            api_key: "test-key".to_string(),
+            api_keys: "test-key".into(),
             timeout: Duration::from_secs(60),
             headers: Vec::new(),
+            ..Default::default()
         };
         let client = OpenRouterClient::new(config);
         assert!(client.is_ok());
@@ -197,9 +180,10 @@ mod tests {
     fn test_provider_name() {
         let config = ClientConfig {
             endpoint: String::new(),
-            api_key: "test-key".to_string(),
+            api_keys: "test-key".into(),
             timeout: Duration::from_secs(60),
             headers: Vec::new(),
+            ..Default::default()
         };
         let client = OpenRouterClient::new(config).unwrap();
         assert_eq!(client.provider_name(), "openrouter");
@@ -242,12 +226,13 @@ mod tests {
 
         let config = ClientConfig {
             endpoint: format!("{}{}", server.base_url(), path),
-            api_key: "or-key".to_string(),
+            api_keys: "or-key".into(),
             timeout: Duration::from_secs(30),
             headers: vec![
                 ("HTTP-Referer".into(), "https://example.com".into()),
                 ("X-Title".into(), "Custom Title".into()),
             ],
+            ..Default::default()
         };
 
         let client = OpenRouterClient::new(config).expect("client initialization");
@@ -20,9 +20,15 @@ use cli::Cli;
 
 fn main() {
     let cli = Cli::parse();
+    if let Err(e) = cli.check_conflicts() {
+        e.exit();
+    }
+    let quiet = cli.quiet();
 
     if let Err(e) = cli.run() {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+        if !quiet {
+            eprintln!("Error: {}", e);
+        }
+        std::process::exit(e.exit_code());
     }
 }
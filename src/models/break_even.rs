@@ -0,0 +1,142 @@
+/// Break-even analysis between model pricing tables.
+use crate::output::TokenResult;
+
+/// Output length at which two models cost the same for a given prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakEven {
+    /// Model that is cheaper for outputs shorter than the break-even point.
+    pub cheaper_below: String,
+    /// Model that is cheaper for outputs longer than the break-even point.
+    pub cheaper_above: String,
+    /// Output length (in tokens) where both models cost the same.
+    pub output_tokens: f64,
+}
+
+impl BreakEven {
+    /// Name of the cheaper model at the given output length.
+    pub fn winner_at(&self, output_tokens: usize) -> &str {
+        if (output_tokens as f64) < self.output_tokens {
+            &self.cheaper_below
+        } else {
+            &self.cheaper_above
+        }
+    }
+
+    /// Human-readable one-line summary.
+    pub fn describe(&self, expected_output: usize) -> String {
+        format!(
+            "{} is cheaper below {:.0} output tokens, {} above ({} wins at {})",
+            self.cheaper_below,
+            self.output_tokens,
+            self.cheaper_above,
+            self.winner_at(expected_output),
+            expected_output
+        )
+    }
+}
+
+/// Ratio of the output rate to the input rate.
+///
+/// Returns `None` when the input rate is zero, since the ratio is undefined.
+pub fn output_input_ratio(input_rate: f64, output_rate: f64) -> Option<f64> {
+    if input_rate > 0.0 {
+        Some(output_rate / input_rate)
+    } else {
+        None
+    }
+}
+
+/// Find the break-even output length for every pair of priced results.
+///
+/// Each result's own input token count is used, since different tokenizers
+/// produce different counts for the same prompt. Results missing either rate
+/// are excluded, as are pairs where one model is cheaper at every length.
+pub fn break_even_points(results: &[TokenResult]) -> Vec<BreakEven> {
+    let priced: Vec<(&TokenResult, f64, f64)> = results
+        .iter()
+        .filter_map(|r| Some((r, r.input_rate?, r.output_rate?)))
+        .collect();
+
+    let mut points = Vec::new();
+    for (i, &(a, a_in, a_out)) in priced.iter().enumerate() {
+        for &(b, b_in, b_out) in &priced[i + 1..] {
+            let out_diff = a_out - b_out;
+            if out_diff == 0.0 {
+                continue;
+            }
+
+            // cost = tokens * in_rate + output * out_rate, solved for equal cost
+            let in_diff = b.tokens as f64 * b_in - a.tokens as f64 * a_in;
+            let output_tokens = in_diff / out_diff;
+            if !output_tokens.is_finite() || output_tokens <= 0.0 {
+                continue;
+            }
+
+            // The model with the higher output rate wins on short outputs
+            let (below, above) = if out_diff > 0.0 { (a, b) } else { (b, a) };
+            points.push(BreakEven {
+                cheaper_below: below.model.clone(),
+                cheaper_above: above.model.clone(),
+                output_tokens,
+            });
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn priced(model: &str, tokens: usize, input: f64, output: f64) -> TokenResult {
+        TokenResult {
+            model: model.to_string(),
+            tokens,
+            input_rate: Some(input),
+            output_rate: Some(output),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_output_input_ratio() {
+        assert_eq!(output_input_ratio(0.01, 0.03), Some(3.0));
+        assert_eq!(output_input_ratio(0.0, 0.03), None);
+    }
+
+    #[test]
+    fn test_break_even_flips_winner() {
+        // "premium": expensive input, cheap output. "budget": the opposite.
+        let results = vec![
+            priced("premium", 1000, 0.01, 0.01),
+            priced("budget", 1000, 0.002, 0.03),
+        ];
+
+        let points = break_even_points(&results);
+        assert_eq!(points.len(), 1);
+
+        let point = &points[0];
+        assert_eq!(point.cheaper_below, "budget");
+        assert_eq!(point.cheaper_above, "premium");
+        assert!((point.output_tokens - 400.0).abs() < 1e-9);
+        assert_eq!(point.winner_at(100), "budget");
+        assert_eq!(point.winner_at(1000), "premium");
+    }
+
+    #[test]
+    fn test_break_even_excludes_unpriced_and_dominated() {
+        let results = vec![
+            priced("cheap", 100, 0.001, 0.002),
+            priced("expensive", 100, 0.01, 0.02),
+            TokenResult {
+                model: "unpriced".to_string(),
+                tokens: 100,
+                input_rate: Some(0.001),
+                ..Default::default()
+            },
+        ];
+
+        assert!(break_even_points(&results).is_empty());
+    }
+}
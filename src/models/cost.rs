@@ -0,0 +1,106 @@
+/// Cost computation from per-1K token pricing.
+use crate::models::pricing::ModelPricing;
+
+/// Cost of a request split into its priced components (in USD).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostBreakdown {
+    /// Cost of fresh (uncached) input tokens.
+    pub input: f64,
+    /// Cost of output tokens.
+    pub output: f64,
+    /// Cost of input tokens served from the prompt cache.
+    pub cached_input: f64,
+    /// Sum of all components.
+    pub total: f64,
+}
+
+impl CostBreakdown {
+    /// Build a breakdown from its components, computing the total.
+    pub fn new(input: f64, output: f64, cached_input: f64) -> Self {
+        Self {
+            input,
+            output,
+            cached_input,
+            total: input + output + cached_input,
+        }
+    }
+}
+
+/// Compute the cost of a request.
+///
+/// `input_tokens` are the fresh input tokens; `cached_input_tokens` are
+/// reported as their own component. Pricing has no separate cached rate yet,
+/// so they are billed at the input rate.
+///
+/// # Example
+///
+/// ```rust
+/// # use tokuin::models::cost::compute_cost;
+/// # use tokuin::models::pricing::ModelPricing;
+/// let pricing = ModelPricing {
+///     input: 0.03,
+///     output: 0.06,
+///     typical_output_ratio: None,
+/// };
+/// let cost = compute_cost(&pricing, 1000, 500, 0);
+/// assert!((cost.total - 0.06).abs() < 1e-12);
+/// ```
+pub fn compute_cost(
+    pricing: &ModelPricing,
+    input_tokens: usize,
+    output_tokens: usize,
+    cached_input_tokens: usize,
+) -> CostBreakdown {
+    CostBreakdown::new(
+        per_1k(input_tokens, pricing.input),
+        per_1k(output_tokens, pricing.output),
+        per_1k(cached_input_tokens, pricing.input),
+    )
+}
+
+fn per_1k(tokens: usize, rate: f64) -> f64 {
+    (tokens as f64 / 1000.0) * rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing() -> ModelPricing {
+        ModelPricing {
+            input: 0.01,
+            output: 0.03,
+            typical_output_ratio: None,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-12,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_each_component() {
+        let cost = compute_cost(&pricing(), 2000, 1000, 4000);
+        assert_close(cost.input, 0.02);
+        assert_close(cost.output, 0.03);
+        assert_close(cost.cached_input, 0.04);
+        assert_close(cost.total, cost.input + cost.output + cost.cached_input);
+    }
+
+    #[test]
+    fn test_cached_billed_at_input_rate() {
+        let cost = compute_cost(&pricing(), 0, 0, 1000);
+        assert_close(cost.cached_input, 0.01);
+        assert_close(cost.total, 0.01);
+    }
+
+    #[test]
+    fn test_zero_tokens() {
+        assert_eq!(compute_cost(&pricing(), 0, 0, 0), CostBreakdown::default());
+    }
+}
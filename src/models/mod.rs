@@ -1,4 +1,7 @@
+pub mod break_even;
+pub mod cost;
 pub mod pricing;
+pub mod projection;
 /// Model registry and pricing information.
 pub mod registry;
 
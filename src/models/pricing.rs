@@ -2,9 +2,16 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// How long to wait for a remote pricing file.
+#[cfg(feature = "load-test")]
+const PRICING_FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 /// Pricing configuration for models.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricingConfig {
+    /// Azure OpenAI deployment names mapped to the models they serve.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub azure_deployments: HashMap<String, String>,
     /// Pricing by provider and model.
     #[serde(flatten)]
     pub providers: HashMap<String, ProviderPricing>,
@@ -24,6 +31,10 @@ pub struct ModelPricing {
     pub input: f64,
     /// Output price per 1K tokens in USD.
     pub output: f64,
+    /// Typical output length as a multiple of the input length, used to
+    /// project output cost when no expected output is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typical_output_ratio: Option<f64>,
 }
 
 impl PricingConfig {
@@ -38,6 +49,7 @@ impl PricingConfig {
             ModelPricing {
                 input: 0.03,
                 output: 0.06,
+                typical_output_ratio: None,
             },
         );
         openai_models.insert(
@@ -45,6 +57,7 @@ impl PricingConfig {
             ModelPricing {
                 input: 0.01,
                 output: 0.03,
+                typical_output_ratio: None,
             },
         );
         openai_models.insert(
@@ -52,6 +65,7 @@ impl PricingConfig {
             ModelPricing {
                 input: 0.0015,
                 output: 0.002,
+                typical_output_ratio: None,
             },
         );
 
@@ -62,7 +76,10 @@ impl PricingConfig {
             },
         );
 
-        Self { providers }
+        Self {
+            azure_deployments: HashMap::new(),
+            providers,
+        }
     }
 
     /// Get pricing for a model.
@@ -97,6 +114,83 @@ impl PricingConfig {
         let config: PricingConfig = toml::from_str(&content)?;
         Ok(config)
     }
+
+    /// Load pricing from a local path or an `http(s)://` URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, fetched or parsed.
+    pub fn from_source(source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if is_url(source) {
+            Self::from_url(source)
+        } else {
+            Self::from_file(source)
+        }
+    }
+
+    /// Fetch pricing from a URL. Nothing is cached, so every run fetches it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, times out or returns an error
+    /// status, or if the response is not a valid pricing file.
+    #[cfg(feature = "load-test")]
+    pub fn from_url(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let content = runtime
+            .block_on(async {
+                reqwest::Client::builder()
+                    .timeout(PRICING_FETCH_TIMEOUT)
+                    .build()?
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await
+            })
+            .map_err(|e| {
+                if e.is_timeout() {
+                    format!(
+                        "request timed out after {}s",
+                        PRICING_FETCH_TIMEOUT.as_secs()
+                    )
+                } else if let Some(status) = e.status() {
+                    format!("server returned {}", status)
+                } else {
+                    // The top-level message hides the cause, e.g. a refused connection
+                    let mut message = e.to_string();
+                    let mut source = std::error::Error::source(&e);
+                    while let Some(cause) = source {
+                        message.push_str(&format!(": {}", cause));
+                        source = cause.source();
+                    }
+                    message
+                }
+            })?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Fetch pricing from a URL; needs the HTTP client of the `load-test` feature.
+    ///
+    /// # Errors
+    ///
+    /// Always fails in this build.
+    #[cfg(not(feature = "load-test"))]
+    pub fn from_url(_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Err("pricing URLs require building with the `load-test` feature".into())
+    }
+}
+
+/// Whether a pricing source is an `http://` or `https://` URL.
+pub fn is_url(source: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        source
+            .get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
 }
 
 impl Default for PricingConfig {
@@ -127,6 +221,14 @@ mod tests {
         assert!(pricing.is_none());
     }
 
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/pricing.toml"));
+        assert!(is_url("HTTP://example.com/pricing.toml"));
+        assert!(!is_url("pricing.toml"));
+        assert!(!is_url("./http/pricing.toml"));
+    }
+
     #[test]
     fn test_from_file() {
         let mut temp = tempfile::NamedTempFile::new().expect("create temp file");
@@ -135,6 +237,7 @@ mod tests {
             [openai.gpt-4]
             input = 0.02
             output = 0.04
+            typical_output_ratio = 2.0
         "#;
         use std::io::Write;
         temp.write_all(content.as_bytes())
@@ -147,6 +250,7 @@ mod tests {
         if let Some(p) = pricing {
             assert_eq!(p.input, 0.02);
             assert_eq!(p.output, 0.04);
+            assert_eq!(p.typical_output_ratio, Some(2.0));
         }
     }
 }
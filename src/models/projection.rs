@@ -0,0 +1,80 @@
+/// Projected latency and cost of generating a response.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ResponseProjection {
+    /// Output throughput the projection assumes, in tokens per second.
+    pub throughput: f64,
+    /// Time to generate the expected output, in seconds.
+    pub latency_secs: f64,
+    /// Total cost of the request (input and output) in USD, if priced.
+    pub cost: Option<f64>,
+}
+
+impl ResponseProjection {
+    /// Project generating `expected_output` tokens at `throughput` tokens per second.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use tokuin::models::projection::ResponseProjection;
+    /// let projection = ResponseProjection::new(500, 50.0, Some(0.03));
+    /// assert_eq!(projection.latency_secs, 10.0);
+    /// assert_eq!(projection.cost_per_second(), Some(0.003));
+    /// ```
+    pub fn new(expected_output: usize, throughput: f64, cost: Option<f64>) -> Self {
+        Self {
+            throughput,
+            latency_secs: expected_output as f64 / throughput,
+            cost,
+        }
+    }
+
+    /// Cost spread over the response time, in USD per second.
+    ///
+    /// Returns `None` without pricing or when the response takes no time.
+    pub fn cost_per_second(&self) -> Option<f64> {
+        self.cost
+            .filter(|_| self.latency_secs > 0.0)
+            .map(|cost| cost / self.latency_secs)
+    }
+}
+
+/// Parse an output throughput in tokens per second.
+///
+/// # Errors
+///
+/// Returns a message unless the rate is a finite number greater than zero.
+pub fn parse_throughput(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        Ok(_) => Err(format!(
+            "throughput must be greater than 0 tokens/sec, got '{}'",
+            value
+        )),
+        Err(_) => Err(format!("invalid throughput '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_is_output_over_throughput() {
+        let projection = ResponseProjection::new(300, 40.0, None);
+        assert_eq!(projection.latency_secs, 300.0 / 40.0);
+        assert_eq!(projection.cost_per_second(), None);
+
+        let projection = ResponseProjection::new(0, 40.0, Some(0.01));
+        assert_eq!(projection.latency_secs, 0.0);
+        assert_eq!(projection.cost_per_second(), None);
+    }
+
+    #[test]
+    fn test_parse_throughput() {
+        assert_eq!(parse_throughput("42.5").unwrap(), 42.5);
+        assert!(parse_throughput("0").is_err());
+        assert!(parse_throughput("-5").is_err());
+        assert!(parse_throughput("inf").is_err());
+        assert!(parse_throughput("fast").is_err());
+    }
+}
@@ -1,15 +1,27 @@
 /// Model registry for managing available models and their tokenizers.
-use crate::error::ModelError;
+use crate::error::{ModelError, TokenizerError};
 use crate::models::pricing::PricingConfig;
-use crate::tokenizers::Tokenizer;
+use crate::tokenizers::{factory, Tokenizer};
 
-#[cfg(feature = "openai")]
-use crate::tokenizers::OpenAITokenizer;
-
-#[cfg(feature = "gemini")]
+#[cfg(all(feature = "gemini", feature = "sentencepiece"))]
 use crate::tokenizers::GeminiTokenizer;
 
+#[cfg(feature = "huggingface")]
+use crate::tokenizers::HfTokenizer;
+
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Text encoded once by [`ModelRegistry::warmup`] to prime a tokenizer.
+const WARMUP_TEXT: &str = "Hello, world!";
+
+/// Flagship model of each provider, compared by `--compare-providers`.
+pub const PROVIDER_FLAGSHIPS: &[(&str, &str)] = &[
+    ("openai", "gpt-4"),
+    ("google", "gemini-2.5-pro"),
+    ("anthropic", "claude-3-sonnet"),
+];
 
 /// Information about a model.
 #[derive(Debug, Clone)]
@@ -23,11 +35,19 @@ pub struct ModelInfo {
     pub input_price: Option<f64>,
     /// Output price per 1K tokens in USD.
     pub output_price: Option<f64>,
+    /// Typical output length as a multiple of the input length.
+    pub typical_output_ratio: Option<f64>,
+    /// Pricing file that set the prices, or `None` for the built-in defaults.
+    pub pricing_file: Option<String>,
 }
 
 /// Registry for managing models and their tokenizers.
 pub struct ModelRegistry {
     models: HashMap<String, ModelInfo>,
+    /// Azure OpenAI deployment names and the models they serve.
+    deployments: HashMap<String, String>,
+    /// Tokenizers constructed ahead of time by `warmup`, by canonical name.
+    warm: HashMap<String, Arc<dyn Tokenizer>>,
 }
 
 impl ModelRegistry {
@@ -35,6 +55,8 @@ impl ModelRegistry {
     pub fn new() -> Self {
         let mut registry = Self {
             models: HashMap::new(),
+            deployments: HashMap::new(),
+            warm: HashMap::new(),
         };
         registry.register_default_models();
         registry
@@ -47,6 +69,14 @@ impl ModelRegistry {
         Ok(registry)
     }
 
+    /// Map an Azure OpenAI deployment name to the model it serves.
+    ///
+    /// The deployment then resolves to that model's tokenizer and pricing,
+    /// with or without an `azure/` prefix.
+    pub fn add_deployment(&mut self, deployment: impl Into<String>, model: impl Into<String>) {
+        self.deployments.insert(deployment.into(), model.into());
+    }
+
     /// Get information about a model.
     #[allow(dead_code)]
     pub fn get_model_info(&self, model_name: &str) -> Option<&ModelInfo> {
@@ -75,27 +105,125 @@ impl ModelRegistry {
     /// Returns `ModelError::ModelNotFound` if the model is not registered,
     /// or `ModelError::Tokenizer` if the tokenizer cannot be created.
     pub fn get_tokenizer(&self, model_name: &str) -> Result<Box<dyn Tokenizer>, ModelError> {
+        // Naive baselines for comparison, never priced
+        if matches!(model_name, "chars" | "words" | "bytes") {
+            return crate::tokenizers::for_model(model_name);
+        }
+
         let model = self.resolve_alias(model_name);
+        if let Some(tokenizer) = self.warm.get(&model) {
+            return Ok(Box::new(Arc::clone(tokenizer)));
+        }
 
-        #[cfg(feature = "openai")]
-        if model.starts_with("gpt-") || model.starts_with("text-") {
-            return OpenAITokenizer::new(&model)
-                .map(|t| Box::new(t) as Box<dyn Tokenizer>)
-                .map_err(ModelError::from);
+        // Prices come from the registry so every name for a model costs the same
+        let (input_price, output_price) = self.prices(model_name);
+        match factory::priced(&model, input_price, output_price) {
+            Some(tokenizer) => tokenizer.map_err(ModelError::from),
+            None => Err(ModelError::ModelNotFound {
+                model: model_name.to_string(),
+            }),
         }
+    }
 
-        #[cfg(feature = "gemini")]
-        if model.starts_with("gemini-") {
-            // Note: This will fail without a model file, but provides the structure
-            // In production, you'd handle model file loading or use an approximation
-            return GeminiTokenizer::new(&model)
-                .map(|t| Box::new(t) as Box<dyn Tokenizer>)
-                .map_err(ModelError::from);
+    /// Whether a model's tokenizer counts with its real vocabulary, judged
+    /// from the model name without constructing the tokenizer.
+    pub fn is_exact(&self, model_name: &str) -> bool {
+        factory::is_exact(&self.resolve_alias(model_name))
+    }
+
+    /// Construct and prime the tokenizer for a model ahead of time.
+    ///
+    /// The tokenizer is built and encodes a short text once, so vocabulary
+    /// loading and first-use costs are paid here. Later calls to
+    /// [`get_tokenizer`](Self::get_tokenizer) for the model share it instead
+    /// of constructing a new one. Returns how long the warmup took.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ModelError` if the tokenizer cannot be created or fails to
+    /// encode the warmup text.
+    pub fn warmup(&mut self, model_name: &str) -> Result<Duration, ModelError> {
+        let start = Instant::now();
+        let tokenizer: Arc<dyn Tokenizer> = Arc::from(self.get_tokenizer(model_name)?);
+        tokenizer.count_tokens(WARMUP_TEXT)?;
+        let elapsed = start.elapsed();
+        self.warm.insert(self.resolve_alias(model_name), tokenizer);
+        Ok(elapsed)
+    }
+
+    /// Whether [`warmup`](Self::warmup) has prepared a model's tokenizer.
+    #[allow(dead_code)]
+    pub fn is_warm(&self, model_name: &str) -> bool {
+        self.warm.contains_key(&self.resolve_alias(model_name))
+    }
+
+    /// Create a tokenizer from a local tokenizer file.
+    ///
+    /// HuggingFace `tokenizer.json` files need the `huggingface` feature and
+    /// SentencePiece `.model` files need `gemini-sentencepiece`. Pricing comes
+    /// from the registry (e.g. a pricing file) and is `None` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ModelError::Tokenizer` if the file does not exist, the file
+    /// type is unsupported, the required feature is disabled, or the file
+    /// cannot be loaded.
+    pub fn tokenizer_from_file(
+        &self,
+        model_name: &str,
+        path: &str,
+    ) -> Result<Box<dyn Tokenizer>, ModelError> {
+        // Asking for a file means an approximation won't do, so fail loudly
+        let file = std::path::Path::new(path);
+        if !file.is_file() {
+            let reason = if file.exists() {
+                "not a file"
+            } else {
+                "no such file"
+            };
+            return Err(ModelError::Tokenizer(TokenizerError::InitializationFailed(
+                format!(
+                    "Cannot load tokenizer for '{}' from '{}': {}",
+                    model_name, path, reason
+                ),
+            )));
         }
 
-        Err(ModelError::ModelNotFound {
-            model: model_name.to_string(),
-        })
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            #[cfg(feature = "huggingface")]
+            Some("json") => Ok(Box::new(
+                HfTokenizer::from_file(model_name, path)?
+                    .with_pricing(self.pricing_for(model_name)),
+            )),
+            #[cfg(all(feature = "gemini", feature = "sentencepiece"))]
+            Some("model") => {
+                let (input_price, output_price) = self.prices(model_name);
+                Ok(Box::new(
+                    GeminiTokenizer::with_model_file(model_name, path)?
+                        .with_pricing(input_price, output_price),
+                ))
+            }
+            _ => {
+                let reason = match extension.as_deref() {
+                    Some("json") => "tokenizer.json files require the `huggingface` feature",
+                    Some("model") => {
+                        "SentencePiece .model files require the `gemini-sentencepiece` feature"
+                    }
+                    _ => "expected a tokenizer.json or SentencePiece .model file",
+                };
+                Err(ModelError::Tokenizer(TokenizerError::InitializationFailed(
+                    format!(
+                        "Cannot load tokenizer for '{}' from '{}': {}",
+                        model_name, path, reason
+                    ),
+                )))
+            }
+        }
     }
 
     /// Register default models.
@@ -104,8 +232,17 @@ impl ModelRegistry {
         #[cfg(feature = "openai")]
         {
             self.upsert_model("openai", "gpt-4", Some(0.03), Some(0.06));
+            self.upsert_model("openai", "gpt-4-0314", Some(0.03), Some(0.06));
+            self.upsert_model("openai", "gpt-4-32k", Some(0.03), Some(0.06));
+            self.upsert_model("openai", "gpt-4-32k-0314", Some(0.03), Some(0.06));
             self.upsert_model("openai", "gpt-4-turbo", Some(0.01), Some(0.03));
+            self.upsert_model("openai", "gpt-4-0125-preview", Some(0.01), Some(0.03));
             self.upsert_model("openai", "gpt-3.5-turbo", Some(0.0015), Some(0.002));
+            self.upsert_model("openai", "gpt-3.5-turbo-0301", Some(0.0015), Some(0.002));
+            self.upsert_model("openai", "gpt-3.5-turbo-16k", Some(0.003), Some(0.004));
+            self.upsert_model("openai", "text-embedding-3-small", Some(0.00002), None);
+            self.upsert_model("openai", "text-embedding-3-large", Some(0.00013), None);
+            self.upsert_model("openai", "text-embedding-ada-002", Some(0.0001), None);
         }
 
         // Gemini models
@@ -114,34 +251,96 @@ impl ModelRegistry {
             self.upsert_model("google", "gemini-pro", Some(0.00125), Some(0.01));
             self.upsert_model("google", "gemini-2.5-pro", Some(0.00125), Some(0.01));
             self.upsert_model("google", "gemini-2.5-flash", Some(0.000075), Some(0.0003));
+            self.upsert_model("google", "gemini-flash", Some(0.000075), Some(0.0003));
         }
     }
 
     /// List all registered models.
+    ///
+    /// Each model appears once, even though it is stored under several alias
+    /// keys, and models are sorted by provider then name so the order is
+    /// stable across runs.
     #[allow(dead_code)]
     pub fn list_models(&self) -> Vec<&ModelInfo> {
-        self.models.values().collect()
+        let mut models: Vec<&ModelInfo> = self.models.values().collect();
+        models.sort_by(|a, b| (&a.provider, &a.model).cmp(&(&b.provider, &b.model)));
+        models.dedup_by(|a, b| a.provider == b.provider && a.model == b.model);
+        models
     }
 
-    /// Apply pricing overrides from a TOML file or environment variable.
-    pub fn apply_pricing_from(&mut self, pricing_path: Option<&str>) -> Result<(), ModelError> {
-        let explicit_path = pricing_path
+    /// Distinct provider names of the registered models, sorted.
+    ///
+    /// Each provider appears once, however many models and alias keys it has.
+    pub fn providers(&self) -> Vec<&str> {
+        let mut providers: Vec<&str> = self
+            .models
+            .values()
+            .map(|info| info.provider.as_str())
+            .collect();
+        providers.sort_unstable();
+        providers.dedup();
+        providers
+    }
+
+    /// One representative flagship model per provider, for head-to-head
+    /// comparisons.
+    ///
+    /// Only registered providers whose tokenizer is compiled in are
+    /// included, in [`PROVIDER_FLAGSHIPS`] order.
+    pub fn provider_flagships(&self) -> Vec<&ModelInfo> {
+        let providers = self.providers();
+        PROVIDER_FLAGSHIPS
+            .iter()
+            .filter(|(provider, model)| providers.contains(provider) && factory::handles(model))
+            .filter_map(|(provider, model)| self.get_model_info(&format!("{}/{}", provider, model)))
+            .collect()
+    }
+
+    /// The pricing file to load: `pricing_path`, else `TOKUIN_PRICING_FILE`.
+    pub fn pricing_file(pricing_path: Option<&str>) -> Option<String> {
+        pricing_path
             .map(|p| p.to_string())
-            .or_else(|| std::env::var("TOKUIN_PRICING_FILE").ok());
+            .or_else(|| std::env::var("TOKUIN_PRICING_FILE").ok())
+    }
 
-        let Some(path) = explicit_path else {
+    /// Apply pricing overrides from a TOML file or URL, or the environment variable.
+    pub fn apply_pricing_from(&mut self, pricing_path: Option<&str>) -> Result<(), ModelError> {
+        let Some(path) = Self::pricing_file(pricing_path) else {
             return Ok(());
         };
 
-        let config = PricingConfig::from_file(&path).map_err(|e| {
+        let config = PricingConfig::from_source(&path).map_err(|e| {
             ModelError::ConfigLoadFailed(format!("Failed to load pricing file '{}': {}", path, e))
         })?;
+        for (provider, provider_pricing) in &config.providers {
+            for (model, pricing) in &provider_pricing.models {
+                if let Some(ratio) = pricing
+                    .typical_output_ratio
+                    .filter(|ratio| !ratio.is_finite() || *ratio < 0.0)
+                {
+                    return Err(ModelError::ConfigLoadFailed(format!(
+                        "Invalid typical_output_ratio {} for {}/{} in '{}': expected a non-negative number",
+                        ratio, provider, model, path
+                    )));
+                }
+            }
+        }
 
-        self.apply_pricing_config(config);
+        self.apply_pricing_config(config, &path);
 
         Ok(())
     }
 
+    /// Whether the provider bills Batch API requests at the discounted rate.
+    ///
+    /// Currently only OpenAI models qualify; unknown models never do.
+    pub fn supports_batch_discount(&self, model_name: &str) -> bool {
+        match self.get_model_info(model_name) {
+            Some(info) => info.provider == "openai",
+            None => false,
+        }
+    }
+
     /// Retrieve pricing information for a given model, if available.
     pub fn pricing_for(&self, model_name: &str) -> Option<(f64, f64)> {
         self.get_model_info(model_name).and_then(|info| {
@@ -152,10 +351,61 @@ impl ModelRegistry {
         })
     }
 
-    fn apply_pricing_config(&mut self, config: PricingConfig) {
+    /// Built-in input and output prices per 1K tokens of a model, each
+    /// `None` if unknown.
+    ///
+    /// Pricing files are not consulted. Tokenizers created without a
+    /// registry are priced from this.
+    #[cfg(any(feature = "openai", feature = "gemini"))]
+    pub fn default_prices(model_name: &str) -> (Option<f64>, Option<f64>) {
+        Self::new().prices(model_name)
+    }
+
+    /// Input and output prices per 1K tokens, each `None` if unknown.
+    ///
+    /// Unlike [`pricing_for`](Self::pricing_for) this keeps an input price
+    /// without an output price, as for embedding models.
+    fn prices(&self, model_name: &str) -> (Option<f64>, Option<f64>) {
+        self.get_model_info(model_name)
+            .map_or((None, None), |info| (info.input_price, info.output_price))
+    }
+
+    /// Typical output length of a model as a multiple of its input length.
+    ///
+    /// Used to project output cost when no expected output is given;
+    /// `None` means the output is assumed to be as long as the input.
+    pub fn typical_output_ratio(&self, model_name: &str) -> Option<f64> {
+        self.get_model_info(model_name)
+            .and_then(|info| info.typical_output_ratio)
+    }
+
+    /// Set the typical output ratio of a registered model.
+    ///
+    /// Unknown models are ignored.
+    pub fn set_typical_output_ratio(&mut self, model_name: &str, ratio: f64) {
+        let model = self.resolve_alias(model_name);
+        for info in self.models.values_mut() {
+            if info.model == model || info.model == model_name {
+                info.typical_output_ratio = Some(ratio);
+            }
+        }
+    }
+
+    fn apply_pricing_config(&mut self, config: PricingConfig, source: &str) {
+        self.deployments.extend(config.azure_deployments);
         for (provider, provider_pricing) in config.providers {
             for (model, pricing) in provider_pricing.models {
                 self.upsert_model(&provider, &model, Some(pricing.input), Some(pricing.output));
+                for info in self
+                    .models
+                    .values_mut()
+                    .filter(|info| info.provider == provider && info.model == model)
+                {
+                    info.pricing_file = Some(source.to_string());
+                }
+                if let Some(ratio) = pricing.typical_output_ratio {
+                    self.set_typical_output_ratio(&model, ratio);
+                }
             }
         }
     }
@@ -172,12 +422,15 @@ impl ModelRegistry {
             model: model.to_string(),
             input_price,
             output_price,
+            typical_output_ratio: None,
+            pricing_file: None,
         };
 
         let keys = [
             model.to_string(),
             format!("{}/{}", provider, model),
             model.to_lowercase(),
+            self.resolve_alias(model),
         ];
 
         for key in keys {
@@ -193,7 +446,18 @@ impl ModelRegistry {
         }
     }
 
-    fn resolve_alias(&self, model_name: &str) -> String {
+    /// Canonical name for a model, without any provider prefix.
+    ///
+    /// Names that resolve to the same canonical name (e.g. `gpt-4` and
+    /// `openai/gpt-4`) refer to the same model. Azure deployment names
+    /// resolve to the model they serve.
+    pub fn resolve_alias(&self, model_name: &str) -> String {
+        let base = model_name.rsplit('/').next().unwrap_or(model_name);
+        let model_name = self
+            .deployments
+            .get(model_name)
+            .or_else(|| self.deployments.get(base))
+            .map_or(model_name, String::as_str);
         let base = model_name.rsplit('/').next().unwrap_or(model_name);
 
         match base {
@@ -203,6 +467,20 @@ impl ModelRegistry {
     }
 }
 
+/// Parse a `NAME=MODEL` Azure deployment mapping.
+///
+/// # Errors
+///
+/// Returns a message if either side is missing.
+pub fn parse_deployment(spec: &str) -> Result<(String, String), String> {
+    match spec.split_once('=') {
+        Some((name, model)) if !name.trim().is_empty() && !model.trim().is_empty() => {
+            Ok((name.trim().to_string(), model.trim().to_string()))
+        }
+        _ => Err(format!("expected NAME=MODEL, got '{}'", spec)),
+    }
+}
+
 impl Default for ModelRegistry {
     fn default() -> Self {
         Self::new()
@@ -223,6 +501,132 @@ mod tests {
         assert!(tokenizer.is_ok());
     }
 
+    #[test]
+    #[cfg(all(feature = "openai", feature = "gemini"))]
+    fn test_prices_match_the_former_tokenizer_tables() {
+        // Prices the OpenAI and Gemini tokenizers used to set themselves
+        let former = [
+            (
+                &["gpt-4", "gpt-4-0314", "gpt-4-32k", "gpt-4-32k-0314"][..],
+                Some(0.03),
+                Some(0.06),
+            ),
+            (
+                &["gpt-4-turbo-preview", "gpt-4-turbo", "gpt-4-0125-preview"],
+                Some(0.01),
+                Some(0.03),
+            ),
+            (
+                &["gpt-3.5-turbo", "gpt-3.5-turbo-0301"],
+                Some(0.0015),
+                Some(0.002),
+            ),
+            (&["gpt-3.5-turbo-16k"], Some(0.003), Some(0.004)),
+            (&["text-embedding-3-small"], Some(0.00002), None),
+            (&["text-embedding-3-large"], Some(0.00013), None),
+            (&["text-embedding-ada-002"], Some(0.0001), None),
+            (&["gemini-2.5-pro", "gemini-pro"], Some(0.00125), Some(0.01)),
+            (
+                &["gemini-2.5-flash", "gemini-flash"],
+                Some(0.000075),
+                Some(0.0003),
+            ),
+        ];
+
+        let registry = ModelRegistry::new();
+        for (models, input, output) in former {
+            for &model in models {
+                let tokenizer = registry.get_tokenizer(model).unwrap();
+                assert_eq!(
+                    (
+                        tokenizer.input_price_per_1k(),
+                        tokenizer.output_price_per_1k()
+                    ),
+                    (input, output),
+                    "{}",
+                    model
+                );
+                assert_eq!(
+                    ModelRegistry::default_prices(model),
+                    (input, output),
+                    "{}",
+                    model
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_tokenizer_baselines() {
+        let registry = ModelRegistry::new();
+        for name in ["chars", "words", "bytes"] {
+            let tokenizer = registry.get_tokenizer(name).expect("baseline tokenizer");
+            assert_eq!(tokenizer.name(), name);
+            assert!(registry.pricing_for(name).is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "huggingface")]
+    fn test_tokenizer_from_json_file() {
+        let registry = ModelRegistry::new();
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/tiny-tokenizer.json"
+        );
+        let tokenizer = registry.tokenizer_from_file("tiny", path).unwrap();
+        assert_eq!(tokenizer.name(), "tiny");
+        assert_eq!(tokenizer.count_tokens("hello world").unwrap(), 2);
+        assert!(tokenizer.input_price_per_1k().is_none());
+    }
+
+    #[test]
+    fn test_tokenizer_from_unsupported_file() {
+        let registry = ModelRegistry::new();
+        let temp = tempfile::Builder::new().suffix(".txt").tempfile().unwrap();
+        let path = temp.path().to_str().unwrap();
+        let error = registry.tokenizer_from_file("x", path).err().unwrap();
+        assert!(error.to_string().contains("expected a tokenizer.json"));
+    }
+
+    #[test]
+    fn test_tokenizer_from_missing_file() {
+        let registry = ModelRegistry::new();
+        for path in ["/no/such/gemini.model", "/no/such/tokenizer.json"] {
+            let error = registry
+                .tokenizer_from_file("gemini-pro", path)
+                .err()
+                .unwrap()
+                .to_string();
+            assert!(error.contains(path), "{}", error);
+            assert!(error.contains("no such file"), "{}", error);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let error = registry
+            .tokenizer_from_file("gemini-pro", dir.path().to_str().unwrap())
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("not a file"));
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_warmup_shares_ready_tokenizer() {
+        let mut registry = ModelRegistry::new();
+        assert!(!registry.is_warm("gpt-4"));
+
+        registry.warmup("openai/gpt-4").unwrap();
+        assert!(registry.is_warm("gpt-4"));
+        let tokenizer = registry.get_tokenizer("gpt-4").unwrap();
+        assert_eq!(tokenizer.name(), "gpt-4");
+        assert_eq!(tokenizer.count_tokens("Hello, world!").unwrap(), 4);
+        assert_eq!(tokenizer.input_price_per_1k(), Some(0.03));
+
+        assert!(registry.warmup("not-a-model").is_err());
+        assert!(!registry.is_warm("not-a-model"));
+    }
+
     #[test]
     fn test_get_model_info() {
         let registry = ModelRegistry::new();
@@ -236,6 +640,27 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_tokenizer_prices_come_from_registry() {
+        let registry = ModelRegistry::new();
+        let prices = |model: &str| {
+            let tokenizer = registry.get_tokenizer(model).unwrap();
+            (
+                tokenizer.input_price_per_1k(),
+                tokenizer.output_price_per_1k(),
+            )
+        };
+
+        assert_eq!(prices("gpt-4-turbo-preview"), prices("gpt-4-turbo"));
+        assert_eq!(prices("gpt-4-turbo"), (Some(0.01), Some(0.03)));
+        assert_eq!(
+            registry.pricing_for("gpt-4-turbo-preview"),
+            Some((0.01, 0.03))
+        );
+        assert_eq!(prices("text-embedding-3-small"), (Some(0.00002), None));
+    }
+
     #[test]
     fn test_list_models() {
         let registry = ModelRegistry::new();
@@ -243,6 +668,26 @@ mod tests {
         assert!(!models.is_empty());
     }
 
+    #[test]
+    fn test_list_models_stable_and_deduped() {
+        let first: Vec<(String, String)> = ModelRegistry::new()
+            .list_models()
+            .into_iter()
+            .map(|info| (info.provider.clone(), info.model.clone()))
+            .collect();
+        let second: Vec<(String, String)> = ModelRegistry::new()
+            .list_models()
+            .into_iter()
+            .map(|info| (info.provider.clone(), info.model.clone()))
+            .collect();
+        assert_eq!(first, second);
+
+        let mut expected = first.clone();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(first, expected);
+    }
+
     #[test]
     fn apply_pricing_overrides_from_file() {
         let mut temp = NamedTempFile::new().expect("create temp pricing file");
@@ -259,6 +704,16 @@ mod tests {
         let pricing = registry.pricing_for("gpt-4").expect("pricing present");
         assert_eq!(pricing.0, 0.02);
         assert_eq!(pricing.1, 0.04);
+        let info = registry.get_model_info("gpt-4").unwrap();
+        assert_eq!(info.pricing_file.as_deref(), Some(path));
+        #[cfg(feature = "openai")]
+        assert_eq!(
+            registry
+                .get_model_info("gpt-3.5-turbo")
+                .unwrap()
+                .pricing_file,
+            None
+        );
 
         // Ensure alias lookup still works
         let pricing_alias = registry
@@ -266,4 +721,168 @@ mod tests {
             .expect("pricing for provider-prefixed key");
         assert_eq!(pricing_alias.0, 0.02);
     }
+
+    #[test]
+    fn test_providers_of_default_registry() {
+        let registry = ModelRegistry::new();
+        let expected: Vec<&str> = [
+            cfg!(feature = "gemini").then_some("google"),
+            cfg!(feature = "openai").then_some("openai"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        assert_eq!(registry.providers(), expected);
+    }
+
+    #[test]
+    fn test_providers_include_pricing_file_providers() {
+        let mut temp = NamedTempFile::new().expect("create temp pricing file");
+        writeln!(
+            temp,
+            "[anthropic]\n[anthropic.claude-3-opus]\ninput = 0.015\noutput = 0.075\n[anthropic.claude-3-haiku]\ninput = 0.00025\noutput = 0.00125\n"
+        )
+        .expect("write pricing overrides");
+
+        let path = temp.path().to_str().expect("pricing path utf8");
+        let registry = ModelRegistry::new_with_pricing(Some(path)).expect("registry");
+        let providers = registry.providers();
+        assert_eq!(providers.iter().filter(|p| **p == "anthropic").count(), 1);
+        assert!(providers.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_provider_flagships_one_per_provider() {
+        let registry = ModelRegistry::new();
+        let flagships = registry.provider_flagships();
+
+        let mut providers: Vec<&str> = flagships.iter().map(|i| i.provider.as_str()).collect();
+        providers.dedup();
+        assert_eq!(providers.len(), flagships.len(), "one model per provider");
+
+        // Every provider with a built-in tokenizer is represented
+        for info in registry.list_models() {
+            if registry.get_tokenizer(&info.model).is_ok() {
+                assert!(
+                    providers.contains(&info.provider.as_str()),
+                    "{}",
+                    info.provider
+                );
+            }
+        }
+        #[cfg(feature = "openai")]
+        assert!(flagships.iter().any(|i| i.model == "gpt-4"));
+        #[cfg(feature = "gemini")]
+        assert!(flagships.iter().any(|i| i.model == "gemini-2.5-pro"));
+        // No tokenizer is built in for Claude models
+        assert!(!providers.contains(&"anthropic"));
+    }
+
+    #[cfg(feature = "load-test")]
+    #[test]
+    fn apply_pricing_overrides_from_url() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/pricing.toml");
+            then.status(200)
+                .body("[openai]\n[openai.gpt-4]\ninput = 0.05\noutput = 0.1\n");
+        });
+
+        let url = server.url("/pricing.toml");
+        let registry = ModelRegistry::new_with_pricing(Some(&url)).expect("registry from url");
+        mock.assert();
+        assert_eq!(registry.pricing_for("gpt-4"), Some((0.05, 0.1)));
+        let info = registry.get_model_info("gpt-4").unwrap();
+        assert_eq!(info.pricing_file.as_deref(), Some(url.as_str()));
+
+        let missing = server.url("/missing.toml");
+        let err = ModelRegistry::new_with_pricing(Some(&missing))
+            .err()
+            .expect("missing pricing url fails");
+        assert!(err.to_string().contains("server returned 404"), "{}", err);
+    }
+
+    #[test]
+    fn typical_output_ratio_from_pricing_file() {
+        let mut temp = NamedTempFile::new().expect("create temp pricing file");
+        writeln!(
+            temp,
+            "[openai.gpt-4]\ninput = 0.03\noutput = 0.06\ntypical_output_ratio = 2.0\n"
+        )
+        .expect("write pricing overrides");
+
+        let path = temp.path().to_str().expect("pricing path utf8");
+        let registry =
+            ModelRegistry::new_with_pricing(Some(path)).expect("registry with overrides");
+        assert_eq!(registry.typical_output_ratio("gpt-4"), Some(2.0));
+        assert_eq!(registry.typical_output_ratio("openai/gpt-4"), Some(2.0));
+        assert_eq!(registry.typical_output_ratio("gpt-3.5-turbo"), None);
+
+        let mut temp = NamedTempFile::new().expect("create temp pricing file");
+        writeln!(
+            temp,
+            "[openai.gpt-4]\ninput = 0.03\noutput = 0.06\ntypical_output_ratio = -1.0\n"
+        )
+        .expect("write pricing overrides");
+        let path = temp.path().to_str().expect("pricing path utf8");
+        assert!(ModelRegistry::new_with_pricing(Some(path)).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_azure_deployment_resolves_to_model() {
+        let mut registry = ModelRegistry::new();
+        assert!(registry.get_tokenizer("prod-chat").is_err());
+
+        registry.add_deployment("prod-chat", "gpt-4");
+        let tokenizer = registry
+            .get_tokenizer("prod-chat")
+            .expect("mapped tokenizer");
+        assert_eq!(tokenizer.name(), "gpt-4");
+        assert_eq!(
+            tokenizer.count_tokens("Hello, world!").unwrap(),
+            registry
+                .get_tokenizer("gpt-4")
+                .unwrap()
+                .count_tokens("Hello, world!")
+                .unwrap()
+        );
+        assert_eq!(registry.resolve_alias("azure/prod-chat"), "gpt-4");
+        assert_eq!(
+            registry.pricing_for("prod-chat"),
+            registry.pricing_for("gpt-4")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_azure_deployments_from_pricing_file() {
+        let mut temp = NamedTempFile::new().expect("create temp pricing file");
+        writeln!(
+            temp,
+            "[azure_deployments]
+prod-chat = \"gpt-3.5-turbo\"\n"
+        )
+        .expect("write deployments");
+
+        let path = temp.path().to_str().expect("pricing path utf8");
+        let registry = ModelRegistry::new_with_pricing(Some(path)).expect("registry");
+        assert_eq!(registry.resolve_alias("prod-chat"), "gpt-3.5-turbo");
+        assert_eq!(
+            registry.pricing_for("prod-chat"),
+            registry.pricing_for("gpt-3.5-turbo")
+        );
+    }
+
+    #[test]
+    fn test_parse_deployment() {
+        assert_eq!(
+            parse_deployment("prod-chat=gpt-4").unwrap(),
+            ("prod-chat".to_string(), "gpt-4".to_string())
+        );
+        assert!(parse_deployment("prod-chat").is_err());
+        assert!(parse_deployment("=gpt-4").is_err());
+    }
 }
@@ -0,0 +1,427 @@
+/// Summary statistics across token results.
+use super::TokenResult;
+use serde::Serialize;
+
+/// Minimum, maximum, mean and percentiles of a set of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Number of values.
+    pub count: usize,
+    /// Smallest value.
+    pub min: f64,
+    /// Largest value.
+    pub max: f64,
+    /// Arithmetic mean.
+    pub mean: f64,
+    /// Median (50th percentile).
+    pub p50: f64,
+    /// 90th percentile.
+    pub p90: f64,
+    /// 95th percentile.
+    pub p95: f64,
+}
+
+impl Stats {
+    /// Statistics of `values`, or `None` if there are none.
+    ///
+    /// Percentiles use the nearest-rank method, so each is one of the values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::output::aggregate::Stats;
+    ///
+    /// let stats = Stats::of([4.0, 1.0, 3.0, 2.0]).unwrap();
+    /// assert_eq!((stats.min, stats.max, stats.mean, stats.p50), (1.0, 4.0, 2.5, 2.0));
+    /// assert!(Stats::of([]).is_none());
+    /// ```
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut sorted: Vec<f64> = values.into_iter().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Some(Self {
+            count: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+        })
+    }
+}
+
+/// The best value of one criterion and every model that reaches it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Winner<'a, T> {
+    /// Models sharing the best value, in result order.
+    pub models: Vec<&'a str>,
+    /// The best value.
+    pub value: T,
+}
+
+/// Winners of a comparison by each criterion; `None` where no result has
+/// the data (e.g. no pricing or no known context window).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Winners<'a> {
+    /// Fewest prompt tokens.
+    pub fewest_tokens: Option<Winner<'a, usize>>,
+    /// Lowest input cost (USD).
+    pub lowest_input_cost: Option<Winner<'a, f64>>,
+    /// Lowest input plus expected output cost (USD).
+    pub lowest_total_cost: Option<Winner<'a, f64>>,
+    /// Most context window left after the prompt.
+    pub most_headroom: Option<Winner<'a, i64>>,
+}
+
+impl<'a> Winners<'a> {
+    /// Each criterion's label and winner for display, with token values
+    /// formatted by `count` and costs (USD) by `money`.
+    pub fn labeled(
+        &self,
+        count: impl Fn(i64) -> String,
+        money: impl Fn(f64) -> String,
+    ) -> Vec<(&'static str, Option<Winner<'a, String>>)> {
+        fn label<'a, T: Copy>(
+            winner: &Option<Winner<'a, T>>,
+            value: impl Fn(T) -> String,
+        ) -> Option<Winner<'a, String>> {
+            winner.as_ref().map(|winner| Winner {
+                models: winner.models.clone(),
+                value: value(winner.value),
+            })
+        }
+
+        vec![
+            (
+                "Fewest tokens",
+                label(&self.fewest_tokens, |tokens| count(tokens as i64)),
+            ),
+            ("Lowest input cost", label(&self.lowest_input_cost, &money)),
+            ("Lowest total cost", label(&self.lowest_total_cost, &money)),
+            (
+                "Most headroom",
+                label(&self.most_headroom, |tokens| {
+                    format!("{} tokens", count(tokens))
+                }),
+            ),
+        ]
+    }
+
+    /// Sort each criterion's tied models by name instead of result order.
+    pub fn sort_models(&mut self) {
+        fn sort<T>(winner: &mut Option<Winner<'_, T>>) {
+            if let Some(winner) = winner {
+                winner.models.sort_unstable();
+            }
+        }
+        sort(&mut self.fewest_tokens);
+        sort(&mut self.lowest_input_cost);
+        sort(&mut self.lowest_total_cost);
+        sort(&mut self.most_headroom);
+    }
+}
+
+/// Statistics across a collection of results, computed once and shared by
+/// the formatters' summary sections.
+#[derive(Debug, Clone)]
+pub struct ResultAggregator<'a> {
+    results: &'a [TokenResult],
+    tokens: Option<Stats>,
+    cost: Option<Stats>,
+}
+
+impl<'a> ResultAggregator<'a> {
+    /// Aggregate `results`.
+    pub fn new(results: &'a [TokenResult]) -> Self {
+        Self {
+            results,
+            tokens: Stats::of(results.iter().map(|result| result.tokens as f64)),
+            cost: Stats::of(results.iter().filter_map(total_cost)),
+        }
+    }
+
+    /// Token count statistics, or `None` without results.
+    pub fn tokens(&self) -> Option<Stats> {
+        self.tokens
+    }
+
+    /// Total cost statistics of the priced results, or `None` if none is priced.
+    pub fn cost(&self) -> Option<Stats> {
+        self.cost
+    }
+
+    /// Total cost of `result` as a multiple of the cheapest priced result,
+    /// or `None` if it has no pricing or the cheapest result is free.
+    pub fn cost_multiple(&self, result: &TokenResult) -> Option<f64> {
+        let cheapest = self.cost?.min;
+        if cheapest <= 0.0 {
+            return None;
+        }
+        total_cost(result).map(|cost| cost / cheapest)
+    }
+
+    /// Distinct encodings of the results in first-seen order, with `None`
+    /// standing for results whose encoding is unknown.
+    pub fn encodings(&self) -> Vec<Option<&'a str>> {
+        let mut encodings = Vec::new();
+        for encoding in self.results.iter().map(|result| result.encoding.as_deref()) {
+            if !encodings.contains(&encoding) {
+                encodings.push(encoding);
+            }
+        }
+        encodings
+    }
+
+    /// Whether the results were counted with more than one encoding, so
+    /// their token counts aren't directly comparable.
+    pub fn mixed_encodings(&self) -> bool {
+        self.encodings().len() > 1
+    }
+
+    /// The result with the most tokens; the first one on ties.
+    pub fn most_tokens(&self) -> Option<&'a TokenResult> {
+        self.results.iter().reduce(|best, result| {
+            if result.tokens > best.tokens {
+                result
+            } else {
+                best
+            }
+        })
+    }
+
+    /// The winner by each criterion, with all models tied for it.
+    pub fn winners(&self) -> Winners<'a> {
+        Winners {
+            fewest_tokens: self.best_by(|result| Some(result.tokens), true),
+            lowest_input_cost: self.best_by(|result| result.input_cost, true),
+            lowest_total_cost: self.best_by(total_cost, true),
+            most_headroom: self.best_by(TokenResult::headroom, false),
+        }
+    }
+
+    /// The lowest (or highest) value of `key` and the results that have it.
+    fn best_by<T: PartialOrd + Copy>(
+        &self,
+        key: impl Fn(&TokenResult) -> Option<T>,
+        lowest: bool,
+    ) -> Option<Winner<'a, T>> {
+        let mut best: Option<Winner<'a, T>> = None;
+        for result in self.results {
+            let Some(value) = key(result) else {
+                continue;
+            };
+            match &mut best {
+                Some(winner) if value == winner.value => winner.models.push(&result.model),
+                Some(winner)
+                    if (lowest && value > winner.value) || (!lowest && value < winner.value) => {}
+                _ => {
+                    best = Some(Winner {
+                        models: vec![&result.model],
+                        value,
+                    })
+                }
+            }
+        }
+        best
+    }
+
+    /// The priced result with the lowest total cost among those `eligible`
+    /// accepts; the first one on ties.
+    pub fn cheapest(&self, eligible: impl Fn(&TokenResult) -> bool) -> Option<&'a TokenResult> {
+        self.results
+            .iter()
+            .filter(|result| eligible(result))
+            .filter_map(|result| Some((result, total_cost(result)?)))
+            .reduce(|best, candidate| {
+                if candidate.1 < best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .map(|(result, _)| result)
+    }
+}
+
+/// Total cost of a result with a known input cost.
+pub(crate) fn total_cost(result: &TokenResult) -> Option<f64> {
+    result.input_cost.and(result.total_cost())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(model: &str, tokens: usize, cost: Option<f64>) -> TokenResult {
+        TokenResult {
+            model: model.to_string(),
+            tokens,
+            input_cost: cost,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stats_percentiles() {
+        let stats = Stats::of((1..=20).map(f64::from)).unwrap();
+        assert_eq!(stats.count, 20);
+        assert_eq!((stats.min, stats.max), (1.0, 20.0));
+        assert_eq!(stats.mean, 10.5);
+        assert_eq!((stats.p50, stats.p90, stats.p95), (10.0, 18.0, 19.0));
+    }
+
+    #[test]
+    fn test_aggregate_results() {
+        let results = vec![
+            result("gpt-4", 100, Some(0.03)),
+            result("chars", 400, None),
+            result("gpt-3.5-turbo", 100, Some(0.001)),
+            result("gpt-4o", 90, Some(0.001)),
+        ];
+        let aggregate = ResultAggregator::new(&results);
+
+        let tokens = aggregate.tokens().unwrap();
+        assert_eq!((tokens.min, tokens.max, tokens.mean), (90.0, 400.0, 172.5));
+        assert_eq!(aggregate.cost().unwrap().count, 3);
+        assert_eq!(aggregate.most_tokens().unwrap().model, "chars");
+        // Ties keep the earlier result
+        assert_eq!(aggregate.cheapest(|_| true).unwrap().model, "gpt-3.5-turbo");
+        let cheapest = aggregate.cheapest(|result| result.tokens < 100);
+        assert_eq!(cheapest.unwrap().model, "gpt-4o");
+        assert!(aggregate.cheapest(|result| result.tokens > 100).is_none());
+    }
+
+    #[test]
+    fn test_cost_multiple_of_cheapest() {
+        let results = vec![
+            result("gpt-4", 100, Some(0.03)),
+            result("gpt-4o", 100, Some(0.01)),
+            result("chars", 400, None),
+        ];
+        let aggregate = ResultAggregator::new(&results);
+        assert_eq!(aggregate.cost_multiple(&results[0]), Some(3.0));
+        assert_eq!(aggregate.cost_multiple(&results[1]), Some(1.0));
+        assert_eq!(aggregate.cost_multiple(&results[2]), None);
+
+        let free = vec![result("local", 10, Some(0.0))];
+        assert_eq!(ResultAggregator::new(&free).cost_multiple(&free[0]), None);
+    }
+
+    #[test]
+    fn test_mixed_encodings() {
+        let encoded = |model: &str, encoding: Option<&str>| TokenResult {
+            encoding: encoding.map(str::to_string),
+            ..result(model, 100, None)
+        };
+        let results = vec![
+            encoded("gpt-4", Some("cl100k_base")),
+            encoded("gpt-3.5-turbo", Some("cl100k_base")),
+        ];
+        assert!(!ResultAggregator::new(&results).mixed_encodings());
+
+        let results = vec![
+            encoded("gpt-4", Some("cl100k_base")),
+            encoded("gpt-4o", Some("o200k_base")),
+            encoded("chars", None),
+            encoded("gpt-4", Some("cl100k_base")),
+        ];
+        let aggregate = ResultAggregator::new(&results);
+        assert!(aggregate.mixed_encodings());
+        assert_eq!(
+            aggregate.encodings(),
+            [Some("cl100k_base"), Some("o200k_base"), None]
+        );
+    }
+
+    #[test]
+    fn test_winners_by_criterion() {
+        let results = vec![
+            TokenResult {
+                output_cost: Some(0.02),
+                context_window: Some(8192),
+                ..result("gpt-4", 100, Some(0.003))
+            },
+            TokenResult {
+                context_window: Some(200_000),
+                ..result("claude-3-haiku", 120, Some(0.0003))
+            },
+            TokenResult {
+                output_cost: Some(0.0015),
+                context_window: Some(128_000),
+                ..result("gpt-4o", 100, Some(0.0003))
+            },
+            result("chars", 400, None),
+        ];
+        let winners = ResultAggregator::new(&results).winners();
+
+        let fewest = winners.fewest_tokens.unwrap();
+        assert_eq!(fewest.models, ["gpt-4", "gpt-4o"]);
+        assert_eq!(fewest.value, 100);
+        let input = winners.lowest_input_cost.unwrap();
+        assert_eq!(input.models, ["claude-3-haiku", "gpt-4o"]);
+        assert_eq!(input.value, 0.0003);
+        let total = winners.lowest_total_cost.unwrap();
+        assert_eq!(total.models, ["claude-3-haiku"]);
+        let headroom = winners.most_headroom.unwrap();
+        assert_eq!(headroom.models, ["claude-3-haiku"]);
+        assert_eq!(headroom.value, 200_000 - 120);
+    }
+
+    #[test]
+    fn test_winners_missing_data() {
+        let results = vec![result("chars", 12, None), result("words", 3, None)];
+        let winners = ResultAggregator::new(&results).winners();
+        let labeled = winners.labeled(|tokens| tokens.to_string(), |cost| cost.to_string());
+        let labels: Vec<&str> = labeled.iter().map(|(label, _)| *label).collect();
+        assert_eq!(
+            labels,
+            [
+                "Fewest tokens",
+                "Lowest input cost",
+                "Lowest total cost",
+                "Most headroom"
+            ]
+        );
+        assert_eq!(labeled[0].1.as_ref().unwrap().value, "3");
+        assert!(labeled[1..].iter().all(|(_, winner)| winner.is_none()));
+
+        assert_eq!(winners.fewest_tokens.unwrap().models, ["words"]);
+        assert!(winners.lowest_input_cost.is_none());
+        assert!(winners.lowest_total_cost.is_none());
+        assert!(winners.most_headroom.is_none());
+    }
+
+    #[test]
+    fn test_single_result() {
+        let results = vec![result("gpt-4", 42, None)];
+        let aggregate = ResultAggregator::new(&results);
+
+        let tokens = aggregate.tokens().unwrap();
+        assert_eq!(tokens.count, 1);
+        assert!(
+            [tokens.min, tokens.max, tokens.mean, tokens.p50, tokens.p95]
+                .iter()
+                .all(|&value| value == 42.0)
+        );
+        assert_eq!(aggregate.most_tokens().unwrap().model, "gpt-4");
+        assert!(aggregate.cost().is_none());
+        assert!(aggregate.cheapest(|_| true).is_none());
+    }
+
+    #[test]
+    fn test_empty_results() {
+        let aggregate = ResultAggregator::new(&[]);
+        assert!(aggregate.tokens().is_none());
+        assert!(aggregate.cost().is_none());
+        assert!(aggregate.most_tokens().is_none());
+        assert!(aggregate.cheapest(|_| true).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::analyzers::batch::BATCH_DISCOUNT;
+/// Formatter for Batch API estimates.
+use crate::analyzers::types::BatchSummary;
+use crate::utils::currency::Currency;
+use serde::Serialize;
+
+/// Formatter for batch summaries.
+pub struct BatchFormatter;
+
+impl BatchFormatter {
+    /// Format a batch summary as text, with costs in `currency`.
+    pub fn format_text(summary: &BatchSummary, currency: &Currency) -> String {
+        let mut output = Vec::new();
+
+        output.push(format!("Batch: {} requests", summary.items.len()));
+        output.push(String::new());
+        output.push(format!(
+            "{:<24} {:<20} {:<10} {}",
+            "custom_id", "Model", "Tokens", "Cost"
+        ));
+        output.push("-".repeat(64));
+
+        for item in &summary.items {
+            let cost = item
+                .cost
+                .map(|c| currency.format(c, 6))
+                .unwrap_or_else(|| "n/a".to_string());
+            output.push(format!(
+                "{:<24} {:<20} {:<10} {}",
+                item.custom_id, item.model, item.tokens, cost
+            ));
+        }
+
+        output.push("-".repeat(64));
+        output.push(format!("Total Tokens: {}", summary.total_tokens));
+
+        if let Some(shared) = &summary.shared_system {
+            output.push(String::new());
+            if shared.is_empty() {
+                output.push("No repeated system prompts.".to_string());
+            } else {
+                output.push("Repeated system prompts:".to_string());
+                for prompt in shared {
+                    output.push(format!(
+                        "  {} requests share a {}-token system prompt ({}): {} redundant tokens",
+                        prompt.custom_ids.len(),
+                        prompt.tokens,
+                        prompt.custom_ids.join(", "),
+                        prompt.redundant_tokens
+                    ));
+                }
+                output.push(format!(
+                    "Caching or hoisting them could save {} input tokens.",
+                    shared.iter().map(|p| p.redundant_tokens).sum::<usize>()
+                ));
+            }
+        }
+
+        if let Some(total) = summary.total_cost {
+            let discounted = summary.items.iter().filter(|i| i.discounted).count();
+            output.push(format!(
+                "Projected Batch Cost: {}",
+                currency.format(total, 6)
+            ));
+            if discounted > 0 {
+                output.push(format!(
+                    "({}% batch discount applied to {} of {} requests)",
+                    ((1.0 - BATCH_DISCOUNT) * 100.0).round(),
+                    discounted,
+                    summary.items.len()
+                ));
+            }
+        }
+
+        output.join("\n")
+    }
+
+    /// Format a batch summary as JSON, with costs in `currency`.
+    pub fn format_json(
+        summary: &BatchSummary,
+        currency: &Currency,
+    ) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct BatchJson<'a> {
+            requests: Vec<ItemJson>,
+            total_tokens: usize,
+            total_cost: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            currency: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            shared_system_prompts: Option<Vec<SharedJson>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            redundant_system_tokens: Option<usize>,
+        }
+
+        #[derive(Serialize)]
+        struct SharedJson {
+            requests: Vec<String>,
+            tokens: usize,
+            redundant_tokens: usize,
+        }
+
+        #[derive(Serialize)]
+        struct ItemJson {
+            custom_id: String,
+            model: String,
+            tokens: usize,
+            cost: Option<f64>,
+            discounted: bool,
+        }
+
+        let json = BatchJson {
+            requests: summary
+                .items
+                .iter()
+                .map(|i| ItemJson {
+                    custom_id: i.custom_id.clone(),
+                    model: i.model.clone(),
+                    tokens: i.tokens,
+                    cost: i.cost.map(|c| currency.convert(c)),
+                    discounted: i.discounted,
+                })
+                .collect(),
+            total_tokens: summary.total_tokens,
+            total_cost: summary.total_cost.map(|c| currency.convert(c)),
+            currency: summary.total_cost.map(|_| currency.code.as_str()),
+            shared_system_prompts: summary.shared_system.as_ref().map(|shared| {
+                shared
+                    .iter()
+                    .map(|p| SharedJson {
+                        requests: p.custom_ids.clone(),
+                        tokens: p.tokens,
+                        redundant_tokens: p.redundant_tokens,
+                    })
+                    .collect()
+            }),
+            redundant_system_tokens: summary
+                .shared_system
+                .as_ref()
+                .map(|shared| shared.iter().map(|p| p.redundant_tokens).sum()),
+        };
+
+        serde_json::to_string_pretty(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::types::BatchItem;
+
+    fn summary() -> BatchSummary {
+        BatchSummary {
+            items: vec![BatchItem {
+                custom_id: "req-1".to_string(),
+                model: "gpt-4".to_string(),
+                tokens: 10,
+                cost: Some(0.00015),
+                discounted: true,
+            }],
+            total_tokens: 10,
+            total_cost: Some(0.00015),
+            shared_system: None,
+        }
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = BatchFormatter::format_text(&summary(), &Currency::usd());
+        assert!(output.contains("req-1"));
+        assert!(output.contains("Total Tokens: 10"));
+        assert!(output.contains("batch discount"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let output = BatchFormatter::format_json(&summary(), &Currency::usd()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["requests"][0]["custom_id"], "req-1");
+        assert_eq!(value["total_tokens"], 10);
+        assert_eq!(value["currency"], "USD");
+    }
+
+    #[test]
+    fn test_costs_in_currency() {
+        let eur = Currency::new("EUR", 2.0).unwrap();
+        let output = BatchFormatter::format_text(&summary(), &eur);
+        assert!(
+            output.contains("Projected Batch Cost: €0.000300"),
+            "{}",
+            output
+        );
+
+        let output = BatchFormatter::format_json(&summary(), &eur).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["total_cost"], 0.0003);
+        assert_eq!(value["requests"][0]["cost"], 0.0003);
+        assert_eq!(value["currency"], "EUR");
+    }
+}
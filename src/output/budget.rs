@@ -0,0 +1,189 @@
+/// Formatter for token budget allocation plans.
+use crate::analyzers::budget::Allocation;
+use crate::utils::numbers::group_digits;
+use serde::Serialize;
+
+/// JSON representation of one section's allocation.
+#[derive(Debug, Serialize)]
+struct SectionJson<'a> {
+    label: &'a str,
+    tokens: usize,
+    allocated: usize,
+    status: &'static str,
+}
+
+/// JSON representation of an allocation plan.
+#[derive(Debug, Serialize)]
+struct AllocationJson<'a> {
+    model: &'a str,
+    budget: usize,
+    requested: usize,
+    used: usize,
+    remaining: usize,
+    sections: Vec<SectionJson<'a>>,
+}
+
+impl<'a> From<&'a Allocation> for AllocationJson<'a> {
+    fn from(plan: &'a Allocation) -> Self {
+        Self {
+            model: &plan.model,
+            budget: plan.budget,
+            requested: plan.requested(),
+            used: plan.used(),
+            remaining: plan.remaining(),
+            sections: plan
+                .sections
+                .iter()
+                .map(|s| SectionJson {
+                    label: &s.label,
+                    tokens: s.tokens,
+                    allocated: s.allocated,
+                    status: s.fit.as_str(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Formatter for per-section budget allocation.
+pub struct AllocationFormatter;
+
+impl AllocationFormatter {
+    /// Format allocation plans as text, one block per model.
+    pub fn format_text(plans: &[Allocation]) -> String {
+        let count = |tokens: usize| group_digits(tokens, Some(','));
+        plans
+            .iter()
+            .map(|plan| {
+                let width = plan
+                    .sections
+                    .iter()
+                    .map(|s| s.label.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let mut lines = vec![
+                    format!("Model: {}", plan.model),
+                    format!("Budget: {} tokens", count(plan.budget)),
+                ];
+                for s in &plan.sections {
+                    lines.push(format!(
+                        "  {:<width$}  {:>6} / {:<6} {}",
+                        s.label,
+                        count(s.allocated),
+                        count(s.tokens),
+                        s.fit.as_str(),
+                        width = width
+                    ));
+                }
+                lines.push(format!(
+                    "Used: {} of {} tokens ({} left, {} requested)",
+                    count(plan.used()),
+                    count(plan.budget),
+                    count(plan.remaining()),
+                    count(plan.requested())
+                ));
+                lines.join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Format allocation plans as JSON: an object for one model, an array for several.
+    pub fn format_json(plans: &[Allocation]) -> Result<String, serde_json::Error> {
+        let json: Vec<AllocationJson> = plans.iter().map(AllocationJson::from).collect();
+        match json.as_slice() {
+            [single] => serde_json::to_string_pretty(single),
+            _ => serde_json::to_string_pretty(&json),
+        }
+    }
+
+    /// Format allocation plans as markdown tables.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(plans: &[Allocation]) -> String {
+        let mut output = vec!["## Budget Allocation".to_string()];
+        for plan in plans {
+            output.push(String::new());
+            output.push(format!(
+                "**{}**: {} of {} tokens used",
+                plan.model,
+                plan.used(),
+                plan.budget
+            ));
+            output.push(String::new());
+            output.push("| Section | Tokens | Allocated | Status |".to_string());
+            output.push("|---------|--------|-----------|--------|".to_string());
+            for s in &plan.sections {
+                output.push(format!(
+                    "| {} | {} | {} | {} |",
+                    s.label,
+                    s.tokens,
+                    s.allocated,
+                    s.fit.as_str()
+                ));
+            }
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::budget::{SectionAllocation, SectionFit};
+
+    fn plan() -> Allocation {
+        Allocation {
+            model: "gpt-4".to_string(),
+            budget: 100,
+            sections: vec![
+                SectionAllocation {
+                    label: "system".to_string(),
+                    tokens: 40,
+                    allocated: 40,
+                    fit: SectionFit::Fits,
+                },
+                SectionAllocation {
+                    label: "context".to_string(),
+                    tokens: 90,
+                    allocated: 60,
+                    fit: SectionFit::Truncated,
+                },
+                SectionAllocation {
+                    label: "examples".to_string(),
+                    tokens: 20,
+                    allocated: 0,
+                    fit: SectionFit::Dropped,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = AllocationFormatter::format_text(&[plan()]);
+        assert!(output.contains("Budget: 100 tokens"));
+        assert!(output.contains("  context       60 / 90     truncated"));
+        assert!(output.contains("  examples       0 / 20     dropped"));
+        assert!(output.contains("Used: 100 of 100 tokens (0 left, 150 requested)"));
+    }
+
+    #[test]
+    fn test_format_text_groups_digits() {
+        let mut plan = plan();
+        plan.budget = 12_000;
+        plan.sections[0].tokens = 4_500;
+        plan.sections[0].allocated = 4_500;
+        let output = AllocationFormatter::format_text(&[plan]);
+        assert!(output.contains("Budget: 12,000 tokens"));
+        assert!(output.contains("  system     4,500 / 4,500  fits"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let output = AllocationFormatter::format_json(&[plan()]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["used"], 100);
+        assert_eq!(value["sections"][1]["status"], "truncated");
+        assert_eq!(value["sections"][2]["allocated"], 0);
+    }
+}
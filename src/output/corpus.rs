@@ -0,0 +1,280 @@
+/// Formatter for corpus-wide transcript statistics.
+use crate::analyzers::corpus::{CorpusSummary, HEAVIEST_FILES};
+use crate::output::json::costs_to_micros;
+use crate::output::TokenBreakdown;
+use crate::utils::currency::{apply_markup, Currency};
+use crate::utils::numbers::group_digits;
+use serde::Serialize;
+
+/// Formatter for `--compare-prompts-from-dir` reports, one summary per model.
+pub struct CorpusFormatter {
+    currency: Currency,
+    markup: Option<f64>,
+    cost_micros: bool,
+}
+
+impl CorpusFormatter {
+    /// Create a formatter that reports costs in US dollars.
+    pub fn new() -> Self {
+        Self {
+            currency: Currency::usd(),
+            markup: None,
+            cost_micros: false,
+        }
+    }
+
+    /// Report costs in `currency` instead of US dollars.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Also show the projected cost with a markup of `percent`.
+    pub fn with_markup(mut self, percent: Option<f64>) -> Self {
+        self.markup = percent;
+        self
+    }
+
+    /// Report JSON costs as integers in millionths of the currency unit.
+    pub fn with_cost_micros(mut self, cost_micros: bool) -> Self {
+        self.cost_micros = cost_micros;
+        self
+    }
+
+    /// Share of the corpus total that `tokens` represents, as a percentage.
+    fn share(summary: &CorpusSummary, tokens: usize) -> f64 {
+        match summary.total_tokens() {
+            0 => 0.0,
+            total => tokens as f64 * 100.0 / total as f64,
+        }
+    }
+
+    /// Format corpus summaries as text, separated by blank lines.
+    pub fn format_text(&self, summaries: &[CorpusSummary]) -> String {
+        summaries
+            .iter()
+            .map(|summary| self.summary_text(summary))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn summary_text(&self, summary: &CorpusSummary) -> String {
+        let count = |tokens: usize| group_digits(tokens, Some(','));
+        let mut output = vec![
+            format!("Corpus: {} files ({})", summary.len(), summary.model),
+            String::new(),
+            format!("Total tokens:    {}", count(summary.total_tokens())),
+        ];
+        if let Some(stats) = summary.tokens_per_file() {
+            output.push(format!("Mean per file:   {:.1}", stats.mean));
+            output.push(format!("Median per file: {}", count(stats.p50 as usize)));
+        }
+
+        let heaviest = summary.heaviest(HEAVIEST_FILES);
+        if !heaviest.is_empty() {
+            output.push(String::new());
+            output.push("Heaviest files:".to_string());
+            for (rank, (path, result)) in heaviest.into_iter().enumerate() {
+                output.push(format!(
+                    "  {}. {} ({} tokens, {:.1}%)",
+                    rank + 1,
+                    path.display(),
+                    count(result.tokens),
+                    Self::share(summary, result.tokens)
+                ));
+            }
+        }
+
+        let breakdown = summary.breakdown();
+        output.push(String::new());
+        output.push("Role breakdown:".to_string());
+        let tool = (breakdown.tool > 0).then_some(("Tool", breakdown.tool));
+        for (role, tokens) in [
+            ("System", breakdown.system),
+            ("User", breakdown.user),
+            ("Assistant", breakdown.assistant),
+        ]
+        .into_iter()
+        .chain(tool)
+        {
+            output.push(format!(
+                "  {:<11} {} tokens ({:.1}%)",
+                format!("{}:", role),
+                count(tokens),
+                breakdown.percent(tokens)
+            ));
+        }
+
+        output.push(String::new());
+        output.push(match summary.total_cost() {
+            Some(cost) => format!(
+                "Projected cost: {} to send every file once",
+                self.currency.format(cost, 4)
+            ),
+            None => format!("Projected cost: n/a (no pricing for {})", summary.model),
+        });
+        if let (Some(percent), Some(cost)) = (self.markup, summary.total_cost()) {
+            output.push(format!(
+                "With {}% markup: {}",
+                percent,
+                self.currency.format(apply_markup(cost, percent), 4)
+            ));
+        }
+
+        output.join("\n")
+    }
+
+    /// Format corpus summaries as JSON: an object for a single model, an
+    /// array of them when models are compared.
+    pub fn format_json(&self, summaries: &[CorpusSummary]) -> Result<String, serde_json::Error> {
+        let mut value = match summaries {
+            [summary] => self.summary_json(summary)?,
+            _ => serde_json::Value::Array(
+                summaries
+                    .iter()
+                    .map(|summary| self.summary_json(summary))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        if self.cost_micros {
+            costs_to_micros(&mut value);
+        }
+        serde_json::to_string_pretty(&value)
+    }
+
+    fn summary_json(
+        &self,
+        summary: &CorpusSummary,
+    ) -> Result<serde_json::Value, serde_json::Error> {
+        #[derive(Serialize)]
+        struct CorpusJson<'a> {
+            model: &'a str,
+            files: usize,
+            total_tokens: usize,
+            mean_tokens: Option<f64>,
+            median_tokens: Option<usize>,
+            heaviest: Vec<FileJson>,
+            breakdown: TokenBreakdown,
+            total_cost: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            total_cost_with_markup: Option<f64>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            currency: Option<&'a str>,
+            skipped: Vec<SkippedJson>,
+        }
+
+        #[derive(Serialize)]
+        struct SkippedJson {
+            path: String,
+            reason: String,
+        }
+
+        #[derive(Serialize)]
+        struct FileJson {
+            path: String,
+            tokens: usize,
+            percent: f64,
+        }
+
+        let stats = summary.tokens_per_file();
+        let breakdown = summary.breakdown();
+        let cost = summary.total_cost();
+        let json = CorpusJson {
+            model: &summary.model,
+            files: summary.len(),
+            total_tokens: summary.total_tokens(),
+            mean_tokens: stats.map(|s| s.mean),
+            median_tokens: stats.map(|s| s.p50 as usize),
+            heaviest: summary
+                .heaviest(HEAVIEST_FILES)
+                .into_iter()
+                .map(|(path, result)| FileJson {
+                    path: path.display().to_string(),
+                    tokens: result.tokens,
+                    percent: Self::share(summary, result.tokens),
+                })
+                .collect(),
+            breakdown,
+            total_cost: cost.map(|c| self.currency.convert(c)),
+            total_cost_with_markup: self
+                .markup
+                .zip(cost)
+                .map(|(percent, c)| self.currency.convert(apply_markup(c, percent))),
+            currency: cost.map(|_| self.currency.code.as_str()),
+            skipped: summary
+                .skipped
+                .iter()
+                .map(|skipped| SkippedJson {
+                    path: skipped.path.display().to_string(),
+                    reason: skipped.reason.clone(),
+                })
+                .collect(),
+        };
+
+        serde_json::to_value(json)
+    }
+
+    /// Format corpus summaries as markdown, one section per model.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(&self, summaries: &[CorpusSummary]) -> String {
+        summaries
+            .iter()
+            .map(|summary| self.summary_markdown(summary))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    #[cfg(feature = "markdown")]
+    fn summary_markdown(&self, summary: &CorpusSummary) -> String {
+        let mut output = vec![
+            "## Corpus Summary".to_string(),
+            String::new(),
+            format!(
+                "Model: `{}`, {} files, {} tokens",
+                summary.model,
+                summary.len(),
+                summary.total_tokens()
+            ),
+        ];
+        if let Some(stats) = summary.tokens_per_file() {
+            output.push(String::new());
+            output.push(format!(
+                "Mean {:.1} and median {} tokens per file",
+                stats.mean, stats.p50 as usize
+            ));
+        }
+
+        output.push(String::new());
+        output.push("| # | File | Tokens | Share |".to_string());
+        output.push("|---|------|--------|-------|".to_string());
+        for (rank, (path, result)) in summary.heaviest(HEAVIEST_FILES).into_iter().enumerate() {
+            output.push(format!(
+                "| {} | `{}` | {} | {:.1}% |",
+                rank + 1,
+                path.display(),
+                result.tokens,
+                Self::share(summary, result.tokens)
+            ));
+        }
+
+        output.push(String::new());
+        output.push(match summary.total_cost() {
+            Some(cost) => format!("**Projected cost:** {}", self.currency.format(cost, 4)),
+            None => "**Projected cost:** n/a".to_string(),
+        });
+        if let (Some(percent), Some(cost)) = (self.markup, summary.total_cost()) {
+            output.push(format!(
+                "**With {}% markup:** {}",
+                percent,
+                self.currency.format(apply_markup(cost, percent), 4)
+            ));
+        }
+        output.join("\n")
+    }
+}
+
+impl Default for CorpusFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
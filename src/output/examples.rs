@@ -0,0 +1,116 @@
+/// Formatter for prompt/examples segment counts.
+use crate::analyzers::examples::SegmentCounts;
+use serde::Serialize;
+
+/// JSON representation of segment counts.
+#[derive(Debug, Serialize)]
+struct SegmentJson {
+    model: String,
+    prompt_tokens: usize,
+    examples_tokens: usize,
+    combined_tokens: usize,
+    boundary_delta: i64,
+}
+
+impl From<&SegmentCounts> for SegmentJson {
+    fn from(counts: &SegmentCounts) -> Self {
+        Self {
+            model: counts.model.clone(),
+            prompt_tokens: counts.prompt,
+            examples_tokens: counts.examples,
+            combined_tokens: counts.combined,
+            boundary_delta: counts.boundary_delta(),
+        }
+    }
+}
+
+/// Formatter for prompt, examples and combined token counts.
+pub struct ExamplesFormatter;
+
+impl ExamplesFormatter {
+    /// Format segment counts as text, one block per model.
+    pub fn format_text(counts: &[SegmentCounts]) -> String {
+        counts
+            .iter()
+            .map(|c| {
+                [
+                    format!("Model: {}", c.model),
+                    format!("Prompt:     {} tokens", c.prompt),
+                    format!("Examples:   {} tokens", c.examples),
+                    format!(
+                        "Combined:   {} tokens ({:+} at the boundary)",
+                        c.combined,
+                        c.boundary_delta()
+                    ),
+                ]
+                .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Format segment counts as JSON: an object for one model, an array for several.
+    pub fn format_json(counts: &[SegmentCounts]) -> Result<String, serde_json::Error> {
+        let json: Vec<SegmentJson> = counts.iter().map(SegmentJson::from).collect();
+        match json.as_slice() {
+            [single] => serde_json::to_string_pretty(single),
+            _ => serde_json::to_string_pretty(&json),
+        }
+    }
+
+    /// Format segment counts as a markdown table.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(counts: &[SegmentCounts]) -> String {
+        let mut output = vec![
+            "## Prompt and Examples".to_string(),
+            String::new(),
+            "| Model | Prompt | Examples | Combined | Boundary |".to_string(),
+            "|-------|--------|----------|----------|----------|".to_string(),
+        ];
+        for c in counts {
+            output.push(format!(
+                "| {} | {} | {} | {} | {:+} |",
+                c.model,
+                c.prompt,
+                c.examples,
+                c.combined,
+                c.boundary_delta()
+            ));
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts() -> SegmentCounts {
+        SegmentCounts {
+            model: "gpt-4".to_string(),
+            prompt: 12,
+            examples: 30,
+            combined: 41,
+        }
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = ExamplesFormatter::format_text(&[counts()]);
+        assert!(output.contains("Prompt:     12 tokens"));
+        assert!(output.contains("Examples:   30 tokens"));
+        assert!(output.contains("Combined:   41 tokens (-1 at the boundary)"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let output = ExamplesFormatter::format_json(&[counts()]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["combined_tokens"], 41);
+        assert_eq!(value["boundary_delta"], -1);
+
+        let output = ExamplesFormatter::format_json(&[counts(), counts()]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+    }
+}
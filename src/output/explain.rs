@@ -0,0 +1,124 @@
+/// Formatter for the `--explain-tokenization` walkthrough.
+use crate::analyzers::explain::Explanation;
+use crate::tokenizers::TokenFragment;
+
+/// Formatter for a step-by-step explanation of how an input is tokenized.
+pub struct ExplainFormatter;
+
+impl ExplainFormatter {
+    /// Format explanations as a short narrative, one block per model.
+    pub fn format_text(explanations: &[Explanation]) -> String {
+        explanations
+            .iter()
+            .map(Self::explain_one)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn explain_one(e: &Explanation) -> String {
+        let mut lines = vec![format!("Model: {}", e.model), String::new()];
+
+        let ratio = match e.chars_per_token() {
+            Some(ratio) => format!(", about {:.1} characters per token", ratio),
+            None => String::new(),
+        };
+        lines.push(format!(
+            "The input is {} characters, {} bytes and {} tokens{}.",
+            e.chars, e.bytes, e.tokens, ratio
+        ));
+        if e.bytes > e.chars {
+            lines.push(
+                "Some characters take more than one byte in UTF-8, and tokenizers work on bytes, \
+                 so non-English text usually needs more tokens per character."
+                    .to_string(),
+            );
+        }
+
+        lines.push(String::new());
+        lines.push(
+            "Tokenizers learn common words with their usual spacing and capitalization as \
+             single tokens, so case and whitespace change the count:"
+                .to_string(),
+        );
+        let variant = |name: &str, tokens: usize| {
+            format!(
+                "  {:<22} {} tokens ({:+})",
+                name,
+                tokens,
+                tokens as i64 - e.tokens as i64
+            )
+        };
+        lines.push(variant("lowercased:", e.lowercase_tokens));
+        lines.push(variant("whitespace collapsed:", e.collapsed_tokens));
+
+        lines.push(String::new());
+        match &e.fragments {
+            Some(fragments) if !fragments.is_empty() => {
+                let shown: usize = fragments.iter().map(TokenFragment::tokens).sum();
+                lines.push(if shown < e.tokens {
+                    format!("The first {} of {} tokens:", shown, e.tokens)
+                } else {
+                    format!("The {} tokens:", shown)
+                });
+                for (index, fragment) in fragments.iter().enumerate() {
+                    let text = match fragment {
+                        TokenFragment::Text { text, .. } => format!("{:?}", text),
+                        TokenFragment::Partial { .. } => fragment.to_string(),
+                    };
+                    let note = match (fragment, fragment.tokens()) {
+                        (_, 1) => String::new(),
+                        (TokenFragment::Text { .. }, n) => {
+                            format!("  ({} tokens joined to complete a character)", n)
+                        }
+                        (TokenFragment::Partial { .. }, n) => format!("  ({} tokens)", n),
+                    };
+                    lines.push(format!("  {}. {}{}", index + 1, text, note));
+                }
+            }
+            Some(_) => lines.push("There are no tokens to show.".to_string()),
+            None => lines.push(format!(
+                "{} only approximates counts, so it has no token boundaries to show.",
+                e.model
+            )),
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_text() {
+        let explanation = Explanation {
+            model: "gpt-4".to_string(),
+            chars: 14,
+            bytes: 14,
+            tokens: 4,
+            lowercase_tokens: 4,
+            collapsed_tokens: 4,
+            fragments: Some(vec![
+                TokenFragment::Text {
+                    text: "Hello".to_string(),
+                    tokens: 1,
+                },
+                TokenFragment::Text {
+                    text: "語".to_string(),
+                    tokens: 2,
+                },
+            ]),
+        };
+
+        let output = ExplainFormatter::format_text(&[explanation]);
+        assert!(output.contains(
+            "The input is 14 characters, 14 bytes and 4 tokens, about 3.5 characters per token."
+        ));
+        assert!(output.contains("  lowercased:            4 tokens (+0)"));
+        assert!(output.contains("The first 3 of 4 tokens:"));
+        assert!(output.contains("  1. \"Hello\""));
+        assert!(output.contains("  2. \"語\"  (2 tokens joined to complete a character)"));
+        assert!(!output.contains("more than one byte"));
+    }
+}
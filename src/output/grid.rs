@@ -0,0 +1,208 @@
+/// Formatter for token count grids of several inputs against several models.
+use crate::utils::numbers::group_digits;
+use serde::Serialize;
+
+/// Largest grid (inputs × models) counted in one run.
+pub const MAX_GRID_CELLS: usize = 10_000;
+
+/// Token counts with one row per input and one column per model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenGrid {
+    /// Column headers, in the order given.
+    pub models: Vec<String>,
+    /// One row per input.
+    pub rows: Vec<GridRow>,
+}
+
+/// Token counts of one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridRow {
+    /// Input file path, or a preview of an inline prompt.
+    pub input: String,
+    /// Tokens per model, in column order; `None` where the model failed.
+    pub tokens: Vec<Option<usize>>,
+}
+
+/// JSON representation of one row.
+#[derive(Debug, Serialize)]
+struct GridRowJson<'a> {
+    input: &'a str,
+    results: Vec<GridCellJson<'a>>,
+}
+
+/// JSON representation of one cell.
+#[derive(Debug, Serialize)]
+struct GridCellJson<'a> {
+    model: &'a str,
+    tokens: Option<usize>,
+}
+
+/// Formatter for `--inputs` grids.
+pub struct GridFormatter;
+
+impl GridFormatter {
+    /// Format an aligned grid, grouping digits with `separator` if given.
+    pub fn format_text(grid: &TokenGrid, separator: Option<char>) -> String {
+        let cell = |tokens: Option<usize>| {
+            tokens.map_or("n/a".to_string(), |tokens| group_digits(tokens, separator))
+        };
+        let cells: Vec<Vec<String>> = grid
+            .rows
+            .iter()
+            .map(|row| row.tokens.iter().map(|&tokens| cell(tokens)).collect())
+            .collect();
+
+        let input_width = grid
+            .rows
+            .iter()
+            .map(|row| row.input.chars().count())
+            .chain(["Input".len()])
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = grid
+            .models
+            .iter()
+            .enumerate()
+            .map(|(column, model)| {
+                cells
+                    .iter()
+                    .map(|row| row[column].len())
+                    .chain([model.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let line = |first: &str, rest: &[String]| {
+            let mut line = format!("{:<width$}", first, width = input_width);
+            for (value, width) in rest.iter().zip(&widths) {
+                line.push_str(&format!("  {:>width$}", value, width = width));
+            }
+            line
+        };
+        let rule = input_width + widths.iter().map(|width| width + 2).sum::<usize>();
+
+        let mut output = vec![line("Input", &grid.models), "-".repeat(rule)];
+        for (row, cells) in grid.rows.iter().zip(&cells) {
+            output.push(line(&row.input, cells));
+        }
+        output.join("\n")
+    }
+
+    /// Format the grid as a JSON array of inputs, each with its per-model results.
+    pub fn format_json(grid: &TokenGrid) -> Result<String, serde_json::Error> {
+        let rows: Vec<GridRowJson> = grid
+            .rows
+            .iter()
+            .map(|row| GridRowJson {
+                input: &row.input,
+                results: grid
+                    .models
+                    .iter()
+                    .zip(&row.tokens)
+                    .map(|(model, &tokens)| GridCellJson { model, tokens })
+                    .collect(),
+            })
+            .collect();
+        serde_json::to_string_pretty(&rows)
+    }
+
+    /// Format the grid as CSV with an `input` column and one column per model.
+    ///
+    /// Failed cells are left empty.
+    pub fn format_csv(grid: &TokenGrid) -> String {
+        let mut output = vec![std::iter::once("input")
+            .chain(grid.models.iter().map(String::as_str))
+            .map(csv_field)
+            .collect::<Vec<_>>()
+            .join(",")];
+        for row in &grid.rows {
+            let mut fields = vec![csv_field(&row.input)];
+            fields.extend(
+                row.tokens
+                    .iter()
+                    .map(|tokens| tokens.map(|t| t.to_string()).unwrap_or_default()),
+            );
+            output.push(fields.join(","));
+        }
+        output.join("\n")
+    }
+
+    /// Format the grid as a markdown table.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(grid: &TokenGrid) -> String {
+        let mut output = vec![
+            "## Token Counts".to_string(),
+            String::new(),
+            format!("| Input | {} |", grid.models.join(" | ")),
+            format!("|-------|{}", "------:|".repeat(grid.models.len())),
+        ];
+        for row in &grid.rows {
+            let cells: Vec<String> = row
+                .tokens
+                .iter()
+                .map(|tokens| tokens.map_or("n/a".to_string(), |t| t.to_string()))
+                .collect();
+            output.push(format!("| {} | {} |", row.input, cells.join(" | ")));
+        }
+        output.join("\n")
+    }
+}
+
+/// Quote a CSV field if it contains a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> TokenGrid {
+        TokenGrid {
+            models: vec!["gpt-4".to_string(), "chars".to_string()],
+            rows: vec![
+                GridRow {
+                    input: "short.txt".to_string(),
+                    tokens: vec![Some(3), Some(12)],
+                },
+                GridRow {
+                    input: "a, b.txt".to_string(),
+                    tokens: vec![None, Some(1500)],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_format_text_aligned() {
+        let output = GridFormatter::format_text(&grid(), Some(','));
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Input      gpt-4  chars");
+        assert_eq!(lines[1], "-".repeat(23));
+        assert_eq!(lines[2], "short.txt      3     12");
+        assert_eq!(lines[3], "a, b.txt     n/a  1,500");
+    }
+
+    #[test]
+    fn test_format_csv_quotes_fields() {
+        assert_eq!(
+            GridFormatter::format_csv(&grid()),
+            "input,gpt-4,chars\nshort.txt,3,12\n\"a, b.txt\",,1500"
+        );
+    }
+
+    #[test]
+    fn test_format_json_nested() {
+        let output = GridFormatter::format_json(&grid()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["input"], "short.txt");
+        assert_eq!(value[0]["results"][1]["model"], "chars");
+        assert_eq!(value[0]["results"][1]["tokens"], 12);
+        assert_eq!(value[1]["results"][0]["tokens"], serde_json::Value::Null);
+    }
+}
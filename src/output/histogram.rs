@@ -0,0 +1,121 @@
+/// Formatter for per-message token histograms.
+use crate::analyzers::histogram::Histogram;
+use serde::Serialize;
+
+/// Width of the longest bar in the text histogram.
+const BAR_WIDTH: usize = 40;
+
+/// JSON representation of one bucket.
+#[derive(Debug, Serialize)]
+struct BucketJson {
+    label: String,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+}
+
+/// Formatter for `--histogram` reports.
+pub struct HistogramFormatter;
+
+impl HistogramFormatter {
+    /// Format one bar per bucket, scaled to the fullest bucket.
+    pub fn format_text(histogram: &Histogram) -> String {
+        let fullest = histogram
+            .buckets
+            .iter()
+            .map(|bucket| bucket.count)
+            .max()
+            .unwrap_or(0);
+        let width = histogram
+            .buckets
+            .iter()
+            .map(|bucket| bucket.label().len())
+            .max()
+            .unwrap_or(0);
+
+        let mut output = vec![
+            format!(
+                "Message sizes: {} messages ({})",
+                histogram.total(),
+                histogram.model
+            ),
+            String::new(),
+        ];
+        for bucket in &histogram.buckets {
+            // Rounded up, so every non-empty bucket shows a bar
+            let bar_length = (bucket.count * BAR_WIDTH + fullest.saturating_sub(1))
+                .checked_div(fullest)
+                .unwrap_or(0);
+            output.push(format!(
+                "{:>width$} tokens | {:<bar$} {}",
+                bucket.label(),
+                "█".repeat(bar_length),
+                bucket.count,
+                width = width,
+                bar = BAR_WIDTH
+            ));
+        }
+        output.push(String::new());
+        output.push(format!("Largest message: {} tokens", histogram.largest));
+
+        output.join("\n")
+    }
+
+    /// Format the buckets as a JSON array.
+    pub fn format_json(histogram: &Histogram) -> Result<String, serde_json::Error> {
+        let buckets: Vec<BucketJson> = histogram
+            .buckets
+            .iter()
+            .map(|bucket| BucketJson {
+                label: bucket.label(),
+                min: bucket.min,
+                max: bucket.max,
+                count: bucket.count,
+            })
+            .collect();
+        serde_json::to_string_pretty(&buckets)
+    }
+
+    /// Format the buckets as a markdown table.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(histogram: &Histogram) -> String {
+        let mut output = vec![
+            "## Message Sizes".to_string(),
+            String::new(),
+            format!("Model: `{}`", histogram.model),
+            String::new(),
+            "| Tokens | Messages |".to_string(),
+            "|--------|----------|".to_string(),
+        ];
+        for bucket in &histogram.buckets {
+            output.push(format!("| {} | {} |", bucket.label(), bucket.count));
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_text_scales_bars() {
+        let histogram = Histogram::from_counts("gpt-4", &[1, 2, 3, 4, 60], &[10, 50]).unwrap();
+        let output = HistogramFormatter::format_text(&histogram);
+        assert!(output.contains("Message sizes: 5 messages (gpt-4)"));
+        assert!(output.contains(&format!(" 0-10 tokens | {} 4", "█".repeat(40))));
+        assert!(output.contains(&format!("  51+ tokens | {:<40} 1", "█".repeat(10))));
+        assert!(output.contains("11-50 tokens | "));
+        assert!(output.contains("Largest message: 60 tokens"));
+    }
+
+    #[test]
+    fn test_format_json_buckets() {
+        let histogram = Histogram::from_counts("gpt-4", &[1, 60], &[10]).unwrap();
+        let output = HistogramFormatter::format_json(&histogram).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["label"], "0-10");
+        assert_eq!(value[1]["max"], serde_json::Value::Null);
+        assert_eq!(value[1]["count"], 1);
+    }
+}
@@ -1,16 +1,18 @@
 /// Formatter for prompt library insights.
 use crate::analyzers::types::LibraryInsights;
+use crate::utils::currency::Currency;
 use serde::Serialize;
 
 /// Formatter for library insights.
 pub struct InsightsFormatter;
 
 impl InsightsFormatter {
-    /// Format insights as text.
+    /// Format insights as text, with costs in `currency`.
     pub fn format_text(
         insights: &LibraryInsights,
         model: &str,
         context_limit: Option<usize>,
+        currency: &Currency,
     ) -> String {
         let mut output = Vec::new();
 
@@ -24,10 +26,13 @@ impl InsightsFormatter {
         output.push(format!("Total Prompts: {}", insights.total_prompts));
         output.push(format!("Total Tokens: {}", insights.total_tokens));
         output.push(format!(
-            "Estimated Cost (per invocation): ${:.4}",
-            insights.total_cost
+            "Estimated Cost (per invocation): {}",
+            currency.format(insights.total_cost, 4)
+        ));
+        output.push(format!(
+            "Monthly Cost: {}",
+            currency.format(insights.monthly_cost, 2)
         ));
-        output.push(format!("Monthly Cost: ${:.2}", insights.monthly_cost));
         output.push(String::new());
 
         // Token Distribution
@@ -65,11 +70,11 @@ impl InsightsFormatter {
             for (i, analysis) in insights.top_expensive.iter().enumerate() {
                 let file_display = analysis.file_path.to_string_lossy();
                 output.push(format!(
-                    "{}. {} - {} tokens - ${:.4}/invocation",
+                    "{}. {} - {} tokens - {}/invocation",
                     i + 1,
                     file_display,
                     analysis.token_count,
-                    analysis.total_cost
+                    currency.format(analysis.total_cost, 4)
                 ));
             }
             output.push(String::new());
@@ -118,10 +123,14 @@ impl InsightsFormatter {
         output.join("\n")
     }
 
-    /// Format insights as JSON.
-    pub fn format_json(insights: &LibraryInsights) -> Result<String, serde_json::Error> {
+    /// Format insights as JSON, with costs in `currency`.
+    pub fn format_json(
+        insights: &LibraryInsights,
+        currency: &Currency,
+    ) -> Result<String, serde_json::Error> {
         #[derive(Serialize)]
-        struct InsightsJson {
+        struct InsightsJson<'a> {
+            currency: &'a str,
             summary: SummaryJson,
             token_distribution: Vec<DistributionJson>,
             top_expensive: Vec<AnalysisJson>,
@@ -172,11 +181,12 @@ impl InsightsFormatter {
         }
 
         let json = InsightsJson {
+            currency: &currency.code,
             summary: SummaryJson {
                 total_prompts: insights.total_prompts,
                 total_tokens: insights.total_tokens,
-                total_cost: insights.total_cost,
-                monthly_cost: insights.monthly_cost,
+                total_cost: currency.convert(insights.total_cost),
+                monthly_cost: currency.convert(insights.monthly_cost),
             },
             token_distribution: distribution,
             top_expensive: insights
@@ -186,7 +196,7 @@ impl InsightsFormatter {
                     file_path: a.file_path.to_string_lossy().to_string(),
                     prompt_id: a.prompt_id.clone(),
                     token_count: a.token_count,
-                    total_cost: a.total_cost,
+                    total_cost: currency.convert(a.total_cost),
                 })
                 .collect(),
             exceeded_limits: insights
@@ -196,7 +206,7 @@ impl InsightsFormatter {
                     file_path: a.file_path.to_string_lossy().to_string(),
                     prompt_id: a.prompt_id.clone(),
                     token_count: a.token_count,
-                    total_cost: a.total_cost,
+                    total_cost: currency.convert(a.total_cost),
                 })
                 .collect(),
             duplicates: insights
@@ -225,14 +235,14 @@ mod tests {
     #[test]
     fn test_format_text_empty() {
         let insights = LibraryInsights::new();
-        let output = InsightsFormatter::format_text(&insights, "gpt-4", None);
+        let output = InsightsFormatter::format_text(&insights, "gpt-4", None, &Currency::usd());
         assert!(output.contains("Total Prompts: 0"));
     }
 
     #[test]
     fn test_format_json_empty() {
         let insights = LibraryInsights::new();
-        let output = InsightsFormatter::format_json(&insights).unwrap();
+        let output = InsightsFormatter::format_json(&insights, &Currency::usd()).unwrap();
         assert!(output.contains("\"total_prompts\": 0"));
     }
 
@@ -244,6 +254,8 @@ mod tests {
             messages: vec![Message {
                 role: "user".to_string(),
                 content: "test".to_string(),
+                name: None,
+                tool_call_id: None,
             }],
             token_count: tokens,
             input_cost: cost,
@@ -264,9 +276,32 @@ mod tests {
         insights.top_expensive = vec![create_test_analysis("test1.txt", 500, 0.03)];
         insights.exceeded_limits = vec![create_test_analysis("test2.txt", 10000, 0.02)];
 
-        let output = InsightsFormatter::format_text(&insights, "gpt-4", Some(8192));
+        let output =
+            InsightsFormatter::format_text(&insights, "gpt-4", Some(8192), &Currency::usd());
         assert!(output.contains("Total Prompts: 2"));
         assert!(output.contains("test1.txt"));
         assert!(output.contains("test2.txt"));
     }
+
+    #[test]
+    fn test_costs_in_currency() {
+        let mut insights = LibraryInsights::new();
+        insights.total_cost = 0.05;
+        insights.monthly_cost = 50.0;
+        insights.top_expensive = vec![create_test_analysis("test1.txt", 500, 0.03)];
+        let eur = Currency::new("EUR", 0.5).unwrap();
+
+        let output = InsightsFormatter::format_text(&insights, "gpt-4", None, &eur);
+        assert!(output.contains("Monthly Cost: €25.00"), "{}", output);
+        assert!(
+            output.contains("500 tokens - €0.0150/invocation"),
+            "{}",
+            output
+        );
+
+        let output = InsightsFormatter::format_json(&insights, &eur).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["currency"], "EUR");
+        assert_eq!(value["summary"]["monthly_cost"], 25.0);
+    }
 }
@@ -1,33 +1,404 @@
 /// JSON formatter for machine-readable output.
-use crate::output::{Formatter, TokenResult};
+use crate::output::aggregate::Winners;
+use crate::output::{
+    BreakEven, Formatter, ModelFailure, ReportMetadata, ResultAggregator, TokenBreakdown,
+    TokenDelta, TokenResult, Warning,
+};
+use crate::utils::currency::{apply_markup, Currency};
 use serde::Serialize;
 
+/// Decimal places floats are rounded to in canonical output.
+pub const CANONICAL_DECIMALS: i32 = 6;
+
+/// Cost fields that `--cost-micros` reports as integer micro-units.
+const COST_FIELDS: [&str; 7] = [
+    "input_cost",
+    "output_cost",
+    "cost",
+    "cost_with_markup",
+    "cost_per_second",
+    "total_cost",
+    "total_cost_with_markup",
+];
+
+/// Winners whose `value` is a cost.
+const COST_WINNERS: [&str; 2] = ["lowest_input_cost", "lowest_total_cost"];
+
 /// JSON representation of a token result.
 #[derive(Debug, Serialize)]
 struct JsonResult {
     model: String,
     tokens: usize,
+    content_tokens: usize,
+    overhead_tokens: usize,
     input_cost: Option<f64>,
     output_cost: Option<f64>,
+    /// Currency code of the costs, present when there are any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    /// Total cost, present with a markup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<f64>,
+    /// Total cost with the markup added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_with_markup: Option<f64>,
+    output_input_ratio: Option<f64>,
+    /// Total cost as a multiple of the cheapest priced model, in comparisons.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_multiple: Option<f64>,
+    /// Completion tokens of the `--response`, which the output cost is for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_tokens: Option<usize>,
+    /// Tokens left in the context window; negative when over it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    headroom: Option<i64>,
     breakdown: Option<JsonBreakdown>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    by_name: Option<Vec<JsonParticipant>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_ids: Option<Vec<usize>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    token_ids_truncated: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    approximate: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta_vs_baseline: Option<JsonDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    projection: Option<JsonProjection>,
+}
+
+/// Projected response latency and cost.
+#[derive(Debug, Serialize)]
+struct JsonProjection {
+    throughput: f64,
+    latency_seconds: f64,
+    cost: Option<f64>,
+    cost_per_second: Option<f64>,
+}
+
+/// Token delta against the baseline model.
+#[derive(Debug, Serialize)]
+struct JsonDelta {
+    tokens: i64,
+    percent: f64,
 }
 
 /// JSON representation of token breakdown.
 #[derive(Debug, Serialize)]
 struct JsonBreakdown {
-    system: usize,
-    user: usize,
-    assistant: usize,
-    total: usize,
+    #[serde(flatten)]
+    tokens: TokenBreakdown,
+    percent: JsonBreakdownPercent,
+}
+
+/// Share of the total per role, in percent.
+#[derive(Debug, Serialize)]
+struct JsonBreakdownPercent {
+    system: f64,
+    user: f64,
+    assistant: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool: Option<f64>,
+}
+
+/// Tokens of one participant with `--breakdown-by name`.
+#[derive(Debug, Serialize)]
+struct JsonParticipant {
+    name: String,
+    tokens: usize,
+    percent: f64,
+}
+
+/// Report metadata, included with `--with-metadata`.
+#[derive(Debug, Serialize)]
+struct JsonMeta {
+    version: String,
+    generated_at: String,
+    input: String,
+    pricing: String,
+}
+
+impl From<&ReportMetadata> for JsonMeta {
+    fn from(metadata: &ReportMetadata) -> Self {
+        Self {
+            version: metadata.version.clone(),
+            generated_at: metadata.generated_at.clone(),
+            input: metadata.input.clone(),
+            pricing: metadata.pricing.clone(),
+        }
+    }
+}
+
+/// A single result with its metadata alongside the result fields.
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<JsonMeta>,
+    #[serde(flatten)]
+    result: JsonResult,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    warnings: &'a [Warning],
+}
+
+/// A comparison with its metadata or winners; the results move under `results`.
+#[derive(Debug, Serialize)]
+struct JsonComparisonReport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<JsonMeta>,
+    results: Vec<JsonResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    winners: Option<Winners<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    warnings: &'a [Warning],
+}
+
+/// JSON representation of a failed model.
+#[derive(Debug, Serialize)]
+struct JsonFailure {
+    model: String,
+    error: String,
 }
 
 /// JSON formatter for machine-readable output.
-pub struct JsonFormatter;
+pub struct JsonFormatter {
+    baseline: Option<String>,
+    metadata: Option<ReportMetadata>,
+    currency: Currency,
+    markup: Option<f64>,
+    cost_micros: bool,
+    warnings: Vec<Warning>,
+    canonical: bool,
+    winners: bool,
+    headroom: bool,
+}
 
 impl JsonFormatter {
     /// Create a new JSON formatter.
     pub fn new() -> Self {
-        Self
+        Self {
+            baseline: None,
+            metadata: None,
+            currency: Currency::usd(),
+            markup: None,
+            cost_micros: false,
+            warnings: Vec::new(),
+            canonical: false,
+            winners: false,
+            headroom: false,
+        }
+    }
+
+    /// Add a `delta_vs_baseline` field against `baseline` to comparisons.
+    pub fn with_baseline(mut self, baseline: Option<String>) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Embed a `meta` object in the output.
+    ///
+    /// A single result gains a `meta` field; a comparison becomes an object
+    /// with `meta` and `results`, since an array has no room for it.
+    pub fn with_metadata(mut self, metadata: Option<ReportMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Report costs in `currency` instead of US dollars.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Add `cost` and `cost_with_markup` fields with the total cost before
+    /// and after a markup of `percent` over the provider's price.
+    pub fn with_markup(mut self, percent: Option<f64>) -> Self {
+        self.markup = percent;
+        self
+    }
+
+    /// Report costs as integers in millionths of the currency unit.
+    ///
+    /// Each cost field `X` is replaced by `X_micros`, so $0.000075 becomes
+    /// `"input_cost_micros": 75` and consumers avoid float drift.
+    pub fn with_cost_micros(mut self, cost_micros: bool) -> Self {
+        self.cost_micros = cost_micros;
+        self
+    }
+
+    /// Embed a `warnings` array in the output.
+    ///
+    /// A single result gains a `warnings` field, as does a comparison that
+    /// is an object with `results` anyway. Warnings never turn a plain
+    /// comparison array into an object; see
+    /// [`embeds_warnings`](Self::embeds_warnings).
+    pub fn with_warnings(mut self, warnings: Vec<Warning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Whether warnings given to [`with_warnings`](Self::with_warnings)
+    /// appear in the output, for a single result if `single` is set and a
+    /// comparison otherwise.
+    pub fn embeds_warnings(&self, single: bool) -> bool {
+        (single && !self.canonical) || self.winners || (self.metadata.is_some() && !self.canonical)
+    }
+
+    /// Produce stable output for snapshot tests.
+    ///
+    /// Results are always an array, sorted by model name with repeated models
+    /// dropped, floats are rounded to [`CANONICAL_DECIMALS`] places and
+    /// metadata such as the generation time is left out.
+    pub fn with_canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Add a `headroom` field with the tokens left in each model's context
+    /// window, where it is known.
+    pub fn with_headroom(mut self, headroom: bool) -> Self {
+        self.headroom = headroom;
+        self
+    }
+
+    /// Add a `winners` object naming the best models by each criterion to
+    /// comparisons, which then become an object with `results` and `winners`.
+    pub fn with_winners(mut self, winners: bool) -> Self {
+        self.winners = winners;
+        self
+    }
+
+    /// Winners of `results`, with costs in the output currency.
+    fn winners<'a>(&self, results: &'a [TokenResult]) -> Winners<'a> {
+        let mut winners = ResultAggregator::new(results).winners();
+        for winner in [
+            &mut winners.lowest_input_cost,
+            &mut winners.lowest_total_cost,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            winner.value = self.currency.convert(winner.value);
+        }
+        if self.canonical {
+            winners.sort_models();
+        }
+        winners
+    }
+
+    fn json_result(&self, result: &TokenResult) -> JsonResult {
+        let mut json = JsonResult::from(result);
+        let convert = |cost: Option<f64>| cost.map(|c| self.currency.convert(c));
+        json.input_cost = convert(json.input_cost);
+        json.output_cost = convert(json.output_cost);
+        if let Some(projection) = json.projection.as_mut() {
+            projection.cost = convert(projection.cost);
+            projection.cost_per_second = convert(projection.cost_per_second);
+        }
+        if let (Some(percent), Some(cost)) = (self.markup, result.total_cost()) {
+            json.cost = convert(Some(cost));
+            json.cost_with_markup = convert(Some(apply_markup(cost, percent)));
+        }
+        if json.input_cost.is_some() || json.output_cost.is_some() {
+            json.currency = Some(self.currency.code.clone());
+        }
+        if self.headroom {
+            json.headroom = result.prompt_headroom();
+        }
+        json
+    }
+
+    /// Pretty-print `report`, with costs in micro-units if requested, or
+    /// `fallback` if it cannot be serialized.
+    fn render(&self, report: &impl Serialize, fallback: &str) -> String {
+        let json = if self.cost_micros {
+            // Going through a `Value` sorts the keys, so only do it when needed
+            serde_json::to_value(report).and_then(|mut value| {
+                costs_to_micros(&mut value);
+                serde_json::to_string_pretty(&value)
+            })
+        } else {
+            serde_json::to_string_pretty(report)
+        };
+        json.unwrap_or_else(|_| fallback.to_string())
+    }
+
+    /// The results ordered for output: as given, or by model name when canonical.
+    fn ordered<'a>(&self, results: &'a [TokenResult]) -> Vec<&'a TokenResult> {
+        let mut ordered: Vec<&TokenResult> = results.iter().collect();
+        if self.canonical {
+            ordered.sort_by(|a, b| a.model.cmp(&b.model));
+            ordered.dedup_by(|a, b| a.model == b.model);
+        }
+        ordered
+    }
+}
+
+impl JsonResult {
+    /// Round every float to [`CANONICAL_DECIMALS`] places.
+    fn round_floats(&mut self) {
+        let round = |value: &mut f64| {
+            let scale = 10f64.powi(CANONICAL_DECIMALS);
+            *value = (*value * scale).round() / scale;
+        };
+        let round_opt = |value: &mut Option<f64>| value.iter_mut().for_each(round);
+
+        round_opt(&mut self.input_cost);
+        round_opt(&mut self.output_cost);
+        round_opt(&mut self.cost);
+        round_opt(&mut self.cost_with_markup);
+        round_opt(&mut self.output_input_ratio);
+        round_opt(&mut self.cost_multiple);
+        if let Some(breakdown) = self.breakdown.as_mut() {
+            round(&mut breakdown.percent.system);
+            round(&mut breakdown.percent.user);
+            round(&mut breakdown.percent.assistant);
+            round_opt(&mut breakdown.percent.tool);
+        }
+        for participant in self.by_name.iter_mut().flatten() {
+            round(&mut participant.percent);
+        }
+        if let Some(delta) = self.delta_vs_baseline.as_mut() {
+            round(&mut delta.percent);
+        }
+        if let Some(projection) = self.projection.as_mut() {
+            round(&mut projection.throughput);
+            round(&mut projection.latency_seconds);
+            round_opt(&mut projection.cost);
+            round_opt(&mut projection.cost_per_second);
+        }
+    }
+}
+
+/// Replace every cost field in `value` by an integer `_micros` field.
+pub(crate) fn costs_to_micros(value: &mut serde_json::Value) {
+    use serde_json::Value;
+
+    let micros = |cost: &Value| match cost.as_f64() {
+        Some(cost) => Value::from((cost * 1_000_000.0).round() as i64),
+        None => Value::Null,
+    };
+    match value {
+        Value::Object(map) => {
+            let keys: Vec<String> = map.keys().cloned().collect();
+            for key in keys {
+                if COST_FIELDS.contains(&key.as_str()) && !map[&key].is_object() {
+                    let cost = map.remove(&key).unwrap_or_default();
+                    map.insert(format!("{}_micros", key), micros(&cost));
+                } else if COST_WINNERS.contains(&key.as_str()) {
+                    if let Some(winner) = map[&key].as_object_mut() {
+                        if let Some(cost) = winner.remove("value") {
+                            winner.insert("value_micros".to_string(), micros(&cost));
+                        }
+                    }
+                } else {
+                    costs_to_micros(&mut map[&key]);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(costs_to_micros),
+        _ => {}
     }
 }
 
@@ -37,46 +408,148 @@ impl Default for JsonFormatter {
     }
 }
 
-impl Formatter for JsonFormatter {
-    fn format_result(&self, result: &TokenResult) -> String {
-        let json_result = JsonResult {
+impl From<&TokenResult> for JsonResult {
+    fn from(result: &TokenResult) -> Self {
+        Self {
             model: result.model.clone(),
             tokens: result.tokens,
+            content_tokens: result.content_tokens,
+            overhead_tokens: result.overhead_tokens,
             input_cost: result.input_cost,
             output_cost: result.output_cost,
+            currency: None,
+            cost: None,
+            cost_with_markup: None,
+            output_input_ratio: result.output_input_ratio(),
+            cost_multiple: None,
+            response_tokens: result.response_tokens,
+            headroom: None,
             breakdown: result.breakdown.as_ref().map(|b| JsonBreakdown {
-                system: b.system,
-                user: b.user,
-                assistant: b.assistant,
-                total: b.total,
+                tokens: b.clone(),
+                percent: JsonBreakdownPercent {
+                    system: b.percent(b.system),
+                    user: b.percent(b.user),
+                    assistant: b.percent(b.assistant),
+                    tool: (b.tool > 0).then(|| b.percent(b.tool)),
+                },
             }),
-        };
-        serde_json::to_string_pretty(&json_result).unwrap_or_else(|_| "{}".to_string())
+            by_name: result.by_name.as_ref().zip(result.breakdown.as_ref()).map(
+                |(_, breakdown)| {
+                    result
+                        .participants()
+                        .into_iter()
+                        .map(|(name, tokens)| JsonParticipant {
+                            name: name.to_string(),
+                            tokens,
+                            percent: breakdown.percent(tokens),
+                        })
+                        .collect()
+                },
+            ),
+            token_ids: result.token_ids.clone(),
+            token_ids_truncated: result.token_ids_truncated,
+            approximate: result.approximate,
+            encoding: result.encoding.clone(),
+            delta_vs_baseline: None,
+            projection: result.projection.map(|p| JsonProjection {
+                throughput: p.throughput,
+                latency_seconds: p.latency_secs,
+                cost: p.cost,
+                cost_per_second: p.cost_per_second(),
+            }),
+        }
+    }
+}
+
+impl Formatter for JsonFormatter {
+    fn format_result(&self, result: &TokenResult) -> String {
+        if self.canonical {
+            return self.format_comparison(std::slice::from_ref(result));
+        }
+        let json_result = self.json_result(result);
+        if self.metadata.is_none() && self.warnings.is_empty() {
+            return self.render(&json_result, "{}");
+        }
+        self.render(
+            &JsonReport {
+                meta: self.metadata.as_ref().map(JsonMeta::from),
+                result: json_result,
+                warnings: &self.warnings,
+            },
+            "{}",
+        )
     }
 
     fn format_comparison(&self, results: &[TokenResult]) -> String {
-        let json_results: Vec<JsonResult> = results
+        let baseline = self
+            .baseline
+            .as_deref()
+            .and_then(|baseline| results.iter().find(|r| r.model == baseline));
+        let aggregate = ResultAggregator::new(results);
+        let json_results: Vec<JsonResult> = self
+            .ordered(results)
+            .into_iter()
+            .map(|result| {
+                let mut json = self.json_result(result);
+                json.cost_multiple = aggregate.cost_multiple(result);
+                if let Some(baseline) = baseline {
+                    let delta = TokenDelta::between(baseline, result);
+                    json.delta_vs_baseline = Some(JsonDelta {
+                        tokens: delta.tokens,
+                        percent: delta.percent,
+                    });
+                }
+                if self.canonical {
+                    json.round_floats();
+                }
+                json
+            })
+            .collect();
+        let meta = self
+            .metadata
+            .as_ref()
+            .filter(|_| !self.canonical)
+            .map(JsonMeta::from);
+        if meta.is_none() && !self.winners {
+            return self.render(&json_results, "[]");
+        }
+        self.render(
+            &JsonComparisonReport {
+                meta,
+                results: json_results,
+                winners: self.winners.then(|| self.winners(results)),
+                warnings: &self.warnings,
+            },
+            "[]",
+        )
+    }
+
+    fn format_metadata(&self, _metadata: &ReportMetadata) -> String {
+        // Embedded as `meta` by `with_metadata`; keep stdout a single document
+        String::new()
+    }
+
+    fn format_break_even(&self, _expected_output: usize, _points: &[BreakEven]) -> String {
+        // Rates and ratios are already part of each result; keep stdout a single document
+        String::new()
+    }
+
+    fn format_failures(&self, failures: &[ModelFailure]) -> String {
+        let json_failures: Vec<JsonFailure> = failures
             .iter()
-            .map(|r| JsonResult {
-                model: r.model.clone(),
-                tokens: r.tokens,
-                input_cost: r.input_cost,
-                output_cost: r.output_cost,
-                breakdown: r.breakdown.as_ref().map(|b| JsonBreakdown {
-                    system: b.system,
-                    user: b.user,
-                    assistant: b.assistant,
-                    total: b.total,
-                }),
+            .map(|f| JsonFailure {
+                model: f.model.clone(),
+                error: f.error.clone(),
             })
             .collect();
-        serde_json::to_string_pretty(&json_results).unwrap_or_else(|_| "[]".to_string())
+        serde_json::to_string_pretty(&json_failures).unwrap_or_else(|_| "[]".to_string())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::output::TokenBreakdown;
 
     #[test]
     fn test_format_result() {
@@ -87,10 +560,333 @@ mod tests {
             input_cost: Some(0.003),
             output_cost: None,
             breakdown: None,
+            ..Default::default()
         };
         let output = formatter.format_result(&result);
         assert!(output.contains("gpt-4"));
         assert!(output.contains("100"));
         assert!(serde_json::from_str::<serde_json::Value>(&output).is_ok());
+        assert!(!output.contains("token_ids"));
+        assert!(!output.contains("approximate"));
+    }
+
+    #[test]
+    fn test_costs_converted_to_currency() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1000,
+            input_cost: Some(0.03),
+            output_cost: Some(0.06),
+            ..Default::default()
+        };
+
+        let value: serde_json::Value =
+            serde_json::from_str(&JsonFormatter::new().format_result(&result)).unwrap();
+        assert_eq!(value["currency"], "USD");
+        assert_eq!(value["input_cost"], 0.03);
+
+        let formatter = JsonFormatter::new().with_currency(Currency::new("EUR", 0.9).unwrap());
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        assert_eq!(value["currency"], "EUR");
+        assert!((value["input_cost"].as_f64().unwrap() - 0.027).abs() < 1e-12);
+        assert!((value["output_cost"].as_f64().unwrap() - 0.054).abs() < 1e-12);
+
+        let unpriced = TokenResult {
+            model: "words".to_string(),
+            tokens: 3,
+            ..Default::default()
+        };
+        assert!(!formatter.format_result(&unpriced).contains("currency"));
+    }
+
+    #[test]
+    fn test_costs_with_markup() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1000,
+            input_cost: Some(0.03),
+            output_cost: Some(0.06),
+            ..Default::default()
+        };
+        assert!(!JsonFormatter::new()
+            .format_result(&result)
+            .contains("cost_with_markup"));
+
+        let formatter = JsonFormatter::new().with_markup(Some(20.0));
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        assert!((value["cost"].as_f64().unwrap() - 0.09).abs() < 1e-12);
+        assert!((value["cost_with_markup"].as_f64().unwrap() - 0.108).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_costs_as_micros() {
+        let result = TokenResult {
+            model: "gemini-flash".to_string(),
+            tokens: 100,
+            input_cost: Some(0.000075),
+            output_cost: None,
+            ..Default::default()
+        };
+        let formatter = JsonFormatter::new()
+            .with_markup(Some(20.0))
+            .with_cost_micros(true);
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        assert_eq!(value["input_cost_micros"], 75);
+        assert_eq!(value["output_cost_micros"], serde_json::Value::Null);
+        assert_eq!(value["cost_micros"], 75);
+        assert_eq!(value["cost_with_markup_micros"], 90);
+        assert!(value.get("input_cost").is_none());
+
+        let output = JsonFormatter::new()
+            .with_winners(true)
+            .with_cost_micros(true)
+            .format_comparison(&[result]);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["results"][0]["input_cost_micros"], 75);
+        assert_eq!(value["winners"]["lowest_input_cost"]["value_micros"], 75);
+    }
+
+    #[test]
+    fn test_format_result_overhead_split() {
+        let formatter = JsonFormatter::new();
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 19,
+            content_tokens: 10,
+            overhead_tokens: 9,
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        assert_eq!(value["content_tokens"], 10);
+        assert_eq!(value["overhead_tokens"], 9);
+        assert_eq!(value["tokens"], 19);
+    }
+
+    #[test]
+    fn test_metadata_embedded_as_meta() {
+        let metadata = ReportMetadata::new("prompt.txt", None);
+        let formatter = JsonFormatter::new().with_metadata(Some(metadata));
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 4,
+            ..Default::default()
+        };
+
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        assert_eq!(value["meta"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(value["meta"]["input"], "prompt.txt");
+        assert_eq!(value["meta"]["pricing"], "defaults");
+        assert_eq!(value["tokens"], 4);
+
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_comparison(&[result])).unwrap();
+        assert_eq!(value["meta"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(value["results"][0]["model"], "gpt-4");
+    }
+
+    #[test]
+    fn test_format_result_token_ids() {
+        let formatter = JsonFormatter::new();
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 3,
+            token_ids: Some(vec![9906, 11, 1917]),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["token_ids"], serde_json::json!([9906, 11, 1917]));
+        assert!(value.get("token_ids_truncated").is_none());
+    }
+
+    #[test]
+    fn test_format_result_breakdown_percent() {
+        let formatter = JsonFormatter::new();
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 30,
+            breakdown: Some(TokenBreakdown {
+                system: 24,
+                user: 5,
+                assistant: 1,
+                tool: 0,
+                total: 30,
+            }),
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&result)).unwrap();
+        let percent = &value["breakdown"]["percent"];
+        assert_eq!(percent["system"], 80.0);
+        let sum: f64 = ["system", "user", "assistant"]
+            .iter()
+            .map(|role| percent[role].as_f64().unwrap())
+            .sum();
+        assert!((sum - 100.0).abs() < 1e-9);
+
+        let empty = TokenResult {
+            breakdown: Some(TokenBreakdown::new()),
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_result(&empty)).unwrap();
+        assert_eq!(value["breakdown"]["percent"]["user"], 0.0);
+    }
+
+    #[test]
+    fn test_format_comparison_delta_vs_baseline() {
+        let result = |model: &str, tokens| TokenResult {
+            model: model.to_string(),
+            tokens,
+            ..Default::default()
+        };
+        let results = vec![result("gpt-4", 200), result("claude-3-haiku", 150)];
+
+        let output = JsonFormatter::new()
+            .with_baseline(Some("gpt-4".to_string()))
+            .format_comparison(&results);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["delta_vs_baseline"]["tokens"], 0);
+        assert_eq!(value[1]["delta_vs_baseline"]["tokens"], -50);
+        assert_eq!(value[1]["delta_vs_baseline"]["percent"], -25.0);
+
+        let output = JsonFormatter::new().format_comparison(&results);
+        assert!(!output.contains("delta_vs_baseline"));
+    }
+
+    #[test]
+    fn test_format_comparison_cost_multiple() {
+        let result = |model: &str, cost| TokenResult {
+            model: model.to_string(),
+            tokens: 100,
+            input_cost: cost,
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", Some(0.03)),
+            result("gpt-4o", Some(0.01)),
+            result("chars", None),
+        ];
+
+        let output = JsonFormatter::new().format_comparison(&results);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["cost_multiple"], 3.0);
+        assert_eq!(value[1]["cost_multiple"], 1.0);
+        assert!(value[2].get("cost_multiple").is_none());
+    }
+
+    #[test]
+    fn test_format_comparison_winners() {
+        let result = |model: &str, tokens, cost, window| TokenResult {
+            model: model.to_string(),
+            tokens,
+            input_cost: cost,
+            context_window: window,
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", 100, Some(0.003), Some(8192)),
+            result("gpt-4o", 90, Some(0.0005), Some(128_000)),
+            result("chars", 400, None, None),
+        ];
+
+        let output = JsonFormatter::new()
+            .with_winners(true)
+            .format_comparison(&results);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["results"][0]["model"], "gpt-4");
+        let winners = &value["winners"];
+        assert_eq!(
+            winners["fewest_tokens"]["models"],
+            serde_json::json!(["gpt-4o"])
+        );
+        assert_eq!(winners["fewest_tokens"]["value"], 90);
+        assert_eq!(winners["lowest_input_cost"]["value"], 0.0005);
+        assert_eq!(winners["most_headroom"]["value"], 127_910);
+        assert!(value.get("meta").is_none());
+
+        let unpriced = JsonFormatter::new()
+            .with_winners(true)
+            .format_comparison(&results[2..]);
+        let value: serde_json::Value = serde_json::from_str(&unpriced).unwrap();
+        assert_eq!(
+            value["winners"]["lowest_total_cost"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_warnings_keep_a_comparison_an_array() {
+        let results = vec![
+            TokenResult {
+                model: "gpt-4".to_string(),
+                tokens: 10,
+                ..Default::default()
+            },
+            TokenResult {
+                model: "gpt-4o".to_string(),
+                tokens: 9,
+                ..Default::default()
+            },
+        ];
+        let warnings = vec![Warning {
+            kind: crate::output::WarningKind::DuplicateModel,
+            message: "skipping duplicate model 'openai/gpt-4'".to_string(),
+        }];
+
+        let formatter = JsonFormatter::new().with_warnings(warnings.clone());
+        assert!(formatter.embeds_warnings(true));
+        assert!(!formatter.embeds_warnings(false));
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_comparison(&results)).unwrap();
+        assert!(value.is_array());
+
+        let formatter = JsonFormatter::new()
+            .with_winners(true)
+            .with_warnings(warnings);
+        assert!(formatter.embeds_warnings(false));
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format_comparison(&results)).unwrap();
+        assert_eq!(value["warnings"][0]["kind"], "duplicate_model");
+    }
+
+    #[test]
+    fn test_canonical_output_is_stable() {
+        let result = |model: &str, cost| TokenResult {
+            model: model.to_string(),
+            tokens: 10,
+            input_cost: Some(cost),
+            ..Default::default()
+        };
+        let formatter = JsonFormatter::new()
+            .with_metadata(Some(ReportMetadata::new("prompt.txt", None)))
+            .with_canonical(true);
+
+        let output = formatter.format_comparison(&[
+            result("gpt-4", 0.1 + 0.2),
+            result("claude-3-haiku", 0.000_000_4),
+            result("gpt-4", 0.3),
+        ]);
+        let reordered = formatter
+            .format_comparison(&[result("claude-3-haiku", 0.000_000_4), result("gpt-4", 0.3)]);
+        assert_eq!(output, reordered);
+
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[0]["model"], "claude-3-haiku");
+        assert_eq!(value[0]["input_cost"], 0.0);
+        assert_eq!(value[1]["input_cost"], 0.3);
+        assert!(!output.contains("meta"));
+
+        // A single result keeps the array shape
+        let single = formatter.format_result(&result("gpt-4", 0.3));
+        assert!(serde_json::from_str::<serde_json::Value>(&single)
+            .unwrap()
+            .is_array());
     }
 }
@@ -1,11 +1,20 @@
 /// Markdown formatter for reports.
 #[cfg(feature = "markdown")]
-use crate::output::{Formatter, TokenResult};
+use crate::output::{
+    BreakEven, Formatter, ModelFailure, ReportMetadata, ResultAggregator, TokenDelta, TokenResult,
+};
+#[cfg(feature = "markdown")]
+use crate::utils::currency::{apply_markup, Currency};
 
 /// Markdown formatter for generating markdown reports.
 #[cfg(feature = "markdown")]
 pub struct MarkdownFormatter {
     show_breakdown: bool,
+    baseline: Option<String>,
+    currency: Currency,
+    markup: Option<f64>,
+    winners: bool,
+    headroom: bool,
 }
 
 #[cfg(feature = "markdown")]
@@ -16,7 +25,64 @@ impl MarkdownFormatter {
     ///
     /// * `show_breakdown` - Whether to show role-based breakdown.
     pub fn new(show_breakdown: bool) -> Self {
-        Self { show_breakdown }
+        Self {
+            show_breakdown,
+            baseline: None,
+            currency: Currency::usd(),
+            markup: None,
+            winners: false,
+            headroom: false,
+        }
+    }
+
+    /// Add a column with each model's token delta against `baseline` to comparisons.
+    pub fn with_baseline(mut self, baseline: Option<String>) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Show costs in `currency` instead of US dollars.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Also show each cost with a markup of `percent` over the provider's price.
+    pub fn with_markup(mut self, percent: Option<f64>) -> Self {
+        self.markup = percent;
+        self
+    }
+
+    /// Show the tokens left in the model's context window, where it is known.
+    pub fn with_headroom(mut self, headroom: bool) -> Self {
+        self.headroom = headroom;
+        self
+    }
+
+    /// Add a section naming the winning models by each criterion to comparisons.
+    pub fn with_winners(mut self, winners: bool) -> Self {
+        self.winners = winners;
+        self
+    }
+
+    /// The winners section, one list item per criterion.
+    fn format_winners(&self, results: &[TokenResult]) -> Vec<String> {
+        let winners = ResultAggregator::new(results).winners();
+        let labeled = winners.labeled(
+            |tokens| tokens.to_string(),
+            |cost| self.currency.format(cost, 4),
+        );
+
+        let mut output = vec![String::new(), "### Winners".to_string(), String::new()];
+        for (label, winner) in labeled {
+            let winner = winner.map_or("n/a".to_string(), |winner| {
+                let models: Vec<String> =
+                    winner.models.iter().map(|m| format!("`{}`", m)).collect();
+                format!("{} ({})", models.join(", "), winner.value)
+            });
+            output.push(format!("- **{}:** {}", label, winner));
+        }
+        output
     }
 }
 
@@ -29,33 +95,132 @@ impl Formatter for MarkdownFormatter {
         output.push(String::new());
         output.push(format!("**Total Tokens:** {}", result.tokens));
         output.push(String::new());
+        if let Some(tokens) = result.response_tokens {
+            output.push(format!("**Response Tokens:** {}", tokens));
+            output.push(String::new());
+        }
+        if let (true, Some(headroom)) = (self.headroom, result.prompt_headroom()) {
+            let over = if headroom < 0 {
+                " (over the context window)"
+            } else {
+                ""
+            };
+            output.push(format!("**Headroom:** {} tokens{}", headroom, over));
+            output.push(String::new());
+        }
 
         if let Some(breakdown) = &result.breakdown {
-            if self.show_breakdown {
+            if self.show_breakdown && result.by_name.is_some() {
+                output.push("### Breakdown by Name".to_string());
+                output.push(String::new());
+                output.push("| Name | Tokens | Share |".to_string());
+                output.push("|------|--------|-------|".to_string());
+                for (name, tokens) in result.participants() {
+                    output.push(format!(
+                        "| {} | {} | {:.1}% |",
+                        name.replace('|', "\\|"),
+                        tokens,
+                        breakdown.percent(tokens)
+                    ));
+                }
+                output.push(String::new());
+            } else if self.show_breakdown {
                 output.push("### Breakdown by Role".to_string());
                 output.push(String::new());
-                output.push("| Role | Tokens |".to_string());
-                output.push("|------|--------|".to_string());
-                output.push(format!("| System | {} |", breakdown.system));
-                output.push(format!("| User | {} |", breakdown.user));
-                output.push(format!("| Assistant | {} |", breakdown.assistant));
+                output.push("| Role | Tokens | Share |".to_string());
+                output.push("|------|--------|-------|".to_string());
+                let tool = (breakdown.tool > 0).then_some(("Tool", breakdown.tool));
+                for (role, tokens) in [
+                    ("System", breakdown.system),
+                    ("User", breakdown.user),
+                    ("Assistant", breakdown.assistant),
+                ]
+                .into_iter()
+                .chain(tool)
+                {
+                    output.push(format!(
+                        "| {} | {} | {:.1}% |",
+                        role,
+                        tokens,
+                        breakdown.percent(tokens)
+                    ));
+                }
                 output.push(String::new());
             }
         }
 
+        if let Some(messages) = result.messages.as_ref().filter(|m| m.len() > 1) {
+            output.push("### Breakdown by Message".to_string());
+            output.push(String::new());
+            if let Some(previews) = &result.message_previews {
+                output.push("| # | Role | Tokens | Content |".to_string());
+                output.push("|---|------|--------|---------|".to_string());
+                for (index, ((role, tokens), preview)) in messages.iter().zip(previews).enumerate()
+                {
+                    output.push(format!(
+                        "| {} | {} | {} | {} |",
+                        index + 1,
+                        role,
+                        tokens,
+                        preview.replace('|', "\\|")
+                    ));
+                }
+            } else {
+                output.push("| # | Role | Tokens |".to_string());
+                output.push("|---|------|--------|".to_string());
+                for (index, (role, tokens)) in messages.iter().enumerate() {
+                    output.push(format!("| {} | {} | {} |", index + 1, role, tokens));
+                }
+            }
+            output.push(String::new());
+        }
+
         if result.input_cost.is_some() || result.output_cost.is_some() {
             output.push("### Cost Estimation".to_string());
             output.push(String::new());
             if let Some(cost) = result.input_cost {
-                output.push(format!("- **Input Cost:** ${:.4}", cost));
+                output.push(format!(
+                    "- **Input Cost:** {}",
+                    self.currency.format(cost, 4)
+                ));
             }
             if let Some(cost) = result.output_cost {
-                output.push(format!("- **Output Cost:** ${:.4}", cost));
+                output.push(format!(
+                    "- **Output Cost:** {}",
+                    self.currency.format(cost, 4)
+                ));
             }
             // Show total cost if both are available
-            if let (Some(input_cost), Some(output_cost)) = (result.input_cost, result.output_cost) {
-                let total_cost = input_cost + output_cost;
-                output.push(format!("- **Total Cost:** ${:.4}", total_cost));
+            if let (Some(_), Some(_), Some(cost)) =
+                (result.input_cost, result.output_cost, result.total_cost())
+            {
+                output.push(format!(
+                    "- **Total Cost:** {}",
+                    self.currency.format(cost, 4)
+                ));
+            }
+            if let (Some(percent), Some(cost)) = (self.markup, result.total_cost()) {
+                output.push(format!(
+                    "- **Cost with {}% Markup:** {}",
+                    percent,
+                    self.currency.format(apply_markup(cost, percent), 4)
+                ));
+            }
+        }
+
+        if let Some(projection) = &result.projection {
+            output.push(String::new());
+            output.push("### Response Projection".to_string());
+            output.push(String::new());
+            output.push(format!(
+                "- **Latency:** {:.1}s at {} tokens/sec",
+                projection.latency_secs, projection.throughput
+            ));
+            if let Some(rate) = projection.cost_per_second() {
+                output.push(format!(
+                    "- **Cost per Second:** {}",
+                    self.currency.format(rate, 6)
+                ));
             }
         }
 
@@ -63,30 +228,123 @@ impl Formatter for MarkdownFormatter {
     }
 
     fn format_comparison(&self, results: &[TokenResult]) -> String {
+        let show_ratio = results.iter().any(|r| r.output_input_ratio().is_some());
+        let deltas = self
+            .baseline
+            .as_deref()
+            .and_then(|baseline| TokenDelta::against(results, baseline));
+
+        let mut header = vec!["Model", "Tokens", "Input Cost", "Output Cost"];
+        if show_ratio {
+            header.push("Out/In");
+        }
+        if deltas.is_some() {
+            header.push("vs Baseline");
+        }
+        let rule: Vec<String> = header.iter().map(|h| "-".repeat(h.len() + 2)).collect();
         let mut output = vec![
             "## Model Comparison".to_string(),
             String::new(),
-            "| Model | Tokens | Input Cost | Output Cost |".to_string(),
-            "|-------|--------|------------|-------------|".to_string(),
+            format!("| {} |", header.join(" | ")),
+            format!("|{}|", rule.join("|")),
         ];
 
-        for result in results {
-            let input_cost = result
-                .input_cost
-                .map(|c| format!("${:.4}", c))
-                .unwrap_or_else(|| "n/a".to_string());
-            let output_cost = result
-                .output_cost
-                .map(|c| format!("${:.4}", c))
-                .unwrap_or_else(|| "n/a".to_string());
+        for (i, result) in results.iter().enumerate() {
+            let mut cells = vec![
+                result.model.clone(),
+                result.tokens.to_string(),
+                result
+                    .input_cost
+                    .map(|c| self.currency.format(c, 4))
+                    .unwrap_or_else(|| "n/a".to_string()),
+                result
+                    .output_cost
+                    .map(|c| self.currency.format(c, 4))
+                    .unwrap_or_else(|| "n/a".to_string()),
+            ];
+            if show_ratio {
+                cells.push(
+                    result
+                        .output_input_ratio()
+                        .map(|r| format!("{:.1}x", r))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                );
+            }
+            if let Some(deltas) = &deltas {
+                if self.baseline.as_deref() == Some(result.model.as_str()) {
+                    cells.push("baseline".to_string());
+                } else {
+                    cells.push(deltas[i].to_string());
+                }
+            }
+            output.push(format!("| {} |", cells.join(" | ")));
+        }
+
+        let aggregate = ResultAggregator::new(results);
+        if aggregate.mixed_encodings() {
+            let encodings: Vec<String> = aggregate
+                .encodings()
+                .into_iter()
+                .map(|encoding| format!("`{}`", encoding.unwrap_or("unknown")))
+                .collect();
+            output.push(String::new());
             output.push(format!(
-                "| {} | {} | {} | {} |",
-                result.model, result.tokens, input_cost, output_cost
+                "> **Note:** models use different encodings ({}), so counts aren't directly comparable",
+                encodings.join(", ")
+            ));
+        }
+
+        if self.winners {
+            output.extend(self.format_winners(results));
+        }
+
+        if let Some(projection) = results.iter().find_map(|r| r.projection) {
+            output.push(String::new());
+            output.push(format!(
+                "**Projected response:** {:.1}s at {} tokens/sec",
+                projection.latency_secs, projection.throughput
             ));
         }
 
         output.join("\n")
     }
+
+    fn format_metadata(&self, metadata: &ReportMetadata) -> String {
+        // An HTML comment keeps the header out of the rendered report
+        format!(
+            "<!--\ntokuin {}\ngenerated: {}\ninput: {}\npricing: {}\n-->",
+            metadata.version, metadata.generated_at, metadata.input, metadata.pricing
+        )
+    }
+
+    fn format_break_even(&self, expected_output: usize, points: &[BreakEven]) -> String {
+        let mut output = vec![
+            format!(
+                "### Break-even (expected output: {} tokens)",
+                expected_output
+            ),
+            String::new(),
+        ];
+        for point in points {
+            output.push(format!("- {}", point.describe(expected_output)));
+        }
+        output.join("\n")
+    }
+
+    fn format_failures(&self, failures: &[ModelFailure]) -> String {
+        let mut output = vec![
+            "### Failed Models".to_string(),
+            String::new(),
+            "| Model | Error |".to_string(),
+            "|-------|-------|".to_string(),
+        ];
+
+        for failure in failures {
+            output.push(format!("| {} | {} |", failure.model, failure.error));
+        }
+
+        output.join("\n")
+    }
 }
 
 #[cfg(test)]
@@ -104,9 +362,70 @@ mod tests {
             input_cost: Some(0.003),
             output_cost: None,
             breakdown: None,
+            ..Default::default()
         };
         let output = formatter.format_result(&result);
         assert!(output.contains("gpt-4"));
         assert!(output.contains("100"));
     }
+
+    #[test]
+    fn test_format_result_per_message_table() {
+        let formatter = MarkdownFormatter::new(false);
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 17,
+            messages: Some(vec![
+                ("system".to_string(), 5),
+                ("user".to_string(), 8),
+                ("assistant".to_string(), 4),
+            ]),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        assert!(output.contains("### Breakdown by Message"));
+        assert!(output.contains("| 1 | system | 5 |"));
+        assert!(output.contains("| 2 | user | 8 |"));
+        assert!(output.contains("| 3 | assistant | 4 |"));
+        assert!(output.contains("**Total Tokens:** 17"));
+    }
+
+    #[test]
+    fn test_format_result_content_preview_column() {
+        let formatter = MarkdownFormatter::new(false);
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 13,
+            messages: Some(vec![("system".to_string(), 5), ("user".to_string(), 8)]),
+            message_previews: Some(vec!["Answer a | b".to_string(), "Which is it…".to_string()]),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        assert!(output.contains("| # | Role | Tokens | Content |"));
+        assert!(output.contains("| 1 | system | 5 | Answer a \\| b |"));
+        assert!(output.contains("| 2 | user | 8 | Which is it… |"));
+    }
+
+    #[test]
+    fn test_format_comparison_notes_mixed_encodings() {
+        let formatter = MarkdownFormatter::new(false);
+        let result = |model: &str, encoding: &str| TokenResult {
+            model: model.to_string(),
+            tokens: 100,
+            encoding: Some(encoding.to_string()),
+            ..Default::default()
+        };
+        let output = formatter.format_comparison(&[
+            result("gpt-4", "cl100k_base"),
+            result("gpt-4o", "o200k_base"),
+        ]);
+        assert!(output
+            .contains("> **Note:** models use different encodings (`cl100k_base`, `o200k_base`)"));
+
+        let output = formatter.format_comparison(&[
+            result("gpt-4", "cl100k_base"),
+            result("gpt-3.5-turbo", "cl100k_base"),
+        ]);
+        assert!(!output.contains("**Note:**"));
+    }
 }
@@ -0,0 +1,166 @@
+/// Formatter for directory-wide minify savings.
+use crate::analyzers::minify::MinifySummary;
+use serde::Serialize;
+
+/// Formatter for minify summaries.
+pub struct MinifyFormatter;
+
+impl MinifyFormatter {
+    /// Format a minify summary as text.
+    pub fn format_text(summary: &MinifySummary) -> String {
+        let mut output = Vec::new();
+
+        output.push(format!(
+            "Minify savings: {} files ({})",
+            summary.files.len(),
+            summary.model
+        ));
+        output.push(String::new());
+        output.push(format!(
+            "{:<40} {:<10} {:<10} {}",
+            "File", "Before", "After", "Saved"
+        ));
+        output.push("-".repeat(72));
+
+        for file in &summary.files {
+            output.push(format!(
+                "{:<40} {:<10} {:<10} {}",
+                file.path.display(),
+                file.savings.original_tokens,
+                file.savings.minified_tokens,
+                file.savings.tokens_saved()
+            ));
+        }
+
+        output.push("-".repeat(72));
+        output.push(format!(
+            "Total: {} -> {} tokens ({} tokens, {} characters saved)",
+            summary.original_tokens(),
+            summary.minified_tokens(),
+            summary.tokens_saved(),
+            summary.chars_saved()
+        ));
+
+        output.join("\n")
+    }
+
+    /// Format a minify summary as JSON.
+    pub fn format_json(summary: &MinifySummary) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct MinifyJson {
+            model: String,
+            files: Vec<FileJson>,
+            original_tokens: usize,
+            minified_tokens: usize,
+            tokens_saved: usize,
+            chars_saved: usize,
+        }
+
+        #[derive(Serialize)]
+        struct FileJson {
+            path: String,
+            original_tokens: usize,
+            minified_tokens: usize,
+            tokens_saved: usize,
+            chars_saved: usize,
+        }
+
+        let json = MinifyJson {
+            model: summary.model.clone(),
+            files: summary
+                .files
+                .iter()
+                .map(|f| FileJson {
+                    path: f.path.display().to_string(),
+                    original_tokens: f.savings.original_tokens,
+                    minified_tokens: f.savings.minified_tokens,
+                    tokens_saved: f.savings.tokens_saved(),
+                    chars_saved: f.savings.chars_saved,
+                })
+                .collect(),
+            original_tokens: summary.original_tokens(),
+            minified_tokens: summary.minified_tokens(),
+            tokens_saved: summary.tokens_saved(),
+            chars_saved: summary.chars_saved(),
+        };
+
+        serde_json::to_string_pretty(&json)
+    }
+
+    /// Format a minify summary as a markdown table.
+    pub fn format_markdown(summary: &MinifySummary) -> String {
+        let mut output = Vec::new();
+
+        output.push("## Minify Savings".to_string());
+        output.push(String::new());
+        output.push(format!("Model: `{}`", summary.model));
+        output.push(String::new());
+        output.push("| File | Before | After | Saved |".to_string());
+        output.push("|------|--------|-------|-------|".to_string());
+
+        for file in &summary.files {
+            output.push(format!(
+                "| `{}` | {} | {} | {} |",
+                file.path.display(),
+                file.savings.original_tokens,
+                file.savings.minified_tokens,
+                file.savings.tokens_saved()
+            ));
+        }
+
+        output.push(format!(
+            "| **Total** | {} | {} | **{}** |",
+            summary.original_tokens(),
+            summary.minified_tokens(),
+            summary.tokens_saved()
+        ));
+
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::minify::{FileSavings, MinifySavings};
+    use std::path::PathBuf;
+
+    fn summary() -> MinifySummary {
+        MinifySummary {
+            model: "gpt-4".to_string(),
+            files: vec![
+                FileSavings {
+                    path: PathBuf::from("a.md"),
+                    savings: MinifySavings {
+                        chars_saved: 12,
+                        original_tokens: 10,
+                        minified_tokens: 7,
+                    },
+                },
+                FileSavings {
+                    path: PathBuf::from("b.md"),
+                    savings: MinifySavings {
+                        chars_saved: 4,
+                        original_tokens: 5,
+                        minified_tokens: 4,
+                    },
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_format_text_total() {
+        let output = MinifyFormatter::format_text(&summary());
+        assert!(output.contains("a.md"));
+        assert!(output.contains("Total: 15 -> 11 tokens (4 tokens, 16 characters saved)"));
+    }
+
+    #[test]
+    fn test_format_json_total() {
+        let output = MinifyFormatter::format_json(&summary()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["tokens_saved"], 4);
+        assert_eq!(value["files"][1]["path"], "b.md");
+    }
+}
@@ -1,20 +1,56 @@
+pub mod aggregate;
+pub mod batch;
+pub mod budget;
+pub mod corpus;
+pub mod examples;
+pub mod explain;
+pub mod grid;
+pub mod histogram;
 pub mod insights;
 pub mod json;
+pub mod model_info;
+pub mod replay;
+pub mod roles;
+#[cfg(feature = "sampling")]
+pub mod sampling;
 /// Output formatters for displaying results.
 pub mod text;
+pub mod warnings;
 
 #[cfg(feature = "markdown")]
 pub mod markdown;
+#[cfg(feature = "markdown")]
+pub mod minify;
 
+pub use crate::models::break_even::BreakEven;
+pub use crate::models::projection::ResponseProjection;
+pub use aggregate::{ResultAggregator, Stats};
+pub use batch::BatchFormatter;
+pub use budget::AllocationFormatter;
+pub use corpus::CorpusFormatter;
+pub use examples::ExamplesFormatter;
+pub use explain::ExplainFormatter;
+pub use histogram::HistogramFormatter;
 pub use insights::InsightsFormatter;
 pub use json::JsonFormatter;
+pub use model_info::{ModelDetails, ModelInfoFormatter};
+pub use replay::ReplayFormatter;
+pub use roles::RolesFormatter;
+#[cfg(feature = "sampling")]
+pub use sampling::SampleFormatter;
 pub use text::TextFormatter;
+pub use warnings::{Warning, WarningKind, Warnings};
 
 #[cfg(feature = "markdown")]
 pub use markdown::MarkdownFormatter;
+#[cfg(feature = "markdown")]
+pub use minify::MinifyFormatter;
+
+use serde::Serialize;
+use std::collections::HashMap;
 
 /// Token count breakdown by role.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct TokenBreakdown {
     /// Token count for system messages.
     pub system: usize,
@@ -22,6 +58,9 @@ pub struct TokenBreakdown {
     pub user: usize,
     /// Token count for assistant messages.
     pub assistant: usize,
+    /// Token count for tool result messages.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub tool: usize,
     /// Total token count.
     pub total: usize,
 }
@@ -33,9 +72,26 @@ impl TokenBreakdown {
             system: 0,
             user: 0,
             assistant: 0,
+            tool: 0,
             total: 0,
         }
     }
+
+    /// Share of the total that `count` represents, as a percentage.
+    ///
+    /// Returns 0 when the total is zero.
+    pub fn percent(&self, count: usize) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// Whether a count is zero, to leave empty buckets out of serialized output.
+fn is_zero(count: &usize) -> bool {
+    *count == 0
 }
 
 impl Default for TokenBreakdown {
@@ -44,19 +100,242 @@ impl Default for TokenBreakdown {
     }
 }
 
+impl std::fmt::Display for TokenBreakdown {
+    /// Formats as `system: N (X.X%), user: N (X.X%), assistant: N (X.X%), total: N`,
+    /// with `tool: N (X.X%)` before the total if there are tool tokens.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "system: {} ({:.1}%), user: {} ({:.1}%), assistant: {} ({:.1}%), ",
+            self.system,
+            self.percent(self.system),
+            self.user,
+            self.percent(self.user),
+            self.assistant,
+            self.percent(self.assistant),
+        )?;
+        if self.tool > 0 {
+            write!(f, "tool: {} ({:.1}%), ", self.tool, self.percent(self.tool))?;
+        }
+        write!(f, "total: {}", self.total)
+    }
+}
+
+impl std::ops::AddAssign<&TokenBreakdown> for TokenBreakdown {
+    fn add_assign(&mut self, other: &TokenBreakdown) {
+        self.system += other.system;
+        self.user += other.user;
+        self.assistant += other.assistant;
+        self.tool += other.tool;
+        self.total += other.total;
+    }
+}
+
 /// Result of token counting.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct TokenResult {
     /// The model name.
     pub model: String,
-    /// Total token count.
+    /// Total token count (`content_tokens + overhead_tokens`).
     pub tokens: usize,
+    /// Tokens from message content.
+    pub content_tokens: usize,
+    /// Chat protocol overhead tokens (per-message framing and reply priming).
+    pub overhead_tokens: usize,
     /// Cost for input tokens (in USD).
     pub input_cost: Option<f64>,
     /// Cost for output tokens (in USD).
     pub output_cost: Option<f64>,
     /// Breakdown by role (if available).
     pub breakdown: Option<TokenBreakdown>,
+    /// Tokens per participant name, with unnamed messages under their role
+    /// (if requested).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_name: Option<HashMap<String, usize>>,
+    /// Input price per 1K tokens used for the cost (in USD).
+    pub input_rate: Option<f64>,
+    /// Output price per 1K tokens used for the cost (in USD).
+    pub output_rate: Option<f64>,
+    /// Expected output tokens the output cost was projected for (if given).
+    pub expected_output: Option<usize>,
+    /// Completion tokens counted from a recorded response (with `--response`);
+    /// the output cost is then for these tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_tokens: Option<usize>,
+    /// Context window of the model in tokens (if known).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    /// Per-message `(role, tokens)` counts, in input order (if available).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<(String, usize)>>,
+    /// Shortened content of each message in `messages`, for display (with
+    /// `--content-preview`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_previews: Option<Vec<String>>,
+    /// Token IDs of the content, in order (if requested).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_ids: Option<Vec<usize>>,
+    /// Whether `token_ids` was cut off at the emit limit.
+    pub token_ids_truncated: bool,
+    /// Whether the tokenizer only approximates the model's tokenization.
+    pub approximate: bool,
+    /// Encoding the tokens were counted with, e.g. `cl100k_base` (if known).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Projected response latency and cost (with `--throughput`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projection: Option<ResponseProjection>,
+}
+
+impl TokenResult {
+    /// Input plus output cost, if either is known.
+    pub fn total_cost(&self) -> Option<f64> {
+        match (self.input_cost, self.output_cost) {
+            (None, None) => None,
+            (input, output) => Some(input.unwrap_or(0.0) + output.unwrap_or(0.0)),
+        }
+    }
+
+    /// Participants from `by_name`, most tokens first (ties by name).
+    pub fn participants(&self) -> Vec<(&str, usize)> {
+        let mut participants: Vec<(&str, usize)> = self
+            .by_name
+            .iter()
+            .flatten()
+            .map(|(name, &tokens)| (name.as_str(), tokens))
+            .collect();
+        participants.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        participants
+    }
+
+    /// Tokens left in the context window after the prompt and the expected
+    /// output; negative when they don't fit.
+    pub fn headroom(&self) -> Option<i64> {
+        let used = self.tokens + self.expected_output.unwrap_or(0);
+        Some(self.context_window? as i64 - used as i64)
+    }
+
+    /// Tokens left in the context window after the prompt alone, i.e. the
+    /// room for the completion; negative when the prompt doesn't fit.
+    pub fn prompt_headroom(&self) -> Option<i64> {
+        Some(self.context_window? as i64 - self.tokens as i64)
+    }
+
+    /// Ratio of the output rate to the input rate, if both are known.
+    pub fn output_input_ratio(&self) -> Option<f64> {
+        crate::models::break_even::output_input_ratio(self.input_rate?, self.output_rate?)
+    }
+}
+
+impl std::fmt::Display for TokenResult {
+    /// Formats as `MODEL: N tokens`, followed by the total cost in USD if known.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} tokens", self.model, self.tokens)?;
+        if let Some(cost) = self.total_cost() {
+            write!(f, " (${:.4})", cost)?;
+        }
+        Ok(())
+    }
+}
+
+/// Signed token difference of a result against a baseline result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenDelta {
+    /// Tokens more (positive) or fewer (negative) than the baseline.
+    pub tokens: i64,
+    /// The difference as a percentage of the baseline's tokens (0 if it has none).
+    pub percent: f64,
+}
+
+impl TokenDelta {
+    /// Delta of `result` relative to `baseline`.
+    pub fn between(baseline: &TokenResult, result: &TokenResult) -> Self {
+        let tokens = result.tokens as i64 - baseline.tokens as i64;
+        let percent = if baseline.tokens == 0 {
+            0.0
+        } else {
+            tokens as f64 * 100.0 / baseline.tokens as f64
+        };
+        Self { tokens, percent }
+    }
+
+    /// Delta of every result against the one named `baseline`.
+    ///
+    /// Returns `None` if no result is for the baseline model.
+    pub fn against(results: &[TokenResult], baseline: &str) -> Option<Vec<Self>> {
+        let base = results.iter().find(|r| r.model == baseline)?;
+        Some(results.iter().map(|r| Self::between(base, r)).collect())
+    }
+}
+
+impl std::fmt::Display for TokenDelta {
+    /// Formats as `+N (+X.X%)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+} ({:+.1}%)", self.tokens, self.percent)
+    }
+}
+
+/// Provenance of a report, included with `--with-metadata`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportMetadata {
+    /// Version of tokuin that produced the report.
+    pub version: String,
+    /// When the report was generated, as an RFC 3339 UTC timestamp.
+    pub generated_at: String,
+    /// Where the input came from: a file path or `stdin`.
+    pub input: String,
+    /// Where prices came from: `defaults` or the pricing file path.
+    pub pricing: String,
+}
+
+impl ReportMetadata {
+    /// Metadata for a report generated now.
+    pub fn new(input: impl Into<String>, pricing_file: Option<&str>) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: utc_timestamp(now),
+            input: input.into(),
+            pricing: pricing_file.unwrap_or("defaults").to_string(),
+        }
+    }
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`.
+fn utc_timestamp(secs: u64) -> String {
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// A model that could not be processed during a comparison.
+#[derive(Debug, Clone)]
+pub struct ModelFailure {
+    /// The model name as requested.
+    pub model: String,
+    /// Description of the error.
+    pub error: String,
 }
 
 /// Trait for formatting token results.
@@ -66,4 +345,125 @@ pub trait Formatter {
 
     /// Format multiple results (for comparison).
     fn format_comparison(&self, results: &[TokenResult]) -> String;
+
+    /// Format break-even points between compared models.
+    ///
+    /// Returns an empty string when the format has no place for the section.
+    fn format_break_even(&self, expected_output: usize, points: &[BreakEven]) -> String {
+        let mut output = vec![format!(
+            "Break-even (expected output: {} tokens):",
+            expected_output
+        )];
+        for point in points {
+            output.push(format!("  {}", point.describe(expected_output)));
+        }
+        output.join("\n")
+    }
+
+    /// Format a metadata header to print before the report.
+    ///
+    /// Returns an empty string when the format embeds metadata in the
+    /// report itself.
+    fn format_metadata(&self, metadata: &ReportMetadata) -> String {
+        format!(
+            "# tokuin {}\n# generated: {}\n# input: {}\n# pricing: {}",
+            metadata.version, metadata.generated_at, metadata.input, metadata.pricing
+        )
+    }
+
+    /// Format the list of models that failed during a comparison.
+    fn format_failures(&self, failures: &[ModelFailure]) -> String {
+        let mut output = vec!["Failed models:".to_string()];
+        for failure in failures {
+            output.push(format!("  {}: {}", failure.model, failure.error));
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_result_serializes() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 40,
+            content_tokens: 33,
+            overhead_tokens: 7,
+            input_cost: Some(0.0012),
+            breakdown: Some(TokenBreakdown {
+                system: 10,
+                user: 20,
+                assistant: 10,
+                tool: 0,
+                total: 40,
+            }),
+            ..Default::default()
+        };
+
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(value["model"], "gpt-4");
+        assert_eq!(value["tokens"], 40);
+        assert_eq!(value["content_tokens"], 33);
+        assert_eq!(value["overhead_tokens"], 7);
+        assert_eq!(value["input_cost"], 0.0012);
+        assert_eq!(value["output_cost"], serde_json::Value::Null);
+        assert_eq!(
+            value["breakdown"],
+            serde_json::json!({"system": 10, "user": 20, "assistant": 10, "total": 40})
+        );
+        assert_eq!(value["approximate"], false);
+        // Unset optional details are left out
+        for key in ["messages", "token_ids", "projection"] {
+            assert!(value.get(key).is_none(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_headroom_under_and_over_window() {
+        let result = |tokens| TokenResult {
+            tokens,
+            context_window: Some(8192),
+            expected_output: Some(500),
+            ..Default::default()
+        };
+        assert_eq!(result(1034).prompt_headroom(), Some(7158));
+        assert_eq!(result(9000).prompt_headroom(), Some(-808));
+        assert_eq!(TokenResult::default().prompt_headroom(), None);
+        // The winners' headroom also leaves room for the expected output
+        assert_eq!(result(1034).headroom(), Some(6658));
+    }
+
+    #[test]
+    fn test_display() {
+        let breakdown = TokenBreakdown {
+            system: 10,
+            user: 20,
+            assistant: 10,
+            tool: 0,
+            total: 40,
+        };
+        assert_eq!(
+            breakdown.to_string(),
+            "system: 10 (25.0%), user: 20 (50.0%), assistant: 10 (25.0%), total: 40"
+        );
+
+        let mut result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 40,
+            ..Default::default()
+        };
+        assert_eq!(result.to_string(), "gpt-4: 40 tokens");
+        result.input_cost = Some(0.0012);
+        assert_eq!(result.to_string(), "gpt-4: 40 tokens ($0.0012)");
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_timestamp(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
 }
@@ -0,0 +1,190 @@
+/// Formatter for `--model-info` details of a single model.
+use crate::utils::currency::Currency;
+use crate::utils::numbers::group_digits;
+use serde::Serialize;
+
+/// What tokuin knows about one model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelDetails {
+    /// Model name as resolved by the registry.
+    pub model: String,
+    /// Provider, or `None` for models outside the registry (e.g. `chars`).
+    pub provider: Option<String>,
+    /// Vocabulary or encoding, e.g. `cl100k_base`, if known.
+    pub encoding: Option<String>,
+    /// Whether counts are exact rather than approximated.
+    pub exact: bool,
+    /// Context window in tokens, if known.
+    pub context_window: Option<usize>,
+    /// Input price per 1K tokens (USD).
+    pub input_price: Option<f64>,
+    /// Output price per 1K tokens (USD).
+    pub output_price: Option<f64>,
+    /// Where the prices came from: `defaults` or the pricing file path.
+    pub pricing_source: Option<String>,
+}
+
+/// Formatter for `--model-info`.
+pub struct ModelInfoFormatter;
+
+impl ModelInfoFormatter {
+    /// Format the details as aligned `Label: value` lines, with prices in
+    /// `currency`.
+    pub fn format_text(details: &ModelDetails, currency: &Currency) -> String {
+        let unknown = || "unknown".to_string();
+        let price = |price: Option<f64>| {
+            price.map_or(unknown(), |p| {
+                format!("{} / 1K tokens", currency.format_rate(p))
+            })
+        };
+        [
+            ("Model", details.model.clone()),
+            ("Provider", details.provider.clone().unwrap_or_else(unknown)),
+            ("Encoding", details.encoding.clone().unwrap_or_else(unknown)),
+            (
+                "Exact counts",
+                if details.exact {
+                    "yes"
+                } else {
+                    "no (approximate)"
+                }
+                .to_string(),
+            ),
+            (
+                "Context window",
+                details.context_window.map_or(unknown(), |tokens| {
+                    format!("{} tokens", group_digits(tokens, Some(',')))
+                }),
+            ),
+            ("Input price", price(details.input_price)),
+            ("Output price", price(details.output_price)),
+            (
+                "Pricing source",
+                details
+                    .pricing_source
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+            ),
+        ]
+        .iter()
+        .map(|(label, value)| format!("{:<16}{}", format!("{}:", label), value))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    /// Format the details as a JSON object, with prices in `currency`.
+    pub fn format_json(
+        details: &ModelDetails,
+        currency: &Currency,
+    ) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct DetailsJson<'a> {
+            #[serde(flatten)]
+            details: ModelDetails,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            currency: Option<&'a str>,
+        }
+
+        let convert = |price: Option<f64>| price.map(|p| currency.convert(p));
+        let json = DetailsJson {
+            details: ModelDetails {
+                input_price: convert(details.input_price),
+                output_price: convert(details.output_price),
+                ..details.clone()
+            },
+            currency: details
+                .input_price
+                .or(details.output_price)
+                .map(|_| currency.code.as_str()),
+        };
+        serde_json::to_string_pretty(&json)
+    }
+
+    /// Format the details as a markdown table, with prices in `currency`.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(details: &ModelDetails, currency: &Currency) -> String {
+        let text = Self::format_text(details, currency);
+        let mut output = vec![
+            format!("## {}", details.model),
+            String::new(),
+            "| Field | Value |".to_string(),
+            "|-------|-------|".to_string(),
+        ];
+        output.extend(text.lines().skip(1).filter_map(|line| {
+            line.split_once(':')
+                .map(|(label, value)| format!("| {} | {} |", label, value.trim()))
+        }));
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details() -> ModelDetails {
+        ModelDetails {
+            model: "gpt-4".to_string(),
+            provider: Some("openai".to_string()),
+            encoding: Some("cl100k_base".to_string()),
+            exact: true,
+            context_window: Some(8192),
+            input_price: Some(0.03),
+            output_price: Some(0.06),
+            pricing_source: Some("defaults".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = ModelInfoFormatter::format_text(&details(), &Currency::usd());
+        assert_eq!(
+            output,
+            "Model:          gpt-4\n\
+             Provider:       openai\n\
+             Encoding:       cl100k_base\n\
+             Exact counts:   yes\n\
+             Context window: 8,192 tokens\n\
+             Input price:    $0.03 / 1K tokens\n\
+             Output price:   $0.06 / 1K tokens\n\
+             Pricing source: defaults"
+        );
+    }
+
+    #[test]
+    fn test_format_text_unknowns() {
+        let output = ModelInfoFormatter::format_text(
+            &ModelDetails {
+                model: "chars".to_string(),
+                provider: None,
+                encoding: None,
+                exact: true,
+                context_window: None,
+                input_price: None,
+                output_price: None,
+                pricing_source: None,
+            },
+            &Currency::usd(),
+        );
+        assert!(output.contains("Provider:       unknown"));
+        assert!(output.contains("Input price:    unknown"));
+        assert!(output.contains("Pricing source: none"));
+    }
+
+    #[test]
+    fn test_prices_in_currency() {
+        let eur = Currency::new("EUR", 0.5).unwrap();
+        let output = ModelInfoFormatter::format_text(&details(), &eur);
+        assert!(
+            output.contains("Input price:    €0.015 / 1K tokens"),
+            "{}",
+            output
+        );
+
+        let output = ModelInfoFormatter::format_json(&details(), &eur).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["output_price"], 0.03);
+        assert_eq!(value["currency"], "EUR");
+        assert_eq!(value["model"], "gpt-4");
+    }
+}
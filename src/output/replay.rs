@@ -0,0 +1,208 @@
+/// Formatter for per-message replay costs.
+use crate::analyzers::replay::{Billed, ReplayCost};
+use crate::utils::currency::Currency;
+use serde::Serialize;
+
+impl Billed {
+    fn label(self) -> &'static str {
+        match self {
+            Billed::Input => "input",
+            Billed::Output => "output",
+        }
+    }
+}
+
+/// Formatter for `--replay-cost` reports.
+pub struct ReplayFormatter;
+
+impl ReplayFormatter {
+    /// Format one cost line per message, followed by the input, output and
+    /// total cost, in `currency`.
+    pub fn format_text(replay: &ReplayCost, currency: &Currency) -> String {
+        let mut output = vec![
+            format!(
+                "Replay cost: {} messages ({})",
+                replay.messages.len(),
+                replay.model
+            ),
+            String::new(),
+            format!(
+                "{:<5} {:<12} {:<10} {:<8} {}",
+                "#", "Role", "Tokens", "Billed", "Cost"
+            ),
+            "-".repeat(50),
+        ];
+
+        for (i, message) in replay.messages.iter().enumerate() {
+            output.push(format!(
+                "{:<5} {:<12} {:<10} {:<8} {}",
+                i + 1,
+                message.role,
+                message.tokens,
+                message.billed.label(),
+                currency.format(message.cost, 6)
+            ));
+        }
+
+        output.push("-".repeat(50));
+        for (label, side, rate) in [
+            ("Input:", Billed::Input, replay.input_rate),
+            ("Output:", Billed::Output, replay.output_rate),
+        ] {
+            output.push(format!(
+                "{:<8} {} tokens at {}/1K = {}",
+                label,
+                replay.tokens(side),
+                currency.format_rate(rate),
+                currency.format(replay.cost(side), 6)
+            ));
+        }
+        output.push(format!(
+            "{:<8} {}",
+            "Total:",
+            currency.format(replay.total(), 6)
+        ));
+
+        output.join("\n")
+    }
+
+    /// Format the report as JSON, with every message's cost and the totals
+    /// in `currency`.
+    pub fn format_json(
+        replay: &ReplayCost,
+        currency: &Currency,
+    ) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct ReplayJson<'a> {
+            model: &'a str,
+            currency: &'a str,
+            input_rate: f64,
+            output_rate: f64,
+            messages: Vec<MessageJson<'a>>,
+            input_tokens: usize,
+            output_tokens: usize,
+            input_cost: f64,
+            output_cost: f64,
+            total_cost: f64,
+        }
+
+        #[derive(Serialize)]
+        struct MessageJson<'a> {
+            role: &'a str,
+            tokens: usize,
+            billed: &'static str,
+            cost: f64,
+        }
+
+        let json = ReplayJson {
+            model: &replay.model,
+            currency: &currency.code,
+            input_rate: currency.convert(replay.input_rate),
+            output_rate: currency.convert(replay.output_rate),
+            messages: replay
+                .messages
+                .iter()
+                .map(|m| MessageJson {
+                    role: &m.role,
+                    tokens: m.tokens,
+                    billed: m.billed.label(),
+                    cost: currency.convert(m.cost),
+                })
+                .collect(),
+            input_tokens: replay.tokens(Billed::Input),
+            output_tokens: replay.tokens(Billed::Output),
+            input_cost: currency.convert(replay.cost(Billed::Input)),
+            output_cost: currency.convert(replay.cost(Billed::Output)),
+            total_cost: currency.convert(replay.total()),
+        };
+
+        serde_json::to_string_pretty(&json)
+    }
+
+    /// Format the report as a markdown table, with costs in `currency`.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(replay: &ReplayCost, currency: &Currency) -> String {
+        let mut output = vec![
+            "## Replay Cost".to_string(),
+            String::new(),
+            format!("Model: `{}`", replay.model),
+            String::new(),
+            "| # | Role | Tokens | Billed | Cost |".to_string(),
+            "|---|------|--------|--------|------|".to_string(),
+        ];
+
+        for (i, message) in replay.messages.iter().enumerate() {
+            output.push(format!(
+                "| {} | {} | {} | {} | {} |",
+                i + 1,
+                message.role,
+                message.tokens,
+                message.billed.label(),
+                currency.format(message.cost, 6)
+            ));
+        }
+        output.push(format!(
+            "| | **Total** | {} | | **{}** |",
+            replay.tokens(Billed::Input) + replay.tokens(Billed::Output),
+            currency.format(replay.total(), 6)
+        ));
+
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::replay::MessageCost;
+
+    fn replay() -> ReplayCost {
+        let message = |role: &str, tokens, billed, cost| MessageCost {
+            role: role.to_string(),
+            tokens,
+            billed,
+            cost,
+        };
+        ReplayCost {
+            model: "gpt-4".to_string(),
+            input_rate: 0.03,
+            output_rate: 0.06,
+            messages: vec![
+                message("user", 100, Billed::Input, 0.003),
+                message("assistant", 50, Billed::Output, 0.003),
+                message("tool", 200, Billed::Input, 0.006),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_format_text_lists_each_message() {
+        let output = ReplayFormatter::format_text(&replay(), &Currency::usd());
+        assert!(output.contains("Replay cost: 3 messages (gpt-4)"));
+        assert!(output.contains("3     tool         200        input    $0.006000"));
+        assert!(output.contains("Output:  50 tokens at $0.06/1K = $0.003000"));
+        assert!(output.contains("Total:   $0.012000"));
+    }
+
+    #[test]
+    fn test_format_json_totals() {
+        let output = ReplayFormatter::format_json(&replay(), &Currency::usd()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["messages"][1]["billed"], "output");
+        assert_eq!(value["input_tokens"], 300);
+        assert!((value["total_cost"].as_f64().unwrap() - 0.012).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_costs_in_currency() {
+        let eur = Currency::new("EUR", 0.5).unwrap();
+        let output = ReplayFormatter::format_text(&replay(), &eur);
+        assert!(output.contains("Output:  50 tokens at €0.03/1K = €0.001500"));
+        assert!(output.contains("Total:   €0.006000"));
+
+        let output = ReplayFormatter::format_json(&replay(), &eur).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["currency"], "EUR");
+        assert!((value["total_cost"].as_f64().unwrap() - 0.006).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,222 @@
+/// Formatter for role breakdowns aggregated across files.
+use crate::analyzers::roles::RoleSummary;
+use crate::output::TokenBreakdown;
+use serde::Serialize;
+
+/// Formatter for `--group-by-role` summaries.
+pub struct RolesFormatter;
+
+impl RolesFormatter {
+    /// Format each model's aggregate breakdown as text, preceded by one row
+    /// per file when `per_file` is set.
+    pub fn format_text(summaries: &[RoleSummary], per_file: bool) -> String {
+        summaries
+            .iter()
+            .map(|summary| Self::summary_text(summary, per_file))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn summary_text(summary: &RoleSummary, per_file: bool) -> String {
+        let total = summary.total();
+        let mut output = vec![
+            format!(
+                "Role breakdown: {} files ({})",
+                summary.files.len(),
+                summary.model
+            ),
+            String::new(),
+        ];
+
+        if per_file {
+            output.push(format!(
+                "{:<40} {:<10} {:<10} {:<10} {}",
+                "File", "System", "User", "Assistant", "Total"
+            ));
+            output.push("-".repeat(80));
+            for file in &summary.files {
+                let b = &file.breakdown;
+                output.push(format!(
+                    "{:<40} {:<10} {:<10} {:<10} {}",
+                    file.path.display(),
+                    b.system,
+                    b.user,
+                    b.assistant,
+                    b.total
+                ));
+            }
+            output.push("-".repeat(80));
+        }
+
+        let tool = (total.tool > 0).then_some(("Tool", total.tool));
+        for (role, tokens) in [
+            ("System", total.system),
+            ("User", total.user),
+            ("Assistant", total.assistant),
+        ]
+        .into_iter()
+        .chain(tool)
+        {
+            output.push(format!(
+                "{:<10} {} tokens ({:.1}%)",
+                format!("{}:", role),
+                tokens,
+                total.percent(tokens)
+            ));
+        }
+        output.push(format!("{:<10} {} tokens", "Total:", total.total));
+
+        output.join("\n")
+    }
+
+    /// Format the summaries as JSON, with every file's breakdown and the
+    /// total: an object for one model, an array for several.
+    pub fn format_json(summaries: &[RoleSummary]) -> Result<String, serde_json::Error> {
+        let value = match summaries {
+            [summary] => Self::summary_json(summary)?,
+            _ => serde_json::Value::Array(
+                summaries
+                    .iter()
+                    .map(Self::summary_json)
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        serde_json::to_string_pretty(&value)
+    }
+
+    fn summary_json(summary: &RoleSummary) -> Result<serde_json::Value, serde_json::Error> {
+        #[derive(Serialize)]
+        struct RolesJson<'a> {
+            model: String,
+            files: Vec<FileJson<'a>>,
+            total: TokenBreakdown,
+        }
+
+        #[derive(Serialize)]
+        struct FileJson<'a> {
+            path: String,
+            #[serde(flatten)]
+            breakdown: &'a TokenBreakdown,
+        }
+
+        let json = RolesJson {
+            model: summary.model.clone(),
+            files: summary
+                .files
+                .iter()
+                .map(|f| FileJson {
+                    path: f.path.display().to_string(),
+                    breakdown: &f.breakdown,
+                })
+                .collect(),
+            total: summary.total(),
+        };
+
+        serde_json::to_value(&json)
+    }
+
+    /// Format each model's summary as a markdown table, with a row per file
+    /// when `per_file` is set.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(summaries: &[RoleSummary], per_file: bool) -> String {
+        summaries
+            .iter()
+            .map(|summary| Self::summary_markdown(summary, per_file))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    #[cfg(feature = "markdown")]
+    fn summary_markdown(summary: &RoleSummary, per_file: bool) -> String {
+        let mut output = vec![
+            "## Role Breakdown".to_string(),
+            String::new(),
+            format!("Model: `{}`", summary.model),
+            String::new(),
+            "| File | System | User | Assistant | Total |".to_string(),
+            "|------|--------|------|-----------|-------|".to_string(),
+        ];
+
+        if per_file {
+            for file in &summary.files {
+                let b = &file.breakdown;
+                output.push(format!(
+                    "| `{}` | {} | {} | {} | {} |",
+                    file.path.display(),
+                    b.system,
+                    b.user,
+                    b.assistant,
+                    b.total
+                ));
+            }
+        }
+
+        let total = summary.total();
+        output.push(format!(
+            "| **Total ({} files)** | {} | {} | {} | **{}** |",
+            summary.files.len(),
+            total.system,
+            total.user,
+            total.assistant,
+            total.total
+        ));
+
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::roles::FileBreakdown;
+    use std::path::PathBuf;
+
+    fn summary() -> RoleSummary {
+        let file = |path: &str, system, user, assistant| FileBreakdown {
+            path: PathBuf::from(path),
+            breakdown: TokenBreakdown {
+                system,
+                user,
+                assistant,
+                tool: 0,
+                total: system + user + assistant,
+            },
+        };
+        RoleSummary {
+            model: "gpt-4".to_string(),
+            files: vec![file("a.json", 10, 20, 30), file("b.json", 0, 15, 25)],
+        }
+    }
+
+    #[test]
+    fn test_format_text_aggregate() {
+        let output = RolesFormatter::format_text(&[summary()], false);
+        assert!(output.contains("Role breakdown: 2 files (gpt-4)"));
+        assert!(output.contains("User:      35 tokens (35.0%)"));
+        assert!(output.contains("Total:     100 tokens"));
+        assert!(!output.contains("a.json"));
+
+        let output = RolesFormatter::format_text(&[summary()], true);
+        assert!(output.contains("a.json"));
+    }
+
+    #[test]
+    fn test_format_json_files_and_total() {
+        let output = RolesFormatter::format_json(&[summary()]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["total"]["assistant"], 55);
+        assert_eq!(value["files"][1]["path"], "b.json");
+        assert_eq!(value["files"][1]["user"], 15);
+    }
+
+    #[test]
+    fn test_format_json_one_entry_per_model() {
+        let mut other = summary();
+        other.model = "claude-3-sonnet".to_string();
+        let output = RolesFormatter::format_json(&[summary(), other]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["model"], "gpt-4");
+        assert_eq!(value[1]["model"], "claude-3-sonnet");
+        assert_eq!(value[1]["total"]["total"], 100);
+    }
+}
@@ -0,0 +1,138 @@
+/// Formatter for token estimates from sampled chunks.
+use crate::analyzers::sampling::SampledEstimate;
+use crate::utils::numbers::group_digits;
+use serde::Serialize;
+
+/// JSON representation of a sampled estimate.
+#[derive(Debug, Serialize)]
+struct SampledJson<'a> {
+    model: &'a str,
+    estimated_tokens: usize,
+    exact: bool,
+    sampled_tokens: usize,
+    sampled_bytes: usize,
+    total_bytes: usize,
+    sampled_chunks: usize,
+    total_chunks: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+/// Formatter for token estimates extrapolated from a sample.
+pub struct SampleFormatter;
+
+impl SampleFormatter {
+    /// Describe how an estimate was sampled, e.g. `estimated from 8 of 120 chunks, seed 42`.
+    fn describe(estimate: &SampledEstimate, seed: Option<u64>) -> String {
+        let mut description = if estimate.is_exact() {
+            format!("all {} chunks counted", estimate.total_chunks)
+        } else {
+            format!(
+                "estimated from {} of {} chunks",
+                estimate.sampled_chunks, estimate.total_chunks
+            )
+        };
+        if let Some(seed) = seed {
+            description.push_str(&format!(", seed {}", seed));
+        }
+        description
+    }
+
+    /// Format sampled estimates as text, one block per model.
+    pub fn format_text(estimates: &[SampledEstimate], seed: Option<u64>) -> String {
+        estimates
+            .iter()
+            .map(|e| {
+                format!(
+                    "Model: {}\nTokens: {}{} ({})",
+                    e.model,
+                    if e.is_exact() { "" } else { "~" },
+                    group_digits(e.tokens, Some(',')),
+                    Self::describe(e, seed)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Format sampled estimates as JSON: an object for one model, an array for several.
+    pub fn format_json(
+        estimates: &[SampledEstimate],
+        seed: Option<u64>,
+    ) -> Result<String, serde_json::Error> {
+        let json: Vec<SampledJson> = estimates
+            .iter()
+            .map(|e| SampledJson {
+                model: &e.model,
+                estimated_tokens: e.tokens,
+                exact: e.is_exact(),
+                sampled_tokens: e.sampled_tokens,
+                sampled_bytes: e.sampled_bytes,
+                total_bytes: e.total_bytes,
+                sampled_chunks: e.sampled_chunks,
+                total_chunks: e.total_chunks,
+                seed,
+            })
+            .collect();
+        match json.as_slice() {
+            [single] => serde_json::to_string_pretty(single),
+            _ => serde_json::to_string_pretty(&json),
+        }
+    }
+
+    /// Format sampled estimates as a markdown table.
+    #[cfg(feature = "markdown")]
+    pub fn format_markdown(estimates: &[SampledEstimate], seed: Option<u64>) -> String {
+        let mut output = vec![
+            "## Sampled Estimate".to_string(),
+            String::new(),
+            "| Model | Tokens | Sample |".to_string(),
+            "|-------|--------|--------|".to_string(),
+        ];
+        for e in estimates {
+            output.push(format!(
+                "| {} | {}{} | {} |",
+                e.model,
+                if e.is_exact() { "" } else { "~" },
+                group_digits(e.tokens, Some(',')),
+                Self::describe(e, seed)
+            ));
+        }
+        output.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate() -> SampledEstimate {
+        SampledEstimate {
+            model: "gpt-4".to_string(),
+            tokens: 123_456,
+            sampled_tokens: 16_000,
+            sampled_bytes: 65_536,
+            total_bytes: 505_000,
+            sampled_chunks: 8,
+            total_chunks: 62,
+        }
+    }
+
+    #[test]
+    fn test_format_text() {
+        let output = SampleFormatter::format_text(&[estimate()], Some(42));
+        assert_eq!(
+            output,
+            "Model: gpt-4\nTokens: ~123,456 (estimated from 8 of 62 chunks, seed 42)"
+        );
+    }
+
+    #[test]
+    fn test_format_json() {
+        let output = SampleFormatter::format_json(&[estimate()], None).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["estimated_tokens"], 123_456);
+        assert_eq!(value["exact"], false);
+        assert!(value.get("seed").is_none());
+    }
+}
@@ -1,9 +1,19 @@
 /// Text formatter for human-readable output.
-use crate::output::{Formatter, TokenResult};
+use crate::output::{Formatter, ModelFailure, ResultAggregator, TokenDelta, TokenResult};
+use crate::utils::color::{paint, BOLD, GREEN, RED};
+use crate::utils::currency::{apply_markup, Currency};
+use crate::utils::numbers::group_digits;
 
 /// Text formatter for human-readable output.
 pub struct TextFormatter {
     show_breakdown: bool,
+    color: bool,
+    separator: Option<char>,
+    baseline: Option<String>,
+    currency: Currency,
+    markup: Option<f64>,
+    winners: bool,
+    headroom: bool,
 }
 
 impl TextFormatter {
@@ -13,7 +23,123 @@ impl TextFormatter {
     ///
     /// * `show_breakdown` - Whether to show role-based breakdown.
     pub fn new(show_breakdown: bool) -> Self {
-        Self { show_breakdown }
+        Self {
+            show_breakdown,
+            color: false,
+            separator: Some(','),
+            baseline: None,
+            currency: Currency::usd(),
+            markup: None,
+            winners: false,
+            headroom: false,
+        }
+    }
+
+    /// Enable or disable ANSI color in the output.
+    ///
+    /// Callers should decide via [`crate::utils::color::stdout_color_enabled`]
+    /// so that `NO_COLOR` and `--no-color` are honored consistently.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the thousands separator for token counts, or `None` to disable grouping.
+    pub fn with_separator(mut self, separator: Option<char>) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Add a column with each model's token delta against `baseline` to comparisons.
+    pub fn with_baseline(mut self, baseline: Option<String>) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Show costs in `currency` instead of US dollars.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Also show each cost with a markup of `percent` over the provider's price.
+    pub fn with_markup(mut self, percent: Option<f64>) -> Self {
+        self.markup = percent;
+        self
+    }
+
+    /// Show the tokens left in the model's context window, where it is known.
+    pub fn with_headroom(mut self, headroom: bool) -> Self {
+        self.headroom = headroom;
+        self
+    }
+
+    /// Name the winning models by each criterion below comparisons.
+    pub fn with_winners(mut self, winners: bool) -> Self {
+        self.winners = winners;
+        self
+    }
+
+    fn money(&self, usd: f64, decimals: usize) -> String {
+        self.currency.format(usd, decimals)
+    }
+
+    /// Whether `usd` is below `threshold` once converted to the output currency.
+    fn below(&self, usd: f64, threshold: f64) -> bool {
+        self.currency.convert(usd) < threshold
+    }
+
+    fn count(&self, value: usize) -> String {
+        group_digits(value, self.separator)
+    }
+
+    fn delta(&self, delta: &TokenDelta) -> String {
+        let sign = if delta.tokens < 0 { '-' } else { '+' };
+        format!(
+            "{}{} ({:+.1}%)",
+            sign,
+            self.count(delta.tokens.unsigned_abs() as usize),
+            delta.percent
+        )
+    }
+
+    /// Note below a comparison whose models use different encodings.
+    fn format_encodings_note(&self, results: &[TokenResult]) -> Vec<String> {
+        let aggregate = ResultAggregator::new(results);
+        if !aggregate.mixed_encodings() {
+            return Vec::new();
+        }
+
+        let encodings: Vec<&str> = aggregate
+            .encodings()
+            .into_iter()
+            .map(|encoding| encoding.unwrap_or("unknown"))
+            .collect();
+        vec![
+            String::new(),
+            format!(
+                "Note: models use different encodings ({}), so counts aren't directly comparable",
+                encodings.join(", ")
+            ),
+        ]
+    }
+
+    /// The winners block below a comparison, one labeled line per criterion.
+    fn format_winners(&self, results: &[TokenResult]) -> Vec<String> {
+        let count = |tokens: i64| {
+            let sign = if tokens < 0 { "-" } else { "" };
+            format!("{}{}", sign, self.count(tokens.unsigned_abs() as usize))
+        };
+        let winners = ResultAggregator::new(results).winners();
+
+        let mut output = vec![String::new(), "Winners:".to_string()];
+        for (label, winner) in winners.labeled(count, |cost| self.money(cost, 4)) {
+            let winner = winner.map_or("n/a".to_string(), |winner| {
+                format!("{} ({})", winner.models.join(", "), winner.value)
+            });
+            output.push(format!("  {:<19}{}", format!("{}:", label), winner));
+        }
+        output
     }
 }
 
@@ -21,45 +147,155 @@ impl Formatter for TextFormatter {
     fn format_result(&self, result: &TokenResult) -> String {
         let mut output = Vec::new();
 
-        output.push(format!("Model: {}", result.model));
-        output.push(format!("Tokens: {}", result.tokens));
+        output.push(format!("Model: {}", paint(&result.model, BOLD, self.color)));
+        output.push(format!("Tokens: {}", self.count(result.tokens)));
+        if let Some(tokens) = result.response_tokens {
+            output.push(format!("Response: {} tokens", self.count(tokens)));
+        }
+        if let (true, Some(headroom), Some(window)) = (
+            self.headroom,
+            result.prompt_headroom(),
+            result.context_window,
+        ) {
+            if headroom >= 0 {
+                output.push(format!(
+                    "Headroom: {} tokens",
+                    self.count(headroom as usize)
+                ));
+            } else {
+                output.push(paint(
+                    &format!(
+                        "Headroom: -{} tokens (over the {}-token context window)",
+                        self.count(headroom.unsigned_abs() as usize),
+                        self.count(window)
+                    ),
+                    RED,
+                    self.color,
+                ));
+            }
+        }
 
         if let Some(breakdown) = &result.breakdown {
-            if self.show_breakdown {
+            if self.show_breakdown && result.by_name.is_some() {
                 output.push(String::new());
-                output.push(format!("System:     {} tokens", breakdown.system));
-                output.push(format!("User:       {} tokens", breakdown.user));
-                output.push(format!("Assistant:   {} tokens", breakdown.assistant));
+                let participants = result.participants();
+                let width = participants
+                    .iter()
+                    .map(|(name, _)| name.chars().count() + 1)
+                    .max()
+                    .unwrap_or(0)
+                    .max("Overhead:".len());
+                for (name, tokens) in participants {
+                    output.push(format!(
+                        "{:<width$} {} tokens ({:.1}%)",
+                        format!("{}:", name),
+                        self.count(tokens),
+                        breakdown.percent(tokens),
+                        width = width
+                    ));
+                }
+                if result.overhead_tokens > 0 {
+                    output.push(format!(
+                        "{:<width$} {} tokens",
+                        "Overhead:",
+                        self.count(result.overhead_tokens),
+                        width = width
+                    ));
+                }
+                output.push("-".repeat(30));
+            } else if self.show_breakdown {
+                output.push(String::new());
+                output.push(format!(
+                    "System:     {} tokens ({:.1}%)",
+                    self.count(breakdown.system),
+                    breakdown.percent(breakdown.system)
+                ));
+                output.push(format!(
+                    "User:       {} tokens ({:.1}%)",
+                    self.count(breakdown.user),
+                    breakdown.percent(breakdown.user)
+                ));
+                output.push(format!(
+                    "Assistant:   {} tokens ({:.1}%)",
+                    self.count(breakdown.assistant),
+                    breakdown.percent(breakdown.assistant)
+                ));
+                if breakdown.tool > 0 {
+                    output.push(format!(
+                        "Tool:       {} tokens ({:.1}%)",
+                        self.count(breakdown.tool),
+                        breakdown.percent(breakdown.tool)
+                    ));
+                }
+                if result.overhead_tokens > 0 {
+                    output.push(format!(
+                        "Overhead:   {} tokens",
+                        self.count(result.overhead_tokens)
+                    ));
+                }
                 output.push("-".repeat(30));
             }
         }
 
         if let Some(cost) = result.input_cost {
             // Use more precision for very small costs
-            if cost < 0.0001 {
-                output.push(format!("Cost: ${:.6} (input)", cost));
+            if self.below(cost, 0.0001) {
+                output.push(format!("Cost: {} (input)", self.money(cost, 6)));
             } else {
-                output.push(format!("Cost: ${:.4} (input)", cost));
+                output.push(format!("Cost: {} (input)", self.money(cost, 4)));
             }
         }
 
         if let Some(cost) = result.output_cost {
             // Use more precision for very small costs
-            if cost < 0.0001 {
-                output.push(format!("Cost: ${:.6} (output)", cost));
+            if self.below(cost, 0.0001) {
+                output.push(format!("Cost: {} (output)", self.money(cost, 6)));
             } else {
-                output.push(format!("Cost: ${:.4} (output)", cost));
+                output.push(format!("Cost: {} (output)", self.money(cost, 4)));
             }
         }
 
         // Show total cost if both input and output costs are available
-        if let (Some(input_cost), Some(output_cost)) = (result.input_cost, result.output_cost) {
-            let total_cost = input_cost + output_cost;
+        if let (Some(input_cost), Some(output_cost), Some(cost)) =
+            (result.input_cost, result.output_cost, result.total_cost())
+        {
+            let total_cost = cost;
             // Use 6 decimals if either component or total is very small
-            if total_cost < 0.001 || input_cost < 0.0001 || output_cost < 0.0001 {
-                output.push(format!("Total: ${:.6}", total_cost));
+            if self.below(total_cost, 0.001)
+                || self.below(input_cost, 0.0001)
+                || self.below(output_cost, 0.0001)
+            {
+                output.push(paint(
+                    &format!("Total: {}", self.money(total_cost, 6)),
+                    GREEN,
+                    self.color,
+                ));
             } else {
-                output.push(format!("Total: ${:.4}", total_cost));
+                output.push(paint(
+                    &format!("Total: {}", self.money(total_cost, 4)),
+                    GREEN,
+                    self.color,
+                ));
+            }
+        }
+
+        if let (Some(percent), Some(cost)) = (self.markup, result.total_cost()) {
+            let marked_up = apply_markup(cost, percent);
+            let decimals = if self.below(marked_up, 0.001) { 6 } else { 4 };
+            output.push(format!(
+                "With {}% markup: {}",
+                percent,
+                self.money(marked_up, decimals)
+            ));
+        }
+
+        if let Some(projection) = &result.projection {
+            output.push(format!(
+                "Projected response: {:.1}s at {} tokens/sec",
+                projection.latency_secs, projection.throughput
+            ));
+            if let Some(rate) = projection.cost_per_second() {
+                output.push(format!("Cost per second: {}", self.money(rate, 6)));
             }
         }
 
@@ -68,30 +304,119 @@ impl Formatter for TextFormatter {
 
     fn format_comparison(&self, results: &[TokenResult]) -> String {
         let mut output = Vec::new();
+        let show_ratio = results.iter().any(|r| r.output_input_ratio().is_some());
+        let deltas = self
+            .baseline
+            .as_deref()
+            .and_then(|baseline| TokenDelta::against(results, baseline));
+
+        let aggregate = ResultAggregator::new(results);
+        let show_multiple = aggregate.cost().is_some();
+
+        // Every column but the last is padded
+        let mut widths = vec![20, 10, 20];
+        if show_multiple {
+            widths.push(12);
+        }
+        if show_ratio {
+            widths.push(10);
+        }
+        let row = |cells: Vec<String>| {
+            let last = cells.len() - 1;
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i == last {
+                        cell.clone()
+                    } else {
+                        format!("{:<width$}", cell, width = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
 
         // Header
-        output.push(format!("{:<20} {:<10} {}", "Model", "Tokens", "Cost"));
-        output.push("-".repeat(50));
+        let mut header = vec![
+            "Model".to_string(),
+            "Tokens".to_string(),
+            "Cost".to_string(),
+        ];
+        let mut rule = 50;
+        if show_multiple {
+            header.push("vs Cheapest".to_string());
+            rule += 12;
+        }
+        if show_ratio {
+            header.push("Out/In".to_string());
+            rule += 10;
+        }
+        if deltas.is_some() {
+            header.push("vs Baseline".to_string());
+            rule += 20;
+        }
+        output.push(paint(&row(header), BOLD, self.color));
+        output.push("-".repeat(rule));
 
         // Rows
-        for result in results {
-            let cost_str = match (result.input_cost, result.output_cost) {
-                (Some(input), Some(output)) => {
-                    let total = input + output;
-                    format!("${:.4} (total)", total)
-                }
-                (Some(input), None) => format!("${:.4} (input)", input),
-                (None, Some(output)) => format!("${:.4} (output)", output),
-                (None, None) => "n/a".to_string(),
+        for (i, result) in results.iter().enumerate() {
+            let cost_str = match (result.input_cost, result.output_cost, result.total_cost()) {
+                (Some(_), Some(_), Some(cost)) => format!("{} (total)", self.money(cost, 4)),
+                (Some(input), None, _) => format!("{} (input)", self.money(input, 4)),
+                (None, Some(output), _) => format!("{} (output)", self.money(output, 4)),
+                _ => "n/a".to_string(),
             };
+            let mut cells = vec![result.model.clone(), self.count(result.tokens), cost_str];
+            if show_multiple {
+                cells.push(
+                    aggregate
+                        .cost_multiple(result)
+                        .map(|multiple| format!("{:.1}×", multiple))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                );
+            }
+            if show_ratio {
+                cells.push(
+                    result
+                        .output_input_ratio()
+                        .map(|r| format!("{:.1}x", r))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                );
+            }
+            if let Some(deltas) = &deltas {
+                if self.baseline.as_deref() == Some(result.model.as_str()) {
+                    cells.push("baseline".to_string());
+                } else {
+                    cells.push(self.delta(&deltas[i]));
+                }
+            }
+            output.push(row(cells));
+        }
+        output.extend(self.format_encodings_note(results));
+        if self.winners {
+            output.extend(self.format_winners(results));
+        }
+
+        // Latency depends only on the expected output, so it is shared by all models
+        if let Some(projection) = results.iter().find_map(|r| r.projection) {
+            output.push(String::new());
             output.push(format!(
-                "{:<20} {:<10} {}",
-                result.model, result.tokens, cost_str
+                "Projected response: {:.1}s at {} tokens/sec",
+                projection.latency_secs, projection.throughput
             ));
         }
 
         output.join("\n")
     }
+
+    fn format_failures(&self, failures: &[ModelFailure]) -> String {
+        let mut output = vec![paint("Failed models:", RED, self.color)];
+        for failure in failures {
+            output.push(format!("  {}: {}", failure.model, failure.error));
+        }
+        output.join("\n")
+    }
 }
 
 #[cfg(test)]
@@ -107,12 +432,60 @@ mod tests {
             input_cost: Some(0.003),
             output_cost: None,
             breakdown: None,
+            ..Default::default()
         };
         let output = formatter.format_result(&result);
         assert!(output.contains("gpt-4"));
         assert!(output.contains("100"));
     }
 
+    #[test]
+    fn test_format_result_in_currency() {
+        let formatter = TextFormatter::new(false).with_currency(Currency::new("EUR", 0.9).unwrap());
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1000,
+            input_cost: Some(0.03),
+            output_cost: Some(0.06),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        assert!(output.contains("Cost: €0.0270 (input)"));
+        assert!(output.contains("Cost: €0.0540 (output)"));
+        assert!(output.contains("Total: €0.0810"));
+        assert!(!output.contains('$'));
+    }
+
+    #[test]
+    fn test_precision_follows_the_converted_amount() {
+        // $0.00005 is small in dollars but ¥0.0075 needs only four decimals
+        let formatter =
+            TextFormatter::new(false).with_currency(Currency::new("JPY", 150.0).unwrap());
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1,
+            input_cost: Some(0.00005),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        assert!(output.contains("Cost: ¥0.0075 (input)"), "{}", output);
+    }
+
+    #[test]
+    fn test_format_result_with_markup() {
+        let formatter = TextFormatter::new(false).with_markup(Some(20.0));
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1000,
+            input_cost: Some(0.03),
+            output_cost: Some(0.06),
+            ..Default::default()
+        };
+        let output = formatter.format_result(&result);
+        assert!(output.contains("Total: $0.0900"));
+        assert!(output.contains("With 20% markup: $0.1080"));
+    }
+
     #[test]
     fn test_format_comparison() {
         let formatter = TextFormatter::new(false);
@@ -123,6 +496,7 @@ mod tests {
                 input_cost: Some(0.003),
                 output_cost: None,
                 breakdown: None,
+                ..Default::default()
             },
             TokenResult {
                 model: "gpt-3.5-turbo".to_string(),
@@ -130,10 +504,196 @@ mod tests {
                 input_cost: Some(0.00015),
                 output_cost: None,
                 breakdown: None,
+                ..Default::default()
             },
         ];
         let output = formatter.format_comparison(&results);
         assert!(output.contains("gpt-4"));
         assert!(output.contains("gpt-3.5-turbo"));
+        assert!(!output.contains("Note:"));
+        assert!(!output.contains("Winners:"));
+        assert!(!output.contains("different encodings"));
+    }
+
+    #[test]
+    fn test_comparison_notes_mixed_encodings() {
+        let result = |model: &str, tokens, encoding: &str| TokenResult {
+            model: model.to_string(),
+            tokens,
+            encoding: Some(encoding.to_string()),
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", 100, "cl100k_base"),
+            result("gpt-4o", 90, "o200k_base"),
+        ];
+        let output = TextFormatter::new(false).format_comparison(&results);
+        // Per-model figures are still shown
+        assert!(output.contains("gpt-4o") && output.contains("90"));
+        assert!(output.contains(
+            "Note: models use different encodings (cl100k_base, o200k_base), so counts aren't directly comparable"
+        ));
+    }
+
+    #[test]
+    fn test_format_result_headroom() {
+        let result = |tokens| TokenResult {
+            model: "gpt-4".to_string(),
+            tokens,
+            context_window: Some(8192),
+            ..Default::default()
+        };
+        let formatter = TextFormatter::new(false).with_headroom(true);
+        assert!(formatter
+            .format_result(&result(1034))
+            .contains("Headroom: 7,158 tokens"));
+        assert!(formatter
+            .format_result(&result(9000))
+            .contains("Headroom: -808 tokens (over the 8,192-token context window)"));
+        assert!(!TextFormatter::new(false)
+            .format_result(&result(1034))
+            .contains("Headroom"));
+    }
+
+    #[test]
+    fn test_comparison_winners() {
+        let result = |model: &str, tokens, cost, window| TokenResult {
+            model: model.to_string(),
+            tokens,
+            input_cost: cost,
+            context_window: window,
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", 100, Some(0.003), Some(8192)),
+            result("gpt-4o", 100, Some(0.0005), Some(128_000)),
+            result("chars", 400, None, None),
+        ];
+        let output = TextFormatter::new(false)
+            .with_winners(true)
+            .format_comparison(&results);
+        assert!(output.contains("Winners:"));
+        assert!(output.contains("  Fewest tokens:     gpt-4, gpt-4o (100)"));
+        assert!(output.contains("  Lowest input cost: gpt-4o ($0.0005)"));
+        assert!(output.contains("  Lowest total cost: gpt-4o ($0.0005)"));
+        assert!(output.contains("  Most headroom:     gpt-4o (127,900 tokens)"));
+
+        let unpriced = TextFormatter::new(false)
+            .with_winners(true)
+            .format_comparison(&results[2..]);
+        assert!(unpriced.contains("  Lowest input cost: n/a"));
+    }
+
+    #[test]
+    fn test_comparison_cost_multiples() {
+        let result = |model: &str, cost| TokenResult {
+            model: model.to_string(),
+            tokens: 100,
+            input_cost: cost,
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", Some(0.03)),
+            result("gpt-4o", Some(0.01)),
+            result("chars", None),
+        ];
+
+        let output = TextFormatter::new(false).format_comparison(&results);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].ends_with("vs Cheapest"));
+        assert!(lines[2].ends_with("3.0×"));
+        assert!(lines[3].ends_with("1.0×"));
+        assert!(lines[4].ends_with("n/a"));
+
+        // No column when nothing is priced
+        let output = TextFormatter::new(false).format_comparison(&results[2..]);
+        assert!(!output.contains("vs Cheapest"));
+    }
+
+    #[test]
+    fn test_comparison_baseline_deltas() {
+        let result = |model: &str, tokens| TokenResult {
+            model: model.to_string(),
+            tokens,
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4", 200),
+            result("claude-3-haiku", 230),
+            result("gemini-pro", 150),
+        ];
+
+        let output = TextFormatter::new(false)
+            .with_baseline(Some("gpt-4".to_string()))
+            .format_comparison(&results);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].ends_with("vs Baseline"));
+        assert!(lines[2].ends_with("baseline"));
+        assert!(lines[3].ends_with("+30 (+15.0%)"));
+        assert!(lines[4].ends_with("-50 (-25.0%)"));
+
+        // No column without a baseline, or when it isn't in the results
+        for baseline in [None, Some("gpt-3.5-turbo".to_string())] {
+            let output = TextFormatter::new(false)
+                .with_baseline(baseline)
+                .format_comparison(&results);
+            assert!(!output.contains("vs Baseline"));
+        }
+    }
+
+    #[test]
+    fn test_breakdown_shows_overhead() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 19,
+            content_tokens: 10,
+            overhead_tokens: 9,
+            breakdown: Some(crate::output::TokenBreakdown::new()),
+            ..Default::default()
+        };
+        let output = TextFormatter::new(true).format_result(&result);
+        assert!(output.contains("Overhead:   9 tokens"));
+        let output = TextFormatter::new(false).format_result(&result);
+        assert!(!output.contains("Overhead"));
+    }
+
+    #[test]
+    fn test_color_toggle() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 100,
+            ..Default::default()
+        };
+        let plain = TextFormatter::new(false).format_result(&result);
+        assert!(!plain.contains('\x1b'));
+        let colored = TextFormatter::new(false)
+            .with_color(true)
+            .format_result(&result);
+        assert!(colored.contains("\x1b[1mgpt-4\x1b[0m"));
+    }
+
+    #[test]
+    fn test_token_counts_grouped() {
+        let result = TokenResult {
+            model: "gpt-4".to_string(),
+            tokens: 1234567,
+            ..Default::default()
+        };
+
+        let output = TextFormatter::new(false).format_result(&result);
+        assert!(output.contains("Tokens: 1,234,567"));
+
+        let output = TextFormatter::new(false)
+            .with_separator(Some('.'))
+            .format_comparison(std::slice::from_ref(&result));
+        assert!(output.contains("1.234.567"));
+
+        let output = TextFormatter::new(false)
+            .with_separator(None)
+            .format_result(&result);
+        assert!(output.contains("Tokens: 1234567"));
+
+        let json = crate::output::JsonFormatter::new().format_result(&result);
+        assert!(json.contains("\"tokens\": 1234567"));
     }
 }
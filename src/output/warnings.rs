@@ -0,0 +1,145 @@
+/// Warnings and notes collected while producing an estimate.
+use serde::Serialize;
+use std::fmt;
+
+/// What a [`Warning`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// Some messages have no content.
+    EmptyContent,
+    /// Inline base64 images were estimated or stripped.
+    Images,
+    /// Messages past `--max-messages` were not counted.
+    MessagesTruncated,
+    /// A model was compared more than once and skipped.
+    DuplicateModel,
+    /// A model could not be counted and was skipped.
+    SkippedModel,
+    /// A file could not be counted and was skipped.
+    SkippedFile,
+    /// A message has whitespace that could be trimmed.
+    Whitespace,
+    /// A model has no pricing to check a cost against.
+    Unpriced,
+    /// Markdown was stripped with `--minify`.
+    #[cfg(feature = "markdown")]
+    MinifySavings,
+}
+
+impl WarningKind {
+    /// Prefix of the warning on stderr.
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::EmptyContent | Self::Images => "Note: ",
+            Self::MessagesTruncated
+            | Self::DuplicateModel
+            | Self::SkippedModel
+            | Self::SkippedFile
+            | Self::Whitespace
+            | Self::Unpriced => "Warning: ",
+            #[cfg(feature = "markdown")]
+            Self::MinifySavings => "",
+        }
+    }
+}
+
+/// A warning or note about an estimate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Warning {
+    /// What the warning is about.
+    pub kind: WarningKind,
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind.prefix(), self.message)
+    }
+}
+
+/// Warnings collected over a run, for stderr or a JSON `warnings` array.
+///
+/// Notes report what a requested transformation did, e.g. the tokens
+/// `--strip-comments` saved. They only ever go to stderr, so they don't
+/// change the shape of JSON output.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::output::warnings::{WarningKind, Warnings};
+///
+/// let mut warnings = Warnings::new();
+/// warnings.push(WarningKind::EmptyContent, "2 message(s) have empty content");
+/// warnings.note("Comments stripped: 120 characters, 31 tokens saved");
+/// let taken = warnings.take();
+/// assert_eq!(taken.len(), 1);
+/// assert_eq!(taken[0].to_string(), "Note: 2 message(s) have empty content");
+/// assert!(warnings.take().is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct Warnings {
+    entries: Vec<Entry>,
+}
+
+/// A collected warning or stderr-only note, in the order they were added.
+#[derive(Debug, PartialEq)]
+enum Entry {
+    Warning(Warning),
+    Note(String),
+}
+
+impl Warnings {
+    /// Create an empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a warning; one identical to an earlier warning is dropped.
+    pub fn push(&mut self, kind: WarningKind, message: impl Into<String>) {
+        self.add(Entry::Warning(Warning {
+            kind,
+            message: message.into(),
+        }));
+    }
+
+    /// Add a note for stderr; one identical to an earlier note is dropped.
+    pub fn note(&mut self, message: impl Into<String>) {
+        self.add(Entry::Note(message.into()));
+    }
+
+    fn add(&mut self, entry: Entry) {
+        if !self.entries.contains(&entry) {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Remove and return the warnings collected so far, keeping the notes.
+    pub fn take(&mut self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        self.entries.retain(|entry| match entry {
+            Entry::Warning(warning) => {
+                warnings.push(warning.clone());
+                false
+            }
+            Entry::Note(_) => true,
+        });
+        warnings
+    }
+
+    /// Print the warnings and notes collected so far to stderr, one per
+    /// line, unless `quiet` is set, and remove them.
+    pub fn flush(&mut self, quiet: bool) {
+        let entries = std::mem::take(&mut self.entries);
+        if quiet {
+            return;
+        }
+        for entry in entries {
+            match entry {
+                Entry::Warning(warning) => eprintln!("{}", warning),
+                Entry::Note(note) => eprintln!("{}", note),
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+/// Parser for OpenAI Batch API input files (JSONL).
+use crate::error::ParseError;
+use crate::parsers::{Message, Parser};
+use serde::Deserialize;
+
+/// A single line of a Batch API input file.
+#[derive(Debug, Deserialize)]
+struct BatchLine {
+    custom_id: String,
+    body: BatchBody,
+}
+
+/// The request body embedded in a batch line.
+#[derive(Debug, Deserialize)]
+struct BatchBody {
+    model: Option<String>,
+    #[serde(default)]
+    messages: Vec<BatchMessage>,
+}
+
+/// Message inside a batch request body.
+#[derive(Debug, Deserialize)]
+struct BatchMessage {
+    role: String,
+    #[serde(deserialize_with = "crate::parsers::json::deserialize_content")]
+    content: String,
+}
+
+/// One request from a batch file.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// The caller-supplied `custom_id`.
+    pub custom_id: String,
+    /// The model named in the request body, if any.
+    pub model: Option<String>,
+    /// The request's chat messages.
+    pub messages: Vec<Message>,
+}
+
+/// Parser for OpenAI Batch API JSONL input.
+///
+/// Each non-empty line must look like
+/// `{"custom_id": "...", "method": "POST", "url": "...", "body": {"model": ..., "messages": [...]}}`.
+pub struct BatchParser;
+
+impl BatchParser {
+    /// Create a new batch parser.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Check whether the input looks like a Batch API file.
+    ///
+    /// Only the first non-empty line is inspected.
+    pub fn is_batch_input(input: &str) -> bool {
+        input
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .is_some_and(|value| value.get("custom_id").is_some() && value.get("body").is_some())
+    }
+
+    /// Parse every line into a [`BatchRequest`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseError::InvalidFormat` naming the first malformed line.
+    pub fn parse_batch(&self, input: &str) -> Result<Vec<BatchRequest>, ParseError> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                let parsed: BatchLine = serde_json::from_str(line).map_err(|e| {
+                    ParseError::InvalidFormat(format!("Batch line {}: {}", index + 1, e))
+                })?;
+                Ok(BatchRequest {
+                    custom_id: parsed.custom_id,
+                    model: parsed.body.model,
+                    messages: parsed
+                        .body
+                        .messages
+                        .into_iter()
+                        .map(|m| Message {
+                            role: m.role,
+                            content: m.content,
+                            name: None,
+                            tool_call_id: None,
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for BatchParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser for BatchParser {
+    fn parse(&self, input: &str) -> Result<Vec<Message>, ParseError> {
+        Ok(self
+            .parse_batch(input)?
+            .into_iter()
+            .flat_map(|request| request.messages)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BATCH: &str = r#"{"custom_id": "req-1", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gpt-4", "messages": [{"role": "user", "content": "Hello!"}]}}
+{"custom_id": "req-2", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gpt-3.5-turbo", "messages": [{"role": "system", "content": "Be brief."}, {"role": "user", "content": "Hi"}]}}
+"#;
+
+    #[test]
+    fn test_is_batch_input() {
+        assert!(BatchParser::is_batch_input(BATCH));
+        assert!(!BatchParser::is_batch_input(
+            r#"{"role": "user", "content": "Hello!"}"#
+        ));
+        assert!(!BatchParser::is_batch_input("plain text"));
+    }
+
+    #[test]
+    fn test_parse_batch_content_parts() {
+        let line = r#"{"custom_id": "req-1", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gpt-4o", "messages": [{"role": "user", "content": [{"type": "text", "text": "Describe"}, {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}, {"type": "text", "text": "briefly."}]}]}}"#;
+        let requests = BatchParser::new().parse_batch(line).unwrap();
+        assert_eq!(requests[0].messages[0].content, "Describe\nbriefly.");
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let requests = BatchParser::new().parse_batch(BATCH).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].custom_id, "req-1");
+        assert_eq!(requests[0].model.as_deref(), Some("gpt-4"));
+        assert_eq!(requests[1].messages.len(), 2);
+        assert_eq!(requests[1].messages[0].role, "system");
+    }
+
+    #[test]
+    fn test_parse_batch_invalid_line() {
+        let input = format!("{}not json\n", BATCH);
+        let err = BatchParser::new().parse_batch(&input).unwrap_err();
+        assert!(err.to_string().contains("Batch line 3"));
+    }
+}
@@ -0,0 +1,66 @@
+/// Completion text of a recorded chat-completion response.
+use serde_json::Value;
+
+/// The completion texts of a response, one per choice.
+///
+/// An OpenAI chat-completion object yields each `choices[].message.content`
+/// (string or text parts); choices without content, such as pure tool calls,
+/// are skipped. Anything else is taken as the raw completion text.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::parsers::completion::completion_texts;
+///
+/// let response = r#"{"choices": [{"message": {"role": "assistant", "content": "Hi!"}}]}"#;
+/// assert_eq!(completion_texts(response), ["Hi!"]);
+/// assert_eq!(completion_texts("Plain text"), ["Plain text"]);
+/// ```
+pub fn completion_texts(response: &str) -> Vec<String> {
+    let choices = serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|value| value.get("choices")?.as_array().cloned());
+    let Some(choices) = choices else {
+        return vec![response.to_string()];
+    };
+
+    choices
+        .iter()
+        .filter_map(|choice| match choice.pointer("/message/content")? {
+            Value::String(text) => Some(text.clone()),
+            Value::Array(parts) => Some(
+                parts
+                    .iter()
+                    .filter_map(|part| part.get("text")?.as_str())
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choices_are_extracted() {
+        let response = r#"{
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "First"}},
+                {"index": 1, "message": {"role": "assistant", "content": [{"type": "text", "text": "Sec"}, {"type": "text", "text": "ond"}]}},
+                {"index": 2, "message": {"role": "assistant", "content": null, "tool_calls": []}}
+            ],
+            "usage": {"prompt_tokens": 9, "completion_tokens": 2}
+        }"#;
+        assert_eq!(completion_texts(response), ["First", "Second"]);
+    }
+
+    #[test]
+    fn test_other_json_is_raw_text() {
+        let response = r#"{"answer": 42}"#;
+        assert_eq!(completion_texts(response), [response]);
+    }
+}
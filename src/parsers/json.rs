@@ -1,19 +1,305 @@
 /// JSON parser for chat-style message formats.
 use crate::error::ParseError;
 use crate::parsers::{Message, Parser};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::ops::ControlFlow;
 
 /// JSON message format (matching OpenAI chat format).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct JsonMessage {
     role: String,
+    #[serde(deserialize_with = "deserialize_content")]
     content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl From<JsonMessage> for Message {
+    fn from(message: JsonMessage) -> Self {
+        Self {
+            role: message.role,
+            content: message.content,
+            name: message.name,
+            tool_call_id: message.tool_call_id,
+        }
+    }
+}
+
+/// Message content as text: a string, or the text parts of a list.
+///
+/// `null` content, as on an assistant turn that only makes tool calls, is
+/// empty text.
+pub(crate) fn deserialize_content<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if value.is_null() {
+        return Ok(String::new());
+    }
+    text_of(&value).ok_or_else(|| de::Error::custom("content must be a string or content parts"))
+}
+
+/// OpenAI Responses API request body.
+///
+/// Only the fields that carry prompt text are read; `model` and the rest
+/// are ignored.
+#[derive(Debug, Deserialize)]
+struct ResponsesPayload {
+    input: ResponsesInput,
+    #[serde(default)]
+    instructions: Option<String>,
+}
+
+/// Responses API `input`: a plain string or a list of typed items.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ResponsesInput {
+    Text(String),
+    Items(Vec<ResponsesItem>),
+}
+
+/// Input item. Non-message items (e.g. tool calls) have no content.
+#[derive(Debug, Deserialize)]
+struct ResponsesItem {
+    #[serde(default = "default_role")]
+    role: String,
+    #[serde(default)]
+    content: Option<ResponsesContent>,
+}
+
+/// Item content: a plain string or a list of content parts.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ResponsesContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+/// Content part such as `input_text` or `output_text`; images carry no text.
+#[derive(Debug, Deserialize)]
+struct ContentPart {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+fn default_role() -> String {
+    "user".to_string()
+}
+
+impl ResponsesPayload {
+    /// Flatten into messages, joining text parts with newlines.
+    fn into_messages(self) -> Vec<Message> {
+        let mut messages = Vec::new();
+        if let Some(instructions) = self.instructions {
+            messages.push(Message {
+                role: "system".to_string(),
+                content: instructions,
+                name: None,
+                tool_call_id: None,
+            });
+        }
+        match self.input {
+            ResponsesInput::Text(content) => messages.push(Message {
+                role: default_role(),
+                content,
+                name: None,
+                tool_call_id: None,
+            }),
+            ResponsesInput::Items(items) => messages.extend(items.into_iter().filter_map(|item| {
+                let content = match item.content? {
+                    ResponsesContent::Text(text) => text,
+                    ResponsesContent::Parts(parts) => parts
+                        .into_iter()
+                        .filter_map(|part| part.text)
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                };
+                Some(Message {
+                    role: item.role,
+                    content,
+                    name: None,
+                    tool_call_id: None,
+                })
+            })),
+        }
+        messages
+    }
+}
+
+/// Gemini `generateContent` request body.
+///
+/// The system prompt sits in `system_instruction`, outside `contents`. Both
+/// the REST (`system_instruction`) and camelCase spellings are accepted.
+#[derive(Debug, Deserialize)]
+struct GeminiPayload {
+    contents: Vec<GeminiContent>,
+    #[serde(default, alias = "systemInstruction")]
+    system_instruction: Option<GeminiContent>,
+}
+
+/// Gemini content: a role and a list of parts. Non-text parts are skipped.
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<ContentPart>,
+}
+
+impl GeminiContent {
+    /// Text parts joined with newlines.
+    fn text(self) -> String {
+        self.parts
+            .into_iter()
+            .filter_map(|part| part.text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl GeminiPayload {
+    /// Flatten into messages, with the system instruction first and Gemini's
+    /// `model` role normalized to `assistant`.
+    fn into_messages(self) -> Vec<Message> {
+        let system = self.system_instruction.map(|instruction| Message {
+            role: "system".to_string(),
+            content: instruction.text(),
+            name: None,
+            tool_call_id: None,
+        });
+        let contents = self.contents.into_iter().map(|content| {
+            let role = match content.role.as_deref() {
+                Some("model") => "assistant".to_string(),
+                Some(role) => role.to_string(),
+                None => default_role(),
+            };
+            Message {
+                role,
+                content: content.text(),
+                name: None,
+                tool_call_id: None,
+            }
+        });
+        system.into_iter().chain(contents).collect()
+    }
+}
+
+/// JSON pointers (RFC 6901) locating messages in a custom payload shape.
+///
+/// `messages` points from the document root to the array of messages (or a
+/// single message object); `role` and `content` point into each message.
+/// Unset pointers default to the root, `/role` and `/content`. As a
+/// [`Parser`], it reads messages with [`JsonPaths::extract`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonPaths {
+    /// Pointer to the messages, e.g. `/request/body/messages`
+    pub messages: Option<String>,
+    /// Pointer to the role within a message, e.g. `/author/type`
+    pub role: Option<String>,
+    /// Pointer to the content within a message, e.g. `/parts/0/text`
+    pub content: Option<String>,
+}
+
+impl JsonPaths {
+    /// Extract messages from `input` by following the pointers.
+    ///
+    /// Content may be a string or a list of content parts, whose `text`
+    /// fields are joined with newlines.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseError::InvalidJson` if `input` is not JSON,
+    /// `ParseError::InvalidFormat` if a pointer is malformed or the messages
+    /// are not an array or object, and `ParseError::MissingField` if a
+    /// message has no string at the role or content pointer.
+    pub fn extract(&self, input: &str) -> Result<Vec<Message>, ParseError> {
+        let document: serde_json::Value = serde_json::from_str(input)?;
+        let root = self.messages.as_deref().unwrap_or("");
+        let role = self.role.as_deref().unwrap_or("/role");
+        let content = self.content.as_deref().unwrap_or("/content");
+        for pointer in [root, role, content] {
+            check_pointer(pointer)?;
+        }
+
+        let found = document.pointer(root).ok_or_else(|| {
+            ParseError::InvalidFormat(format!("JSON pointer '{}' matches nothing", root))
+        })?;
+        let items = match found {
+            serde_json::Value::Array(items) => items.as_slice(),
+            serde_json::Value::Object(_) => std::slice::from_ref(found),
+            _ => {
+                return Err(ParseError::InvalidFormat(format!(
+                    "JSON pointer '{}' does not point to an array of messages",
+                    root
+                )))
+            }
+        };
+
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let missing = |pointer: &str| ParseError::MissingField {
+                    field: format!("{} (message {})", pointer, i + 1),
+                };
+                let role = item
+                    .pointer(role)
+                    .and_then(|value| value.as_str())
+                    .ok_or_else(|| missing(role))?;
+                let content = item
+                    .pointer(content)
+                    .and_then(text_of)
+                    .ok_or_else(|| missing(content))?;
+                Ok(Message {
+                    role: role.to_string(),
+                    content,
+                    name: None,
+                    tool_call_id: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Reject pointers that `serde_json` would silently never match.
+fn check_pointer(pointer: &str) -> Result<(), ParseError> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        Ok(())
+    } else {
+        Err(ParseError::InvalidFormat(format!(
+            "'{}' is not a JSON pointer (pointers start with '/', e.g. '/{}')",
+            pointer, pointer
+        )))
+    }
+}
+
+/// Text of a content value: a string, or the `text` of each content part.
+fn text_of(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(|text| text.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    }
 }
 
 /// Parser for JSON chat format.
 ///
-/// Supports both single message objects and arrays of messages.
-/// Format matches OpenAI's chat API format.
+/// Supports both single message objects and arrays of messages, matching
+/// OpenAI's Chat Completions format. Request bodies for the Responses API
+/// (detected by a top-level `input` key) and Gemini's `generateContent`
+/// (a top-level `contents` key) are also accepted. Other shapes
+/// can be read with [`JsonPaths`], which is a parser of its own.
 pub struct JsonParser;
 
 impl JsonParser {
@@ -23,6 +309,86 @@ impl JsonParser {
     }
 }
 
+impl JsonParser {
+    /// Stream messages from `reader` without holding the whole array.
+    ///
+    /// Accepts the Chat Completions shapes of [`Parser::parse`] (an array of
+    /// messages or a single message object), calling `on_message` for each
+    /// message as it is read. Returns the number of messages.
+    ///
+    /// If `on_message` returns `ControlFlow::Break`, nothing more is read and
+    /// the count ends with that message.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same `ParseError` as [`Parser::parse`] for malformed input,
+    /// or `ParseError::Io` if reading fails.
+    pub fn stream<R: Read>(
+        &self,
+        reader: R,
+        mut on_message: impl FnMut(Message) -> ControlFlow<()>,
+    ) -> Result<usize, ParseError> {
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        let mut stopped_at = None;
+        let result = deserializer
+            .deserialize_any(MessageVisitor {
+                on_message: &mut on_message,
+                stopped_at: &mut stopped_at,
+            })
+            .and_then(|count| deserializer.end().map(|_| count));
+        if let Some(count) = stopped_at {
+            return Ok(count);
+        }
+        result.map_err(|e| {
+            if e.is_io() {
+                ParseError::Io(e.into())
+            } else {
+                invalid_format()
+            }
+        })
+    }
+}
+
+fn invalid_format() -> ParseError {
+    ParseError::InvalidFormat("Input is not valid JSON message format".to_string())
+}
+
+/// Visitor handing each message to a callback as it is deserialized.
+struct MessageVisitor<'a, F> {
+    on_message: &'a mut F,
+    /// Number of messages read when the callback stopped the stream.
+    stopped_at: &'a mut Option<usize>,
+}
+
+impl<'de, F: FnMut(Message) -> ControlFlow<()>> Visitor<'de> for MessageVisitor<'_, F> {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a chat message or an array of chat messages")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(message) = seq.next_element::<JsonMessage>()? {
+            count += 1;
+            if (self.on_message)(message.into()).is_break() {
+                // Abandon the rest of the array
+                *self.stopped_at = Some(count);
+                return Err(de::Error::custom("stopped"));
+            }
+        }
+        Ok(count)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<usize, A::Error> {
+        let message = JsonMessage::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        if (self.on_message)(message.into()).is_break() {
+            *self.stopped_at = Some(1);
+        }
+        Ok(1)
+    }
+}
+
 impl Default for JsonParser {
     fn default() -> Self {
         Self::new()
@@ -33,26 +399,31 @@ impl Parser for JsonParser {
     fn parse(&self, input: &str) -> Result<Vec<Message>, ParseError> {
         // Try parsing as array first
         if let Ok(messages) = serde_json::from_str::<Vec<JsonMessage>>(input) {
-            return Ok(messages
-                .into_iter()
-                .map(|m| Message {
-                    role: m.role,
-                    content: m.content,
-                })
-                .collect());
+            return Ok(messages.into_iter().map(Message::from).collect());
         }
 
         // Try parsing as single message
         if let Ok(message) = serde_json::from_str::<JsonMessage>(input) {
-            return Ok(vec![Message {
-                role: message.role,
-                content: message.content,
-            }]);
+            return Ok(vec![message.into()]);
+        }
+
+        // Responses API request body
+        if let Ok(payload) = serde_json::from_str::<ResponsesPayload>(input) {
+            return Ok(payload.into_messages());
         }
 
-        Err(ParseError::InvalidFormat(
-            "Input is not valid JSON message format".to_string(),
-        ))
+        // Gemini request body
+        if let Ok(payload) = serde_json::from_str::<GeminiPayload>(input) {
+            return Ok(payload.into_messages());
+        }
+
+        Err(invalid_format())
+    }
+}
+
+impl Parser for JsonPaths {
+    fn parse(&self, input: &str) -> Result<Vec<Message>, ParseError> {
+        self.extract(input)
     }
 }
 
@@ -60,6 +431,45 @@ impl Parser for JsonParser {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_reads_participant_names() {
+        let messages = JsonParser::new()
+            .parse(r#"[{"role": "user", "name": "alice", "content": "Hi"}, {"role": "assistant", "content": "Hello"}]"#)
+            .unwrap();
+        assert_eq!(messages[0].name.as_deref(), Some("alice"));
+        assert_eq!(messages[1].name, None);
+    }
+
+    #[test]
+    fn test_parse_reads_tool_results() {
+        let messages = JsonParser::new()
+            .parse(
+                r#"[{"role": "tool", "tool_call_id": "call_abc123", "content": [{"type": "text", "text": "{\"temp\": 18}"}]}]"#,
+            )
+            .unwrap();
+        assert_eq!(messages[0].role, "tool");
+        assert_eq!(messages[0].tool_call_id.as_deref(), Some("call_abc123"));
+        assert_eq!(messages[0].content, r#"{"temp": 18}"#);
+    }
+
+    #[test]
+    fn test_parse_assistant_tool_call_then_result() {
+        let messages = JsonParser::new()
+            .parse(
+                r#"[
+                    {"role": "user", "content": "Weather in Paris?"},
+                    {"role": "assistant", "content": null, "tool_calls": [{"id": "call_abc123", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\": \"Paris\"}"}}]},
+                    {"role": "tool", "tool_call_id": "call_abc123", "content": "{\"temp\": 18}"}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role, "assistant");
+        assert_eq!(messages[1].content, "");
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_abc123"));
+        assert_eq!(messages[2].content, r#"{"temp": 18}"#);
+    }
+
     #[test]
     fn test_json_parser_single_message() {
         let parser = JsonParser::new();
@@ -80,10 +490,199 @@ mod tests {
         assert_eq!(messages[1].role, "user");
     }
 
+    #[test]
+    fn test_json_parser_responses_string_input() {
+        let parser = JsonParser::new();
+        let input = r#"{"model": "gpt-4o", "input": "Tell me a joke."}"#;
+        let messages = parser.parse(input).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, "Tell me a joke.");
+    }
+
+    #[test]
+    fn test_json_parser_responses_item_input() {
+        let parser = JsonParser::new();
+        let input = r#"{
+            "model": "gpt-4o",
+            "instructions": "Be brief.",
+            "input": [
+                {"role": "user", "content": [
+                    {"type": "input_text", "text": "What is in this image?"},
+                    {"type": "input_image", "image_url": "https://example.com/a.png"},
+                    {"type": "input_text", "text": "Answer in one word."}
+                ]},
+                {"type": "message", "role": "assistant", "content": [
+                    {"type": "output_text", "text": "Cat."}
+                ]},
+                {"role": "user", "content": "Thanks"},
+                {"type": "function_call", "name": "lookup", "arguments": "{}"}
+            ]
+        }"#;
+        let messages = parser.parse(input).unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+        assert_eq!(messages[0].content, "Be brief.");
+        assert_eq!(
+            messages[1].content,
+            "What is in this image?\nAnswer in one word."
+        );
+        assert_eq!(messages[2].content, "Cat.");
+        assert_eq!(messages[3].content, "Thanks");
+    }
+
+    #[test]
+    fn test_json_parser_gemini_payload() {
+        let input = r#"{
+            "system_instruction": {"parts": [{"text": "You are a cat."}, {"text": "Be brief."}]},
+            "contents": [
+                {"role": "user", "parts": [
+                    {"text": "What is this?"},
+                    {"inline_data": {"mime_type": "image/png", "data": "iVBORw0KGgo="}}
+                ]},
+                {"role": "model", "parts": [{"text": "A mouse."}]},
+                {"parts": [{"text": "Catch it!"}]}
+            ],
+            "generationConfig": {"temperature": 0.2}
+        }"#;
+        let messages = JsonParser::new().parse(input).unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+        assert_eq!(messages[0].content, "You are a cat.\nBe brief.");
+        assert_eq!(messages[1].content, "What is this?");
+        assert_eq!(messages[2].content, "A mouse.");
+
+        // camelCase spelling, and no system instruction at all
+        let input = r#"{"systemInstruction": {"parts": [{"text": "Hi"}]}, "contents": []}"#;
+        assert_eq!(JsonParser::new().parse(input).unwrap()[0].role, "system");
+        let input = r#"{"contents": [{"role": "user", "parts": [{"text": "Hi"}]}]}"#;
+        assert_eq!(JsonParser::new().parse(input).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_json_paths_extract_nested_payload() {
+        let input = r#"{
+            "trace_id": "abc",
+            "request": {"body": {"history": [
+                {"author": {"type": "system"}, "parts": [{"text": "Be brief."}]},
+                {"author": {"type": "human"}, "parts": [{"text": "Hi"}, {"image": "a.png"}, {"text": "there"}]},
+                {"author": {"type": "ai"}, "parts": "Hello!"}
+            ]}}
+        }"#;
+        let parser = JsonPaths {
+            messages: Some("/request/body/history".to_string()),
+            role: Some("/author/type".to_string()),
+            content: Some("/parts".to_string()),
+        };
+        let messages = parser.parse(input).unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "human", "ai"]);
+        assert_eq!(messages[0].content, "Be brief.");
+        assert_eq!(messages[1].content, "Hi\nthere");
+        assert_eq!(messages[2].content, "Hello!");
+
+        // Field pointers default to /role and /content
+        let input = r#"{"data": {"messages": [{"role": "user", "content": "Hello"}]}}"#;
+        let parser = JsonPaths {
+            messages: Some("/data/messages".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(parser.parse(input).unwrap()[0].content, "Hello");
+    }
+
+    #[test]
+    fn test_json_paths_errors() {
+        let input = r#"{"messages": [{"speaker": "user", "text": "Hi"}, {"speaker": "bot"}]}"#;
+        let paths = |messages: &str| JsonPaths {
+            messages: Some(messages.to_string()),
+            role: Some("/speaker".to_string()),
+            content: Some("/text".to_string()),
+        };
+
+        let error = paths("/messages").parse(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing required field: /text (message 2)"
+        );
+
+        let error = paths("/request/messages").parse(input).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("'/request/messages' matches nothing"));
+
+        let error = paths("messages").parse(input).unwrap_err();
+        assert!(error.to_string().contains("is not a JSON pointer"));
+    }
+
     #[test]
     fn test_json_parser_invalid() {
         let parser = JsonParser::new();
         let input = "not json";
         assert!(parser.parse(input).is_err());
     }
+
+    #[test]
+    fn test_stream_matches_parse() {
+        let parser = JsonParser::new();
+        let input =
+            r#"[{"role": "system", "content": "Be brief."}, {"role": "user", "content": "Hi"}]"#;
+
+        let mut streamed = Vec::new();
+        let count = parser
+            .stream(input.as_bytes(), |message| {
+                streamed.push(message);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        let parsed = parser.parse(input).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(streamed.len(), parsed.len());
+        for (a, b) in streamed.iter().zip(&parsed) {
+            assert_eq!(a.role, b.role);
+            assert_eq!(a.content, b.content);
+        }
+
+        let single = r#"{"role": "user", "content": "Hello!"}"#;
+        assert_eq!(
+            parser
+                .stream(single.as_bytes(), |_| ControlFlow::Continue(()))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_stream_stops_on_break() {
+        let parser = JsonParser::new();
+        // Malformed after the second message, which is never reached
+        let input = r#"[{"role": "user", "content": "a"}, {"role": "user", "content": "b"}, oops"#;
+
+        let mut seen = Vec::new();
+        let count = parser
+            .stream(input.as_bytes(), |message| {
+                seen.push(message.content);
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(seen, ["a"]);
+    }
+
+    #[test]
+    fn test_stream_malformed_matches_parse_error() {
+        let parser = JsonParser::new();
+        for input in [
+            "not json",
+            r#"[{"role": "user"}]"#,
+            r#"[{"role": "user", "content": "a"}"#,
+            "[] trailing",
+        ] {
+            let streamed = parser
+                .stream(input.as_bytes(), |_| ControlFlow::Continue(()))
+                .unwrap_err();
+            let parsed = parser.parse(input).unwrap_err();
+            assert_eq!(streamed.to_string(), parsed.to_string());
+        }
+    }
 }
@@ -0,0 +1,113 @@
+/// Safety limit on the number of messages parsed from one input.
+use crate::error::ParseError;
+use crate::parsers::Message;
+use clap::ValueEnum;
+
+/// What to do with input holding more messages than the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LimitAction {
+    /// Fail without counting anything
+    #[default]
+    Error,
+    /// Count only the first messages up to the limit
+    Truncate,
+}
+
+/// A cap on the number of messages counted from one input.
+///
+/// Guards against accidentally huge inputs, such as a JSON array with
+/// millions of tiny messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLimit {
+    /// Maximum number of messages
+    pub max: usize,
+    /// What to do when there are more
+    pub action: LimitAction,
+}
+
+impl MessageLimit {
+    /// Check a parsed message count against the limit.
+    ///
+    /// Returns how many messages beyond the limit should be dropped, which is
+    /// zero unless the count exceeds the limit and the action is `Truncate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseError::InvalidFormat` if the count exceeds the limit and
+    /// the action is `Error`.
+    pub fn check(&self, count: usize) -> Result<usize, ParseError> {
+        if count <= self.max {
+            return Ok(0);
+        }
+        match self.action {
+            LimitAction::Error => Err(ParseError::InvalidFormat(format!(
+                "input has {} messages, more than --max-messages {} (pass --on-max-messages truncate to count the first {})",
+                count, self.max, self.max
+            ))),
+            LimitAction::Truncate => Ok(count - self.max),
+        }
+    }
+
+    /// The error for an input found to have more messages than the limit
+    /// before all of them were read.
+    pub fn exceeded(&self) -> ParseError {
+        ParseError::InvalidFormat(format!(
+            "input has more than --max-messages {} messages (pass --on-max-messages truncate to count the first {})",
+            self.max, self.max
+        ))
+    }
+
+    /// Apply the limit to parsed messages, returning how many were dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseError::InvalidFormat` if there are too many messages and
+    /// the action is `Error`.
+    pub fn apply(&self, messages: &mut Vec<Message>) -> Result<usize, ParseError> {
+        let dropped = self.check(messages.len())?;
+        messages.truncate(self.max);
+        Ok(dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(count: usize) -> Vec<Message> {
+        (0..count)
+            .map(|i| Message {
+                role: "user".to_string(),
+                content: format!("message {}", i),
+                name: None,
+                tool_call_id: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_error_above_limit() {
+        let limit = MessageLimit {
+            max: 3,
+            action: LimitAction::Error,
+        };
+        let mut within = messages(3);
+        assert_eq!(limit.apply(&mut within).unwrap(), 0);
+        assert_eq!(within.len(), 3);
+
+        let error = limit.apply(&mut messages(4)).unwrap_err().to_string();
+        assert!(error.contains("input has 4 messages, more than --max-messages 3"));
+    }
+
+    #[test]
+    fn test_truncate_keeps_first_messages() {
+        let limit = MessageLimit {
+            max: 2,
+            action: LimitAction::Truncate,
+        };
+        let mut parsed = messages(5);
+        assert_eq!(limit.apply(&mut parsed).unwrap(), 3);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].content, "message 1");
+    }
+}
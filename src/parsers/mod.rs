@@ -1,11 +1,19 @@
+pub mod batch;
+pub mod completion;
 pub mod json;
+pub mod limit;
+pub mod roles;
 /// Parsers for different input formats.
 pub mod text;
 
-pub use json::JsonParser;
+pub use batch::{BatchParser, BatchRequest};
+pub use json::{JsonParser, JsonPaths};
+pub use limit::{LimitAction, MessageLimit};
+pub use roles::RoleMap;
 pub use text::TextParser;
 
 use crate::error::ParseError;
+use clap::ValueEnum;
 
 /// A message with a role (for chat-style APIs).
 #[derive(Debug, Clone)]
@@ -14,6 +22,12 @@ pub struct Message {
     pub role: String,
     /// The content of the message.
     pub content: String,
+    /// Name of the participant who sent the message, if given (e.g. the
+    /// OpenAI `name` field in multi-agent transcripts).
+    pub name: Option<String>,
+    /// Id of the tool call a `tool` message answers (the OpenAI
+    /// `tool_call_id` field), if given.
+    pub tool_call_id: Option<String>,
 }
 
 /// Trait for parsing prompts into messages.
@@ -33,3 +47,83 @@ pub trait Parser {
     /// Returns `ParseError` if the input cannot be parsed.
     fn parse(&self, input: &str) -> Result<Vec<Message>, ParseError>;
 }
+
+/// Format of prompt input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum InputFormat {
+    /// Detect from the content: JSON objects and arrays parse as JSON
+    #[default]
+    Auto,
+    /// Plain text, treated as a single user message
+    Text,
+    /// JSON chat messages
+    Json,
+}
+
+impl InputFormat {
+    /// Resolve `Auto` to a concrete format by inspecting `input`.
+    pub fn detect(self, input: &str) -> Self {
+        match self {
+            InputFormat::Auto => {
+                let trimmed = input.trim_start();
+                if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                    InputFormat::Json
+                } else {
+                    InputFormat::Text
+                }
+            }
+            format => format,
+        }
+    }
+
+    /// Parser for this format, detecting it from `input` when `Auto`.
+    pub fn parser_for(self, input: &str) -> Box<dyn Parser> {
+        match self.detect(input) {
+            InputFormat::Json => Box::new(JsonParser::new()),
+            _ => Box::new(TextParser::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_detects_json_and_text() {
+        assert_eq!(
+            InputFormat::Auto.detect("  [{\"role\": \"user\"}]"),
+            InputFormat::Json
+        );
+        assert_eq!(InputFormat::Auto.detect("{}"), InputFormat::Json);
+        assert_eq!(InputFormat::Auto.detect("Hello"), InputFormat::Text);
+    }
+
+    #[test]
+    fn test_explicit_format_forces_parser() {
+        let json = r#"[{"role": "system", "content": "Be brief."}]"#;
+
+        let messages = InputFormat::Text.parser_for(json).parse(json).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[0].content, json);
+
+        let messages = InputFormat::Json.parser_for(json).parse(json).unwrap();
+        assert_eq!(messages[0].role, "system");
+
+        // Text that merely looks like JSON no longer fails with --input-format text
+        let bracketed = "[draft] Summarize the notes below";
+        assert!(InputFormat::Auto
+            .parser_for(bracketed)
+            .parse(bracketed)
+            .is_err());
+        assert!(InputFormat::Text
+            .parser_for(bracketed)
+            .parse(bracketed)
+            .is_ok());
+        assert!(InputFormat::Json
+            .parser_for("plain")
+            .parse("plain")
+            .is_err());
+    }
+}
@@ -0,0 +1,159 @@
+/// Normalization of role names that providers spell differently.
+use crate::error::ParseError;
+use crate::parsers::{Message, Parser};
+use std::collections::HashMap;
+
+/// Roles accepted by `--strict-json`.
+pub const KNOWN_ROLES: [&str; 6] = [
+    "system",
+    "developer",
+    "user",
+    "assistant",
+    "tool",
+    "function",
+];
+
+/// Maps role names onto the canonical roles used for bucketing.
+///
+/// By default `developer` (OpenAI's newer name for the system prompt) is
+/// treated as `system`. Further aliases can be added, and an alias mapped
+/// to itself keeps that role separate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleMap {
+    aliases: HashMap<String, String>,
+}
+
+impl RoleMap {
+    /// A map with no aliases; every role is its own canonical role.
+    pub fn empty() -> Self {
+        Self {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Add or replace an alias so that `alias` counts as `role`.
+    pub fn with_alias(mut self, alias: impl Into<String>, role: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), role.into());
+        self
+    }
+
+    /// The canonical role for `role`.
+    pub fn canonical<'r>(&'r self, role: &'r str) -> &'r str {
+        self.aliases.get(role).map_or(role, String::as_str)
+    }
+}
+
+impl Default for RoleMap {
+    fn default() -> Self {
+        Self::empty().with_alias("developer", "system")
+    }
+}
+
+/// Check that every message has a known role, directly or through an alias.
+///
+/// # Errors
+///
+/// Returns `ParseError::UnknownRole` for the first message with another
+/// role, numbered from 1.
+pub fn check_known_roles(messages: &[Message], roles: &RoleMap) -> Result<(), ParseError> {
+    match messages
+        .iter()
+        .position(|message| !is_known(&message.role, roles))
+    {
+        Some(index) => Err(ParseError::UnknownRole {
+            role: messages[index].role.clone(),
+            index: index + 1,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Whether `role`, or the role it is an alias for, is one of [`KNOWN_ROLES`].
+pub fn is_known(role: &str, roles: &RoleMap) -> bool {
+    KNOWN_ROLES.contains(&role) || KNOWN_ROLES.contains(&roles.canonical(role))
+}
+
+/// Parser rejecting messages whose role is not known, for `--strict-json`.
+pub struct StrictRolesParser {
+    inner: Box<dyn Parser>,
+    roles: RoleMap,
+}
+
+impl StrictRolesParser {
+    /// Wrap `inner`, accepting the roles of [`KNOWN_ROLES`] and their aliases in `roles`.
+    pub fn new(inner: Box<dyn Parser>, roles: RoleMap) -> Self {
+        Self { inner, roles }
+    }
+}
+
+impl Parser for StrictRolesParser {
+    fn parse(&self, input: &str) -> Result<Vec<Message>, ParseError> {
+        let messages = self.inner.parse(input)?;
+        check_known_roles(&messages, &self.roles)?;
+        Ok(messages)
+    }
+}
+
+/// Parse an `ALIAS=ROLE` role alias.
+///
+/// # Errors
+///
+/// Returns a message if either side is missing.
+pub fn parse_role_alias(spec: &str) -> Result<(String, String), String> {
+    match spec.split_once('=') {
+        Some((alias, role)) if !alias.trim().is_empty() && !role.trim().is_empty() => {
+            Ok((alias.trim().to_string(), role.trim().to_string()))
+        }
+        _ => Err(format!("expected ALIAS=ROLE, got '{}'", spec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_developer_is_system_by_default() {
+        let roles = RoleMap::default();
+        assert_eq!(roles.canonical("developer"), "system");
+        assert_eq!(roles.canonical("user"), "user");
+
+        let roles = roles.with_alias("developer", "developer");
+        assert_eq!(roles.canonical("developer"), "developer");
+    }
+
+    #[test]
+    fn test_strict_roles_reject_unknown_role() {
+        use crate::parsers::JsonParser;
+
+        let input = r#"[{"role": "system", "content": "Be brief."}, {"role": "assistent", "content": "Hi"}]"#;
+        assert_eq!(JsonParser::new().parse(input).unwrap().len(), 2);
+
+        let strict = StrictRolesParser::new(Box::new(JsonParser::new()), RoleMap::default());
+        let error = strict.parse(input).unwrap_err();
+        assert!(matches!(
+            &error,
+            ParseError::UnknownRole { role, index: 2 } if role == "assistent"
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("Unknown role 'assistent' in message 2 (expected one of system,"));
+
+        // Aliased roles count as their canonical role
+        let aliased = StrictRolesParser::new(
+            Box::new(JsonParser::new()),
+            RoleMap::default().with_alias("assistent", "assistant"),
+        );
+        assert_eq!(aliased.parse(input).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_role_alias() {
+        assert_eq!(
+            parse_role_alias("human=user").unwrap(),
+            ("human".to_string(), "user".to_string())
+        );
+        assert!(parse_role_alias("human").is_err());
+        assert!(parse_role_alias("=user").is_err());
+    }
+}
@@ -25,6 +25,8 @@ impl Parser for TextParser {
         Ok(vec![Message {
             role: "user".to_string(),
             content: input.to_string(),
+            name: None,
+            tool_call_id: None,
         }])
     }
 }
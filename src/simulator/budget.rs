@@ -0,0 +1,185 @@
+/// Cost budget for `max_cost`, charged for every attempt including retries.
+#[cfg(feature = "load-test")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Slack for floating-point rounding when comparing spend to the limit.
+#[cfg(feature = "load-test")]
+const EPSILON: f64 = 1e-9;
+
+/// Prices used to charge load-test attempts against the budget.
+#[cfg(feature = "load-test")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttemptPricing {
+    /// Input price per 1K tokens (USD)
+    pub input_per_1k: f64,
+    /// Output price per 1K tokens (USD)
+    pub output_per_1k: f64,
+    /// Tokens in the prompt, charged when a response reports no usage
+    pub prompt_tokens: usize,
+    /// Output tokens reserved for each attempt until its usage is known
+    pub expected_output_tokens: usize,
+}
+
+#[cfg(feature = "load-test")]
+impl AttemptPricing {
+    /// Cost of sending the prompt once, which a failed attempt may still incur.
+    pub fn prompt_cost(&self) -> f64 {
+        self.prompt_tokens as f64 / 1000.0 * self.input_per_1k
+    }
+
+    /// Projected cost of one attempt: the prompt plus the expected output.
+    pub fn reserve_cost(&self) -> f64 {
+        self.prompt_cost() + self.expected_output_tokens as f64 / 1000.0 * self.output_per_1k
+    }
+
+    /// Cost of a completed attempt from its reported token usage.
+    pub fn usage_cost(&self, input_tokens: Option<usize>, output_tokens: Option<usize>) -> f64 {
+        let input = input_tokens.unwrap_or(self.prompt_tokens);
+        let output = output_tokens.unwrap_or(0);
+        input as f64 / 1000.0 * self.input_per_1k + output as f64 / 1000.0 * self.output_per_1k
+    }
+}
+
+/// Running spend shared by all workers of a load test.
+///
+/// Every attempt reserves its projected cost before it is sent and settles
+/// with the actual cost afterwards. An attempt whose reservation would take
+/// spend plus pending reservations past the limit is never sent, so retries
+/// can't run past the budget.
+#[cfg(feature = "load-test")]
+#[derive(Debug)]
+pub struct CostBudget {
+    limit: f64,
+    pricing: AttemptPricing,
+    state: Mutex<BudgetState>,
+}
+
+#[cfg(feature = "load-test")]
+#[derive(Debug, Default)]
+struct BudgetState {
+    spent: f64,
+    reserved: f64,
+}
+
+#[cfg(feature = "load-test")]
+impl CostBudget {
+    /// A budget of `limit` USD with attempts priced by `pricing`.
+    pub fn new(limit: f64, pricing: AttemptPricing) -> Self {
+        Self {
+            limit,
+            pricing,
+            state: Mutex::new(BudgetState::default()),
+        }
+    }
+
+    /// The spending limit in USD.
+    pub fn limit(&self) -> f64 {
+        self.limit
+    }
+
+    /// Reserve the projected cost of one attempt.
+    ///
+    /// Returns `false`, reserving nothing, if the attempt would exceed the limit.
+    pub fn try_reserve(&self) -> bool {
+        let mut state = self.state();
+        if !self.fits(&state) {
+            return false;
+        }
+        state.reserved += self.pricing.reserve_cost();
+        true
+    }
+
+    /// Whether another attempt still fits in the budget.
+    pub fn can_afford_attempt(&self) -> bool {
+        self.fits(&self.state())
+    }
+
+    fn fits(&self, state: &BudgetState) -> bool {
+        state.spent + state.reserved + self.pricing.reserve_cost() <= self.limit + EPSILON
+    }
+
+    /// Lock the running spend.
+    ///
+    /// The state is two plain sums, so a worker that panicked while holding
+    /// the lock can't leave it inconsistent and poisoning is ignored.
+    fn state(&self) -> MutexGuard<'_, BudgetState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Release a reservation and charge the attempt's actual cost.
+    ///
+    /// Successful attempts pass their reported usage; failed attempts pass
+    /// `None` and are charged for the prompt.
+    pub fn settle(&self, usage: Option<(Option<usize>, Option<usize>)>) {
+        let cost = match usage {
+            Some((input, output)) => self.pricing.usage_cost(input, output),
+            None => self.pricing.prompt_cost(),
+        };
+        let mut state = self.state();
+        state.reserved = (state.reserved - self.pricing.reserve_cost()).max(0.0);
+        state.spent += cost;
+    }
+
+    /// Cost charged so far, in USD.
+    pub fn spent(&self) -> f64 {
+        self.state().spent
+    }
+}
+
+#[cfg(all(test, feature = "load-test"))]
+mod tests {
+    use super::*;
+
+    fn pricing() -> AttemptPricing {
+        AttemptPricing {
+            input_per_1k: 1.0,
+            output_per_1k: 2.0,
+            prompt_tokens: 100,
+            expected_output_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn test_failed_attempts_are_charged() {
+        // Each attempt costs $0.10 of input, so three fit in $0.30
+        let budget = CostBudget::new(0.3, pricing());
+        for _ in 0..3 {
+            assert!(budget.try_reserve());
+            budget.settle(None);
+        }
+        assert!((budget.spent() - 0.3).abs() < 1e-9);
+        assert!(!budget.can_afford_attempt());
+        assert!(!budget.try_reserve());
+    }
+
+    #[test]
+    fn test_pending_reservations_count() {
+        let budget = CostBudget::new(0.25, pricing());
+        assert!(budget.try_reserve());
+        assert!(budget.try_reserve());
+        assert!(
+            !budget.try_reserve(),
+            "two pending attempts fill the budget"
+        );
+        budget.settle(Some((Some(50), Some(25))));
+        assert!((budget.spent() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expected_output_is_reserved() {
+        // $0.10 of input plus $0.10 of expected output per attempt
+        let pricing = AttemptPricing {
+            expected_output_tokens: 50,
+            ..pricing()
+        };
+        let budget = CostBudget::new(0.3, pricing);
+        assert!(budget.try_reserve());
+        assert!(
+            !budget.try_reserve(),
+            "a second attempt could spend $0.40 in output"
+        );
+        budget.settle(Some((Some(100), Some(50))));
+        assert!((budget.spent() - 0.2).abs() < 1e-9);
+        assert!(!budget.can_afford_attempt());
+    }
+}
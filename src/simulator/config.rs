@@ -1,5 +1,11 @@
 /// Configuration for load testing simulator.
 #[cfg(feature = "load-test")]
+use crate::simulator::budget::AttemptPricing;
+#[cfg(feature = "load-test")]
+use serde::Deserialize;
+#[cfg(feature = "load-test")]
+use std::path::Path;
+#[cfg(feature = "load-test")]
 use std::time::Duration;
 
 /// Simulator configuration.
@@ -20,6 +26,10 @@ pub struct SimulatorConfig {
     pub dry_run: bool,
     /// Maximum cost threshold (stop if exceeded)
     pub max_cost: Option<f64>,
+    /// Prices charged per attempt against `max_cost`; without them the cap is not enforced
+    pub pricing: Option<AttemptPricing>,
+    /// Seed for think-time randomness, for reproducible runs
+    pub seed: Option<u64>,
 }
 
 /// Think time configuration.
@@ -44,23 +54,27 @@ impl SimulatorConfig {
             timeout: Duration::from_secs(60),
             dry_run: false,
             max_cost: None,
+            pricing: None,
+            seed: None,
         }
     }
 
-    /// Parse think time from string (e.g., "250-750ms" or "500ms").
+    /// Parse think time from string (e.g., "250-750ms", "500ms" or "1.5s").
+    ///
+    /// Values are milliseconds unless suffixed with `ms` or `s`; seconds may
+    /// be fractional, and the result is rounded to whole milliseconds. In a
+    /// range, a unit written only after the maximum applies to both ends
+    /// (`1-2s`), and spaces around the numbers, units and dash are ignored.
     pub fn parse_think_time(s: &str) -> Result<ThinkTime, String> {
         let s = s.trim().to_lowercase();
-        let s = s.strip_suffix("ms").unwrap_or(&s);
 
         if let Some((min_str, max_str)) = s.split_once('-') {
-            let min_ms = min_str
-                .trim()
-                .parse::<u64>()
-                .map_err(|_| "Invalid min think time")?;
-            let max_ms = max_str
-                .trim()
-                .parse::<u64>()
-                .map_err(|_| "Invalid max think time")?;
+            let (max_num, max_unit) = split_unit(max_str);
+            let (min_num, min_unit) = split_unit(min_str);
+            let max_ms = to_ms(max_num, max_unit.unwrap_or(1))
+                .ok_or_else(|| format!("Invalid max think time '{}'", max_str.trim()))?;
+            let min_ms = to_ms(min_num, min_unit.or(max_unit).unwrap_or(1))
+                .ok_or_else(|| format!("Invalid min think time '{}'", min_str.trim()))?;
 
             if min_ms > max_ms {
                 return Err("Min think time must be <= max think time".to_string());
@@ -68,10 +82,10 @@ impl SimulatorConfig {
 
             Ok(ThinkTime { min_ms, max_ms })
         } else {
-            let ms = s
-                .trim()
-                .parse::<u64>()
-                .map_err(|_| "Invalid think time format. Use '250-750ms' or '500ms'".to_string())?;
+            let (num, unit) = split_unit(&s);
+            let ms = to_ms(num, unit.unwrap_or(1)).ok_or_else(|| {
+                "Invalid think time format. Use '250-750ms', '500ms' or '2s'".to_string()
+            })?;
             Ok(ThinkTime {
                 min_ms: ms,
                 max_ms: ms,
@@ -80,6 +94,122 @@ impl SimulatorConfig {
     }
 }
 
+/// Split a think-time value into its number and unit scale in milliseconds.
+#[cfg(feature = "load-test")]
+fn split_unit(value: &str) -> (&str, Option<u64>) {
+    let value = value.trim();
+    if let Some(number) = value.strip_suffix("ms") {
+        (number.trim_end(), Some(1))
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number.trim_end(), Some(1000))
+    } else {
+        (value, None)
+    }
+}
+
+/// Convert a non-negative number of `scale`-millisecond units to milliseconds.
+#[cfg(feature = "load-test")]
+fn to_ms(number: &str, scale: u64) -> Option<u64> {
+    // `f64` parsing accepts "inf" and "nan"; only plain decimals are valid here
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let ms = number.parse::<f64>().ok()? * scale as f64;
+    (ms.is_finite() && ms < u64::MAX as f64).then(|| ms.round() as u64)
+}
+
+/// Load-test settings from a config file or the command line.
+///
+/// Every field is optional so that settings can be layered: values from a
+/// config file's `[load_test]` section are overridden by those given as CLI
+/// flags, and anything left unset falls back to the defaults.
+#[cfg(feature = "load-test")]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoadTestSettings {
+    /// Number of concurrent requests
+    pub concurrency: Option<usize>,
+    /// Total number of requests to make
+    pub runs: Option<usize>,
+    /// Retry count on failure
+    pub retry: Option<u32>,
+    /// Think time between requests (e.g., "250-750ms", "500ms" or "1-2s")
+    pub think_time: Option<String>,
+    /// Request timeout in seconds
+    pub timeout: Option<u64>,
+    /// Maximum cost threshold
+    pub max_cost: Option<f64>,
+    /// Seed for think-time randomness
+    pub seed: Option<u64>,
+}
+
+/// Top-level layout of a load-test config file.
+#[cfg(feature = "load-test")]
+#[derive(Debug, Deserialize)]
+struct LoadTestConfigFile {
+    #[serde(default)]
+    load_test: LoadTestSettings,
+}
+
+#[cfg(feature = "load-test")]
+impl LoadTestSettings {
+    /// Read the `[load_test]` section of a TOML config file.
+    ///
+    /// A file without the section yields empty settings.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read load-test config '{}': {}",
+                path.display(),
+                e
+            )
+        })?;
+        let file: LoadTestConfigFile = toml::from_str(&content)
+            .map_err(|e| format!("Invalid load-test config '{}': {}", path.display(), e))?;
+        Ok(file.load_test)
+    }
+
+    /// Layer `overrides` on top of these settings; values set there win.
+    pub fn overridden_by(self, overrides: Self) -> Self {
+        Self {
+            concurrency: overrides.concurrency.or(self.concurrency),
+            runs: overrides.runs.or(self.runs),
+            retry: overrides.retry.or(self.retry),
+            think_time: overrides.think_time.or(self.think_time),
+            timeout: overrides.timeout.or(self.timeout),
+            max_cost: overrides.max_cost.or(self.max_cost),
+            seed: overrides.seed.or(self.seed),
+        }
+    }
+
+    /// Build a simulator configuration, filling unset values with defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `runs` is unset or the think time is invalid.
+    pub fn to_simulator_config(&self) -> Result<SimulatorConfig, String> {
+        let runs = self
+            .runs
+            .ok_or("Number of runs is required (--runs or `runs` in the config file)")?;
+        let defaults = SimulatorConfig::default();
+
+        let mut config =
+            SimulatorConfig::new(self.concurrency.unwrap_or(defaults.concurrency), runs);
+        config.retry = self.retry.unwrap_or(defaults.retry);
+        config.timeout = self
+            .timeout
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.timeout);
+        config.max_cost = self.max_cost;
+        config.seed = self.seed;
+        if let Some(ref think_time) = self.think_time {
+            config.think_time = Some(SimulatorConfig::parse_think_time(think_time)?);
+        }
+        Ok(config)
+    }
+}
+
 #[cfg(feature = "load-test")]
 impl Default for SimulatorConfig {
     fn default() -> Self {
@@ -137,4 +267,120 @@ mod tests {
         let result = SimulatorConfig::parse_think_time("invalid");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_think_time_units_and_spacing() {
+        let parse = |s: &str| {
+            let t = SimulatorConfig::parse_think_time(s).unwrap();
+            (t.min_ms, t.max_ms)
+        };
+        assert_eq!(parse("2s"), (2000, 2000));
+        assert_eq!(parse("1.5s"), (1500, 1500));
+        assert_eq!(parse("0.25 s"), (250, 250));
+        assert_eq!(parse("250 - 750 ms"), (250, 750));
+        assert_eq!(parse("1-2s"), (1000, 2000));
+        assert_eq!(parse("500ms-2s"), (500, 2000));
+        assert_eq!(parse("0.5s - 1500ms"), (500, 1500));
+        assert_eq!(parse(" 300MS "), (300, 300));
+        assert_eq!(parse("2.4ms"), (2, 2));
+    }
+
+    #[test]
+    fn test_parse_think_time_rejections() {
+        for input in [
+            "", "s", "ms", "-", "-5ms", "1-", "1--2", "1-2-3", "1.2.3s", "nan", "inf", "1e3", "5m",
+            "5 sec", "2s-1s", "1 000ms",
+        ] {
+            assert!(
+                SimulatorConfig::parse_think_time(input).is_err(),
+                "'{}' should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_think_time_never_panics() {
+        // Random strings over the grammar's alphabet plus some noise
+        let alphabet: Vec<char> = "0123456789.-+ \tmsMSeinf\u{e9}\u{221e}x".chars().collect();
+        let mut rng = fastrand::Rng::with_seed(7);
+        for _ in 0..20_000 {
+            let len = rng.usize(0..12);
+            let input: String = (0..len)
+                .map(|_| alphabet[rng.usize(..alphabet.len())])
+                .collect();
+            if let Ok(t) = SimulatorConfig::parse_think_time(&input) {
+                assert!(t.min_ms <= t.max_ms, "'{}' gave {:?}", input, t);
+            }
+        }
+
+        // Generated valid ranges parse back to the same bounds
+        for _ in 0..2_000 {
+            let min = rng.u64(0..100_000);
+            let max = rng.u64(min..=200_000);
+            let input = match rng.u8(0..3) {
+                0 => format!("{}-{}ms", min, max),
+                1 => format!("{} ms - {} ms", min, max),
+                _ => format!("{}s-{}s", min as f64 / 1000.0, max as f64 / 1000.0),
+            };
+            let t = SimulatorConfig::parse_think_time(&input).unwrap();
+            assert_eq!((t.min_ms, t.max_ms), (min, max), "'{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_settings_from_file_with_cli_override() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+            [load_test]
+            concurrency = 4
+            runs = 20
+            retry = 1
+            think_time = "250-750ms"
+            timeout = 30
+            max_cost = 2.5
+            seed = 42
+            "#
+        )
+        .unwrap();
+
+        let from_file = LoadTestSettings::from_file(file.path()).unwrap();
+        let cli = LoadTestSettings {
+            concurrency: Some(8),
+            ..Default::default()
+        };
+        let config = from_file.overridden_by(cli).to_simulator_config().unwrap();
+
+        assert_eq!(config.concurrency, 8, "CLI value should take precedence");
+        assert_eq!(config.runs, 20);
+        assert_eq!(config.retry, 1);
+        let think_time = config.think_time.unwrap();
+        assert_eq!((think_time.min_ms, think_time.max_ms), (250, 750));
+        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert_eq!(config.max_cost, Some(2.5));
+        assert_eq!(config.seed, Some(42));
+    }
+
+    #[test]
+    fn test_settings_defaults_and_errors() {
+        let config = LoadTestSettings {
+            runs: Some(5),
+            ..Default::default()
+        }
+        .to_simulator_config()
+        .unwrap();
+        assert_eq!(config.concurrency, 10);
+        assert_eq!(config.retry, 3);
+        assert_eq!(config.timeout, Duration::from_secs(60));
+
+        assert!(LoadTestSettings::default().to_simulator_config().is_err());
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"[load_test]\nconcurency = 4\n").unwrap();
+        assert!(LoadTestSettings::from_file(file.path()).is_err());
+    }
 }
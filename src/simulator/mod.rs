@@ -1,6 +1,8 @@
 #![allow(clippy::module_inception)]
 /// Load testing simulator for LLM APIs.
 #[cfg(feature = "load-test")]
+pub mod budget;
+#[cfg(feature = "load-test")]
 pub mod config;
 #[cfg(feature = "load-test")]
 pub mod simulator;
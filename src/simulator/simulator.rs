@@ -4,6 +4,8 @@ use crate::error::AppError;
 #[cfg(feature = "load-test")]
 use crate::http::client::LlmClient;
 #[cfg(feature = "load-test")]
+use crate::simulator::budget::CostBudget;
+#[cfg(feature = "load-test")]
 use crate::simulator::config::SimulatorConfig;
 #[cfg(feature = "load-test")]
 use std::sync::Arc;
@@ -36,13 +38,28 @@ pub struct RequestResult {
 #[cfg(feature = "load-test")]
 pub struct Simulator {
     config: SimulatorConfig,
+    budget: Option<Arc<CostBudget>>,
 }
 
 #[cfg(feature = "load-test")]
 impl Simulator {
     /// Create a new simulator.
+    ///
+    /// With both `max_cost` and `pricing` set, every attempt, retries
+    /// included, is charged against the cost cap.
     pub fn new(config: SimulatorConfig) -> Self {
-        Self { config }
+        let budget = match (config.max_cost, config.pricing) {
+            (Some(limit), Some(pricing)) if !config.dry_run => {
+                Some(Arc::new(CostBudget::new(limit, pricing)))
+            }
+            _ => None,
+        };
+        Self { config, budget }
+    }
+
+    /// Cost charged against `max_cost` so far, if the cap is enforced.
+    pub fn spent(&self) -> Option<f64> {
+        self.budget.as_ref().map(|budget| budget.spent())
     }
 
     /// Run the load test.
@@ -70,16 +87,29 @@ impl Simulator {
         let failed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
         let total_latency = Arc::new(std::sync::atomic::AtomicU64::new(0));
         let start_time = Arc::new(std::sync::Mutex::new(Instant::now()));
+        // A fixed seed makes think-time delays repeat across runs
+        let mut rng = match self.config.seed {
+            Some(seed) => fastrand::Rng::with_seed(seed),
+            None => fastrand::Rng::new(),
+        };
 
         for _i in 0..self.config.runs {
             let permit = semaphore.clone().acquire_owned().await.map_err(|e| {
                 AppError::Config(format!("Failed to acquire semaphore permit: {}", e))
             })?;
 
+            // Stop scheduling once the cost cap can't cover another attempt
+            if let Some(ref budget) = self.budget {
+                if !budget.can_afford_attempt() {
+                    break;
+                }
+            }
+
             let client = client.clone();
             let prompt = prompt.to_string();
             let model = model.to_string();
             let config = self.config.clone();
+            let budget = self.budget.clone();
             let progress = progress_bar.clone();
             let completed_clone = completed.clone();
             let successful_clone = successful.clone();
@@ -89,7 +119,7 @@ impl Simulator {
 
             let handle = tokio::spawn(async move {
                 let _permit = permit;
-                let result = Self::execute_request(client, &prompt, &model, &config).await;
+                let result = Self::execute_request(client, &prompt, &model, &config, budget).await;
 
                 // Update progress
                 let completed_count =
@@ -141,7 +171,7 @@ impl Simulator {
                 let delay_ms = if think_time.min_ms == think_time.max_ms {
                     think_time.min_ms
                 } else {
-                    fastrand::u64(think_time.min_ms..=think_time.max_ms)
+                    rng.u64(think_time.min_ms..=think_time.max_ms)
                 };
                 sleep(Duration::from_millis(delay_ms)).await;
             }
@@ -173,11 +203,15 @@ impl Simulator {
     }
 
     /// Execute a single request with retries.
+    ///
+    /// Each attempt reserves its cost in `budget` first; a retry that would
+    /// exceed the cap is cancelled and the request fails.
     async fn execute_request<C: LlmClient>(
         client: Arc<C>,
         prompt: &str,
         model: &str,
         config: &SimulatorConfig,
+        budget: Option<Arc<CostBudget>>,
     ) -> RequestResult {
         if config.dry_run {
             return RequestResult {
@@ -194,9 +228,29 @@ impl Simulator {
         let mut last_error = None;
 
         for attempt in 0..=config.retry {
+            if let Some(ref budget) = budget {
+                if !budget.try_reserve() {
+                    let reason = format!("Cost cap of ${:.4} reached", budget.limit());
+                    last_error = Some(match last_error {
+                        Some(error) => format!("{}; retry cancelled: {}", error, reason),
+                        None => reason,
+                    });
+                    break;
+                }
+            }
+
             let start = Instant::now();
+            let outcome = client.send_request(prompt, model).await;
+            if let Some(ref budget) = budget {
+                budget.settle(
+                    outcome
+                        .as_ref()
+                        .ok()
+                        .map(|response| (response.input_tokens, response.output_tokens)),
+                );
+            }
 
-            match client.send_request(prompt, model).await {
+            match outcome {
                 Ok(response) => {
                     let latency_ms = start.elapsed().as_millis() as u64;
                     return RequestResult {
@@ -357,4 +411,42 @@ mod tests {
             Some("API error: persistent failure")
         );
     }
+
+    #[tokio::test]
+    async fn retries_count_against_max_cost() {
+        use crate::simulator::budget::AttemptPricing;
+
+        let failures = (0..10)
+            .map(|i| Err(AppError::Api(format!("forced failure {}", i))))
+            .collect();
+        let client = Arc::new(MockClient::new(failures));
+
+        // Every attempt costs $0.10, so the cap covers four attempts
+        let mut config = SimulatorConfig::new(1, 5);
+        config.retry = 2;
+        config.max_cost = Some(0.45);
+        config.pricing = Some(AttemptPricing {
+            input_per_1k: 1.0,
+            output_per_1k: 2.0,
+            prompt_tokens: 100,
+            expected_output_tokens: 0,
+        });
+        let simulator = Simulator::new(config);
+
+        let results = simulator
+            .run(client.clone(), "prompt", "mock-model")
+            .await
+            .expect("simulation should complete");
+
+        assert_eq!(
+            client.calls(),
+            4,
+            "three attempts of the first request and one of the second"
+        );
+        assert_eq!(results.len(), 2, "no requests scheduled past the cap");
+        assert!(results.iter().all(|r| !r.success));
+        let error = results[1].error.as_deref().unwrap();
+        assert!(error.contains("retry cancelled"), "{}", error);
+        assert!((simulator.spent().unwrap() - 0.4).abs() < 1e-9);
+    }
 }
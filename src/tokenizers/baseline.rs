@@ -0,0 +1,205 @@
+/// Naive baseline "tokenizers" for putting real token counts in context.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+
+/// Counts Unicode scalar values.
+///
+/// Token IDs are the scalar values themselves, so encoding round-trips. They
+/// aren't a model's vocabulary, so the tokenizer isn't exact.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::tokenizers::{CharTokenizer, Tokenizer};
+///
+/// let tokenizer = CharTokenizer::new();
+/// assert_eq!(tokenizer.count_tokens("héllo")?, 5);
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharTokenizer;
+
+impl CharTokenizer {
+    /// Create a new character baseline.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tokenizer for CharTokenizer {
+    fn encode(&self, text: &str) -> Result<Vec<usize>, TokenizerError> {
+        Ok(text.chars().map(|c| c as usize).collect())
+    }
+
+    fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError> {
+        tokens
+            .iter()
+            .map(|&t| {
+                u32::try_from(t)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        TokenizerError::DecodingFailed(format!("Invalid scalar value: {}", t))
+                    })
+            })
+            .collect()
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize, TokenizerError> {
+        Ok(text.chars().count())
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> &str {
+        "chars"
+    }
+
+    fn input_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+
+    fn output_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Counts whitespace-delimited words.
+///
+/// Token IDs are word positions; words cannot be decoded back to text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WordTokenizer;
+
+impl WordTokenizer {
+    /// Create a new word baseline.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tokenizer for WordTokenizer {
+    fn encode(&self, text: &str) -> Result<Vec<usize>, TokenizerError> {
+        Ok((0..text.split_whitespace().count()).collect())
+    }
+
+    fn decode(&self, _tokens: &[usize]) -> Result<String, TokenizerError> {
+        Err(TokenizerError::DecodingFailed(
+            "Word baseline does not keep a vocabulary".to_string(),
+        ))
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize, TokenizerError> {
+        Ok(text.split_whitespace().count())
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> &str {
+        "words"
+    }
+
+    fn input_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+
+    fn output_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Counts UTF-8 bytes.
+///
+/// Token IDs are byte values, so encoding round-trips. They aren't a model's
+/// vocabulary, so the tokenizer isn't exact.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByteTokenizer;
+
+impl ByteTokenizer {
+    /// Create a new byte baseline.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Tokenizer for ByteTokenizer {
+    fn encode(&self, text: &str) -> Result<Vec<usize>, TokenizerError> {
+        Ok(text.bytes().map(usize::from).collect())
+    }
+
+    fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError> {
+        let bytes = tokens
+            .iter()
+            .map(|&t| {
+                u8::try_from(t).map_err(|_| {
+                    TokenizerError::DecodingFailed(format!("Invalid byte value: {}", t))
+                })
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        String::from_utf8(bytes).map_err(|e| TokenizerError::DecodingFailed(e.to_string()))
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize, TokenizerError> {
+        Ok(text.len())
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> &str {
+        "bytes"
+    }
+
+    fn input_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+
+    fn output_price_per_1k(&self) -> Option<f64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_count_whitespace_delimited() {
+        let tokenizer = WordTokenizer::new();
+        assert_eq!(
+            tokenizer.count_tokens("Hello,  world!\n\tagain").unwrap(),
+            3
+        );
+        assert_eq!(tokenizer.count_tokens("   ").unwrap(), 0);
+        assert_eq!(tokenizer.encode("a b c").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_chars_count_scalar_values() {
+        let tokenizer = CharTokenizer::new();
+        let text = "Hello 世界 🌍";
+        assert_eq!(tokenizer.count_tokens(text).unwrap(), 10);
+        let tokens = tokenizer.encode(text).unwrap();
+        assert_eq!(tokenizer.decode(&tokens).unwrap(), text);
+    }
+
+    #[test]
+    fn test_bytes_count_utf8_bytes() {
+        let tokenizer = ByteTokenizer::new();
+        let text = "héllo";
+        assert_eq!(tokenizer.count_tokens(text).unwrap(), 6);
+        let tokens = tokenizer.encode(text).unwrap();
+        assert_eq!(tokenizer.decode(&tokens).unwrap(), text);
+    }
+
+    #[test]
+    fn test_baselines_have_no_pricing() {
+        assert!(CharTokenizer::new().input_price_per_1k().is_none());
+        assert!(WordTokenizer::new().output_price_per_1k().is_none());
+        assert!(ByteTokenizer::new().input_price_per_1k().is_none());
+    }
+}
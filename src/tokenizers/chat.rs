@@ -0,0 +1,479 @@
+/// Fixed token overhead added by chat-format APIs.
+use crate::error::TokenizerError;
+use crate::parsers::Message;
+use crate::tokenizers::Tokenizer;
+
+/// Text markers a provider inserts around each message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// No text markers; only the fixed token counts apply.
+    #[default]
+    Fixed,
+    /// Claude-style `\n\nHuman:` / `\n\nAssistant:` turns.
+    HumanAssistant,
+}
+
+impl Framing {
+    /// Marker placed before a message with the given role.
+    pub fn role_prefix(&self, role: &str) -> &'static str {
+        match (self, role) {
+            (Framing::HumanAssistant, "user") => "\n\nHuman: ",
+            (Framing::HumanAssistant, "assistant") => "\n\nAssistant: ",
+            _ => "",
+        }
+    }
+
+    /// Marker that primes the reply at the end of the prompt.
+    pub fn reply_prefix(&self) -> &'static str {
+        match self {
+            Framing::Fixed => "",
+            Framing::HumanAssistant => "\n\nAssistant:",
+        }
+    }
+}
+
+/// Tokens a chat API adds around message content.
+///
+/// Chat endpoints wrap every message in framing tokens and prime the
+/// assistant's reply, so a transcript costs more than its raw content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChatOverhead {
+    /// Tokens added for every message, including empty ones.
+    pub per_message: usize,
+    /// Tokens added once per request to prime the reply.
+    pub reply_priming: usize,
+    /// Text markers, tokenized with the counting tokenizer.
+    pub framing: Framing,
+}
+
+impl ChatOverhead {
+    /// OpenAI chat models: 3 tokens per message and 3 to prime the reply.
+    pub const OPENAI: Self = Self {
+        per_message: 3,
+        reply_priming: 3,
+        framing: Framing::Fixed,
+    };
+
+    /// Anthropic models: `Human:`/`Assistant:` turn markers.
+    ///
+    /// The markers are the prompt format of Anthropic's Text Completions API
+    /// (<https://docs.anthropic.com/en/api/complete>). The Messages API
+    /// frames turns the same way server-side but doesn't publish its token
+    /// overhead, so for it this is an estimate.
+    pub const ANTHROPIC: Self = Self {
+        per_message: 0,
+        reply_priming: 0,
+        framing: Framing::HumanAssistant,
+    };
+
+    /// Overhead for the given model, or none if its chat format is unknown.
+    pub fn for_model(model: &str) -> Self {
+        let base = model.rsplit('/').next().unwrap_or(model);
+        if base.starts_with("gpt-") {
+            Self::OPENAI
+        } else if base.starts_with("claude") {
+            Self::ANTHROPIC
+        } else {
+            Self::default()
+        }
+    }
+}
+
+/// Token counts for a message list, split into content and overhead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChatCount {
+    /// Tokens from message content.
+    pub content: usize,
+    /// Tokens from per-message framing and reply priming.
+    pub overhead: usize,
+}
+
+impl ChatCount {
+    /// Total tokens (content plus overhead).
+    pub fn total(&self) -> usize {
+        self.content + self.overhead
+    }
+}
+
+/// Callback receiving each message and its token count.
+#[allow(dead_code)]
+pub type MessageCallback<'a> = &'a mut dyn FnMut(&Message, usize);
+
+/// Count the tokens of a message list, split into content and chat
+/// overhead.
+///
+/// `on_message` is called once per message, in order, with that message's
+/// count (content plus its framing). Embedders can use it for logging or
+/// metrics without re-implementing the loop; pass `None` to skip. The
+/// returned overhead also includes the reply priming.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if any message cannot be tokenized.
+///
+/// # Example
+///
+/// ```rust
+/// # use tokuin::parsers::Message;
+/// # use tokuin::tokenizers::{chat, ChatOverhead, CharTokenizer};
+/// let messages = vec![Message {
+///     role: "user".to_string(),
+///     content: "hi".to_string(),
+///     name: None,
+///     tool_call_id: None,
+/// }];
+/// let mut seen = Vec::new();
+/// let mut log = |message: &Message, count: usize| seen.push((message.role.clone(), count));
+/// let count = chat::count_chat_messages(
+///     &CharTokenizer::new(),
+///     &messages,
+///     ChatOverhead::default(),
+///     Some(&mut log),
+/// )?;
+/// assert_eq!(count.total(), 2);
+/// assert_eq!(seen, vec![("user".to_string(), 2)]);
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+#[allow(dead_code)]
+pub fn count_chat_messages(
+    tokenizer: &dyn Tokenizer,
+    messages: &[Message],
+    overhead: ChatOverhead,
+    mut on_message: Option<MessageCallback<'_>>,
+) -> Result<ChatCount, TokenizerError> {
+    let mut counter = ChatCounter::new(tokenizer, overhead);
+    for message in messages {
+        let count = counter.add(message)?;
+        if let Some(callback) = on_message.as_mut() {
+            callback(message, count);
+        }
+    }
+    counter.finish()
+}
+
+/// Incremental form of [`count_chat_messages`] for messages that arrive one at a
+/// time, e.g. from a streaming parser.
+pub struct ChatCounter<'a> {
+    tokenizer: &'a dyn Tokenizer,
+    overhead: ChatOverhead,
+    role_markers: bool,
+    count: ChatCount,
+}
+
+impl<'a> ChatCounter<'a> {
+    /// Create a counter with no messages.
+    pub fn new(tokenizer: &'a dyn Tokenizer, overhead: ChatOverhead) -> Self {
+        Self {
+            tokenizer,
+            overhead,
+            role_markers: false,
+            count: ChatCount::default(),
+        }
+    }
+
+    /// Also count each message's role string, as OpenAI tokenizes it with
+    /// the message.
+    ///
+    /// The role's tokens are framing, attributed to that message. Framings
+    /// with text markers such as `Human:` already spell out the role, so
+    /// they add nothing more.
+    pub fn with_role_markers(mut self) -> Self {
+        self.role_markers = true;
+        self
+    }
+
+    /// Count one message, returning its content plus framing tokens.
+    ///
+    /// A tool result's `tool_call_id` is sent with it, so its tokens count
+    /// as content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the message cannot be tokenized.
+    pub fn add(&mut self, message: &Message) -> Result<usize, TokenizerError> {
+        let content = self.tokenizer.count_tokens(&message.content)?;
+        self.add_counted(message, content)
+    }
+
+    /// Like [`add`](Self::add), but also return the token ids of the
+    /// message content, from the same encoding that is counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the message cannot be tokenized.
+    pub fn add_encoded(
+        &mut self,
+        message: &Message,
+    ) -> Result<(usize, Vec<usize>), TokenizerError> {
+        let ids = self.tokenizer.encode(&message.content)?;
+        let count = self.add_counted(message, ids.len())?;
+        Ok((count, ids))
+    }
+
+    fn add_counted(
+        &mut self,
+        message: &Message,
+        mut content: usize,
+    ) -> Result<usize, TokenizerError> {
+        if let Some(id) = &message.tool_call_id {
+            content += self.tokenizer.count_tokens(id)?;
+        }
+        // Empty messages still pay the per-message framing
+        let mut framing = self.overhead.per_message
+            + count_marker(
+                self.tokenizer,
+                self.overhead.framing.role_prefix(&message.role),
+            )?;
+        if self.role_markers && self.overhead.framing == Framing::Fixed {
+            framing += count_marker(self.tokenizer, &message.role)?;
+        }
+        self.count.content += content;
+        self.count.overhead += framing;
+        Ok(content + framing)
+    }
+
+    /// Finish counting, adding the reply priming.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the reply marker cannot be tokenized.
+    pub fn finish(self) -> Result<ChatCount, TokenizerError> {
+        let mut count = self.count;
+        count.overhead += self.overhead.reply_priming
+            + count_marker(self.tokenizer, self.overhead.framing.reply_prefix())?;
+        Ok(count)
+    }
+}
+
+fn count_marker(tokenizer: &dyn Tokenizer, marker: &str) -> Result<usize, TokenizerError> {
+    if marker.is_empty() {
+        Ok(0)
+    } else {
+        tokenizer.count_tokens(marker)
+    }
+}
+
+/// Number of messages whose content is empty or whitespace only.
+pub fn count_empty(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .filter(|message| message.content.trim().is_empty())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_model() {
+        assert_eq!(ChatOverhead::for_model("gpt-4"), ChatOverhead::OPENAI);
+        assert_eq!(
+            ChatOverhead::for_model("openai/gpt-4"),
+            ChatOverhead::OPENAI
+        );
+        assert_eq!(
+            ChatOverhead::for_model("claude-3-haiku"),
+            ChatOverhead::ANTHROPIC
+        );
+        assert_eq!(ChatOverhead::for_model("chars"), ChatOverhead::default());
+    }
+
+    #[test]
+    fn test_count_messages_callback() {
+        use crate::tokenizers::CharTokenizer;
+
+        let messages = vec![
+            Message {
+                role: "system".to_string(),
+                content: "abc".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            Message {
+                role: "user".to_string(),
+                content: "hello".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        let overhead = ChatOverhead {
+            per_message: 1,
+            reply_priming: 2,
+            framing: Framing::Fixed,
+        };
+        let mut seen = Vec::new();
+        let mut record = |message: &Message, count: usize| {
+            seen.push((message.role.clone(), count));
+        };
+
+        let count = count_chat_messages(
+            &CharTokenizer::new(),
+            &messages,
+            overhead,
+            Some(&mut record),
+        )
+        .unwrap();
+
+        assert_eq!(
+            seen,
+            vec![("system".to_string(), 4), ("user".to_string(), 6)]
+        );
+        assert_eq!(count.total(), 4 + 6 + 2);
+        assert_eq!(
+            count_chat_messages(&CharTokenizer::new(), &messages, overhead, None).unwrap(),
+            count
+        );
+    }
+
+    #[test]
+    fn test_add_encoded_counts_like_add() {
+        use crate::tokenizers::CharTokenizer;
+
+        let tokenizer = CharTokenizer::new();
+        let mut counted = ChatCounter::new(&tokenizer, ChatOverhead::OPENAI);
+        let mut encoded = ChatCounter::new(&tokenizer, ChatOverhead::OPENAI);
+        for message in transcript() {
+            let (count, ids) = encoded.add_encoded(&message).unwrap();
+            assert_eq!(count, counted.add(&message).unwrap());
+            assert_eq!(ids, tokenizer.encode(&message.content).unwrap());
+        }
+        assert_eq!(encoded.finish().unwrap(), counted.finish().unwrap());
+    }
+
+    fn transcript() -> Vec<Message> {
+        [("system", "abc"), ("user", "hi"), ("assistant", "ok")]
+            .into_iter()
+            .map(|(role, content)| Message {
+                role: role.to_string(),
+                content: content.to_string(),
+                name: None,
+                tool_call_id: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_openai_vs_anthropic_framing() {
+        use crate::tokenizers::CharTokenizer;
+
+        let tokenizer = CharTokenizer::new();
+        let messages = transcript();
+
+        let mut openai_roles = Vec::new();
+        let mut record = |message: &Message, count: usize| {
+            openai_roles.push((message.role.clone(), count));
+        };
+        let openai = count_chat_messages(
+            &tokenizer,
+            &messages,
+            ChatOverhead::OPENAI,
+            Some(&mut record),
+        )
+        .unwrap();
+
+        let mut claude_roles = Vec::new();
+        let mut record = |message: &Message, count: usize| {
+            claude_roles.push((message.role.clone(), count));
+        };
+        let claude = count_chat_messages(
+            &tokenizer,
+            &messages,
+            ChatOverhead::ANTHROPIC,
+            Some(&mut record),
+        )
+        .unwrap();
+
+        // Same content, different framing
+        assert_eq!(openai.content, 7);
+        assert_eq!(claude.content, 7);
+        assert_eq!(openai.overhead, 3 * 3 + 3);
+        // "\n\nHuman: " + "\n\nAssistant: " + "\n\nAssistant:"
+        assert_eq!(claude.overhead, 9 + 13 + 12);
+
+        // Framing is attributed to each message's role
+        assert_eq!(
+            openai_roles,
+            vec![
+                ("system".to_string(), 6),
+                ("user".to_string(), 5),
+                ("assistant".to_string(), 5)
+            ]
+        );
+        assert_eq!(
+            claude_roles,
+            vec![
+                ("system".to_string(), 3),
+                ("user".to_string(), 11),
+                ("assistant".to_string(), 15)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_role_markers_add_role_tokens() {
+        use crate::tokenizers::CharTokenizer;
+
+        let tokenizer = CharTokenizer::new();
+        let per_message = |counter: &mut ChatCounter| {
+            transcript()
+                .iter()
+                .map(|message| counter.add(message).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let plain = per_message(&mut ChatCounter::new(&tokenizer, ChatOverhead::OPENAI));
+        let mut counter = ChatCounter::new(&tokenizer, ChatOverhead::OPENAI).with_role_markers();
+        let marked = per_message(&mut counter);
+        // Each role adds its own length in characters, so "assistant" adds 9
+        assert_eq!(marked, vec![plain[0] + 6, plain[1] + 4, plain[2] + 9]);
+        assert_eq!(counter.finish().unwrap().overhead, 3 * 3 + 3 + 19);
+
+        // Turn markers already spell out the role
+        let mut counter = ChatCounter::new(&tokenizer, ChatOverhead::ANTHROPIC).with_role_markers();
+        let mut claude = ChatCounter::new(&tokenizer, ChatOverhead::ANTHROPIC);
+        assert_eq!(per_message(&mut counter), per_message(&mut claude));
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_role_markers_with_bpe() {
+        use crate::tokenizers::OpenAITokenizer;
+
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        let message = Message {
+            role: "assistant".to_string(),
+            content: "Sure, here you go.".to_string(),
+            name: None,
+            tool_call_id: None,
+        };
+        let role_tokens = tokenizer.count_tokens("assistant").unwrap();
+        let plain = ChatCounter::new(&tokenizer, ChatOverhead::OPENAI)
+            .add(&message)
+            .unwrap();
+        let marked = ChatCounter::new(&tokenizer, ChatOverhead::OPENAI)
+            .with_role_markers()
+            .add(&message)
+            .unwrap();
+        assert_eq!(marked - plain, role_tokens);
+    }
+
+    #[test]
+    fn test_count_empty() {
+        let messages = vec![
+            Message {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+            Message {
+                role: "assistant".to_string(),
+                content: "  ".to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        ];
+        assert_eq!(count_empty(&messages), 1);
+    }
+}
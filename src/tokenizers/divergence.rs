@@ -0,0 +1,171 @@
+/// Where two tokenizers first split the same text differently.
+use crate::error::TokenizerError;
+use crate::tokenizers::{decode_fragments, Tokenizer};
+
+/// Number of tokens shown from each side, starting at the divergence.
+const CONTEXT_TOKENS: usize = 5;
+
+/// Outcome of comparing two tokenizations of the same text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// Both tokenizers put token boundaries in the same places.
+    None {
+        /// Number of tokens on each side.
+        tokens: usize,
+    },
+    /// The token boundaries differ from `position` on.
+    At {
+        /// Character index where the first differing token starts.
+        position: usize,
+        /// Index of the first differing token, the same on both sides.
+        token: usize,
+        /// Decoded tokens from the left, starting at the divergence.
+        left: Vec<String>,
+        /// Decoded tokens from the right, starting at the divergence.
+        right: Vec<String>,
+    },
+    /// A tokenizer approximates counts and has no token boundaries.
+    Unsupported,
+}
+
+/// Encode `text` with both tokenizers and find the first token boundary
+/// they disagree on.
+///
+/// Only boundaries are compared, so two vocabularies that give the same
+/// split under different token ids do not diverge. A character whose tokens
+/// are cut off by the context is shown as `<partial character>`.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if either tokenizer cannot encode `text`.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::tokenizers::divergence::{find_divergence, Divergence};
+/// use tokuin::tokenizers::OpenAITokenizer;
+///
+/// let gpt4 = OpenAITokenizer::new("gpt-4")?;
+/// let same = find_divergence(&gpt4, &gpt4, "Hello, world!")?;
+/// assert_eq!(same, Divergence::None { tokens: 4 });
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+pub fn find_divergence(
+    left: &dyn Tokenizer,
+    right: &dyn Tokenizer,
+    text: &str,
+) -> Result<Divergence, TokenizerError> {
+    if !left.is_exact() || !right.is_exact() {
+        return Ok(Divergence::Unsupported);
+    }
+
+    let (left_tokens, left_ends) = token_ends(left, text)?;
+    let (right_tokens, right_ends) = token_ends(right, text)?;
+
+    let shared = left_ends
+        .iter()
+        .zip(&right_ends)
+        .take_while(|(l, r)| l == r)
+        .count();
+    if shared == left_ends.len() && shared == right_ends.len() {
+        return Ok(Divergence::None { tokens: shared });
+    }
+
+    let offset = match shared {
+        0 => 0,
+        n => left_ends[n - 1],
+    };
+    let context = |tokenizer: &dyn Tokenizer, tokens: &[usize]| {
+        let end = (shared + CONTEXT_TOKENS).min(tokens.len());
+        decode_fragments(tokenizer, &tokens[shared..end])
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    };
+
+    Ok(Divergence::At {
+        position: char_index(text, offset),
+        token: shared,
+        left: context(left, &left_tokens),
+        right: context(right, &right_tokens),
+    })
+}
+
+/// Tokens of `text` and the byte offset just past each of them.
+fn token_ends(
+    tokenizer: &dyn Tokenizer,
+    text: &str,
+) -> Result<(Vec<usize>, Vec<usize>), TokenizerError> {
+    Ok(tokenizer
+        .encode_with_offsets(text)?
+        .into_iter()
+        .map(|(token, range)| (token, range.end))
+        .unzip())
+}
+
+/// Character index of a byte offset, rounding down inside a character.
+fn char_index(text: &str, offset: usize) -> usize {
+    text.char_indices()
+        .take_while(|(index, _)| *index < offset)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::{ByteTokenizer, CharTokenizer, WordTokenizer};
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_identical_split_has_no_divergence() {
+        let gpt4 = crate::tokenizers::OpenAITokenizer::new("gpt-4").unwrap();
+        assert_eq!(
+            find_divergence(&gpt4, &gpt4, "abc").unwrap(),
+            Divergence::None { tokens: 1 }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_cl100k_and_o200k_diverge() {
+        use crate::tokenizers::OpenAITokenizer;
+
+        let cl100k = OpenAITokenizer::new("gpt-4").unwrap();
+        let o200k = OpenAITokenizer::new("gpt-4o").unwrap();
+        // o200k_base has longer Cyrillic tokens than cl100k_base
+        let text = "Привет, как дела?";
+
+        match find_divergence(&cl100k, &o200k, text).unwrap() {
+            Divergence::At {
+                position,
+                token,
+                left,
+                right,
+            } => {
+                assert_eq!(position, 2);
+                assert_eq!(token, 1);
+                assert_eq!(left[..2], ["ив", "ет"]);
+                assert_eq!(right[0], "ивет");
+            }
+            other => panic!("expected a divergence, got {:?}", other),
+        }
+
+        assert_eq!(
+            find_divergence(&cl100k, &o200k, "Hello world").unwrap(),
+            Divergence::None { tokens: 2 }
+        );
+    }
+
+    #[test]
+    fn test_approximate_unsupported() {
+        assert_eq!(
+            find_divergence(&WordTokenizer::new(), &CharTokenizer::new(), "a b").unwrap(),
+            Divergence::Unsupported
+        );
+        // Characters and bytes are baselines, not a model's vocabulary
+        assert_eq!(
+            find_divergence(&CharTokenizer::new(), &ByteTokenizer::new(), "ab語c").unwrap(),
+            Divergence::Unsupported
+        );
+    }
+}
@@ -0,0 +1,135 @@
+/// Tokenizer construction by model name, without a model registry.
+use crate::error::{ModelError, TokenizerError};
+use crate::tokenizers::{ByteTokenizer, CharTokenizer, Tokenizer, WordTokenizer};
+
+#[cfg(feature = "openai")]
+use crate::tokenizers::OpenAITokenizer;
+
+#[cfg(feature = "gemini")]
+use crate::tokenizers::GeminiTokenizer;
+
+/// Create an unpriced tokenizer for a model by name.
+///
+/// Models are routed by name the same way as
+/// [`ModelRegistry::get_tokenizer`], but without alias resolution, Azure
+/// deployments or pricing: `gpt-*` and `text-*` need the `openai` feature,
+/// `gemini-*` needs `gemini`, and `chars`, `words` and `bytes` are the naive
+/// baselines.
+///
+/// [`ModelRegistry::get_tokenizer`]: crate::models::ModelRegistry::get_tokenizer
+///
+/// # Errors
+///
+/// Returns `ModelError::ModelNotFound` if no enabled tokenizer handles the
+/// name, or `ModelError::Tokenizer` if the tokenizer cannot be created.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::tokenizers::for_model;
+///
+/// let tokenizer = for_model("gpt-4")?;
+/// assert_eq!(tokenizer.count_tokens("Hello, world!")?, 4);
+/// assert!(tokenizer.input_price_per_1k().is_none());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn for_model(name: &str) -> Result<Box<dyn Tokenizer>, ModelError> {
+    match priced(name, None, None) {
+        Some(tokenizer) => tokenizer.map_err(ModelError::from),
+        None => Err(ModelError::ModelNotFound {
+            model: name.to_string(),
+        }),
+    }
+}
+
+/// Create a tokenizer for `name` with the given prices per 1K tokens, or
+/// `None` if no enabled tokenizer handles the name.
+///
+/// The baselines are never priced.
+pub(crate) fn priced(
+    name: &str,
+    input_price: Option<f64>,
+    output_price: Option<f64>,
+) -> Option<Result<Box<dyn Tokenizer>, TokenizerError>> {
+    match name {
+        "chars" => return Some(Ok(Box::new(CharTokenizer::new()))),
+        "words" => return Some(Ok(Box::new(WordTokenizer::new()))),
+        "bytes" => return Some(Ok(Box::new(ByteTokenizer::new()))),
+        _ => {}
+    }
+
+    #[cfg(feature = "openai")]
+    if is_openai(name) {
+        return Some(
+            OpenAITokenizer::new(name)
+                .map(|t| Box::new(t.with_pricing(input_price, output_price)) as Box<dyn Tokenizer>),
+        );
+    }
+
+    #[cfg(feature = "gemini")]
+    if is_gemini(name) {
+        // Without a model file Gemini counts are an approximation
+        return Some(
+            GeminiTokenizer::new(name)
+                .map(|t| Box::new(t.with_pricing(input_price, output_price)) as Box<dyn Tokenizer>),
+        );
+    }
+
+    // Prices are only used by the feature-gated tokenizers above
+    #[cfg(not(any(feature = "openai", feature = "gemini")))]
+    let _ = (input_price, output_price);
+
+    None
+}
+
+/// Whether an enabled tokenizer handles `name`, without creating it.
+pub(crate) fn handles(name: &str) -> bool {
+    matches!(name, "chars" | "words" | "bytes")
+        || (cfg!(feature = "openai") && is_openai(name))
+        || (cfg!(feature = "gemini") && is_gemini(name))
+}
+
+/// Whether the tokenizer for `name` counts with the model's real
+/// vocabulary, without creating it.
+///
+/// Gemini counts are always approximate, and so are the baselines.
+pub(crate) fn is_exact(name: &str) -> bool {
+    !matches!(name, "chars" | "words" | "bytes") && !is_gemini(name)
+}
+
+fn is_openai(name: &str) -> bool {
+    name.starts_with("gpt-") || name.starts_with("text-")
+}
+
+fn is_gemini(name: &str) -> bool {
+    name.starts_with("gemini-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baselines_and_unknown_models() {
+        assert_eq!(
+            for_model("words")
+                .unwrap()
+                .count_tokens("two words")
+                .unwrap(),
+            2
+        );
+        assert!(matches!(
+            for_model("not-a-model"),
+            Err(ModelError::ModelNotFound { model }) if model == "not-a-model"
+        ));
+    }
+
+    #[test]
+    fn test_is_exact_matches_tokenizers() {
+        for name in ["chars", "words", "bytes", "gpt-4", "gemini-2.5-pro"] {
+            if let Ok(tokenizer) = for_model(name) {
+                assert_eq!(is_exact(name), tokenizer.is_exact(), "{}", name);
+            }
+        }
+    }
+}
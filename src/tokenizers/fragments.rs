@@ -0,0 +1,185 @@
+/// Per-token decoding that keeps multi-byte characters intact.
+use crate::tokenizers::Tokenizer;
+
+/// Decoded text for a run of consecutive tokens.
+///
+/// A BPE token may hold only part of a character's UTF-8 encoding, so one
+/// fragment can span several tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenFragment {
+    /// Valid text decoded from `tokens` consecutive tokens.
+    Text { text: String, tokens: usize },
+    /// `tokens` consecutive tokens that never decoded to text, e.g. the
+    /// first half of a character cut off at the end of the tokens.
+    Partial { tokens: usize },
+}
+
+impl TokenFragment {
+    /// Number of tokens the fragment covers.
+    pub fn tokens(&self) -> usize {
+        match self {
+            TokenFragment::Text { tokens, .. } | TokenFragment::Partial { tokens } => *tokens,
+        }
+    }
+}
+
+impl std::fmt::Display for TokenFragment {
+    /// Text as is; tokens without text as a label, e.g. `<partial character>`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenFragment::Text { text, .. } => f.write_str(text),
+            TokenFragment::Partial { .. } => f.write_str("<partial character>"),
+        }
+    }
+}
+
+/// Most tokens joined while waiting for a split character to complete.
+///
+/// A UTF-8 character is at most four bytes, but a token can finish one
+/// split character and start the next, so runs can be a little longer.
+const MAX_RUN_TOKENS: usize = 8;
+
+/// Decode tokens one at a time, joining tokens that split a character.
+///
+/// A token that doesn't decode on its own is decoded together with the
+/// following tokens, up to eight of them, which yields a
+/// [`TokenFragment::Text`] once the character is complete. A token that
+/// never completes (the tail of a character cut off at the start of the
+/// tokens, the head of one cut off at the end, or an unknown id) yields a
+/// [`TokenFragment::Partial`] instead.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::tokenizers::{decode_fragments, OpenAITokenizer, Tokenizer};
+///
+/// let tokenizer = OpenAITokenizer::new("gpt-4")?;
+/// let tokens = tokenizer.encode("Hello, world!")?;
+/// let fragments = decode_fragments(&tokenizer, &tokens);
+/// assert_eq!(fragments[0].to_string(), "Hello");
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+pub fn decode_fragments<T: Tokenizer + ?Sized>(
+    tokenizer: &T,
+    tokens: &[usize],
+) -> Vec<TokenFragment> {
+    let mut fragments = Vec::new();
+    let mut start = 0;
+
+    while start < tokens.len() {
+        let longest = (tokens.len() - start).min(MAX_RUN_TOKENS);
+        let decoded = (1..=longest).find_map(|length| {
+            let text = tokenizer.decode(&tokens[start..start + length]).ok()?;
+            Some((text, length))
+        });
+        match (decoded, fragments.last_mut()) {
+            (Some((text, length)), _) => {
+                fragments.push(TokenFragment::Text {
+                    text,
+                    tokens: length,
+                });
+                start += length;
+            }
+            (None, Some(TokenFragment::Partial { tokens })) => {
+                *tokens += 1;
+                start += 1;
+            }
+            (None, _) => {
+                fragments.push(TokenFragment::Partial { tokens: 1 });
+                start += 1;
+            }
+        }
+    }
+
+    fragments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_character_split_across_tokens_is_reassembled() {
+        use crate::tokenizers::OpenAITokenizer;
+
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        // "語" is encoded as two cl100k tokens, neither valid UTF-8 alone
+        let tokens = tokenizer.encode("語").unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokenizer.decode(&tokens[..1]).is_err());
+
+        let fragments = decode_fragments(&tokenizer, &tokens);
+        assert_eq!(
+            fragments,
+            vec![TokenFragment::Text {
+                text: "語".to_string(),
+                tokens: 2
+            }]
+        );
+
+        let tokens = tokenizer.encode("Say 語 twice").unwrap();
+        let fragments = decode_fragments(&tokenizer, &tokens);
+        let text: String = fragments.iter().map(|f| f.to_string()).collect();
+        assert_eq!(text, "Say 語 twice");
+        assert_eq!(
+            fragments.iter().map(TokenFragment::tokens).sum::<usize>(),
+            tokens.len()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_incomplete_character_is_labeled_partial() {
+        use crate::tokenizers::OpenAITokenizer;
+
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        let tokens = tokenizer.encode("語").unwrap();
+
+        let fragments = decode_fragments(&tokenizer, &tokens[..1]);
+        assert_eq!(fragments, vec![TokenFragment::Partial { tokens: 1 }]);
+        assert_eq!(fragments[0].to_string(), "<partial character>");
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_tokens_starting_inside_a_character() {
+        use crate::tokenizers::OpenAITokenizer;
+
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        let tokens = tokenizer.encode("語 is a word").unwrap();
+
+        let fragments = decode_fragments(&tokenizer, &tokens[1..]);
+        assert_eq!(fragments[0], TokenFragment::Partial { tokens: 1 });
+        let text: String = fragments[1..].iter().map(|f| f.to_string()).collect();
+        assert_eq!(text, " is a word");
+    }
+
+    #[test]
+    fn test_byte_tokens_join_into_characters() {
+        use crate::tokenizers::ByteTokenizer;
+
+        let tokenizer = ByteTokenizer::new();
+        let tokens = tokenizer.encode("a語").unwrap();
+        let fragments = decode_fragments(&tokenizer, &tokens);
+        assert_eq!(
+            fragments,
+            vec![
+                TokenFragment::Text {
+                    text: "a".to_string(),
+                    tokens: 1
+                },
+                TokenFragment::Text {
+                    text: "語".to_string(),
+                    tokens: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragment_display() {
+        let fragment = TokenFragment::Partial { tokens: 2 };
+        assert_eq!(fragment.to_string(), "<partial character>");
+    }
+}
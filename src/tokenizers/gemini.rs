@@ -1,10 +1,28 @@
 /// Google Gemini tokenizer implementation.
 #[cfg(feature = "gemini")]
 use crate::error::TokenizerError;
+#[cfg(feature = "gemini")]
+use crate::models::ModelRegistry;
 use crate::tokenizers::Tokenizer;
 
 #[cfg(all(feature = "gemini", feature = "sentencepiece"))]
 use sentencepiece::SentencePieceProcessor;
+#[cfg(feature = "gemini-model-file")]
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the SentencePiece model used with the
+/// `gemini-model-file` feature.
+///
+/// The model is the tokenizer of Google's open Gemma models, which
+/// approximates the tokenizer Gemini serves; see `assets/gemini/README.md`.
+#[cfg(feature = "gemini-model-file")]
+const GEMINI_MODEL_ENV: &str = "TOKUIN_GEMINI_MODEL";
+
+/// Path of the model used with the `gemini-model-file` feature, if set.
+#[cfg(feature = "gemini-model-file")]
+fn model_file_path() -> Option<PathBuf> {
+    std::env::var_os(GEMINI_MODEL_ENV).map(PathBuf::from)
+}
 
 /// Google Gemini tokenizer implementation.
 ///
@@ -24,6 +42,9 @@ use sentencepiece::SentencePieceProcessor;
 pub struct GeminiTokenizer {
     #[cfg(feature = "sentencepiece")]
     processor: Option<SentencePieceProcessor>,
+    /// Whether `processor` is the model's own tokenizer rather than Gemma's.
+    #[cfg(feature = "sentencepiece")]
+    exact: bool,
     model_name: String,
     input_price: Option<f64>,
     output_price: Option<f64>,
@@ -43,29 +64,54 @@ impl GeminiTokenizer {
     ///
     /// # Note
     ///
-    /// This implementation uses a generic SentencePiece model as an approximation.
-    /// For production use, consider using Google's official tokenizer or API.
+    /// With the `gemini-model-file` feature, tokens are counted with Gemma's
+    /// SentencePiece model, loaded at runtime from the path in
+    /// `TOKUIN_GEMINI_MODEL`. It is itself an approximation of Google's
+    /// tokenizer, so counts are still reported as approximate. Otherwise, or
+    /// if the variable is unset or the model fails to load, counts are
+    /// estimated at about four characters per token.
     pub fn new(model: &str) -> Result<Self, TokenizerError> {
-        // Set pricing based on model (as of 2024)
-        let (input_price, output_price) = match model {
-            "gemini-2.5-pro" | "gemini-pro" => {
-                // $1.25 per 1M input, $10 per 1M output
-                (Some(0.00125), Some(0.01))
+        #[cfg(feature = "gemini-model-file")]
+        let tokenizer = Self::with_gemma_model(model, model_file_path().as_deref());
+        #[cfg(not(feature = "gemini-model-file"))]
+        let tokenizer = {
+            let (input_price, output_price) = ModelRegistry::default_prices(model);
+            Self {
+                #[cfg(feature = "sentencepiece")]
+                processor: None,
+                #[cfg(feature = "sentencepiece")]
+                exact: false,
+                model_name: model.to_string(),
+                input_price,
+                output_price,
             }
-            "gemini-2.5-flash" | "gemini-flash" => {
-                // $0.075 per 1M input, $0.30 per 1M output
-                (Some(0.000075), Some(0.0003))
-            }
-            _ => (None, None),
         };
+        Ok(tokenizer)
+    }
 
-        Ok(Self {
-            #[cfg(feature = "sentencepiece")]
-            processor: None,
+    /// A tokenizer counting with the Gemma model at `path`, or with the
+    /// character estimate if there is none or it can't be loaded.
+    #[cfg(feature = "gemini-model-file")]
+    fn with_gemma_model(model: &str, path: Option<&Path>) -> Self {
+        let (input_price, output_price) = ModelRegistry::default_prices(model);
+        Self {
+            processor: path.and_then(|path| SentencePieceProcessor::open(path).ok()),
+            exact: false,
             model_name: model.to_string(),
             input_price,
             output_price,
-        })
+        }
+    }
+
+    /// Set the input and output price per 1K tokens.
+    ///
+    /// A new tokenizer has the built-in prices for its model; the model
+    /// registry sets its own prices instead, such as those from a pricing
+    /// file.
+    pub fn with_pricing(mut self, input_price: Option<f64>, output_price: Option<f64>) -> Self {
+        self.input_price = input_price;
+        self.output_price = output_price;
+        self
     }
 
     /// Create a Gemini tokenizer with a custom model file.
@@ -88,14 +134,10 @@ impl GeminiTokenizer {
             ))
         })?;
 
-        let (input_price, output_price) = match model {
-            "gemini-2.5-pro" | "gemini-pro" => (Some(0.00125), Some(0.01)),
-            "gemini-2.5-flash" | "gemini-flash" => (Some(0.000075), Some(0.0003)),
-            _ => (None, None),
-        };
-
+        let (input_price, output_price) = ModelRegistry::default_prices(model);
         Ok(Self {
             processor: Some(processor),
+            exact: true,
             model_name: model.to_string(),
             input_price,
             output_price,
@@ -155,6 +197,32 @@ impl Tokenizer for GeminiTokenizer {
         Ok((text.chars().count() as f64 / 4.0).ceil() as usize)
     }
 
+    fn is_exact(&self) -> bool {
+        #[cfg(feature = "sentencepiece")]
+        if self.processor.is_some() {
+            return self.exact;
+        }
+
+        false
+    }
+
+    fn encoding(&self) -> Option<&str> {
+        #[cfg(feature = "sentencepiece")]
+        if self.processor.is_some() {
+            return Some("sentencepiece");
+        }
+
+        None
+    }
+
+    fn context_window(&self) -> Option<usize> {
+        match self.model_name.as_str() {
+            "gemini-pro" => Some(32_760),
+            "gemini-2.5-pro" | "gemini-2.5-flash" => Some(1_048_576),
+            _ => None,
+        }
+    }
+
     fn name(&self) -> &str {
         &self.model_name
     }
@@ -167,3 +235,55 @@ impl Tokenizer for GeminiTokenizer {
         self.output_price
     }
 }
+
+#[cfg(all(test, feature = "gemini-model-file"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_model_falls_back() {
+        let path = std::env::temp_dir().join("tokuin-missing-gemma.model");
+        for path in [None, Some(path.as_path())] {
+            let tokenizer = GeminiTokenizer::with_gemma_model("gemini-2.5-pro", path);
+            assert!(!tokenizer.is_exact());
+            assert_eq!(tokenizer.encoding(), None);
+            assert_eq!(tokenizer.count_tokens("12345678").unwrap(), 2);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs Gemma's tokenizer.model in TOKUIN_GEMINI_MODEL"]
+    fn test_gemma_model_counts_differ_from_fallback() {
+        let path = model_file_path().expect("TOKUIN_GEMINI_MODEL is not set");
+        let gemma = GeminiTokenizer::with_gemma_model("gemini-2.5-pro", Some(path.as_path()));
+        assert!(
+            gemma.processor.is_some(),
+            "failed to load {}",
+            path.display()
+        );
+        let fallback = GeminiTokenizer {
+            processor: None,
+            ..GeminiTokenizer::with_gemma_model("gemini-2.5-pro", Some(path.as_path()))
+        };
+        // Gemma's vocabulary only approximates Gemini's
+        assert!(!gemma.is_exact());
+        assert_eq!(gemma.encoding(), Some("sentencepiece"));
+        assert!(!fallback.is_exact());
+
+        // Common long words are single tokens, not one per four characters
+        let text = "internationalization documentation";
+        let tokens = gemma.encode(text).unwrap();
+        assert_eq!(gemma.count_tokens(text).unwrap(), tokens.len());
+        assert!(tokens.len() < fallback.count_tokens(text).unwrap());
+        assert_eq!(gemma.decode(&tokens).unwrap(), text);
+
+        // The fallback only sees length: scrambling the letters keeps its
+        // estimate, while the real tokenizer needs more pieces for nonsense
+        let scrambled = "nitaretnoinalaztioni tnoitatnemucod";
+        assert_eq!(
+            fallback.count_tokens(scrambled).unwrap(),
+            fallback.count_tokens(text).unwrap()
+        );
+        assert!(gemma.count_tokens(scrambled).unwrap() > tokens.len());
+    }
+}
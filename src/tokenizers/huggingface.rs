@@ -0,0 +1,123 @@
+/// HuggingFace `tokenizer.json` implementation using the tokenizers crate.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+
+/// Tokenizer loaded from a HuggingFace `tokenizer.json` file.
+///
+/// Most open models (Llama, Mistral, Qwen, ...) publish this file, which gives
+/// exact counts without model-specific code. Pricing is unknown unless set
+/// with [`HfTokenizer::with_pricing`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use tokuin::tokenizers::{HfTokenizer, Tokenizer};
+///
+/// let tokenizer = HfTokenizer::from_file("llama-3", "tokenizer.json")?;
+/// let count = tokenizer.count_tokens("Hello, world!")?;
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+pub struct HfTokenizer {
+    inner: tokenizers::Tokenizer,
+    model_name: String,
+    input_price: Option<f64>,
+    output_price: Option<f64>,
+}
+
+impl HfTokenizer {
+    /// Load a tokenizer from a `tokenizer.json` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The name to report for this tokenizer.
+    /// * `path` - Path to the `tokenizer.json` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError::InitializationFailed` if the file cannot be
+    /// read or is not a valid tokenizer definition.
+    pub fn from_file(model: &str, path: &str) -> Result<Self, TokenizerError> {
+        let inner = tokenizers::Tokenizer::from_file(path).map_err(|e| {
+            TokenizerError::InitializationFailed(format!(
+                "Failed to load tokenizer from '{}': {}",
+                path, e
+            ))
+        })?;
+
+        Ok(Self {
+            inner,
+            model_name: model.to_string(),
+            input_price: None,
+            output_price: None,
+        })
+    }
+
+    /// Set the input and output price per 1K tokens.
+    pub fn with_pricing(mut self, pricing: Option<(f64, f64)>) -> Self {
+        self.input_price = pricing.map(|p| p.0);
+        self.output_price = pricing.map(|p| p.1);
+        self
+    }
+}
+
+impl Tokenizer for HfTokenizer {
+    fn encode(&self, text: &str) -> Result<Vec<usize>, TokenizerError> {
+        // Special tokens belong to the chat template, not the content
+        self.inner
+            .encode(text, false)
+            .map(|encoding| encoding.get_ids().iter().map(|&id| id as usize).collect())
+            .map_err(|e| TokenizerError::EncodingFailed(e.to_string()))
+    }
+
+    fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError> {
+        let ids: Vec<u32> = tokens.iter().map(|&id| id as u32).collect();
+        self.inner
+            .decode(&ids, false)
+            .map_err(|e| TokenizerError::DecodingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn input_price_per_1k(&self) -> Option<f64> {
+        self.input_price
+    }
+
+    fn output_price_per_1k(&self) -> Option<f64> {
+        self.output_price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/tiny-tokenizer.json"
+    );
+
+    #[test]
+    fn test_count_known_string() {
+        let tokenizer = HfTokenizer::from_file("tiny", FIXTURE).unwrap();
+        assert_eq!(tokenizer.count_tokens("Hello, world!").unwrap(), 4);
+        assert_eq!(tokenizer.encode("Hello, world!").unwrap(), vec![1, 3, 2, 4]);
+        assert_eq!(tokenizer.name(), "tiny");
+        assert!(tokenizer.input_price_per_1k().is_none());
+    }
+
+    #[test]
+    fn test_with_pricing() {
+        let tokenizer = HfTokenizer::from_file("tiny", FIXTURE)
+            .unwrap()
+            .with_pricing(Some((0.001, 0.002)));
+        assert_eq!(tokenizer.input_price_per_1k(), Some(0.001));
+        assert_eq!(tokenizer.output_price_per_1k(), Some(0.002));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert!(HfTokenizer::from_file("tiny", "does-not-exist.json").is_err());
+    }
+}
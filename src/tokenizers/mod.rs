@@ -1,4 +1,11 @@
 /// Tokenizer implementations for various LLM providers.
+pub mod baseline;
+pub mod chat;
+pub mod divergence;
+pub mod factory;
+pub mod fragments;
+pub mod roundtrip;
+pub mod template;
 pub mod trait_impl;
 
 #[cfg(feature = "openai")]
@@ -7,6 +14,15 @@ pub mod openai;
 #[cfg(feature = "gemini")]
 pub mod gemini;
 
+#[cfg(feature = "huggingface")]
+pub mod huggingface;
+
+pub use baseline::{ByteTokenizer, CharTokenizer, WordTokenizer};
+pub use chat::{ChatCounter, ChatOverhead};
+pub use factory::for_model;
+pub use fragments::{decode_fragments, TokenFragment};
+pub use roundtrip::{verify_roundtrip, RoundTrip};
+pub use template::ChatTemplate;
 pub use trait_impl::Tokenizer;
 
 #[cfg(feature = "openai")]
@@ -14,3 +30,6 @@ pub use openai::OpenAITokenizer;
 
 #[cfg(feature = "gemini")]
 pub use gemini::GeminiTokenizer;
+
+#[cfg(feature = "huggingface")]
+pub use huggingface::HfTokenizer;
@@ -1,8 +1,12 @@
 /// OpenAI tokenizer implementation using tiktoken-rs.
 #[cfg(feature = "openai")]
 use crate::error::TokenizerError;
+use crate::models::ModelRegistry;
 use crate::tokenizers::Tokenizer;
-use tiktoken_rs::{get_bpe_from_model, CoreBPE};
+use tiktoken_rs::{get_bpe_from_model, CoreBPE, Rank};
+
+/// Text counted per call to the encoder; longer text is split at spaces.
+const COUNT_CHUNK_BYTES: usize = 16 * 1024;
 
 /// OpenAI tokenizer implementation.
 ///
@@ -21,6 +25,7 @@ use tiktoken_rs::{get_bpe_from_model, CoreBPE};
 pub struct OpenAITokenizer {
     bpe: CoreBPE,
     model_name: String,
+    encoding: Option<&'static str>,
     input_price: Option<f64>,
     output_price: Option<f64>,
 }
@@ -57,24 +62,38 @@ impl OpenAITokenizer {
             ))
         })?;
 
-        // Set pricing based on model (default pricing as of 2024)
-        let (input_price, output_price) = match model {
-            "gpt-4" | "gpt-4-0314" | "gpt-4-32k" | "gpt-4-32k-0314" => (Some(0.03), Some(0.06)),
-            "gpt-4-turbo-preview" | "gpt-4-turbo" | "gpt-4-0125-preview" => {
-                (Some(0.01), Some(0.03))
+        let encoding = tiktoken_rs::tokenizer::get_tokenizer(model).map(|encoding| {
+            use tiktoken_rs::tokenizer::Tokenizer as Encoding;
+            match encoding {
+                Encoding::O200kBase => "o200k_base",
+                Encoding::Cl100kBase => "cl100k_base",
+                Encoding::P50kBase => "p50k_base",
+                Encoding::R50kBase => "r50k_base",
+                Encoding::P50kEdit => "p50k_edit",
+                Encoding::Gpt2 => "gpt2",
             }
-            "gpt-3.5-turbo" | "gpt-3.5-turbo-0301" => (Some(0.0015), Some(0.002)),
-            "gpt-3.5-turbo-16k" => (Some(0.003), Some(0.004)),
-            _ => (None, None),
-        };
+        });
 
+        let (input_price, output_price) = ModelRegistry::default_prices(model);
         Ok(Self {
             bpe,
             model_name: model.to_string(),
+            encoding,
             input_price,
             output_price,
         })
     }
+
+    /// Set the input and output price per 1K tokens.
+    ///
+    /// A new tokenizer has the built-in prices for its model;
+    /// [`ModelRegistry::get_tokenizer`] sets the registry's prices instead,
+    /// such as those from a pricing file.
+    pub fn with_pricing(mut self, input_price: Option<f64>, output_price: Option<f64>) -> Self {
+        self.input_price = input_price;
+        self.output_price = output_price;
+        self
+    }
 }
 
 impl Tokenizer for OpenAITokenizer {
@@ -88,15 +107,35 @@ impl Tokenizer for OpenAITokenizer {
     }
 
     fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError> {
-        let tokens_u32: Vec<u32> = tokens.iter().map(|&t| t as u32).collect();
+        let ranks = tokens
+            .iter()
+            .map(|&t| {
+                Rank::try_from(t)
+                    .map_err(|_| TokenizerError::DecodingFailed(format!("Invalid token id: {}", t)))
+            })
+            .collect::<Result<Vec<Rank>, _>>()?;
         self.bpe
-            .decode(tokens_u32)
+            .decode(ranks)
             .map_err(|e| TokenizerError::DecodingFailed(e.to_string()))
     }
 
     fn count_tokens(&self, text: &str) -> Result<usize, TokenizerError> {
-        // Use optimized counting method
-        Ok(self.bpe.encode_with_special_tokens(text).len())
+        // Encode in bounded chunks so only one chunk's tokens are held at a time
+        Ok(count_chunks(text)
+            .map(|chunk| self.bpe.encode_with_special_tokens(chunk).len())
+            .sum())
+    }
+
+    fn encoding(&self) -> Option<&str> {
+        self.encoding
+    }
+
+    fn context_window(&self) -> Option<usize> {
+        match self.model_name.as_str() {
+            // tiktoken-rs falls back to 4,096 for the newer embeddings models
+            "text-embedding-3-small" | "text-embedding-3-large" => Some(8191),
+            model => Some(tiktoken_rs::model::get_context_size(model)),
+        }
     }
 
     fn name(&self) -> &str {
@@ -112,10 +151,86 @@ impl Tokenizer for OpenAITokenizer {
     }
 }
 
+/// Split `text` into chunks that can be counted separately.
+///
+/// Chunks of about [`COUNT_CHUNK_BYTES`] end before a space that follows a
+/// non-space character. No pre-tokenizer piece spans such a point, so the
+/// counts add up exactly.
+fn count_chunks(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut cut = rest.len();
+        let mut last_space = None;
+        let mut previous: Option<char> = None;
+        for (index, c) in rest.char_indices() {
+            if c == ' ' && previous.is_some_and(|p| !p.is_whitespace()) {
+                last_space = Some(index);
+            }
+            if index >= COUNT_CHUNK_BYTES {
+                if let Some(space) = last_space {
+                    cut = space;
+                    break;
+                }
+            }
+            previous = Some(c);
+        }
+
+        let (chunk, tail) = rest.split_at(cut);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encode_with_offsets_covers_text() {
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        // Emoji and CJK characters split across several tokens
+        for text in [
+            "Hello, world!",
+            "naïve café 🦀🦀 日本語のテキスト",
+            "👨‍👩‍👧 <|endoftext|> done",
+            "",
+        ] {
+            let offsets = tokenizer.encode_with_offsets(text).unwrap();
+            let tokens: Vec<usize> = offsets.iter().map(|(token, _)| *token).collect();
+            assert_eq!(tokens, tokenizer.encode(text).unwrap());
+
+            let mut expected_start = 0;
+            let mut rebuilt = String::new();
+            for (_, range) in &offsets {
+                assert_eq!(range.start, expected_start, "contiguous in {:?}", text);
+                rebuilt.push_str(&text[range.clone()]);
+                expected_start = range.end;
+            }
+            assert_eq!(expected_start, text.len());
+            assert_eq!(rebuilt, text);
+        }
+
+        let text = "Hello, world!";
+        let offsets = tokenizer.encode_with_offsets(text).unwrap();
+        let pieces: Vec<&str> = offsets.iter().map(|(_, r)| &text[r.clone()]).collect();
+        assert_eq!(pieces, ["Hello", ",", " world", "!"]);
+    }
+
+    #[test]
+    fn test_encode_with_offsets_joins_split_characters() {
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        let text = "🦀";
+        let offsets = tokenizer.encode_with_offsets(text).unwrap();
+        assert!(offsets.len() > 1, "the crab emoji spans several tokens");
+        let (last, rest) = offsets.split_last().unwrap();
+        assert_eq!(last.1, 0..text.len());
+        assert!(rest.iter().all(|(_, r)| *r == (0..0)));
+    }
+
     #[test]
     fn test_openai_tokenizer_creation() {
         let tokenizer = OpenAITokenizer::new("gpt-4");
@@ -151,6 +266,40 @@ mod tests {
         assert_eq!(count, 0);
     }
 
+    #[test]
+    fn test_count_tokens_in_chunks_is_exact() {
+        let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
+        let text =
+            "The quick  brown fox, don't\n\tjump 12345 times; naïve 🦀 café.\n\n".repeat(1000);
+        assert!(count_chunks(&text).count() > 1);
+        assert_eq!(
+            tokenizer.count_tokens(&text).unwrap(),
+            tokenizer.encode(&text).unwrap().len()
+        );
+        assert_eq!(count_chunks(&text).collect::<String>(), text);
+    }
+
+    #[test]
+    fn test_count_tokens_long_runs_is_exact() {
+        // Long runs of one character class are encoded whole
+        for model in ["gpt-4", "gpt-4o"] {
+            let tokenizer = OpenAITokenizer::new(model).unwrap();
+            for text in [
+                "1234567".repeat(100),
+                "0123456789abcdef".repeat(100),
+                "日本語のテキストを数える".repeat(40),
+            ] {
+                assert_eq!(
+                    tokenizer.count_tokens(&text).unwrap(),
+                    tokenizer.encode(&text).unwrap().len(),
+                    "{} on {:?}",
+                    model,
+                    &text[..12]
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_unicode_handling() {
         let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
@@ -164,5 +313,9 @@ mod tests {
         let tokenizer = OpenAITokenizer::new("gpt-4").unwrap();
         assert_eq!(tokenizer.input_price_per_1k(), Some(0.03));
         assert_eq!(tokenizer.output_price_per_1k(), Some(0.06));
+
+        let tokenizer = tokenizer.with_pricing(Some(0.01), None);
+        assert_eq!(tokenizer.input_price_per_1k(), Some(0.01));
+        assert_eq!(tokenizer.output_price_per_1k(), None);
     }
 }
@@ -0,0 +1,133 @@
+/// Encode/decode round-trip checks for tokenizers.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+
+/// Number of characters shown on each side of a mismatch.
+const SNIPPET_CHARS: usize = 20;
+
+/// Outcome of a round-trip check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoundTrip {
+    /// Decoding the encoded text reproduced the original.
+    Passed {
+        /// Number of tokens the text encoded to.
+        tokens: usize,
+    },
+    /// The decoded text differs from the original.
+    Mismatch {
+        /// Character index of the first difference.
+        position: usize,
+        /// Original text starting at `position`.
+        expected: String,
+        /// Decoded text starting at `position`.
+        actual: String,
+    },
+    /// The tokenizer approximates counts and cannot decode.
+    Unsupported,
+}
+
+/// Encode `text`, decode the tokens and compare with the original.
+///
+/// Both sides are normalized to `\n` line endings and trimmed before the
+/// comparison, since some tokenizers drop surrounding whitespace.
+///
+/// # Errors
+///
+/// Returns `TokenizerError` if encoding or decoding fails.
+pub fn verify_roundtrip(
+    tokenizer: &dyn Tokenizer,
+    text: &str,
+) -> Result<RoundTrip, TokenizerError> {
+    if !tokenizer.is_exact() {
+        return Ok(RoundTrip::Unsupported);
+    }
+
+    let tokens = tokenizer.encode(text)?;
+    let decoded = tokenizer.decode(&tokens)?;
+
+    let expected = normalize(text);
+    let actual = normalize(&decoded);
+    if expected == actual {
+        return Ok(RoundTrip::Passed {
+            tokens: tokens.len(),
+        });
+    }
+
+    let position = expected
+        .chars()
+        .zip(actual.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    Ok(RoundTrip::Mismatch {
+        position,
+        expected: expected
+            .chars()
+            .skip(position)
+            .take(SNIPPET_CHARS)
+            .collect(),
+        actual: actual.chars().skip(position).take(SNIPPET_CHARS).collect(),
+    })
+}
+
+fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::{CharTokenizer, WordTokenizer};
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_openai_passes() {
+        let tokenizer = crate::tokenizers::OpenAITokenizer::new("gpt-4").unwrap();
+        let result = verify_roundtrip(&tokenizer, "Hello, world!\r\nSecond line ü").unwrap();
+        assert!(matches!(result, RoundTrip::Passed { tokens } if tokens > 0));
+    }
+
+    #[test]
+    #[cfg(feature = "gemini")]
+    fn test_gemini_fallback_unsupported() {
+        let tokenizer = crate::tokenizers::GeminiTokenizer::new("gemini-pro").unwrap();
+        assert_eq!(
+            verify_roundtrip(&tokenizer, "Hello, world!").unwrap(),
+            RoundTrip::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_approximate_unsupported() {
+        assert_eq!(
+            verify_roundtrip(&WordTokenizer::new(), "Hello, world!").unwrap(),
+            RoundTrip::Unsupported
+        );
+        assert_eq!(
+            verify_roundtrip(&CharTokenizer::new(), "abc").unwrap(),
+            RoundTrip::Unsupported
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "huggingface")]
+    fn test_mismatch_reports_position() {
+        // The fixture lowercases and splits on whitespace, so decoding is lossy
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/tiny-tokenizer.json"
+        );
+        let tokenizer = crate::tokenizers::HfTokenizer::from_file("tiny", path).unwrap();
+        match verify_roundtrip(&tokenizer, "hello World").unwrap() {
+            RoundTrip::Mismatch {
+                position,
+                expected,
+                actual,
+            } => {
+                assert_eq!(position, 6);
+                assert_eq!(expected, "World");
+                assert_eq!(actual, "world");
+            }
+            other => panic!("expected mismatch, got {:?}", other),
+        }
+    }
+}
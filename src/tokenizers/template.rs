@@ -0,0 +1,296 @@
+/// Chat templates that render messages into the exact prompt a model sees.
+use crate::error::TokenizerError;
+use crate::parsers::Message;
+use minijinja::{Environment, Error, ErrorKind};
+use serde::Serialize;
+use std::path::Path;
+
+/// BOS token used when a template file doesn't name one.
+pub const DEFAULT_BOS_TOKEN: &str = "<s>";
+
+/// EOS token used when a template file doesn't name one.
+pub const DEFAULT_EOS_TOKEN: &str = "</s>";
+
+/// Name the template is registered under in its environment.
+const TEMPLATE_NAME: &str = "chat_template";
+
+/// A Jinja chat template, as shipped in a model's `tokenizer_config.json`.
+///
+/// Templates are rendered with [`minijinja`], set up like Hugging Face's
+/// renderer: a newline after a block tag is dropped (`trim_blocks`),
+/// indentation before one is stripped (`lstrip_blocks`), Python string
+/// methods such as `strip()` work, and `raise_exception` fails the render.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::parsers::Message;
+/// use tokuin::tokenizers::template::ChatTemplate;
+///
+/// let template = ChatTemplate::parse(
+///     "{{ bos_token }}{% for m in messages %}<|{{ m.role }}|>{{ m.content }}\n{% endfor %}",
+/// )?;
+/// let messages = vec![Message {
+///     role: "user".to_string(),
+///     content: "Hi".to_string(),
+///     name: None,
+///     tool_call_id: None,
+/// }];
+/// assert_eq!(template.render(&messages)?, "<s><|user|>Hi\n");
+/// # Ok::<(), tokuin::error::TokenizerError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChatTemplate {
+    env: Environment<'static>,
+    bos_token: String,
+    eos_token: String,
+    add_generation_prompt: bool,
+}
+
+/// Message as the template sees it.
+#[derive(Serialize)]
+struct TemplateMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+/// Variables a chat template is rendered with.
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    messages: Vec<TemplateMessage<'a>>,
+    bos_token: &'a str,
+    eos_token: &'a str,
+    add_generation_prompt: bool,
+}
+
+impl ChatTemplate {
+    /// Parse a template with the default BOS and EOS tokens and
+    /// `add_generation_prompt` set, so the assistant's reply is primed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the template is malformed.
+    pub fn parse(source: &str) -> Result<Self, TokenizerError> {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_unknown_method_callback(minijinja_contrib::pycompat::unknown_method_callback);
+        env.add_function("raise_exception", raise_exception);
+        env.add_template_owned(TEMPLATE_NAME, source.to_string())
+            .map_err(|e| template_error(e.to_string()))?;
+        Ok(Self {
+            env,
+            bos_token: DEFAULT_BOS_TOKEN.to_string(),
+            eos_token: DEFAULT_EOS_TOKEN.to_string(),
+            add_generation_prompt: true,
+        })
+    }
+
+    /// Read the `chat_template`, `bos_token` and `eos_token` of a
+    /// `tokenizer_config.json`.
+    ///
+    /// When the config holds several named templates, the `default` one is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the JSON has no usable chat template.
+    pub fn from_tokenizer_config(json: &str) -> Result<Self, TokenizerError> {
+        let config: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| template_error(format!("invalid tokenizer config: {}", e)))?;
+        let source = match &config["chat_template"] {
+            serde_json::Value::String(source) => source.as_str(),
+            serde_json::Value::Array(templates) => templates
+                .iter()
+                .find(|t| t["name"] == "default")
+                .and_then(|t| t["template"].as_str())
+                .ok_or_else(|| template_error("no default chat template in config"))?,
+            _ => return Err(template_error("config has no chat_template")),
+        };
+
+        // Special tokens are either plain strings or `{"content": ...}` objects
+        let token = |key: &str| match &config[key] {
+            serde_json::Value::String(token) => Some(token.clone()),
+            value => value["content"].as_str().map(str::to_string),
+        };
+        let mut template = Self::parse(source)?;
+        if let Some(bos) = token("bos_token") {
+            template.bos_token = bos;
+        }
+        if let Some(eos) = token("eos_token") {
+            template.eos_token = eos;
+        }
+        Ok(template)
+    }
+
+    /// Load a template file: a `tokenizer_config.json`, or the raw template.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the file cannot be read or parsed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TokenizerError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            template_error(format!(
+                "failed to read chat template '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let is_json = path.extension().is_some_and(|ext| ext == "json")
+            || source.trim_start().starts_with('{') && source.contains("\"chat_template\"");
+        if is_json {
+            Self::from_tokenizer_config(&source)
+        } else {
+            Self::parse(&source)
+        }
+    }
+
+    /// Render `messages` into the prompt string the model receives.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if evaluation fails, e.g. when the template
+    /// calls `raise_exception` for an unsupported role order.
+    pub fn render(&self, messages: &[Message]) -> Result<String, TokenizerError> {
+        let context = TemplateContext {
+            messages: messages
+                .iter()
+                .map(|m| TemplateMessage {
+                    role: &m.role,
+                    content: &m.content,
+                    name: m.name.as_deref(),
+                    tool_call_id: m.tool_call_id.as_deref(),
+                })
+                .collect(),
+            bos_token: &self.bos_token,
+            eos_token: &self.eos_token,
+            add_generation_prompt: self.add_generation_prompt,
+        };
+        self.env
+            .get_template(TEMPLATE_NAME)
+            .and_then(|template| template.render(context))
+            .map_err(|e| template_error(e.to_string()))
+    }
+}
+
+/// `raise_exception(message)`, which templates call to reject their input.
+fn raise_exception(message: String) -> Result<String, Error> {
+    Err(Error::new(ErrorKind::InvalidOperation, message))
+}
+
+fn template_error(message: impl Into<String>) -> TokenizerError {
+    TokenizerError::InvalidInput(format!("chat template: {}", message.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        [("system", "Be brief."), ("user", "Hi")]
+            .into_iter()
+            .map(|(role, content)| Message {
+                role: role.to_string(),
+                content: content.to_string(),
+                name: None,
+                tool_call_id: None,
+            })
+            .collect()
+    }
+
+    const CHATML: &str = "{{ bos_token }}{% for message in messages %}\
+<|{{ message['role'] }}|>\n{{ message['content'] }}<|end|>\n{% endfor %}\
+{% if add_generation_prompt %}<|assistant|>\n{% endif %}";
+
+    #[test]
+    fn test_render_counts_role_tags() {
+        let template = ChatTemplate::parse(CHATML).unwrap();
+        let rendered = template.render(&messages()).unwrap();
+        assert_eq!(
+            rendered,
+            "<s><|system|>\nBe brief.<|end|>\n<|user|>\nHi<|end|>\n<|assistant|>\n"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_control_and_trim_blocks() {
+        let source = "{% for m in messages %}\n    {%- if m.role == 'system' %}\n[SYS] {{ m.content | trim }}\n    {% else %}\n[{{ m.role | upper }}] {{ m.content }}\n    {% endif %}\n{% endfor %}";
+        let rendered = ChatTemplate::parse(source)
+            .unwrap()
+            .render(&messages())
+            .unwrap();
+        assert_eq!(rendered, "[SYS] Be brief.\n[USER] Hi\n");
+    }
+
+    #[test]
+    fn test_mistral_style_template() {
+        // The role order check and system-prompt handling of a Mistral/Llama template
+        let source = "{%- if messages[0]['role'] == 'system' %}\
+{%- set system_message = messages[0]['content'] %}\
+{%- set loop_messages = messages[1:] %}\
+{%- else %}{%- set loop_messages = messages %}{%- endif %}\
+{{- bos_token }}\
+{%- for message in loop_messages %}\
+{%- if (message['role'] == 'user') != (loop.index0 % 2 == 0) %}\
+{{- raise_exception('Conversation roles must alternate user/assistant/user/assistant/...') }}\
+{%- endif %}\
+{%- if message['role'] == 'user' %}\
+{%- if loop.first and system_message is defined %}\
+{{- ' [INST] ' + system_message + '\\n\\n' + message['content'] + ' [/INST]' }}\
+{%- else %}{{- ' [INST] ' + message['content'] + ' [/INST]' }}{%- endif %}\
+{%- elif message['role'] == 'assistant' %}{{- ' ' + message['content'] + eos_token }}\
+{%- endif %}{%- endfor %}";
+        let template = ChatTemplate::parse(source).unwrap();
+        assert_eq!(
+            template.render(&messages()).unwrap(),
+            "<s> [INST] Be brief.\n\nHi [/INST]"
+        );
+
+        let mut out_of_order = messages();
+        out_of_order[0].role = "assistant".to_string();
+        let error = template.render(&out_of_order).unwrap_err().to_string();
+        assert!(error.contains("roles must alternate"), "{}", error);
+    }
+
+    #[test]
+    fn test_python_methods_and_tojson() {
+        let source = "{% for m in messages %}{{ m.content.strip().upper() }}|{% endfor %}\
+{{ messages[0] | tojson }}";
+        let rendered = ChatTemplate::parse(source)
+            .unwrap()
+            .render(&messages())
+            .unwrap();
+        assert_eq!(
+            rendered,
+            r#"BE BRIEF.|HI|{"content":"Be brief.","role":"system"}"#
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_config_and_errors() {
+        let config = r#"{
+            "bos_token": {"content": "<|begin|>", "special": true},
+            "eos_token": "<|eot|>",
+            "chat_template": "{{ bos_token }}{% for m in messages %}{{ m.content }}{{ eos_token }}{% endfor %}"
+        }"#;
+        let template = ChatTemplate::from_tokenizer_config(config).unwrap();
+        assert_eq!(
+            template.render(&messages()).unwrap(),
+            "<|begin|>Be brief.<|eot|>Hi<|eot|>"
+        );
+
+        for source in [
+            "{% for m in messages %}",
+            "{% if x %}a{% endfor %}",
+            "{{ 'unterminated }}",
+            "{{ raise_exception('Unsupported') }}",
+        ] {
+            let result = ChatTemplate::parse(source).and_then(|t| t.render(&messages()).map(|_| t));
+            assert!(result.is_err(), "'{}' should fail", source);
+        }
+    }
+}
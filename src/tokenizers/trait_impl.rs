@@ -1,5 +1,7 @@
 /// Core trait for tokenizer implementations.
 use crate::error::TokenizerError;
+use crate::tokenizers::{decode_fragments, TokenFragment};
+use std::ops::Range;
 
 /// Trait for tokenizing text into tokens and counting tokens.
 ///
@@ -37,6 +39,59 @@ pub trait Tokenizer: Send + Sync {
     #[allow(dead_code)]
     fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError>;
 
+    /// Encode `text`, pairing each token with the byte range of `text` it
+    /// covers.
+    ///
+    /// Ranges come from decoding the tokens back, so they follow `text`
+    /// only where decoding reproduces it, as with byte-level BPE. They are
+    /// contiguous and can always be used to slice `text`. Tokens that split
+    /// a multi-byte character only decode together: the last of them covers
+    /// the character and the ones before it get an empty range where it
+    /// starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the text cannot be tokenized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::tokenizers::{OpenAITokenizer, Tokenizer};
+    ///
+    /// let tokenizer = OpenAITokenizer::new("gpt-4")?;
+    /// let text = "Hello, world!";
+    /// let offsets = tokenizer.encode_with_offsets(text)?;
+    /// assert_eq!(&text[offsets[0].1.clone()], "Hello");
+    /// assert_eq!(offsets.last().unwrap().1.end, text.len());
+    /// # Ok::<(), tokuin::error::TokenizerError>(())
+    /// ```
+    fn encode_with_offsets(
+        &self,
+        text: &str,
+    ) -> Result<Vec<(usize, Range<usize>)>, TokenizerError> {
+        let tokens = self.encode(text)?;
+        let mut offsets = Vec::with_capacity(tokens.len());
+        let mut start = 0;
+        let mut ids = tokens.iter().copied();
+        for fragment in decode_fragments(self, &tokens) {
+            let mut end = match &fragment {
+                TokenFragment::Text { text: decoded, .. } => {
+                    (start + decoded.len()).min(text.len())
+                }
+                TokenFragment::Partial { .. } => start,
+            };
+            while !text.is_char_boundary(end) {
+                end += 1;
+            }
+            for _ in 1..fragment.tokens() {
+                offsets.extend(ids.next().map(|id| (id, start..start)));
+            }
+            offsets.extend(ids.next().map(|id| (id, start..end)));
+            start = end;
+        }
+        Ok(offsets)
+    }
+
     /// Count tokens in text (optimized path).
     ///
     /// This method is optimized for cases where you only need the count,
@@ -67,6 +122,24 @@ pub trait Tokenizer: Send + Sync {
         self.encode(text).map(|tokens| tokens.len())
     }
 
+    /// Whether token IDs come from the model's real vocabulary.
+    ///
+    /// Approximating tokenizers still count tokens, but their IDs are
+    /// placeholders and must not be used for truncation or caching.
+    fn is_exact(&self) -> bool {
+        true
+    }
+
+    /// Name of the vocabulary or encoding, e.g. `cl100k_base`, if known.
+    fn encoding(&self) -> Option<&str> {
+        None
+    }
+
+    /// Maximum number of tokens the model accepts, if known.
+    fn context_window(&self) -> Option<usize> {
+        None
+    }
+
     /// Get the tokenizer's name/identifier.
     ///
     /// # Returns
@@ -88,3 +161,43 @@ pub trait Tokenizer: Send + Sync {
     /// The price in USD per 1K output tokens, or `None` if pricing is unknown.
     fn output_price_per_1k(&self) -> Option<f64>;
 }
+
+/// Shared tokenizers, e.g. ones constructed once by a warmup, count like the
+/// tokenizer they wrap.
+impl<T: Tokenizer + ?Sized> Tokenizer for std::sync::Arc<T> {
+    fn encode(&self, text: &str) -> Result<Vec<usize>, TokenizerError> {
+        (**self).encode(text)
+    }
+
+    fn decode(&self, tokens: &[usize]) -> Result<String, TokenizerError> {
+        (**self).decode(tokens)
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize, TokenizerError> {
+        (**self).count_tokens(text)
+    }
+
+    fn is_exact(&self) -> bool {
+        (**self).is_exact()
+    }
+
+    fn encoding(&self) -> Option<&str> {
+        (**self).encoding()
+    }
+
+    fn context_window(&self) -> Option<usize> {
+        (**self).context_window()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn input_price_per_1k(&self) -> Option<f64> {
+        (**self).input_price_per_1k()
+    }
+
+    fn output_price_per_1k(&self) -> Option<f64> {
+        (**self).output_price_per_1k()
+    }
+}
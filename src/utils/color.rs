@@ -0,0 +1,112 @@
+/// Terminal color handling.
+use clap::ValueEnum;
+
+/// When to emit ANSI color codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    /// Always color, even when piped or `NO_COLOR` is set
+    Always,
+    /// Never color
+    Never,
+}
+
+/// ANSI bold.
+pub const BOLD: &str = "1";
+/// ANSI red foreground.
+pub const RED: &str = "31";
+/// ANSI green foreground.
+pub const GREEN: &str = "32";
+
+/// Decide whether color should be used.
+///
+/// Precedence follows the usual CLI conventions: `--color=always` wins,
+/// then `--no-color`, `--color=never` or a non-empty `NO_COLOR` disable
+/// color, and otherwise color is used only when writing to a terminal.
+///
+/// # Arguments
+///
+/// * `choice` - The `--color` setting.
+/// * `no_color_flag` - Whether `--no-color` was passed.
+/// * `no_color_env` - The value of the `NO_COLOR` environment variable, if set.
+/// * `is_tty` - Whether the output stream is a terminal.
+pub fn resolve_color(
+    choice: ColorChoice,
+    no_color_flag: bool,
+    no_color_env: Option<&str>,
+    is_tty: bool,
+) -> bool {
+    if choice == ColorChoice::Always {
+        return true;
+    }
+    if no_color_flag || choice == ColorChoice::Never {
+        return false;
+    }
+    if no_color_env.is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    is_tty
+}
+
+/// Decide whether color should be used for stdout, reading `NO_COLOR` from
+/// the environment.
+pub fn stdout_color_enabled(choice: ColorChoice, no_color_flag: bool) -> bool {
+    use std::io::IsTerminal;
+
+    let no_color_env = std::env::var("NO_COLOR").ok();
+    resolve_color(
+        choice,
+        no_color_flag,
+        no_color_env.as_deref(),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+/// Wrap `text` in an ANSI SGR sequence when `enabled` is true.
+pub fn paint(text: &str, code: &str, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_always_beats_everything() {
+        assert!(resolve_color(ColorChoice::Always, true, Some("1"), false));
+    }
+
+    #[test]
+    fn test_no_color_env_disables_on_tty() {
+        assert!(!resolve_color(ColorChoice::Auto, false, Some("1"), true));
+        let painted = paint(
+            "Model",
+            BOLD,
+            resolve_color(ColorChoice::Auto, false, Some("1"), true),
+        );
+        assert!(!painted.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_empty_no_color_env_is_ignored() {
+        assert!(resolve_color(ColorChoice::Auto, false, Some(""), true));
+    }
+
+    #[test]
+    fn test_flag_and_never_disable() {
+        assert!(!resolve_color(ColorChoice::Auto, true, None, true));
+        assert!(!resolve_color(ColorChoice::Never, false, None, true));
+    }
+
+    #[test]
+    fn test_auto_follows_tty() {
+        assert!(resolve_color(ColorChoice::Auto, false, None, true));
+        assert!(!resolve_color(ColorChoice::Auto, false, None, false));
+    }
+}
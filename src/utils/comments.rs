@@ -0,0 +1,208 @@
+/// Comment stripping for code embedded in prompts.
+use clap::ValueEnum;
+
+/// Comment syntax to strip, selected by language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CommentStyle {
+    /// `//` line and `/* */` block comments (C, C++, Rust, Java, JavaScript, Go)
+    #[value(alias = "cpp", alias = "rust", alias = "java", alias = "js")]
+    #[value(alias = "javascript", alias = "ts", alias = "typescript", alias = "go")]
+    C,
+    /// `#` line comments (Python, shell, Ruby, YAML, TOML)
+    #[value(alias = "python", alias = "py", alias = "shell", alias = "sh")]
+    #[value(alias = "bash", alias = "ruby", alias = "yaml", alias = "toml")]
+    Hash,
+    /// `<!-- -->` block comments (HTML, XML)
+    #[value(alias = "xml")]
+    Html,
+}
+
+impl CommentStyle {
+    fn line_marker(self) -> Option<&'static str> {
+        match self {
+            CommentStyle::C => Some("//"),
+            CommentStyle::Hash => Some("#"),
+            CommentStyle::Html => None,
+        }
+    }
+
+    fn block_markers(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            CommentStyle::C => Some(("/*", "*/")),
+            CommentStyle::Hash => None,
+            CommentStyle::Html => Some(("<!--", "-->")),
+        }
+    }
+
+    /// Quote characters that delimit string literals.
+    ///
+    /// Markup text is prose, where quotes and apostrophes don't pair up.
+    fn quotes(self) -> &'static [char] {
+        match self {
+            CommentStyle::C | CommentStyle::Hash => &['"', '\''],
+            CommentStyle::Html => &[],
+        }
+    }
+}
+
+/// Remove comments from `text`.
+///
+/// Comment markers inside string literals are kept, so `"http://example.com"`
+/// survives. Strings end at the closing quote or the end of the line, which
+/// keeps a stray apostrophe (or a Rust lifetime) from swallowing the rest of
+/// the input. Lines left blank by a removed comment are dropped, and
+/// whitespace before a trailing comment is trimmed.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::utils::comments::{strip_comments, CommentStyle};
+///
+/// let code = "// setup\nlet url = \"http://x\"; // fetch\n";
+/// assert_eq!(strip_comments(code, CommentStyle::C), "let url = \"http://x\";\n");
+/// ```
+pub fn strip_comments(text: &str, style: CommentStyle) -> String {
+    // Each line with whether a comment was removed from it
+    let mut lines: Vec<(String, bool)> = Vec::new();
+    let mut line = String::new();
+    let mut stripped = false;
+    let mut quote: Option<char> = None;
+    let mut rest = text;
+
+    while let Some(ch) = rest.chars().next() {
+        if let Some(open) = quote {
+            let len = match ch {
+                '\\' => rest[1..].chars().next().map_or(1, |c| 1 + c.len_utf8()),
+                _ => ch.len_utf8(),
+            };
+            if ch == '\n' {
+                quote = None;
+                lines.push((std::mem::take(&mut line), stripped));
+                stripped = false;
+            } else {
+                line.push_str(&rest[..len]);
+                if ch == open {
+                    quote = None;
+                }
+            }
+            rest = &rest[len..];
+            continue;
+        }
+
+        if let Some(marker) = style.line_marker().filter(|m| rest.starts_with(m)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            stripped = true;
+            rest = &rest[end.max(marker.len())..];
+            continue;
+        }
+
+        if let Some((open, close)) = style.block_markers().filter(|(o, _)| rest.starts_with(o)) {
+            let body = &rest[open.len()..];
+            let end = body.find(close).map_or(body.len(), |i| i + close.len());
+            // Keep the line structure so that fully commented lines drop out
+            for _ in body[..end].matches('\n') {
+                lines.push((std::mem::take(&mut line), true));
+            }
+            stripped = true;
+            rest = &body[end..];
+            continue;
+        }
+
+        if ch == '\n' {
+            lines.push((std::mem::take(&mut line), stripped));
+            stripped = false;
+        } else {
+            if style.quotes().contains(&ch) {
+                quote = Some(ch);
+            }
+            line.push(ch);
+        }
+        rest = &rest[ch.len_utf8()..];
+    }
+    let last = (!line.is_empty() || stripped).then_some((line, stripped));
+
+    let mut output = String::with_capacity(text.len());
+    for (content, stripped) in lines {
+        if !stripped {
+            output.push_str(&content);
+            output.push('\n');
+        } else if !content.trim().is_empty() {
+            output.push_str(content.trim_end());
+            output.push('\n');
+        }
+    }
+    if let Some((content, stripped)) = last {
+        if !stripped {
+            output.push_str(&content);
+        } else {
+            output.push_str(content.trim_end());
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_style_comments() {
+        let code = "// header\nint x = 1; // one\n/* block\n   spans */\nint y = /* inline */ 2;\n";
+        assert_eq!(
+            strip_comments(code, CommentStyle::C),
+            "int x = 1;\nint y =  2;\n"
+        );
+    }
+
+    #[test]
+    fn test_c_style_keeps_strings() {
+        let code = "let url = \"http://example.com/*x*/\"; // link\nlet s = \"a \\\" // b\";\n";
+        assert_eq!(
+            strip_comments(code, CommentStyle::C),
+            "let url = \"http://example.com/*x*/\";\nlet s = \"a \\\" // b\";\n"
+        );
+    }
+
+    #[test]
+    fn test_apostrophe_does_not_hide_comments() {
+        let code = "fn f<'a>(s: &'a str) {} // lifetime\nlet c = '/'; // slash\n";
+        assert_eq!(
+            strip_comments(code, CommentStyle::C),
+            "fn f<'a>(s: &'a str) {}\nlet c = '/';\n"
+        );
+    }
+
+    #[test]
+    fn test_hash_comments() {
+        let code = "# setup\nx = 1  # one\nurl = \"http://x/#top\"\n";
+        assert_eq!(
+            strip_comments(code, CommentStyle::Hash),
+            "x = 1\nurl = \"http://x/#top\"\n"
+        );
+    }
+
+    #[test]
+    fn test_html_comments() {
+        let html = "<p>Don't</p>\n<!-- note\n-->\n<p>a <!-- b --> c</p>";
+        assert_eq!(
+            strip_comments(html, CommentStyle::Html),
+            "<p>Don't</p>\n<p>a  c</p>"
+        );
+    }
+
+    #[test]
+    fn test_language_aliases() {
+        assert_eq!(
+            CommentStyle::from_str("rust", true).unwrap(),
+            CommentStyle::C
+        );
+        assert_eq!(
+            CommentStyle::from_str("python", true).unwrap(),
+            CommentStyle::Hash
+        );
+        assert_eq!(
+            CommentStyle::from_str("xml", true).unwrap(),
+            CommentStyle::Html
+        );
+    }
+}
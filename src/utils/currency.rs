@@ -0,0 +1,191 @@
+/// A currency to show costs in, with its exchange rate from USD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. `EUR`.
+    pub code: String,
+    /// Units of this currency per US dollar.
+    pub rate: f64,
+}
+
+impl Currency {
+    /// US dollars, the currency all pricing is stored in.
+    pub fn usd() -> Self {
+        Self {
+            code: "USD".to_string(),
+            rate: 1.0,
+        }
+    }
+
+    /// A currency with `rate` units per US dollar.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message unless the code is three ASCII letters and the rate
+    /// is a finite number greater than zero.
+    pub fn new(code: &str, rate: f64) -> Result<Self, String> {
+        let code = parse_currency_code(code)?;
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(format!(
+                "exchange rate must be greater than 0, got {}",
+                rate
+            ));
+        }
+        Ok(Self { code, rate })
+    }
+
+    /// Symbol placed before amounts, or the code followed by a space.
+    pub fn symbol(&self) -> String {
+        match self.code.as_str() {
+            "USD" => "$".to_string(),
+            "EUR" => "€".to_string(),
+            "GBP" => "£".to_string(),
+            "JPY" | "CNY" => "¥".to_string(),
+            "INR" => "₹".to_string(),
+            "KRW" => "₩".to_string(),
+            "CAD" => "CA$".to_string(),
+            "AUD" => "A$".to_string(),
+            code => format!("{} ", code),
+        }
+    }
+
+    /// Convert a USD amount into this currency.
+    pub fn convert(&self, usd: f64) -> f64 {
+        usd * self.rate
+    }
+
+    /// Format a USD amount in this currency with `decimals` decimal places.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::currency::Currency;
+    ///
+    /// let eur = Currency::new("eur", 0.9).unwrap();
+    /// assert_eq!(eur.format(0.03, 4), "€0.0270");
+    /// assert_eq!(Currency::usd().format(0.03, 4), "$0.0300");
+    /// ```
+    pub fn format(&self, usd: f64, decimals: usize) -> String {
+        format!("{}{:.*}", self.symbol(), decimals, self.convert(usd))
+    }
+
+    /// Format a USD price per 1K tokens in this currency, with up to six
+    /// decimal places and no trailing zeros.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::currency::Currency;
+    ///
+    /// let eur = Currency::new("EUR", 0.9).unwrap();
+    /// assert_eq!(eur.format_rate(0.03), "€0.027");
+    /// assert_eq!(Currency::usd().format_rate(2.0), "$2");
+    /// ```
+    pub fn format_rate(&self, usd: f64) -> String {
+        let amount = self.format(usd, 6);
+        let amount = amount.trim_end_matches('0');
+        amount.strip_suffix('.').unwrap_or(amount).to_string()
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::usd()
+    }
+}
+
+/// Parse an ISO 4217 currency code, normalized to upper case.
+///
+/// # Errors
+///
+/// Returns a message unless the code is three ASCII letters.
+pub fn parse_currency_code(value: &str) -> Result<String, String> {
+    let code = value.trim();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code.to_ascii_uppercase())
+    } else {
+        Err(format!(
+            "invalid currency code '{}': expected three letters such as EUR",
+            value
+        ))
+    }
+}
+
+/// Parse an exchange rate in units of the target currency per US dollar.
+///
+/// # Errors
+///
+/// Returns a message unless the rate is a finite number greater than zero.
+pub fn parse_fx_rate(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 => Ok(rate),
+        Ok(_) => Err(format!(
+            "exchange rate must be greater than 0, got '{}'",
+            value
+        )),
+        Err(_) => Err(format!("invalid exchange rate '{}'", value)),
+    }
+}
+
+/// A cost with a markup of `percent` added, as charged by a reseller.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::utils::currency::apply_markup;
+///
+/// assert!((apply_markup(0.05, 20.0) - 0.06).abs() < 1e-12);
+/// ```
+pub fn apply_markup(cost: f64, percent: f64) -> f64 {
+    cost * (1.0 + percent / 100.0)
+}
+
+/// Parse a markup percentage over the provider's price.
+///
+/// # Errors
+///
+/// Returns a message unless the percentage is a finite number of at least 0.
+pub fn parse_markup(value: &str) -> Result<f64, String> {
+    match value.trim().trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent.is_finite() && percent >= 0.0 => Ok(percent),
+        Ok(_) => Err(format!("markup must be at least 0%, got '{}'", value)),
+        Err(_) => Err(format!("invalid markup percentage '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_and_format() {
+        let gbp = Currency::new("GBP", 0.8).unwrap();
+        assert!((gbp.convert(0.05) - 0.04).abs() < 1e-12);
+        assert_eq!(gbp.format(0.05, 4), "£0.0400");
+
+        let chf = Currency::new("chf", 0.9).unwrap();
+        assert_eq!(chf.code, "CHF");
+        assert_eq!(chf.format(1.0, 2), "CHF 0.90");
+    }
+
+    #[test]
+    fn test_invalid_currency_and_rate() {
+        assert!(Currency::new("EURO", 1.1).is_err());
+        assert!(Currency::new("EUR", 0.0).is_err());
+        assert!(Currency::new("EUR", f64::NAN).is_err());
+        assert!(parse_fx_rate("-1").is_err());
+        assert!(parse_fx_rate("abc").is_err());
+        assert_eq!(parse_fx_rate("0.92").unwrap(), 0.92);
+        assert_eq!(parse_currency_code("eur").unwrap(), "EUR");
+    }
+
+    #[test]
+    fn test_markup() {
+        assert!((apply_markup(1.25, 20.0) - 1.5).abs() < 1e-12);
+        assert_eq!(apply_markup(1.25, 0.0), 1.25);
+        assert_eq!(parse_markup("20").unwrap(), 20.0);
+        assert_eq!(parse_markup("12.5%").unwrap(), 12.5);
+        assert!(parse_markup("-5").is_err());
+        assert!(parse_markup("inf").is_err());
+        assert!(parse_markup("lots").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+/// Detection of metadata frontmatter at the start of Markdown prompts.
+use std::fmt;
+
+/// Syntax of a frontmatter block, as given by its fence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterFormat {
+    /// YAML between `---` fences
+    Yaml,
+    /// TOML between `+++` fences
+    Toml,
+}
+
+impl FrontmatterFormat {
+    fn fence(self) -> &'static str {
+        match self {
+            FrontmatterFormat::Yaml => "---",
+            FrontmatterFormat::Toml => "+++",
+        }
+    }
+}
+
+impl fmt::Display for FrontmatterFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontmatterFormat::Yaml => write!(f, "YAML"),
+            FrontmatterFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+/// A frontmatter block split off the rest of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frontmatter<'a> {
+    /// Syntax of the block.
+    pub format: FrontmatterFormat,
+    /// The block including both fences and the closing line break.
+    pub block: &'a str,
+    /// Everything after the block.
+    pub body: &'a str,
+}
+
+/// Split the frontmatter off `text`, or return `None` if it has none.
+///
+/// Frontmatter must open on the very first line with a `---` (YAML) or
+/// `+++` (TOML) fence and be closed by the same fence on a line of its own.
+/// An unclosed fence is treated as ordinary content.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::utils::frontmatter::{split_frontmatter, FrontmatterFormat};
+///
+/// let doc = "---\ntitle: Summary\n---\nSummarize the report.\n";
+/// let frontmatter = split_frontmatter(doc).unwrap();
+/// assert_eq!(frontmatter.format, FrontmatterFormat::Yaml);
+/// assert_eq!(frontmatter.block, "---\ntitle: Summary\n---\n");
+/// assert_eq!(frontmatter.body, "Summarize the report.\n");
+/// assert!(split_frontmatter("Summarize the report.").is_none());
+/// ```
+pub fn split_frontmatter(text: &str) -> Option<Frontmatter<'_>> {
+    let first = text.split_inclusive('\n').next()?;
+    if !first.ends_with('\n') {
+        return None;
+    }
+    let format = [FrontmatterFormat::Yaml, FrontmatterFormat::Toml]
+        .into_iter()
+        .find(|format| first.trim_end() == format.fence())?;
+
+    let mut end = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        end += line.len();
+        if index > 0 && line.trim_end() == format.fence() {
+            let (block, body) = text.split_at(end);
+            return Some(Frontmatter {
+                format,
+                block,
+                body,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_and_crlf_fences() {
+        let doc = "+++\r\ntitle = \"x\"\r\n+++\r\nBody";
+        let frontmatter = split_frontmatter(doc).unwrap();
+        assert_eq!(frontmatter.format, FrontmatterFormat::Toml);
+        assert_eq!(frontmatter.body, "Body");
+    }
+
+    #[test]
+    fn test_not_frontmatter() {
+        // Unclosed, not on the first line, or a horizontal rule alone
+        assert!(split_frontmatter("---\ntitle: x\nBody").is_none());
+        assert!(split_frontmatter("Intro\n---\nx\n---\n").is_none());
+        assert!(split_frontmatter("---").is_none());
+        // Mismatched fences don't close each other
+        assert!(split_frontmatter("---\ntitle: x\n+++\nBody").is_none());
+    }
+
+    #[test]
+    fn test_closing_fence_at_end_of_input() {
+        let frontmatter = split_frontmatter("---\ntitle: x\n---").unwrap();
+        assert_eq!(frontmatter.body, "");
+    }
+}
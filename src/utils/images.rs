@@ -0,0 +1,134 @@
+/// Inline base64 images in prompt text.
+use crate::error::TokenizerError;
+use crate::tokenizers::Tokenizer;
+use clap::ValueEnum;
+use std::borrow::Cow;
+
+/// Tokens counted per inline image with `--image-tokens estimate`.
+///
+/// This is the flat cost of a low-detail image on OpenAI vision models.
+pub const IMAGE_TOKEN_ESTIMATE: usize = 85;
+
+/// Start of an inline image URI.
+const DATA_IMAGE_PREFIX: &str = "data:image/";
+
+/// How inline `data:image/...;base64,` URIs are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ImageTokens {
+    /// Count each image as a fixed estimate instead of its base64 text
+    #[default]
+    Estimate,
+    /// Remove images and count nothing for them
+    Strip,
+    /// Count the base64 text like any other text
+    Literal,
+}
+
+impl ImageTokens {
+    /// Remove inline images from `text`, unless counting them literally.
+    ///
+    /// Returns the remaining text and the tokens to add for the removed images.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::images::{ImageTokens, IMAGE_TOKEN_ESTIMATE};
+    ///
+    /// let text = "Describe data:image/png;base64,iVBORw0KGgo= please";
+    /// let (rest, tokens) = ImageTokens::Estimate.apply(text);
+    /// assert_eq!((rest.as_ref(), tokens), ("Describe  please", IMAGE_TOKEN_ESTIMATE));
+    /// assert_eq!(ImageTokens::Strip.apply(text).1, 0);
+    /// assert_eq!(ImageTokens::Literal.apply(text).0, text);
+    /// ```
+    pub fn apply(self, text: &str) -> (Cow<'_, str>, usize) {
+        if self == ImageTokens::Literal {
+            return (Cow::Borrowed(text), 0);
+        }
+        let (rest, images) = strip_data_uris(text);
+        match self {
+            ImageTokens::Estimate => (rest, images * IMAGE_TOKEN_ESTIMATE),
+            _ => (rest, 0),
+        }
+    }
+
+    /// Count `text` with `tokenizer`, treating inline images by this mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TokenizerError` if the text cannot be tokenized.
+    pub fn count(self, tokenizer: &dyn Tokenizer, text: &str) -> Result<usize, TokenizerError> {
+        let (rest, image_tokens) = self.apply(text);
+        Ok(tokenizer.count_tokens(&rest)? + image_tokens)
+    }
+}
+
+/// Remove base64 `data:image/...` URIs from `text`.
+///
+/// Returns the remaining text and the number of images removed. Text without
+/// images is borrowed unchanged.
+pub fn strip_data_uris(text: &str) -> (Cow<'_, str>, usize) {
+    let mut output = String::new();
+    let mut images = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find(DATA_IMAGE_PREFIX) {
+        let uri = &rest[start + DATA_IMAGE_PREFIX.len()..];
+        match base64_image_len(uri) {
+            Some(len) => {
+                output.push_str(&rest[..start]);
+                rest = &uri[len..];
+                images += 1;
+            }
+            None => {
+                output.push_str(&rest[..start + DATA_IMAGE_PREFIX.len()]);
+                rest = uri;
+            }
+        }
+    }
+
+    if images == 0 {
+        return (Cow::Borrowed(text), 0);
+    }
+    output.push_str(rest);
+    (Cow::Owned(output), images)
+}
+
+/// Length of `<subtype>;base64,<data>` at the start of `uri`, if present.
+fn base64_image_len(uri: &str) -> Option<usize> {
+    const BASE64_MARKER: &str = ";base64,";
+
+    let subtype = uri
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)))
+        .filter(|&len| len > 0)?;
+    let data = uri[subtype..].strip_prefix(BASE64_MARKER)?;
+    let data_len = data
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "+/=".contains(c)))
+        .unwrap_or(data.len());
+    (data_len > 0).then_some(subtype + BASE64_MARKER.len() + data_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_data_uris() {
+        let text = r#"{"a": "data:image/jpeg;base64,/9j/4AAQ==", "b": "data:image/svg+xml;base64,PHN2Zz4="}"#;
+        let (rest, images) = strip_data_uris(text);
+        assert_eq!(rest, r#"{"a": "", "b": ""}"#);
+        assert_eq!(images, 2);
+    }
+
+    #[test]
+    fn test_non_base64_uris_are_kept() {
+        for text in [
+            "no images here",
+            "data:image/png,rawbytes",
+            "data:image/;base64,AAAA",
+            "see data:image/png;base64, later",
+        ] {
+            let (rest, images) = strip_data_uris(text);
+            assert!(matches!(rest, Cow::Borrowed(_)), "{}", text);
+            assert_eq!(images, 0);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+/// Reading prompt inputs from stdin, files or direct text.
+use crate::error::AppError;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Where an input is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// Standard input.
+    Stdin,
+    /// A file; `kind` names it in errors, e.g. `diff file`.
+    File { path: String, kind: &'static str },
+    /// Text given directly, e.g. as a command-line argument.
+    Text(String),
+}
+
+impl InputSource {
+    /// Source of an input argument: stdin if it is missing or `-`, otherwise
+    /// the named file.
+    pub fn from_arg(arg: Option<&str>) -> Self {
+        match arg {
+            None | Some("-") => InputSource::Stdin,
+            Some(path) => InputSource::file(path, "file"),
+        }
+    }
+
+    /// A file, described as `kind` in errors.
+    pub fn file(path: impl Into<String>, kind: &'static str) -> Self {
+        InputSource::File {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    /// Read the input, or fail if it is larger than `max_bytes`.
+    ///
+    /// Larger inputs are rejected without reading more than one byte past
+    /// the limit; files are checked by size before reading anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InputTooLarge` for oversized inputs, or
+    /// `AppError::Io` if the input cannot be read or is not UTF-8.
+    pub fn read(&self, max_bytes: Option<usize>) -> Result<String, AppError> {
+        self.read_with_stdin(io::stdin(), max_bytes)
+    }
+
+    /// Like [`read`](Self::read), with `stdin` standing in for standard
+    /// input.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::input::InputSource;
+    ///
+    /// let input = InputSource::from_arg(Some("-")).read_with_stdin(&b"Hello"[..], None)?;
+    /// assert_eq!(input, "Hello");
+    ///
+    /// let error = InputSource::Text("Hello".to_string()).read(Some(4)).unwrap_err();
+    /// assert_eq!(error.to_string(), "text is larger than --max-bytes 4");
+    /// # Ok::<(), tokuin::error::AppError>(())
+    /// ```
+    pub fn read_with_stdin(
+        &self,
+        stdin: impl Read,
+        max_bytes: Option<usize>,
+    ) -> Result<String, AppError> {
+        match self {
+            InputSource::Stdin => read_limited(stdin, "stdin", max_bytes),
+            InputSource::File { path, kind } => read_file(path, kind, max_bytes),
+            InputSource::Text(text) => read_limited(text.as_bytes(), "text", max_bytes),
+        }
+    }
+
+    /// Open the input to be read incrementally, failing once it passes
+    /// `max_bytes`.
+    ///
+    /// Files are checked by size before anything is read. Other inputs fail
+    /// with an I/O error as soon as a read goes past the limit; [`too_large`]
+    /// recovers the `AppError::InputTooLarge` it carries.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::InputTooLarge` for oversized files, or
+    /// `AppError::Io` if a file cannot be opened.
+    pub fn open(&self, max_bytes: Option<usize>) -> Result<Box<dyn Read>, AppError> {
+        let (reader, name): (Box<dyn Read>, &str) = match self {
+            InputSource::Stdin => (Box::new(io::stdin()), "stdin"),
+            InputSource::File { path, kind } => {
+                // Already checked by size
+                return Ok(Box::new(open_file(path, kind, max_bytes)?));
+            }
+            InputSource::Text(text) => (Box::new(io::Cursor::new(text.clone())), "text"),
+        };
+        Ok(match max_bytes {
+            Some(limit) => Box::new(LimitedReader {
+                inner: reader,
+                name: name.to_string(),
+                limit,
+                read: 0,
+            }),
+            None => reader,
+        })
+    }
+}
+
+/// The `--max-bytes` error carried by an I/O error from an input opened with
+/// [`InputSource::open`], if it is one.
+pub fn too_large(error: &io::Error) -> Option<AppError> {
+    let TooLarge { input, limit } = error.get_ref()?.downcast_ref::<TooLarge>()?;
+    Some(AppError::InputTooLarge {
+        input: input.clone(),
+        limit: *limit,
+    })
+}
+
+/// An input found to be larger than its limit while being read.
+#[derive(Debug)]
+struct TooLarge {
+    input: String,
+    limit: usize,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is larger than --max-bytes {}",
+            self.input, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Reader that fails once more than `limit` bytes have been read.
+struct LimitedReader<R> {
+    inner: R,
+    name: String,
+    limit: usize,
+    read: usize,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n;
+        if self.read > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                TooLarge {
+                    input: self.name.clone(),
+                    limit: self.limit,
+                },
+            ));
+        }
+        Ok(n)
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Stdin => write!(f, "stdin"),
+            InputSource::File { path, kind } => write!(f, "{} '{}'", kind, path),
+            InputSource::Text(_) => write!(f, "text"),
+        }
+    }
+}
+
+/// Error for a file that could not be opened or read.
+fn read_failed(path: &str, kind: &str, e: io::Error) -> AppError {
+    AppError::Io(io::Error::new(
+        io::ErrorKind::Other,
+        format!("Failed to read {} '{}': {}", kind, path, e),
+    ))
+}
+
+/// Error for a file larger than `limit`.
+fn file_too_large(path: &str, kind: &str, limit: usize) -> AppError {
+    AppError::InputTooLarge {
+        input: format!("{} '{}'", kind, path),
+        limit,
+    }
+}
+
+/// Open a file, or fail if its size is larger than `max_bytes`.
+fn open_file(path: &str, kind: &str, max_bytes: Option<usize>) -> Result<std::fs::File, AppError> {
+    let file = std::fs::File::open(path).map_err(|e| read_failed(path, kind, e))?;
+    // Reject by size up front, before reading anything
+    if let (Some(limit), Ok(metadata)) = (max_bytes, file.metadata()) {
+        if metadata.is_file() && metadata.len() > limit as u64 {
+            return Err(file_too_large(path, kind, limit));
+        }
+    }
+    Ok(file)
+}
+
+/// Read a file, or fail if it is larger than `max_bytes`.
+///
+/// `kind` names the file in errors, e.g. `diff file`.
+fn read_file(path: &str, kind: &str, max_bytes: Option<usize>) -> Result<String, AppError> {
+    let file = open_file(path, kind, max_bytes)?;
+    read_limited(file, kind, max_bytes).map_err(|e| match e {
+        AppError::InputTooLarge { limit, .. } => file_too_large(path, kind, limit),
+        AppError::Io(e) => read_failed(path, kind, e),
+        e => e,
+    })
+}
+
+/// Read `reader` to a string, failing once it passes `max_bytes`.
+///
+/// `name` describes the input in the error, e.g. `stdin`.
+fn read_limited(
+    mut reader: impl Read,
+    name: &str,
+    max_bytes: Option<usize>,
+) -> Result<String, AppError> {
+    let mut bytes = Vec::new();
+    match max_bytes {
+        Some(limit) => {
+            reader.take(limit as u64 + 1).read_to_end(&mut bytes)?;
+            if bytes.len() > limit {
+                return Err(AppError::InputTooLarge {
+                    input: name.to_string(),
+                    limit,
+                });
+            }
+        }
+        None => {
+            reader.read_to_end(&mut bytes)?;
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|e| AppError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdin_rejects_oversized_input() {
+        let input = "x".repeat(100);
+        let stdin = InputSource::from_arg(None);
+        assert_eq!(
+            stdin.read_with_stdin(input.as_bytes(), Some(100)).unwrap(),
+            input
+        );
+        assert_eq!(
+            stdin.read_with_stdin(input.as_bytes(), None).unwrap(),
+            input
+        );
+
+        let error = stdin
+            .read_with_stdin(input.as_bytes(), Some(99))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "stdin is larger than --max-bytes 99");
+
+        let error = stdin.read_with_stdin(&[0xff, 0xfe][..], None).unwrap_err();
+        assert!(matches!(error, AppError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn test_file_checks_size_before_reading() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&[b'a'; 4096]).unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let source = InputSource::from_arg(Some(path));
+        assert_eq!(source.read(None).unwrap().len(), 4096);
+        let error = InputSource::file(path, "diff file")
+            .read(Some(1024))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("diff file '{}' is larger than --max-bytes 1024", path)
+        );
+    }
+
+    #[test]
+    fn test_missing_file_is_named() {
+        let error = InputSource::file("no/such/prompt.txt", "baseline file")
+            .read(None)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Failed to read baseline file 'no/such/prompt.txt'"));
+    }
+
+    #[test]
+    fn test_open_fails_past_the_limit() {
+        let source = InputSource::Text("x".repeat(100));
+        let mut read = String::new();
+        source
+            .open(Some(100))
+            .unwrap()
+            .read_to_string(&mut read)
+            .unwrap();
+        assert_eq!(read.len(), 100);
+
+        let error = source
+            .open(Some(99))
+            .unwrap()
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert_eq!(
+            too_large(&error).unwrap().to_string(),
+            "text is larger than --max-bytes 99"
+        );
+        assert!(too_large(&io::Error::new(io::ErrorKind::Other, "other")).is_none());
+    }
+
+    #[test]
+    fn test_text_is_read_as_given() {
+        let source = InputSource::Text("Summarize the report".to_string());
+        // Stdin is not touched for other sources
+        assert_eq!(
+            source.read_with_stdin(&b"ignored"[..], Some(20)).unwrap(),
+            "Summarize the report"
+        );
+        assert_eq!(source.to_string(), "text");
+    }
+}
@@ -0,0 +1,148 @@
+/// Line-ending normalization applied before counting.
+use clap::ValueEnum;
+use std::borrow::Cow;
+
+/// How line endings are treated before counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LineEndings {
+    /// Count the input as given
+    #[default]
+    Preserve,
+    /// Convert CRLF line endings to LF
+    Lf,
+    /// Convert LF line endings to CRLF
+    Crlf,
+}
+
+impl LineEndings {
+    /// Apply this line-ending style to `text`.
+    ///
+    /// Only CRLF pairs and bare LF are converted; a lone CR is left alone.
+    /// Text that is already in the target style is borrowed unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::line_endings::LineEndings;
+    ///
+    /// assert_eq!(LineEndings::Lf.normalize("a\r\nb\r\n"), "a\nb\n");
+    /// assert_eq!(LineEndings::Crlf.normalize("a\nb\r\n"), "a\r\nb\r\n");
+    /// assert_eq!(LineEndings::Preserve.normalize("a\r\nb\n"), "a\r\nb\n");
+    /// ```
+    pub fn normalize(self, text: &str) -> Cow<'_, str> {
+        match self {
+            LineEndings::Preserve => Cow::Borrowed(text),
+            LineEndings::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            LineEndings::Lf => Cow::Borrowed(text),
+            LineEndings::Crlf if count_bare_lf(text) > 0 => {
+                Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+            }
+            LineEndings::Crlf => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// Number of CRLF line endings in `text`.
+pub fn count_crlf(text: &str) -> usize {
+    text.matches("\r\n").count()
+}
+
+/// Number of LF line endings in `text` not preceded by a CR.
+pub fn count_bare_lf(text: &str) -> usize {
+    text.matches('\n').count() - count_crlf(text)
+}
+
+/// `text` without a single trailing LF or CRLF, or `None` if it has none.
+///
+/// Editors end files with a newline that pasted text usually lacks; only
+/// that one line break is removed, so blank lines at the end still count.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::utils::line_endings::trim_trailing_newline;
+///
+/// assert_eq!(trim_trailing_newline("Hello\n\n"), Some("Hello\n"));
+/// assert_eq!(trim_trailing_newline("Hello\r\n"), Some("Hello"));
+/// assert_eq!(trim_trailing_newline("Hello"), None);
+/// ```
+pub fn trim_trailing_newline(text: &str) -> Option<&str> {
+    text.strip_suffix("\r\n")
+        .or_else(|| text.strip_suffix('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LF: &str = "First line\nSecond line\n\nThird line\n";
+    const CRLF: &str = "First line\r\nSecond line\r\n\r\nThird line\r\n";
+
+    #[test]
+    fn test_normalize_round_trip() {
+        assert_eq!(LineEndings::Lf.normalize(CRLF), LF);
+        assert_eq!(LineEndings::Crlf.normalize(LF), CRLF);
+        assert!(matches!(LineEndings::Lf.normalize(LF), Cow::Borrowed(_)));
+        assert!(matches!(
+            LineEndings::Crlf.normalize(CRLF),
+            Cow::Borrowed(_)
+        ));
+        // A lone CR is not a line ending
+        assert_eq!(LineEndings::Lf.normalize("a\rb\r\n"), "a\rb\n");
+        assert_eq!(count_crlf(CRLF), 4);
+        assert_eq!(count_bare_lf(CRLF), 0);
+        assert_eq!(count_bare_lf(LF), 4);
+    }
+
+    #[test]
+    fn test_trim_trailing_newline() {
+        assert_eq!(
+            trim_trailing_newline(LF),
+            Some("First line\nSecond line\n\nThird line")
+        );
+        assert_eq!(
+            trim_trailing_newline(CRLF),
+            Some("First line\r\nSecond line\r\n\r\nThird line")
+        );
+        assert_eq!(trim_trailing_newline("\n"), Some(""));
+        assert_eq!(trim_trailing_newline("Third line\r"), None);
+    }
+
+    #[test]
+    fn test_crlf_and_lf_token_counts() {
+        use crate::tokenizers::{CharTokenizer, Tokenizer};
+
+        // Every CR is a token of its own when counting characters
+        let chars = CharTokenizer::new();
+        let lf = chars.count_tokens(LF).unwrap();
+        let crlf = chars.count_tokens(CRLF).unwrap();
+        assert_eq!(crlf - lf, 4);
+        assert_eq!(
+            chars
+                .count_tokens(&LineEndings::Lf.normalize(CRLF))
+                .unwrap(),
+            lf
+        );
+        assert_eq!(
+            chars
+                .count_tokens(&LineEndings::Crlf.normalize(LF))
+                .unwrap(),
+            crlf
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_crlf_changes_bpe_count() {
+        use crate::tokenizers::{OpenAITokenizer, Tokenizer};
+
+        // o200k_base merges "\r\n" into most neighbours, but not after ":)"
+        let tokenizer = OpenAITokenizer::new("gpt-4o").unwrap();
+        let lf = "Hi!\n:)\n";
+        let crlf = LineEndings::Crlf.normalize(lf);
+        assert_eq!(tokenizer.count_tokens(lf).unwrap(), 3);
+        assert_eq!(tokenizer.count_tokens(&crlf).unwrap(), 4);
+        assert_eq!(tokenizer.count_tokens(LF).unwrap(), 9);
+        assert_eq!(tokenizer.count_tokens(CRLF).unwrap(), 9);
+    }
+}
@@ -1,3 +1,14 @@
 /// Utility functions.
+pub mod color;
+pub mod comments;
+pub mod currency;
+pub mod frontmatter;
+pub mod images;
+pub mod input;
+pub mod line_endings;
 #[cfg(feature = "markdown")]
 pub mod markdown;
+pub mod numbers;
+pub mod parallel;
+pub mod preview;
+pub mod unicode;
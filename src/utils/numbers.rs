@@ -0,0 +1,69 @@
+/// Number formatting for human-readable output.
+use clap::ValueEnum;
+
+/// Locale convention for grouping the digits of large numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum NumberLocale {
+    /// Comma separators: 1,234,567
+    #[default]
+    En,
+    /// Period separators: 1.234.567
+    De,
+    /// Space separators: 1 234 567
+    Fr,
+}
+
+impl NumberLocale {
+    /// The thousands separator for this locale.
+    pub fn separator(self) -> char {
+        match self {
+            NumberLocale::En => ',',
+            NumberLocale::De => '.',
+            NumberLocale::Fr => ' ',
+        }
+    }
+}
+
+/// Format `value` with `separator` between groups of three digits.
+///
+/// With no separator the number is formatted as-is.
+///
+/// # Example
+///
+/// ```rust
+/// # use tokuin::utils::numbers::group_digits;
+/// assert_eq!(group_digits(1234567, Some(',')), "1,234,567");
+/// assert_eq!(group_digits(1234567, None), "1234567");
+/// ```
+pub fn group_digits(value: usize, separator: Option<char>) -> String {
+    let digits = value.to_string();
+    let Some(separator) = separator else {
+        return digits;
+    };
+
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|group| std::str::from_utf8(group).expect("digits are ASCII"))
+        .collect();
+    groups.join(&separator.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits(0, Some(',')), "0");
+        assert_eq!(group_digits(999, Some(',')), "999");
+        assert_eq!(group_digits(1000, Some(',')), "1,000");
+        assert_eq!(group_digits(123456, Some(',')), "123,456");
+        assert_eq!(
+            group_digits(1234567, Some(NumberLocale::De.separator())),
+            "1.234.567"
+        );
+        assert_eq!(group_digits(1234567, None), "1234567");
+    }
+}
@@ -0,0 +1,77 @@
+/// Bounded parallel helpers.
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Default number of jobs: the available parallelism, or 1 if unknown.
+pub fn default_jobs() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Apply `f` to every item using at most `jobs` worker threads.
+///
+/// Workers pull the next index from a shared counter, so no more than
+/// `jobs` items are ever in flight at once. Results are returned in the
+/// same order as `items`, regardless of `jobs` or completion order.
+pub fn map_bounded<I, O, F>(items: &[I], jobs: NonZeroUsize, f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync,
+{
+    let workers = jobs.get().min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<O>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let output = f(item);
+                if let Ok(mut guard) = slots.lock() {
+                    guard[index] = Some(output);
+                }
+            });
+        }
+    });
+
+    slots
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .map(|slot| slot.expect("every index is processed exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizers::{CharTokenizer, Tokenizer};
+
+    #[test]
+    fn test_map_bounded_preserves_order() {
+        let tokenizer = CharTokenizer::new();
+        let inputs: Vec<String> = (0..50).map(|i| "x".repeat(i)).collect();
+        let jobs = NonZeroUsize::new(4).unwrap();
+
+        let counts = map_bounded(&inputs, jobs, |text| tokenizer.count_tokens(text).unwrap());
+
+        let expected: Vec<usize> = (0..50).collect();
+        assert_eq!(counts, expected);
+    }
+
+    #[test]
+    fn test_map_bounded_single_job_and_empty() {
+        let one = NonZeroUsize::MIN;
+        assert_eq!(map_bounded(&[1, 2, 3], one, |n| n * 2), vec![2, 4, 6]);
+        let empty: [u8; 0] = [];
+        assert!(map_bounded(&empty, default_jobs(), |n| *n).is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+/// Default number of content characters shown per message.
+pub const DEFAULT_PREVIEW_CHARS: usize = 60;
+
+/// Marker appended to content that was cut short.
+const ELLIPSIS: char = '…';
+
+/// A single-line preview of `text` showing at most `max_chars` characters.
+///
+/// Runs of whitespace, including newlines, collapse to one space so a
+/// preview never spans lines. Longer content is cut after `max_chars`
+/// characters (never inside a character) and marked with `…`.
+///
+/// # Example
+///
+/// ```rust
+/// use tokuin::utils::preview::preview;
+///
+/// assert_eq!(preview("Hello,\n  world!", 20), "Hello, world!");
+/// assert_eq!(preview("Hello, world!", 5), "Hello…");
+/// ```
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let mut words = text.split_whitespace();
+    let mut output = String::new();
+    let mut chars = 0;
+
+    let mut next = words.next();
+    while let Some(word) = next {
+        for ch in word.chars() {
+            if chars == max_chars {
+                output.push(ELLIPSIS);
+                return output;
+            }
+            output.push(ch);
+            chars += 1;
+        }
+        next = words.next();
+        if next.is_some() {
+            if chars == max_chars {
+                output.push(ELLIPSIS);
+                return output;
+            }
+            output.push(' ');
+            chars += 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_message_is_truncated_with_ellipsis() {
+        let text = "abcdefghij".repeat(10);
+        let shown = preview(&text, 25);
+        assert_eq!(shown.chars().count(), 26);
+        assert_eq!(shown, format!("{}…", &text[..25]));
+    }
+
+    #[test]
+    fn test_short_message_is_unchanged() {
+        assert_eq!(preview("Hello", 5), "Hello");
+        assert_eq!(preview("  spaced\n\tout  ", 20), "spaced out");
+        assert_eq!(preview("", 10), "");
+    }
+
+    #[test]
+    fn test_truncation_keeps_whole_characters() {
+        assert_eq!(preview("語語語語", 2), "語語…");
+        assert_eq!(preview("héllo wörld", 7), "héllo w…");
+        assert_eq!(preview("ab cd", 2), "ab…");
+    }
+}
@@ -0,0 +1,78 @@
+/// Unicode normalization applied before counting.
+use clap::ValueEnum;
+#[cfg(feature = "unicode")]
+use std::borrow::Cow;
+
+/// Unicode normalization form applied before counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum UnicodeNormalization {
+    /// Count the input as given
+    #[default]
+    None,
+    /// Canonical composition, e.g. `e` + combining acute accent becomes `é`
+    Nfc,
+    /// Compatibility composition, which also folds ligatures, full-width forms and the like
+    Nfkc,
+}
+
+impl UnicodeNormalization {
+    /// Apply this normalization form to `text`.
+    ///
+    /// Text that is already normalized is borrowed unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tokuin::utils::unicode::UnicodeNormalization;
+    ///
+    /// assert_eq!(UnicodeNormalization::Nfc.normalize("cafe\u{301}"), "caf\u{e9}");
+    /// assert_eq!(UnicodeNormalization::Nfkc.normalize("\u{fb01}le"), "file");
+    /// assert_eq!(UnicodeNormalization::None.normalize("cafe\u{301}"), "cafe\u{301}");
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn normalize(self, text: &str) -> Cow<'_, str> {
+        use unicode_normalization::{is_nfc, is_nfkc, UnicodeNormalization as _};
+
+        match self {
+            UnicodeNormalization::None => Cow::Borrowed(text),
+            UnicodeNormalization::Nfc if is_nfc(text) => Cow::Borrowed(text),
+            UnicodeNormalization::Nfc => Cow::Owned(text.nfc().collect()),
+            UnicodeNormalization::Nfkc if is_nfkc(text) => Cow::Borrowed(text),
+            UnicodeNormalization::Nfkc => Cow::Owned(text.nfkc().collect()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "unicode"))]
+mod tests {
+    use super::*;
+    use crate::tokenizers::{CharTokenizer, Tokenizer};
+
+    const COMPOSED: &str = "Caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e";
+    const DECOMPOSED: &str = "Cafe\u{301} cre\u{300}me bru\u{302}le\u{301}e";
+
+    #[test]
+    fn test_nfc_composes_decomposed_text() {
+        assert_eq!(UnicodeNormalization::Nfc.normalize(DECOMPOSED), COMPOSED);
+        assert!(matches!(
+            UnicodeNormalization::Nfc.normalize(COMPOSED),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            UnicodeNormalization::None.normalize(DECOMPOSED),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_nfc_changes_the_count() {
+        let tokenizer = CharTokenizer::new();
+        let before = tokenizer.count_tokens(DECOMPOSED).unwrap();
+        let after = tokenizer
+            .count_tokens(&UnicodeNormalization::Nfc.normalize(DECOMPOSED))
+            .unwrap();
+        // One combining accent per accented letter
+        assert_eq!(before - after, 4);
+        assert_eq!(after, tokenizer.count_tokens(COMPOSED).unwrap());
+    }
+}
@@ -0,0 +1,1580 @@
+/// Integration tests for the estimate command.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_with_stdin(args: &[&str], stdin: &str) -> std::process::Output {
+    run_with_env(args, stdin, &[])
+}
+
+/// Messages of the `warnings` array in a JSON report, one per line.
+fn json_warnings(value: &serde_json::Value) -> String {
+    value["warnings"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|warning| warning["message"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn run_with_env(args: &[&str], stdin: &str, env: &[(&str, &str)]) -> std::process::Output {
+    let mut child = Command::new("cargo")
+        .args(["run", "--"])
+        .args(args)
+        .env_remove("TOKUIN_FORMAT")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_diff_from_delimited_stdin() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--diff", "-"],
+        "Hello, world!\n---DIFF---\nHello, world! How are you today?\n",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Original:"));
+    assert!(stdout.contains("Modified:"));
+    assert!(stdout.contains("Difference: +"));
+}
+
+#[test]
+fn test_diff_from_stdin_custom_separator() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--diff", "-", "--diff-separator", "%%"],
+        "Hello, world! How are you today?\n%%\nHello, world!\n",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Difference: -"));
+}
+
+#[test]
+fn test_diff_messages_aligns_by_role() {
+    let original = r#"[{"role":"user","content":"Hi"},{"role":"assistant","content":"Hello!"}]"#;
+    let modified = r#"[{"role":"system","content":"Be brief."},{"role":"user","content":"Hi"},{"role":"assistant","content":"Hello!"}]"#;
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--diff", "-", "--diff-messages"],
+        &format!("{}\n---DIFF---\n{}\n", original, modified),
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("+ system"), "{}", stdout);
+    assert!(stdout.contains("= user"), "{}", stdout);
+    assert!(stdout.contains("= assistant"), "{}", stdout);
+}
+
+#[test]
+fn test_canonical_json_is_byte_identical() {
+    let args = [
+        "--compare",
+        "gpt-4",
+        "gpt-3.5-turbo",
+        "--price",
+        "--with-metadata",
+        "--canonical",
+    ];
+    let first = run_with_stdin(&args, "Hello, world!\n");
+    let second = run_with_stdin(&args, "Hello, world!\n");
+
+    assert!(first.status.success(), "Command should succeed");
+    assert_eq!(first.stdout, second.stdout);
+    let value: serde_json::Value = serde_json::from_slice(&first.stdout).unwrap();
+    assert_eq!(value[0]["model"], "gpt-3.5-turbo");
+    assert!(value[0].get("meta").is_none());
+}
+
+#[test]
+fn test_canonical_conflicts_with_other_formats() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--canonical", "--format", "json"],
+        "Hello, world!\n",
+    );
+    assert!(output.status.success(), "JSON is what --canonical prints");
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--canonical", "--format", "text"],
+        "Hello, world!\n",
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'--canonical' cannot be used with '--format text'"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_max_bytes_rejects_large_input() {
+    let input = "a".repeat(16 * 1024);
+    let output = run_with_stdin(&["--model", "gpt-4", "--max-bytes", "1024"], &input);
+
+    assert!(!output.status.success(), "Oversized input should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("stdin is larger than --max-bytes 1024"),
+        "{}",
+        stderr
+    );
+
+    let output = run_with_stdin(&["--model", "gpt-4"], &input);
+    assert!(output.status.success(), "Repeated characters should count");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Tokens: 2,048"));
+}
+
+#[test]
+fn test_max_bytes_applies_to_streamed_input() {
+    let message = r#"{"role": "user", "content": "Hello there"}"#;
+    let input = format!("[{}]", vec![message; 100].join(","));
+    let args = ["--model", "gpt-4", "--stream", "--max-bytes"];
+
+    let output = run_with_stdin(&[&args[..], &["1024"]].concat(), &input);
+    assert!(!output.status.success(), "Oversized stream should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("stdin is larger than --max-bytes 1024"),
+        "{}",
+        stderr
+    );
+
+    let limit = input.len().to_string();
+    let output = run_with_stdin(&[&args[..], &[limit.as_str()]].concat(), &input);
+    assert!(
+        output.status.success(),
+        "Stream within the limit should count"
+    );
+}
+
+#[test]
+fn test_gemini_payload_roles() {
+    let payload = r#"{
+        "system_instruction": {"parts": [{"text": "You are a terse assistant."}]},
+        "contents": [
+            {"role": "user", "parts": [{"text": "Hi"}]},
+            {"role": "model", "parts": [{"text": "Hello there, how can I help?"}]}
+        ]
+    }"#;
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--breakdown", "--format", "json"],
+        payload,
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let breakdown = &value["breakdown"];
+    assert!(breakdown["system"].as_u64().unwrap() > 0);
+    assert!(breakdown["user"].as_u64().unwrap() > 0);
+    assert!(breakdown["assistant"].as_u64().unwrap() > breakdown["user"].as_u64().unwrap());
+}
+
+#[test]
+fn test_strict_json_rejects_unknown_role() {
+    let payload =
+        r#"[{"role": "user", "content": "Hi"}, {"role": "assistent", "content": "Hello"}]"#;
+
+    let output = run_with_stdin(&["--model", "gpt-4"], payload);
+    assert!(
+        output.status.success(),
+        "Lenient mode should count the input"
+    );
+
+    let output = run_with_stdin(&["--model", "gpt-4", "--strict-json"], payload);
+    assert!(
+        !output.status.success(),
+        "Strict mode should reject the role"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown role 'assistent' in message 2"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_allocate_drops_low_priority_section() {
+    let dir = tempfile::tempdir().unwrap();
+    let system = dir.path().join("system.txt");
+    let context = dir.path().join("context.txt");
+    let examples = dir.path().join("examples.txt");
+    std::fs::write(&system, "You answer questions about the docs.").unwrap();
+    std::fs::write(&context, "The cache is flushed every ten minutes.").unwrap();
+    std::fs::write(
+        &examples,
+        "Q: How often is the cache flushed? A: Every ten minutes.",
+    )
+    .unwrap();
+
+    let system_section = format!("system={}", system.display());
+    let context_section = format!("context={}", context.display());
+    let examples_section = format!("examples={}", examples.display());
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--allocate",
+            "15",
+            "--section",
+            &system_section,
+            "--section",
+            &context_section,
+            "--section",
+            &examples_section,
+            "--format",
+            "json",
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let sections = value["sections"].as_array().unwrap();
+    let statuses: Vec<&str> = sections
+        .iter()
+        .map(|s| s["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["fits", "fits", "dropped"]);
+    assert_eq!(sections[2]["allocated"], 0);
+    assert_eq!(value["used"], 15);
+}
+
+#[test]
+fn test_explain_tokenization() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--explain-tokenization"],
+        "Hello, world!",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("The input is 13 characters, 13 bytes and 4 tokens"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("  1. \"Hello\""), "{}", stdout);
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--explain-tokenization"],
+        &"word ".repeat(1_000),
+    );
+    assert!(!output.status.success(), "Long inputs should be rejected");
+}
+
+#[test]
+fn test_trim_trailing_newline_matches_pasted_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let with_newline = dir.path().join("with.txt");
+    let without_newline = dir.path().join("without.txt");
+    std::fs::write(&with_newline, "Summarize the report\n").unwrap();
+    std::fs::write(&without_newline, "Summarize the report").unwrap();
+
+    let count = |path: &std::path::Path, extra: &[&str]| {
+        let mut args = vec![
+            path.to_str().unwrap(),
+            "--model",
+            "gpt-4",
+            "--format",
+            "json",
+        ];
+        args.extend_from_slice(extra);
+        let output = run_with_stdin(&args, "");
+        assert!(output.status.success(), "Command should succeed");
+        let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        (value["tokens"].as_u64().unwrap(), stderr)
+    };
+
+    let (without, _) = count(&without_newline, &[]);
+    let (with, _) = count(&with_newline, &[]);
+    assert_eq!(with, without + 1, "The newline is counted by default");
+
+    let (_, notes) = count(&with_newline, &["--trim-trailing-newline", "--quiet"]);
+    assert!(!notes.contains("Trailing newline trimmed"), "{}", notes);
+
+    let (trimmed, notes) = count(&with_newline, &["--trim-trailing-newline"]);
+    assert_eq!(trimmed, without);
+    assert!(
+        notes.contains(&format!(
+            "Trailing newline trimmed: {} -> {} tokens (-1)",
+            with, without
+        )),
+        "{}",
+        notes
+    );
+}
+
+#[test]
+fn test_diff_cost_includes_expected_output() {
+    let stdin = "Summarize this.\n---DIFF---\nSummarize this report in three bullet points.\n";
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--diff",
+            "-",
+            "--price",
+            "--expected-output",
+            "500",
+            "--format",
+            "json",
+        ],
+        stdin,
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let cost = |side: &str, part: &str| value[side][part].as_f64().unwrap();
+    for side in ["original_cost", "modified_cost"] {
+        assert!(cost(side, "output") > 0.0);
+        let total = cost(side, "input") + cost(side, "output");
+        assert!((cost(side, "total") - total).abs() < 1e-12);
+    }
+    let difference = value["cost_difference"].as_f64().unwrap();
+    assert!(
+        (difference - (cost("modified_cost", "total") - cost("original_cost", "total"))).abs()
+            < 1e-12
+    );
+
+    // Without an expected output only the input cost is compared
+    let output = run_with_stdin(
+        &[
+            "--model", "gpt-4", "--diff", "-", "--price", "--format", "json",
+        ],
+        stdin,
+    );
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(value.get("original_cost").is_none());
+    assert!(value["cost_difference"].as_f64().is_some());
+}
+
+#[test]
+#[cfg(feature = "sampling")]
+fn test_seeded_sample_is_reproducible() {
+    let input: String = (0..20_000)
+        .map(|i| format!("Entry {}: {}\n", i, "lorem ipsum ".repeat(i % 11)))
+        .collect();
+    let estimate = || {
+        let mut child = Command::new("cargo")
+            .args(["run", "--features", "sampling", "--"])
+            .args(["--model", "gpt-4", "--sample", "4", "--seed", "1234"])
+            .args(["--format", "json"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "Command should succeed");
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let first = estimate();
+    let second = estimate();
+    assert_eq!(first["exact"], false);
+    assert_eq!(first["sampled_chunks"], 4);
+    assert_eq!(first["seed"], 1234);
+    assert_eq!(first["estimated_tokens"], second["estimated_tokens"]);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_response_prices_recorded_completion() {
+    let dir = tempfile::tempdir().unwrap();
+    let response = dir.path().join("response.json");
+    std::fs::write(
+        &response,
+        r#"{
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello! How can I help you today?"}}]
+        }"#,
+    )
+    .unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--price",
+            "--response",
+            response.to_str().unwrap(),
+            "--format",
+            "json",
+        ],
+        "Say hello to the user.",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["tokens"], 6);
+    assert_eq!(value["response_tokens"], 9);
+    // gpt-4 charges $0.03 per 1K input and $0.06 per 1K output tokens
+    let input_cost = value["input_cost"].as_f64().unwrap();
+    let output_cost = value["output_cost"].as_f64().unwrap();
+    assert!((input_cost - 6.0 * 0.03 / 1000.0).abs() < 1e-12);
+    assert!((output_cost - 9.0 * 0.06 / 1000.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_diff_from_stdin_missing_separator() {
+    let output = run_with_stdin(&["--model", "gpt-4", "--diff", "-"], "Hello, world!\n");
+
+    assert!(!output.status.success(), "Missing separator should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("---DIFF---"));
+}
+
+#[test]
+fn test_compare_models_from_file() {
+    let mut list = tempfile::NamedTempFile::new().unwrap();
+    writeln!(list, "# models to compare\ngpt-4\n\ngpt-3.5-turbo").unwrap();
+    let compare = format!("@{}", list.path().display());
+
+    let output = run_with_stdin(&["--compare", &compare, "--format", "json"], "Hello!");
+
+    assert!(output.status.success(), "Command should succeed");
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let models: Vec<&str> = rows
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["model"].as_str().unwrap())
+        .collect();
+    assert_eq!(models, vec!["gpt-4", "gpt-3.5-turbo"]);
+}
+
+#[test]
+fn test_stream_matches_whole_input_totals() {
+    let messages: Vec<serde_json::Value> = (0..5000)
+        .map(|i| {
+            serde_json::json!({
+                "role": if i % 2 == 0 { "user" } else { "assistant" },
+                "content": format!("Message number {} with some repeated filler text.", i),
+            })
+        })
+        .collect();
+    let input = serde_json::to_string(&messages).unwrap();
+    let args = [
+        "--compare",
+        "gpt-4",
+        "chars",
+        "--breakdown",
+        "--format",
+        "json",
+    ];
+
+    let whole = run_with_stdin(&args, &input);
+    let streamed = run_with_stdin(&[&args[..], &["--stream"]].concat(), &input);
+
+    assert!(whole.status.success(), "Command should succeed");
+    assert!(streamed.status.success(), "Streaming should succeed");
+    let whole: serde_json::Value = serde_json::from_slice(&whole.stdout).unwrap();
+    let streamed: serde_json::Value = serde_json::from_slice(&streamed.stdout).unwrap();
+    assert_eq!(streamed, whole);
+    assert!(whole[0]["tokens"].as_u64().unwrap() > 5000);
+}
+
+#[test]
+fn test_stream_rejects_malformed_json() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--stream"],
+        r#"[{"role": "user", "content": "hi"}"#,
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Input is not valid JSON message format"));
+}
+
+#[test]
+fn test_format_from_env() {
+    let output = run_with_env(
+        &["--model", "gpt-4"],
+        "Hello, world!",
+        &[("TOKUIN_FORMAT", "json")],
+    );
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("JSON output");
+    assert_eq!(value["model"], "gpt-4");
+
+    // The flag wins over the environment
+    let output = run_with_env(
+        &["--model", "gpt-4", "--format", "text"],
+        "Hello, world!",
+        &[("TOKUIN_FORMAT", "json")],
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Model: gpt-4"));
+}
+
+#[test]
+fn test_format_from_env_rejects_disabled_format() {
+    // Integration tests run the default feature set, without markdown
+    let output = run_with_env(
+        &["--model", "gpt-4"],
+        "Hello, world!",
+        &[("TOKUIN_FORMAT", "markdown")],
+    );
+    assert!(!output.status.success(), "Unknown format should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("markdown"));
+}
+
+#[test]
+fn test_examples_file_counted_separately() {
+    let dir = tempfile::tempdir().unwrap();
+    let prompt = dir.path().join("instruction.txt");
+    let examples = dir.path().join("examples.txt");
+    std::fs::write(&prompt, "Classify the sentiment of the review.\n").unwrap();
+    std::fs::write(
+        &examples,
+        "Review: I loved it\nSentiment: positive\n\nReview: Awful\nSentiment: negative\n",
+    )
+    .unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "words",
+            "--examples",
+            examples.to_str().unwrap(),
+            "--format",
+            "json",
+            prompt.to_str().unwrap(),
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["prompt_tokens"], 6);
+    assert_eq!(value["examples_tokens"], 10);
+    assert_eq!(value["combined_tokens"], 16);
+    assert_eq!(value["boundary_delta"], 0);
+}
+
+#[test]
+fn test_compare_dedupes_alias_equivalent_models() {
+    let output = run_with_stdin(
+        &["--compare", "gpt-4", "openai/gpt-4", "--format", "json"],
+        "Hello, world!",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    // A single remaining model is reported as one result, not a comparison
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["model"], "gpt-4");
+    assert_eq!(value["warnings"][0]["kind"], "duplicate_model");
+    assert!(json_warnings(&value).contains("skipping duplicate model 'openai/gpt-4'"));
+
+    let output = run_with_stdin(
+        &["--compare", "gpt-4", "openai/gpt-4", "--quiet"],
+        "Hello, world!",
+    );
+    assert!(output.status.success(), "Command should succeed");
+    assert!(
+        !String::from_utf8_lossy(&output.stderr).contains("Warning:"),
+        "--quiet suppresses warnings"
+    );
+
+    let output = run_with_stdin(
+        &[
+            "--compare",
+            "gpt-4",
+            "openai/gpt-4",
+            "--allow-duplicates",
+            "--format",
+            "json",
+        ],
+        "Hello, world!",
+    );
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_cheapest_prints_single_model_name() {
+    let output = run_with_stdin(
+        &["--compare", "gpt-4", "gpt-3.5-turbo", "chars", "--cheapest"],
+        "Hello, world!",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "gpt-3.5-turbo\n");
+
+    let output = run_with_stdin(&["--compare", "chars", "words", "--cheapest"], "Hi");
+    assert!(!output.status.success(), "No priced model should fail");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pricing"));
+}
+
+#[test]
+fn test_since_file_prints_bare_delta() {
+    let dir = tempfile::tempdir().unwrap();
+    let baseline = dir.path().join("baseline.txt");
+    std::fs::write(&baseline, "Hello, world!").unwrap();
+    let baseline = baseline.to_str().unwrap();
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--since-file", baseline],
+        "Hello, world!",
+    );
+    assert!(output.status.success(), "Unchanged prompt should succeed");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n");
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--since-file", baseline, "--quiet"],
+        "Hello, world! How are you today?",
+    );
+    assert!(!output.status.success(), "Growth should fail");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "+5\n");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Error"));
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--since-file",
+            baseline,
+            "--max-growth",
+            "5",
+        ],
+        "Hello, world! How are you today?",
+    );
+    assert!(
+        output.status.success(),
+        "Growth within the threshold should succeed"
+    );
+
+    let output = run_with_stdin(&["--model", "gpt-4", "--since-file", baseline], "Hello");
+    assert!(output.status.success(), "Shrinking should succeed");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "-3\n");
+}
+
+#[test]
+fn test_throughput_projects_response_latency() {
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--price",
+            "--expected-output",
+            "500",
+            "--throughput",
+            "40",
+            "--format",
+            "json",
+        ],
+        "Hello, world!",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let projection = &value["projection"];
+    assert_eq!(projection["latency_seconds"], 500.0 / 40.0);
+    assert_eq!(projection["throughput"], 40.0);
+    assert!(projection["cost"].as_f64().unwrap() > 0.0);
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--expected-output",
+            "500",
+            "--throughput",
+            "0",
+        ],
+        "Hello",
+    );
+    assert!(
+        !output.status.success(),
+        "A non-positive rate should be rejected"
+    );
+}
+
+#[test]
+fn test_with_metadata_adds_meta_to_json() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--with-metadata", "--format", "json"],
+        "Hello, world!",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["meta"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(value["meta"]["input"], "stdin");
+    assert_eq!(value["tokens"], 4);
+}
+
+#[test]
+fn test_fail_if_unpriced() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--price", "--fail-if-unpriced"],
+        "Hello, world!",
+    );
+    assert!(output.status.success(), "Priced model should succeed");
+
+    let output = run_with_stdin(
+        &[
+            "--compare",
+            "gpt-4",
+            "words",
+            "--price",
+            "--fail-if-unpriced",
+        ],
+        "Hello, world!",
+    );
+    assert!(!output.status.success(), "Unpriced model should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No pricing data for words"));
+    assert!(stderr.contains("--pricing-file"));
+    assert!(
+        output.stdout.is_empty(),
+        "No partial report should be printed"
+    );
+}
+
+#[test]
+fn test_currency_conversion_in_json() {
+    let args = ["--model", "gpt-4", "--price", "--format", "json"];
+    let output = run_with_stdin(&args, "Hello, world!");
+    assert!(output.status.success());
+    let usd: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(usd["currency"], "USD");
+
+    let output = run_with_stdin(
+        &[&args[..], &["--currency", "gbp", "--fx-rate", "0.5"]].concat(),
+        "Hello, world!",
+    );
+    assert!(output.status.success());
+    let gbp: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(gbp["currency"], "GBP");
+    let converted = gbp["input_cost"].as_f64().unwrap();
+    assert!((converted - usd["input_cost"].as_f64().unwrap() * 0.5).abs() < 1e-12);
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--price", "--currency", "EUR"],
+        "Hello, world!",
+    );
+    assert!(!output.status.success(), "A non-USD currency needs a rate");
+}
+
+#[test]
+fn test_group_by_role_sums_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.json"),
+        r#"[{"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "What is Rust"},
+            {"role": "assistant", "content": "A systems language"}]"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("b.json"),
+        r#"[{"role": "user", "content": "Hello there"},
+            {"role": "assistant", "content": "Hi"}]"#,
+    )
+    .unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "words",
+            "--group-by-role",
+            "--format",
+            "json",
+            dir.path().to_str().unwrap(),
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["files"].as_array().unwrap().len(), 2);
+    assert_eq!(value["files"][1]["user"], 2);
+    assert_eq!(value["total"]["system"], 2);
+    assert_eq!(value["total"]["user"], 5);
+    assert_eq!(value["total"]["assistant"], 4);
+    assert_eq!(value["total"]["total"], 11);
+}
+
+#[test]
+fn test_group_by_role_reports_every_compared_model() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.json"),
+        r#"[{"role": "user", "content": "What is Rust"}]"#,
+    )
+    .unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--compare",
+            "words",
+            "chars",
+            "--group-by-role",
+            "--format",
+            "json",
+            dir.path().to_str().unwrap(),
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value[0]["model"], "words");
+    assert_eq!(value[0]["total"]["user"], 3);
+    assert_eq!(value[1]["model"], "chars");
+    assert_eq!(value[1]["total"]["user"], 12);
+}
+
+#[test]
+fn test_line_endings_normalized_before_counting() {
+    let crlf = "First line\r\nSecond line\r\n";
+    let args = ["--model", "chars", "--format", "json"];
+
+    let output = run_with_stdin(&args, crlf);
+    assert!(output.status.success());
+    let preserved: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(preserved["tokens"], 25);
+
+    let output = run_with_stdin(&[&args[..], &["--line-endings", "lf"]].concat(), crlf);
+    assert!(output.status.success());
+    let normalized: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(normalized["tokens"], 23);
+    // The note goes to stderr, leaving the JSON report unchanged
+    assert!(normalized.get("warnings").is_none());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Line endings converted to LF: 2 line breaks, 25 -> 23 tokens (-2)"));
+
+    let output = run_with_stdin(
+        &[&args[..], &["--line-endings", "lf", "--quiet"]].concat(),
+        crlf,
+    );
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Line endings converted"));
+}
+
+#[test]
+fn test_chat_template_counts_rendered_prompt() {
+    let dir = tempfile::tempdir().unwrap();
+    let template = dir.path().join("template.jinja");
+    std::fs::write(
+        &template,
+        "{{ bos_token }}{% for m in messages %}<|{{ m.role }}|>{{ m.content }}\n{% endfor %}",
+    )
+    .unwrap();
+    let messages = r#"[{"role": "system", "content": "Be brief"},
+        {"role": "user", "content": "Hi"}]"#;
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "chars",
+            "--format",
+            "json",
+            "--chat-template",
+            template.to_str().unwrap(),
+        ],
+        messages,
+    );
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    // "<s><|system|>Be brief\n<|user|>Hi\n"
+    assert_eq!(json["tokens"], 3 + 10 + 9 + 8 + 3);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Chat template applied: 2 messages rendered to 33 characters"));
+}
+
+#[test]
+fn test_max_messages_errors_or_truncates() {
+    let messages = r#"[{"role": "user", "content": "aaaa"},
+        {"role": "assistant", "content": "bb"},
+        {"role": "user", "content": "c"},
+        {"role": "assistant", "content": "ddddddd"}]"#;
+    let args = [
+        "--model",
+        "chars",
+        "--format",
+        "json",
+        "--max-messages",
+        "3",
+    ];
+
+    let output = run_with_stdin(&args, messages);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("input has 4 messages, more than --max-messages 3"),
+        "{}",
+        stderr
+    );
+
+    let output = run_with_stdin(&[&args[..], &["--stream"]].concat(), messages);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("input has more than --max-messages 3 messages"),
+        "{}",
+        stderr
+    );
+
+    for extra in [
+        &["--on-max-messages", "truncate"][..],
+        &["--on-max-messages", "truncate", "--stream"],
+    ] {
+        let output = run_with_stdin(&[&args[..], extra].concat(), messages);
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json["tokens"], 7);
+        assert_eq!(json["warnings"][0]["kind"], "messages_truncated");
+        assert!(json_warnings(&json).contains("counted the first 3 of 4 messages"));
+    }
+}
+
+#[test]
+fn test_replay_cost_attributes_each_message() {
+    let messages = r#"[{"role": "system", "content": "You are a weather bot."},
+        {"role": "user", "content": "Weather in Paris?"},
+        {"role": "assistant", "content": "Calling the weather tool."},
+        {"role": "tool", "content": "{\"temp\": 18}"},
+        {"role": "assistant", "content": "It is 18 degrees in Paris."}]"#;
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--replay-cost", "--format", "json"],
+        messages,
+    );
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let billed: Vec<&str> = json["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["billed"].as_str().unwrap())
+        .collect();
+    assert_eq!(billed, ["input", "input", "output", "input", "output"]);
+
+    let sum: f64 = json["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["cost"].as_f64().unwrap())
+        .sum();
+    let total = json["total_cost"].as_f64().unwrap();
+    assert!(total > 0.0);
+    assert!((sum - total).abs() < 1e-12, "{} != {}", sum, total);
+
+    let output = run_with_stdin(&["--model", "chars", "--replay-cost"], messages);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_json_path_pointers_extract_nested_messages() {
+    let payload = r#"{"meta": {"app": "support-bot"},
+        "request": {"body": {"history": [
+            {"author": {"type": "system"}, "text": "aaaa"},
+            {"author": {"type": "human"}, "text": "bb"},
+            {"author": {"type": "ai"}, "text": "c"}
+        ]}}}"#;
+    let args = [
+        "--model",
+        "chars",
+        "--format",
+        "json",
+        "--breakdown",
+        "--json-path-messages",
+        "/request/body/history",
+        "--json-path-role",
+        "/author/type",
+        "--json-path-content",
+        "/text",
+    ];
+
+    let output = run_with_stdin(&args, payload);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["tokens"], 7);
+    assert_eq!(json["breakdown"]["system"], 4);
+
+    // Without the pointers the payload is not a known chat format
+    let output = run_with_stdin(&["--model", "chars"], payload);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_histogram_buckets_message_sizes() {
+    let sizes = [1, 4, 9, 12, 30, 75, 300];
+    let messages: Vec<serde_json::Value> = sizes
+        .iter()
+        .map(|&n| serde_json::json!({"role": "user", "content": "x".repeat(n)}))
+        .collect();
+    let input = serde_json::to_string(&messages).unwrap();
+
+    let output = run_with_stdin(
+        &["--model", "chars", "--histogram", "--format", "json"],
+        &input,
+    );
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let buckets: Vec<(String, u64)> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            (
+                b["label"].as_str().unwrap().to_string(),
+                b["count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            ("0-10".to_string(), 3),
+            ("11-50".to_string(), 2),
+            ("51-200".to_string(), 1),
+            ("201+".to_string(), 1)
+        ]
+    );
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "chars",
+            "--histogram",
+            "--histogram-buckets",
+            "5,100",
+        ],
+        &input,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Message sizes: 7 messages (chars)"));
+    assert!(stdout.contains("Largest message: 300 tokens"));
+    let count = |label: &str| {
+        let line = stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(label))
+            .unwrap();
+        line.split_whitespace().last().unwrap().to_string()
+    };
+    assert_eq!(count("0-5 "), "2");
+    assert_eq!(count("6-100 "), "4");
+    assert_eq!(count("101+ "), "1");
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "chars",
+            "--histogram",
+            "--histogram-buckets",
+            "50,10",
+        ],
+        &input,
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_missing_tokenizer_file_is_an_error() {
+    let path = "/no/such/dir/gemini.model";
+    for args in [
+        &["--model", "gemini-pro", "--tokenizer-file", path][..],
+        &[
+            "--model",
+            "gemini-pro",
+            "--tokenizer-file",
+            path,
+            "--diff",
+            "-",
+        ],
+    ] {
+        let output = run_with_stdin(args, "Hello, world!\n---DIFF---\nHello there!\n");
+        assert!(!output.status.success(), "should not fall back silently");
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Cannot load tokenizer for 'gemini-pro' from '/no/such/dir/gemini.model': no such file"),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_compare_providers_annotates_chosen_models() {
+    let output = run_with_stdin(&["--compare-providers", "--format", "json"], "Hello there!");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Comparing providers: gpt-4 (openai)"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Not built in: "), "{}", stderr);
+}
+
+#[test]
+fn test_model_info_reports_gpt4_details() {
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--model-info", "--format", "json"],
+        "",
+    );
+    assert!(output.status.success());
+    let details: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(details["model"], "gpt-4");
+    assert_eq!(details["provider"], "openai");
+    assert_eq!(details["encoding"], "cl100k_base");
+    assert_eq!(details["context_window"], 8192);
+    assert_eq!(details["input_price"], 0.03);
+    assert_eq!(details["output_price"], 0.06);
+    assert_eq!(details["pricing_source"], "defaults");
+
+    let output = run_with_stdin(&["--model", "gpt-4", "--model-info"], "");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Provider:       openai"), "{}", stdout);
+    assert!(
+        stdout.contains("Context window: 8,192 tokens"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Pricing source: defaults"), "{}", stdout);
+}
+
+#[test]
+fn test_inline_base64_image_counts_as_estimate() {
+    let image = format!(
+        "data:image/png;base64,{}",
+        "iVBORw0KGgoAAAANSUhEUgAA".repeat(500)
+    );
+    let payload = serde_json::json!([
+        {"role": "user", "content": format!("What is in this picture? {}", image)}
+    ])
+    .to_string();
+    let tokens = |mode: &str| {
+        let output = run_with_stdin(
+            &[
+                "--model",
+                "gpt-4",
+                "--image-tokens",
+                mode,
+                "--format",
+                "json",
+            ],
+            &payload,
+        );
+        assert!(output.status.success());
+        let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        result["tokens"].as_u64().unwrap()
+    };
+
+    let stripped = tokens("strip");
+    assert_eq!(tokens("estimate"), stripped + 85);
+    assert!(tokens("literal") > 1000);
+
+    // Estimating is the default
+    let output = run_with_stdin(&["--model", "gpt-4", "--format", "json"], &payload);
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["tokens"], stripped + 85);
+    assert!(json_warnings(&result).contains("1 inline base64 image(s) counted as 85 tokens each"));
+}
+
+#[test]
+fn test_inputs_grid_counts_every_file_with_every_model() {
+    let dir = tempfile::tempdir().unwrap();
+    let short = dir.path().join("short.txt");
+    let long = dir.path().join("long.txt");
+    std::fs::write(&short, "Hello, world!").unwrap();
+    std::fs::write(&long, "The quick brown fox jumps over the lazy dog.").unwrap();
+    let (short, long) = (short.to_str().unwrap(), long.to_str().unwrap());
+
+    let output = run_with_stdin(
+        &[
+            "--inputs",
+            short,
+            long,
+            "--compare",
+            "gpt-4",
+            "chars",
+            "--format",
+            "json",
+        ],
+        "",
+    );
+    assert!(output.status.success());
+    let grid: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let counts: Vec<Vec<(String, u64)>> = grid
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| {
+            row["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|cell| {
+                    (
+                        cell["model"].as_str().unwrap().to_string(),
+                        cell["tokens"].as_u64().unwrap(),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    assert_eq!(grid[0]["input"], short);
+    assert_eq!(grid[1]["input"], long);
+    assert_eq!(
+        counts,
+        [
+            [("gpt-4".to_string(), 4), ("chars".to_string(), 13)],
+            [("gpt-4".to_string(), 10), ("chars".to_string(), 44)],
+        ]
+    );
+
+    let output = run_with_stdin(
+        &[
+            "--inputs",
+            short,
+            long,
+            "--compare",
+            "gpt-4",
+            "chars",
+            "--csv",
+        ],
+        "",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "input,gpt-4,chars".to_string(),
+            format!("{},4,13", short),
+            format!("{},10,44", long),
+        ]
+    );
+}
+
+#[test]
+fn test_compare_inputs_counts_each_inline_prompt() {
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--compare-inputs",
+            "--format",
+            "json",
+            "--",
+            "Hello, world!",
+            "Hello",
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let grid: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rows = grid.as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["input"], "Hello, world!");
+    assert_eq!(rows[0]["results"][0]["tokens"], 4);
+    assert_eq!(rows[1]["input"], "Hello");
+    assert_eq!(rows[1]["results"][0]["tokens"], 1);
+}
+
+#[test]
+fn test_min_and_max_tokens_band() {
+    // "Hello, world!" is 4 gpt-4 tokens
+    let in_band = run_with_stdin(
+        &["--model", "gpt-4", "--min-tokens", "2", "--max-tokens", "10"],
+        "Hello, world!",
+    );
+    assert!(in_band.status.success());
+
+    let below = run_with_stdin(
+        &["--model", "gpt-4", "--min-tokens", "5", "--max-tokens", "10"],
+        "Hello, world!",
+    );
+    assert_eq!(below.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&below.stderr);
+    assert!(
+        stderr.contains("4 tokens for gpt-4 is below --min-tokens 5"),
+        "{}",
+        stderr
+    );
+
+    let above = run_with_stdin(&["--model", "gpt-4", "--max-tokens", "3"], "Hello, world!");
+    assert_eq!(above.status.code(), Some(4));
+}
+
+#[test]
+fn test_strip_frontmatter_reports_savings() {
+    let prompt =
+        "---\ntitle: Quarterly summary\ntags: [finance, reports]\n---\nSummarize the report.\n";
+    let count = |input: &str, extra: &[&str]| {
+        let mut args = vec!["--model", "gpt-4", "--format", "json"];
+        args.extend_from_slice(extra);
+        let output = run_with_stdin(&args, input);
+        assert!(output.status.success(), "Command should succeed");
+        let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(value.get("warnings").is_none(), "Notes stay out of JSON");
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        (value["tokens"].as_u64().unwrap(), stderr)
+    };
+
+    let (with, notes) = count(prompt, &[]);
+    assert!(
+        notes.contains("Input starts with YAML frontmatter"),
+        "{}",
+        notes
+    );
+    let (body, _) = count("Summarize the report.\n", &[]);
+    let (without, notes) = count(prompt, &["--strip-frontmatter"]);
+    assert_eq!(without, body, "Frontmatter should not be counted");
+    assert!(
+        notes.contains(&format!("{} tokens saved", with - without)),
+        "{}",
+        notes
+    );
+}
+
+#[test]
+fn test_strip_frontmatter_applies_to_every_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let with = dir.path().join("with.md");
+    let body = dir.path().join("body.md");
+    std::fs::write(
+        &with,
+        "---\ntitle: Quarterly summary\n---\nSummarize the report.\n",
+    )
+    .unwrap();
+    std::fs::write(&body, "Summarize the report.\n").unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--inputs",
+            with.to_str().unwrap(),
+            body.to_str().unwrap(),
+            "--model",
+            "gpt-4",
+            "--strip-frontmatter",
+            "--format",
+            "json",
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        value[0]["results"][0]["tokens"],
+        value[1]["results"][0]["tokens"]
+    );
+}
+
+#[test]
+fn test_compare_notes_mixed_encodings() {
+    let output = run_with_stdin(&["--compare", "gpt-4", "gpt-4o"], "Hello, world!");
+    assert!(output.status.success(), "Command should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("different encodings (cl100k_base, o200k_base)"),
+        "{}",
+        stdout
+    );
+
+    let output = run_with_stdin(&["--compare", "gpt-4", "gpt-3.5-turbo"], "Hello, world!");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("different encodings"), "{}", stdout);
+}
+
+#[test]
+fn test_compare_prompts_from_dir_reports_corpus() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.json"),
+        r#"[{"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "What is Rust"}]"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("b.json"),
+        r#"[{"role": "user", "content": "Summarize the attached quarterly report please"},
+            {"role": "assistant", "content": "Revenue grew in every region"}]"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("c.txt"), "Hello").unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "words",
+            "--compare-prompts-from-dir",
+            dir.path().to_str().unwrap(),
+            "--format",
+            "json",
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["files"], 3);
+    assert_eq!(value["total_tokens"], 17);
+    assert_eq!(value["median_tokens"], 5);
+    let heaviest: Vec<&str> = value["heaviest"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+    assert!(heaviest[0].ends_with("b.json"), "{:?}", heaviest);
+    assert!(heaviest[1].ends_with("a.json"), "{:?}", heaviest);
+    assert!(heaviest[2].ends_with("c.txt"), "{:?}", heaviest);
+    assert_eq!(value["heaviest"][0]["tokens"], 11);
+    assert_eq!(value["breakdown"]["system"], 2);
+    assert_eq!(value["breakdown"]["assistant"], 5);
+    // The word baseline has no pricing
+    assert_eq!(value["total_cost"], serde_json::Value::Null);
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--compare-prompts-from-dir",
+            dir.path().to_str().unwrap(),
+        ],
+        "",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Corpus: 3 files (gpt-4)"), "{}", stdout);
+    assert!(stdout.contains("Projected cost: $"), "{}", stdout);
+}
+
+#[test]
+fn test_compare_prompts_from_dir_skips_bad_files_per_model() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.json"),
+        r#"[{"role": "user", "content": "What is Rust"}]"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("bad.json"), "{not json").unwrap();
+
+    let output = run_with_stdin(
+        &[
+            "--compare",
+            "words",
+            "gpt-4",
+            "--compare-prompts-from-dir",
+            dir.path().to_str().unwrap(),
+            "--currency",
+            "EUR",
+            "--fx-rate",
+            "0.5",
+            "--format",
+            "json",
+        ],
+        "",
+    );
+
+    assert!(output.status.success(), "Command should succeed");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("Warning: skipped").count(), 1, "{}", stderr);
+    assert!(stderr.contains("bad.json"), "{}", stderr);
+
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summaries = value.as_array().unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0]["model"], "words");
+    assert_eq!(summaries[1]["model"], "gpt-4");
+    assert_eq!(summaries[1]["files"], 1);
+    assert_eq!(summaries[1]["currency"], "EUR");
+    assert!(summaries[1]["skipped"][0]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("bad.json"));
+
+    let output = run_with_stdin(
+        &[
+            "--model",
+            "gpt-4",
+            "--compare-prompts-from-dir",
+            dir.path().to_str().unwrap(),
+            "--format",
+            "json",
+        ],
+        "",
+    );
+    let usd: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let usd = usd["total_cost"].as_f64().unwrap();
+    let eur = summaries[1]["total_cost"].as_f64().unwrap();
+    assert!((eur - usd * 0.5).abs() < 1e-12, "{} vs {}", eur, usd);
+}
+
+#[test]
+fn test_warmup_reports_load_time_unless_quiet() {
+    let output = run_with_stdin(&["--model", "gpt-4", "--warmup"], "Hello, world!");
+    assert!(output.status.success(), "Command should succeed");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warmed up gpt-4 in"));
+
+    let output = run_with_stdin(
+        &["--model", "gpt-4", "--warmup", "--quiet"],
+        "Hello, world!",
+    );
+    assert!(output.status.success(), "Command should succeed");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Warmed up"));
+}
+
+#[test]
+fn test_json_failures_respect_quiet() {
+    let args = ["--compare", "gpt-4", "no-such-model", "--format", "json"];
+
+    let output = run_with_stdin(&args, "Hello, world!");
+    assert!(!output.status.success(), "A failed model fails the run");
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value[0]["model"], "gpt-4");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Model not found"));
+
+    let output = run_with_stdin(&[&args[..], &["--quiet"]].concat(), "Hello, world!");
+    assert!(!output.status.success(), "A failed model fails the run");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Model not found"));
+}
+
+#[test]
+fn test_empty_content_note_respects_quiet() {
+    let messages = r#"[{"role": "user", "content": ""}, {"role": "user", "content": "Hi"}]"#;
+
+    let output = run_with_stdin(&["--model", "gpt-4"], messages);
+    assert!(output.status.success(), "Command should succeed");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Note: 1 message(s) have empty content")
+    );
+
+    let output = run_with_stdin(&["--model", "gpt-4", "--quiet"], messages);
+    assert!(output.status.success(), "Command should succeed");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("empty content"));
+}
@@ -0,0 +1,27 @@
+/// Coarse throughput regression test for token counting.
+///
+/// `benches/tokenization.rs` measures throughput precisely; this only
+/// catches gross regressions, so the bound is generous enough for debug
+/// builds on slow CI machines.
+#[cfg(feature = "openai")]
+#[test]
+fn test_one_megabyte_counts_within_bound() {
+    use std::time::{Duration, Instant};
+    use tokuin::models::ModelRegistry;
+
+    let paragraph = "The quick brown fox jumps over the lazy dog. \
+Numbers like 3.14159 and dates like 2024-01-01 take other merge paths.\n";
+    let input = paragraph.repeat(1024 * 1024 / paragraph.len() + 1);
+    let tokenizer = ModelRegistry::new().get_tokenizer("gpt-4").unwrap();
+
+    let start = Instant::now();
+    let tokens = tokenizer.count_tokens(&input).unwrap();
+    let elapsed = start.elapsed();
+
+    assert!(tokens > 0);
+    assert!(
+        elapsed < Duration::from_secs(30),
+        "counting 1MB took {:?}",
+        elapsed
+    );
+}
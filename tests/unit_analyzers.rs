@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use tokuin::analyzers::duplicates::{detect_duplicates, normalize_content};
 use tokuin::analyzers::scanner::PromptScanner;
 use tokuin::analyzers::types::PromptAnalysis;
+use tokuin::analyzers::whitespace::{find_whitespace_issues, trim_whitespace};
 use tokuin::parsers::Message;
 
 #[test]
@@ -34,6 +35,8 @@ fn create_test_analysis(path: &str, content: &str, tokens: usize, cost: f64) ->
         messages: vec![Message {
             role: "user".to_string(),
             content: content.to_string(),
+            name: None,
+            tool_call_id: None,
         }],
         token_count: tokens,
         input_cost: cost,
@@ -148,3 +151,74 @@ fn test_generate_insights_duplicates() {
     assert_eq!(insights.duplicates.len(), 1);
     assert_eq!(insights.duplicates[0].len(), 2);
 }
+
+#[test]
+#[cfg(feature = "openai")]
+fn test_scan_directory_progress_totals() {
+    use tokuin::analyzers::types::ScanProgress;
+    use tokuin::models::ModelRegistry;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "Hello, world!").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "Another prompt with more words").unwrap();
+    std::fs::write(dir.path().join("ignored.bin"), "not a prompt").unwrap();
+
+    let scanner = PromptScanner::new(ModelRegistry::new(), "gpt-4".to_string(), None);
+    let mut updates: Vec<ScanProgress> = Vec::new();
+    let analyses = scanner
+        .scan_directory_with_progress(dir.path(), |p| updates.push(*p))
+        .unwrap();
+
+    assert_eq!(analyses.len(), 2);
+    assert_eq!(updates.len(), 2, "One update per candidate file");
+
+    let last = updates.last().unwrap();
+    assert_eq!(last.processed, last.total);
+    assert_eq!(
+        last.tokens,
+        analyses.iter().map(|a| a.token_count).sum::<usize>(),
+        "Running token sum should match the final totals"
+    );
+
+    let plain = scanner.scan_directory(dir.path()).unwrap();
+    assert_eq!(
+        plain.iter().map(|a| a.token_count).sum::<usize>(),
+        last.tokens
+    );
+}
+
+#[test]
+fn test_trim_whitespace() {
+    assert_eq!(trim_whitespace("\n\tHello   \nWorld\t\n"), "Hello\nWorld");
+}
+
+#[test]
+fn test_whitespace_issues_trailing_spaces() {
+    use tokuin::tokenizers::CharTokenizer;
+
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: "Be brief.".to_string(),
+            name: None,
+            tool_call_id: None,
+        },
+        Message {
+            role: "user".to_string(),
+            content: "Hello there    ".to_string(),
+            name: None,
+            tool_call_id: None,
+        },
+    ];
+
+    let issues = find_whitespace_issues(&CharTokenizer::new(), &messages).unwrap();
+    assert_eq!(issues.len(), 1);
+    let issue = &issues[0];
+    assert_eq!(issue.index, 1);
+    assert_eq!(issue.role, "user");
+    assert!(issue.trailing_whitespace);
+    assert!(!issue.edge_newlines);
+    assert!(!issue.tab_indentation);
+    assert_eq!(issue.savings, 4);
+    assert_eq!(issue.describe(), "trailing whitespace");
+}